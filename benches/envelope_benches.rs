@@ -0,0 +1,176 @@
+use std::{cell::{Cell, RefCell}, collections::HashSet, hint::black_box, rc::Rc};
+
+use bc_components::{Digest, DigestProvider, PrivateKeyBase, PublicKeyBaseProvider, SigningOptions};
+use bc_envelope::{base::walk::EdgeType, prelude::*};
+use bc_rand::make_fake_random_number_generator;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+/// The number of assertions on the envelopes benchmarked.
+const SIZES: [(&str, usize); 3] = [
+    ("small", 4),
+    ("medium", 64),
+    ("large", 1024),
+];
+
+/// Returns an envelope with `count` assertions, every eighth of which has a
+/// nested envelope as its object.
+fn make_envelope(count: usize) -> Envelope {
+    let assertions: Vec<Envelope> = (0..count)
+        .map(|i| {
+            let predicate = format!("predicate-{}", i);
+            if i % 8 == 0 {
+                let object = Envelope::new(format!("object-{}", i))
+                    .add_assertion("index", i as u64)
+                    .add_assertion("note", "A nested object.");
+                Envelope::new_assertion(predicate, object)
+            } else {
+                Envelope::new_assertion(predicate, format!("object-{}", i))
+            }
+        })
+        .collect();
+    Envelope::new("Alice").add_assertions(&assertions)
+}
+
+fn private_key() -> PrivateKeyBase {
+    PrivateKeyBase::from_data(hex::decode("82f32c855d3d542256180810797e0073").unwrap())
+}
+
+fn signing_options() -> SigningOptions {
+    SigningOptions::Schnorr { rng: Rc::new(RefCell::new(make_fake_random_number_generator())) }
+}
+
+fn bench_construction(c: &mut Criterion) {
+    let mut group = c.benchmark_group("construction");
+    for (name, count) in SIZES {
+        group.bench_with_input(BenchmarkId::from_parameter(name), &count, |b, &count| {
+            b.iter(|| make_envelope(black_box(count)))
+        });
+    }
+    group.finish();
+}
+
+fn bench_signing(c: &mut Criterion) {
+    let private_key = private_key();
+    let public_key = private_key.public_key_base();
+    let mut group = c.benchmark_group("signing");
+    for (name, count) in SIZES {
+        let envelope = make_envelope(count).wrap_envelope();
+        group.bench_with_input(BenchmarkId::new("sign", name), &envelope, |b, envelope| {
+            b.iter(|| envelope.add_signature_opt(&private_key, Some(signing_options()), None))
+        });
+        let signed = envelope.add_signature_opt(&private_key, Some(signing_options()), None);
+        group.bench_with_input(BenchmarkId::new("verify", name), &signed, |b, signed| {
+            b.iter(|| signed.verify_signature_from(&public_key).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn bench_elision(c: &mut Criterion) {
+    let mut group = c.benchmark_group("elision");
+    for (name, count) in SIZES {
+        let envelope = make_envelope(count);
+        // Elide every other assertion.
+        let target: HashSet<Digest> = envelope
+            .assertions()
+            .iter()
+            .step_by(2)
+            .map(|assertion| assertion.digest().into_owned())
+            .collect();
+        group.bench_with_input(BenchmarkId::from_parameter(name), &envelope, |b, envelope| {
+            b.iter(|| envelope.elide_removing_set(&target))
+        });
+    }
+    group.finish();
+}
+
+fn bench_search(c: &mut Criterion) {
+    let mut group = c.benchmark_group("search");
+    for (name, count) in SIZES {
+        let envelope = make_envelope(count);
+        let predicate = format!("predicate-{}", count / 2);
+        group.bench_with_input(BenchmarkId::new("predicate", name), &envelope, |b, envelope| {
+            b.iter(|| envelope.assertions_with_predicate(predicate.as_str()))
+        });
+        // Walk the whole tree counting the leaves that match a pattern.
+        group.bench_with_input(BenchmarkId::new("walk", name), &envelope, |b, envelope| {
+            b.iter(|| {
+                let matches = Cell::new(0usize);
+                let visit = |element: Envelope, _: usize, _: EdgeType, _: Option<()>| -> Option<()> {
+                    if let Ok(text) = element.extract_subject::<String>() {
+                        if text.ends_with('7') {
+                            matches.set(matches.get() + 1);
+                        }
+                    }
+                    None
+                };
+                envelope.walk(false, &visit);
+                matches.get()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_pattern(c: &mut Criterion) {
+    let mut group = c.benchmark_group("pattern");
+    for (name, count) in SIZES {
+        let envelope = make_envelope(count);
+        // Match the nested objects, which are the only nodes with exactly
+        // two assertions.
+        let nodes = Pattern::node_with_assertions_count(2);
+        group.bench_with_input(BenchmarkId::new("node", name), &envelope, |b, envelope| {
+            b.iter(|| envelope.paths_matching(&nodes))
+        });
+        let prefix = Pattern::digest_prefix(&envelope.assertions()[count / 2].digest().data()[..2]);
+        group.bench_with_input(BenchmarkId::new("digest_prefix", name), &envelope, |b, envelope| {
+            b.iter(|| envelope.paths_matching(&prefix))
+        });
+        // Find the elided half of the assertions.
+        let target: HashSet<Digest> = envelope
+            .assertions()
+            .iter()
+            .step_by(2)
+            .map(|assertion| assertion.digest().into_owned())
+            .collect();
+        let elided = envelope.elide_removing_set(&target);
+        let obscured = Pattern::elided();
+        group.bench_with_input(BenchmarkId::new("elided", name), &elided, |b, elided| {
+            b.iter(|| elided.paths_matching(&obscured))
+        });
+        // Answer a repeated query from a cache.
+        let cache = QueryCache::new(16);
+        cache.paths_matching_pattern(&envelope, &nodes);
+        group.bench_with_input(BenchmarkId::new("cached", name), &envelope, |b, envelope| {
+            b.iter(|| cache.paths_matching_pattern(envelope, &nodes))
+        });
+    }
+    group.finish();
+}
+
+fn bench_ur(c: &mut Criterion) {
+    bc_envelope::register_tags();
+    let mut group = c.benchmark_group("ur");
+    for (name, count) in SIZES {
+        let envelope = make_envelope(count);
+        group.bench_with_input(BenchmarkId::new("encode", name), &envelope, |b, envelope| {
+            b.iter(|| envelope.ur_string())
+        });
+        let ur_string = envelope.ur_string();
+        group.bench_with_input(BenchmarkId::new("decode", name), &ur_string, |b, ur_string| {
+            b.iter(|| Envelope::from_ur_string(ur_string.as_str()).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_construction,
+    bench_signing,
+    bench_elision,
+    bench_search,
+    bench_pattern,
+    bench_ur,
+);
+criterion_main!(benches);
@@ -3,13 +3,23 @@
 set -e
 
 cargo test
+cargo test --features ffi
+cargo test --features fixtures
+cargo test --features mmap
 cargo test --no-default-features
+cargo test --no-default-features --features anonymize
 cargo test --no-default-features --features attachment
+cargo test --no-default-features --features claims
 cargo test --no-default-features --features compress
 cargo test --no-default-features --features encrypt
 cargo test --no-default-features --features expression
+cargo test --no-default-features --features fixtures
 cargo test --no-default-features --features known_value
+cargo test --no-default-features --features legacy
+cargo test --no-default-features --features mmap
 cargo test --no-default-features --features proof
+cargo test --no-default-features --features provenance
+cargo test --no-default-features --features rdf
 cargo test --no-default-features --features recipient
 cargo test --no-default-features --features salt
 cargo test --no-default-features --features signature
@@ -3,7 +3,7 @@ use anyhow::{bail, Error, Result};
 use bc_components::{Digest, DigestProvider};
 use dcbor::prelude::*;
 
-use crate::{Envelope, EnvelopeEncodable};
+use crate::{base::cbor::MAX_DECODE_DEPTH, Envelope, EnvelopeEncodable};
 
 /// Represents an assertion.
 ///
@@ -86,12 +86,20 @@ impl TryFrom<Map> for Assertion {
     type Error = Error;
 
     fn try_from(map: Map) -> Result<Self> {
+        Self::decode(map, false, MAX_DECODE_DEPTH)
+    }
+}
+
+impl Assertion {
+    /// Decodes an assertion from its map, decoding its predicate and object
+    /// as [`Envelope::decode`] does.
+    pub(crate) fn decode(map: Map, lenient: bool, max_depth: usize) -> Result<Self> {
         if map.len() != 1 {
             bail!("assertion map must have exactly one element")
         }
         let elem = map.iter().next().unwrap();
-        let predicate = Envelope::from_untagged_cbor(elem.0.clone())?;
-        let object = Envelope::from_untagged_cbor(elem.1.clone())?;
+        let predicate = Envelope::decode(elem.0.clone(), lenient, max_depth)?;
+        let object = Envelope::decode(elem.1.clone(), lenient, max_depth)?;
         Ok(Self::new(predicate, object))
     }
 }
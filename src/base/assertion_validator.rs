@@ -0,0 +1,61 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+
+use crate::{Envelope, EnvelopeEncodable};
+
+/// A function that checks an assertion as it is added to an envelope.
+///
+/// The function receives the assertion envelope, and returns an error to
+/// reject it.
+///
+/// Validators let an application enforce its own rules as envelopes are
+/// built, such as that the object of every `date` assertion is a date, or
+/// that predicates come from an approved vocabulary, so that malformed
+/// documents are caught at construction rather than discovered later. They
+/// apply only where they are passed, to [`Envelope::try_add_assertion`] and
+/// [`Envelope::add_assertion_envelope_validated`], so the assertions other
+/// components add, such as signatures and salt, are not subject to them.
+pub type AssertionValidator = Arc<dyn Fn(&Envelope) -> Result<()> + Send + Sync>;
+
+/// Checks `assertion` with each of `validators` in turn, returning the first
+/// error one returns.
+///
+/// Obscured assertions cannot be inspected and are not checked.
+pub fn validate_assertion(assertion: &Envelope, validators: &[AssertionValidator]) -> Result<()> {
+    if !assertion.is_subject_assertion() {
+        return Ok(());
+    }
+    validators.iter().try_for_each(|validator| validator(assertion))
+}
+
+/// Support for adding validated assertions.
+impl Envelope {
+    /// Returns the result of adding the given assertion to the envelope,
+    /// after checking it with `validators`.
+    ///
+    /// Returns the first error a validator returns.
+    pub fn try_add_assertion(
+        &self,
+        predicate: impl EnvelopeEncodable,
+        object: impl EnvelopeEncodable,
+        validators: &[AssertionValidator],
+    ) -> Result<Self> {
+        self.add_assertion_envelope_validated(Self::new_assertion(predicate, object), validators)
+    }
+
+    /// Returns the result of adding the given assertion envelope to the
+    /// envelope, after checking it with `validators`.
+    ///
+    /// Returns the first error a validator returns, or an error if the
+    /// envelope is not an assertion envelope or an obscured variant of one.
+    pub fn add_assertion_envelope_validated(
+        &self,
+        assertion_envelope: impl EnvelopeEncodable,
+        validators: &[AssertionValidator],
+    ) -> Result<Self> {
+        let assertion = assertion_envelope.into_envelope();
+        validate_assertion(&assertion, validators)?;
+        self.add_assertion_envelope(assertion)
+    }
+}
@@ -1,5 +1,5 @@
 use anyhow::{bail, Result};
-use bc_components::DigestProvider;
+use bc_components::{DigestProvider, URI};
 
 use crate::{Envelope, EnvelopeEncodable, EnvelopeError};
 
@@ -95,6 +95,45 @@ impl Envelope {
     }
 }
 
+/// Support for URI predicates.
+impl Envelope {
+    /// Returns an envelope containing the given URI, for use as the predicate
+    /// of assertions drawn from a Linked Data vocabulary such as
+    /// `https://schema.org/name`.
+    ///
+    /// Use [`FormatContext::set_uri_prefix`](crate::FormatContext::set_uri_prefix)
+    /// to abbreviate such predicates when formatting envelopes.
+    ///
+    /// Returns an error if `uri` is not a valid URI.
+    pub fn uri_predicate(uri: &str) -> Result<Self> {
+        Ok(Self::new(URI::new(uri)?))
+    }
+}
+
+/// Support for adding assertions to obscured subjects.
+impl Envelope {
+    /// Returns the result of adding the given assertion to an envelope whose
+    /// subject is elided, encrypted, or compressed.
+    ///
+    /// An obscured subject has the same digest as the subject it replaces, so
+    /// the resulting envelope has the same digest as it would if the assertion
+    /// had been added before the subject was obscured, and signatures on it
+    /// cover the original subject. The assertion is added in the clear.
+    ///
+    /// [`Envelope::add_assertion`] also accepts obscured subjects, but adding
+    /// assertions to an elided subject is more often a mistake than intended,
+    /// and [`Envelope::lint`] reports envelopes where it occurs. Use this
+    /// method to make the intent explicit.
+    ///
+    /// Returns an error if the subject of the envelope is not obscured.
+    pub fn add_assertion_to_obscured(&self, predicate: impl EnvelopeEncodable, object: impl EnvelopeEncodable) -> Result<Self> {
+        if !self.is_subject_obscured() {
+            bail!(EnvelopeError::NotObscured);
+        }
+        self.add_assertion_envelope(Self::new_assertion(predicate, object))
+    }
+}
+
 /// Support for adding conditional assertions.
 impl Envelope {
     /// If the condition is true, returns the result of adding the given assertion to the envelope.
@@ -212,4 +251,38 @@ impl Envelope {
     pub fn replace_subject(&self, subject: Self) -> Self {
         self.assertions().into_iter().fold(subject, |e, a| e.add_assertion_envelope(a).unwrap())
     }
+
+    /// Returns a new envelope with its subject replaced by the provided one,
+    /// refusing to do so if the new subject's digest differs from the existing
+    /// subject's digest.
+    ///
+    /// A subject with the same digest is semantically equivalent to the
+    /// original (for example, an elided, encrypted, or compressed variant of
+    /// it), so the replacement preserves the envelope's digest and any proofs
+    /// or signatures that depend on it. If `force` is `true`, the replacement
+    /// is performed regardless.
+    ///
+    /// Returns `EnvelopeError::SubjectDigestMismatch` if the digests differ and
+    /// `force` is `false`.
+    pub fn replace_subject_checked(&self, subject: Self, force: bool) -> Result<Self> {
+        if !force && subject.digest() != self.subject().digest() {
+            bail!(EnvelopeError::SubjectDigestMismatch);
+        }
+        Ok(self.replace_subject(subject))
+    }
+
+    /// Returns a new envelope with its subject replaced by the provided one,
+    /// keeping only those assertions for which `keep` returns `true`.
+    ///
+    /// This is useful when some assertions are bound to the old subject (for
+    /// example, `'signed'` assertions) and would be invalid on the new one.
+    pub fn replace_subject_keeping_assertions<F>(&self, subject: Self, keep: F) -> Self
+    where
+        F: Fn(&Self) -> bool,
+    {
+        self.assertions()
+            .into_iter()
+            .filter(|a| keep(a))
+            .fold(subject, |e, a| e.add_assertion_envelope(a).unwrap())
+    }
 }
@@ -0,0 +1,52 @@
+use anyhow::{bail, Result};
+
+use crate::EnvelopeError;
+
+/// The optional features of this crate, with whether each was enabled when it
+/// was compiled.
+const FEATURES: &[(&str, bool)] = &[
+    ("anonymize", cfg!(feature = "anonymize")),
+    ("attachment", cfg!(feature = "attachment")),
+    ("claims", cfg!(feature = "claims")),
+    ("compress", cfg!(feature = "compress")),
+    ("encrypt", cfg!(feature = "encrypt")),
+    ("expression", cfg!(feature = "expression")),
+    ("ffi", cfg!(feature = "ffi")),
+    ("fixtures", cfg!(feature = "fixtures")),
+    ("known_value", cfg!(feature = "known_value")),
+    ("legacy", cfg!(feature = "legacy")),
+    ("mmap", cfg!(feature = "mmap")),
+    ("multithreaded", cfg!(feature = "multithreaded")),
+    ("proof", cfg!(feature = "proof")),
+    ("provenance", cfg!(feature = "provenance")),
+    ("rdf", cfg!(feature = "rdf")),
+    ("recipient", cfg!(feature = "recipient")),
+    ("salt", cfg!(feature = "salt")),
+    ("signature", cfg!(feature = "signature")),
+    ("ssh", cfg!(feature = "ssh")),
+    ("sskr", cfg!(feature = "sskr")),
+    ("types", cfg!(feature = "types")),
+];
+
+/// Returns the names of the features this crate was compiled with, such as
+/// `"signature"` or `"compress"`, in alphabetical order.
+///
+/// Applications, and consumers of the crate through FFI, can use this to
+/// adapt to the extensions available at runtime.
+pub fn capabilities() -> Vec<&'static str> {
+    FEATURES.iter().filter(|(_, enabled)| *enabled).map(|(name, _)| *name).collect()
+}
+
+/// Returns `true` if this crate was compiled with the feature `name`.
+pub fn has_capability(name: &str) -> bool {
+    FEATURES.iter().any(|(feature, enabled)| *feature == name && *enabled)
+}
+
+/// Returns an error naming the feature `name` if this crate was compiled
+/// without it, so that applications can explain which extension is missing.
+pub fn require_capability(name: &str) -> Result<()> {
+    if !has_capability(name) {
+        bail!(EnvelopeError::MissingCapability(name.to_string()));
+    }
+    Ok(())
+}
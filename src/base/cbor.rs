@@ -6,11 +6,20 @@ use bc_components::EncryptedMessage;
 #[cfg(feature = "compress")]
 use bc_components::Compressed;
 use crate::{Assertion, Envelope};
+use crate::EnvelopeError;
 #[cfg(feature = "known_value")]
 use crate::extension::KnownValue;
 
 use super::envelope::EnvelopeCase;
 
+/// The deepest nesting of elements that decoding an envelope from CBOR
+/// accepts, unless a different limit is given with
+/// [`IngestLimits::with_max_depth`](crate::IngestLimits::with_max_depth).
+///
+/// Envelopes are decoded recursively, so this bounds the stack used by
+/// hostile data no matter how it reached the decoder.
+pub const MAX_DECODE_DEPTH: usize = 256;
+
 /// Support for CBOR encoding and decoding of ``Envelope``.
 ///
 /// All envelopes are tagged with the `envelope` tag. Within that tag, each of
@@ -70,6 +79,32 @@ impl CBORTaggedEncodable for Envelope {
 
 impl CBORTaggedDecodable for Envelope {
     fn from_untagged_cbor(cbor: CBOR) -> Result<Self> {
+        Self::decode(cbor, false, MAX_DECODE_DEPTH)
+    }
+}
+
+impl Envelope {
+    /// Decodes an envelope from untagged CBOR.
+    ///
+    /// If `lenient` is `true`, elements this build does not support are
+    /// decoded instead of failing:
+    ///
+    /// * Encrypted and compressed elements and known values are decoded as
+    ///   elided elements with their original digests when the corresponding
+    ///   feature is disabled, so the digests of their ancestors, and any
+    ///   signatures on them, are unchanged.
+    /// * Elements with unrecognized tags, such as those introduced by later
+    ///   versions of the format, are decoded as opaque leaves containing the
+    ///   tagged CBOR. Their digests, and those of their ancestors, differ from
+    ///   the ones computed by the encoder.
+    ///
+    /// Returns an error if elements are nested more than `max_depth` levels
+    /// below this one.
+    pub(crate) fn decode(cbor: CBOR, lenient: bool, max_depth: usize) -> Result<Self> {
+        let child_depth = || match max_depth.checked_sub(1) {
+            Some(child_depth) => Ok(child_depth),
+            None => Err(Error::from(EnvelopeError::TooDeeplyNested(max_depth))),
+        };
         match cbor.as_case() {
             CBORCase::Tagged(tag, item) => {
                 match tag.value() {
@@ -77,7 +112,7 @@ impl CBORTaggedDecodable for Envelope {
                         Ok(Self::new_leaf(item.clone()))
                     },
                     tags::TAG_ENVELOPE => {
-                        let envelope = Envelope::try_from(cbor)?;
+                        let envelope = Self::decode(item.clone(), lenient, child_depth()?)?;
                         Ok(Self::new_wrapped(envelope))
                     },
                     #[cfg(feature = "encrypt")]
@@ -86,12 +121,29 @@ impl CBORTaggedDecodable for Envelope {
                         let envelope = Self::new_with_encrypted(encrypted)?;
                         Ok(envelope)
                     },
+                    #[cfg(not(feature = "encrypt"))]
+                    tags::TAG_ENCRYPTED if lenient => {
+                        let encrypted = bc_components::EncryptedMessage::from_untagged_cbor(item.clone())?;
+                        let Some(digest) = encrypted.opt_digest() else {
+                            bail!(EnvelopeError::MissingDigest);
+                        };
+                        Ok(Self::new_elided(digest))
+                    },
                     #[cfg(feature = "compress")]
                     tags::TAG_COMPRESSED => {
                         let compressed = Compressed::from_untagged_cbor(item.clone())?;
                         let envelope = Self::new_with_compressed(compressed)?;
                         Ok(envelope)
                     },
+                    #[cfg(not(feature = "compress"))]
+                    tags::TAG_COMPRESSED if lenient => {
+                        let compressed = bc_components::Compressed::from_untagged_cbor(item.clone())?;
+                        let Some(digest) = compressed.digest_ref_opt() else {
+                            bail!(EnvelopeError::MissingDigest);
+                        };
+                        Ok(Self::new_elided(digest.clone()))
+                    },
+                    _ if lenient => Ok(Self::new_leaf(cbor.clone())),
                     _ => bail!("unknown envelope tag: {}", tag.value()),
                 }
             }
@@ -102,16 +154,17 @@ impl CBORTaggedDecodable for Envelope {
                 if elements.len() < 2 {
                     bail!("node must have at least two elements")
                 }
-                let subject = Self::from_untagged_cbor(elements[0].clone())?;
+                let child_depth = child_depth()?;
+                let subject = Self::decode(elements[0].clone(), lenient, child_depth)?;
                 let assertions: Vec<Envelope> = elements[1..]
                     .iter()
                     .cloned()
-                    .map(Self::from_untagged_cbor)
+                    .map(|element| Self::decode(element, lenient, child_depth))
                     .collect::<Result<Vec<Self>, Error>>()?;
                 Ok(Self::new_with_assertions(subject, assertions)?)
             }
-            CBORCase::Map(_) => {
-                let assertion = Assertion::try_from(cbor)?;
+            CBORCase::Map(map) => {
+                let assertion = Assertion::decode(map.clone(), lenient, child_depth()?)?;
                 Ok(Self::new_with_assertion(assertion))
             }
             #[cfg(feature = "known_value")]
@@ -119,6 +172,11 @@ impl CBORTaggedDecodable for Envelope {
                 let known_value = KnownValue::new(*value);
                 Ok(Self::new_with_known_value(known_value))
             }
+            #[cfg(not(feature = "known_value"))]
+            CBORCase::Unsigned(value) if lenient => {
+                let known_value = CBOR::to_tagged_value(tags::TAG_KNOWN_VALUE, *value);
+                Ok(Self::new_elided(Digest::from_image(known_value.to_cbor_data())))
+            }
             _ => bail!("invalid envelope"),
         }
     }
@@ -0,0 +1,193 @@
+use std::io::{self, Write};
+
+use dcbor::prelude::*;
+
+use crate::{Envelope, IngestError, IngestLimits};
+
+use super::ingest::{decode_cbor, item_len};
+
+/// The signature that begins an indexed CBOR sequence: tag 55800 applied to
+/// the byte string `"BOR"`, as described in RFC 9277.
+const SEQ_SIGNATURE: [u8; 7] = [0xd9, 0xd9, 0xf8, 0x43, 0x42, 0x4f, 0x52];
+
+/// Writes envelopes to a CBOR sequence (RFC 8742), such as a `.cborseq` file.
+///
+/// A CBOR sequence is simply the tagged CBOR of each envelope, one after
+/// another, so envelopes can be appended to an existing sequence at any
+/// time. To append to an existing file, open it for appending and create the
+/// writer with [`EnvelopeSeqWriter::new_at`] so that offsets continue from the
+/// end of the file.
+///
+/// For random access to a sequence that is written all at once, see
+/// [`write_indexed_envelope_seq`].
+#[derive(Debug)]
+pub struct EnvelopeSeqWriter<W: Write> {
+    writer: W,
+    offset: u64,
+}
+
+impl<W: Write> EnvelopeSeqWriter<W> {
+    /// Creates a writer for a new sequence.
+    pub fn new(writer: W) -> Self {
+        Self::new_at(writer, 0)
+    }
+
+    /// Creates a writer that appends to a sequence already `offset` bytes
+    /// long.
+    pub fn new_at(writer: W, offset: u64) -> Self {
+        Self { writer, offset }
+    }
+
+    /// Appends an envelope, returning its offset in the sequence.
+    pub fn append(&mut self, envelope: &Envelope) -> io::Result<u64> {
+        let data = envelope.tagged_cbor_data();
+        self.writer.write_all(&data)?;
+        let offset = self.offset;
+        self.offset += data.len() as u64;
+        Ok(offset)
+    }
+
+    /// The length of the sequence written so far, which is the offset of the
+    /// next envelope.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Writes envelopes as an indexed CBOR sequence, returning their offsets.
+///
+/// The sequence begins with the RFC 9277 CBOR sequence signature and an array
+/// of the offsets of the envelopes, so that [`EnvelopeSeqReader`] can find
+/// each envelope without reading the ones before it. Offsets are measured
+/// from the end of the array, so they are the same as those of a plain
+/// sequence of the same envelopes.
+pub fn write_indexed_envelope_seq(writer: &mut impl Write, envelopes: &[Envelope]) -> io::Result<Vec<u64>> {
+    let items: Vec<Vec<u8>> = envelopes.iter().map(|envelope| envelope.tagged_cbor_data()).collect();
+    let offsets: Vec<u64> = items
+        .iter()
+        .scan(0u64, |offset, item| {
+            let start = *offset;
+            *offset += item.len() as u64;
+            Some(start)
+        })
+        .collect();
+    writer.write_all(&SEQ_SIGNATURE)?;
+    writer.write_all(&CBOR::from(offsets.clone()).to_cbor_data())?;
+    for item in &items {
+        writer.write_all(item)?;
+    }
+    Ok(offsets)
+}
+
+/// Reads envelopes from a CBOR sequence, such as a `.cborseq` file, either
+/// one after another or by offset.
+///
+/// Both plain sequences and indexed sequences written by
+/// [`write_indexed_envelope_seq`] are accepted. Every envelope is checked
+/// against the reader's [`IngestLimits`], so sequences from untrusted
+/// sources can be read safely. Large files can be read without loading them
+/// into memory by memory-mapping them.
+#[derive(Debug, Clone)]
+pub struct EnvelopeSeqReader<'a> {
+    body: &'a [u8],
+    index: Option<Vec<u64>>,
+    limits: IngestLimits,
+}
+
+impl<'a> EnvelopeSeqReader<'a> {
+    /// Creates a reader for the sequence in `data`, reading its index if it
+    /// has one.
+    pub fn new(data: &'a [u8], limits: &IngestLimits) -> Result<Self, IngestError> {
+        let Some(rest) = data.strip_prefix(&SEQ_SIGNATURE) else {
+            return Ok(Self { body: data, index: None, limits: *limits });
+        };
+        let index_len = item_len(rest, limits.max_depth())?;
+        let invalid_index = || IngestError::MalformedCBOR {
+            offset: Some(SEQ_SIGNATURE.len()),
+            message: "invalid sequence index".to_string(),
+        };
+        let index = decode_cbor(&rest[..index_len], limits)?
+            .try_into_array()
+            .map_err(|_| invalid_index())?
+            .into_iter()
+            .map(|offset| u64::try_from(offset).map_err(|_| invalid_index()))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { body: &rest[index_len..], index: Some(index), limits: *limits })
+    }
+
+    /// The offsets of the envelopes, if the sequence is indexed.
+    pub fn index(&self) -> Option<&[u64]> {
+        self.index.as_deref()
+    }
+
+    /// Returns the envelope at `offset`, which is measured from the start of
+    /// the sequence, or from the end of the index of an indexed sequence.
+    pub fn get(&self, offset: u64) -> Result<Envelope, IngestError> {
+        self.get_with_len(offset).map(|(envelope, _)| envelope)
+    }
+
+    /// Returns an iterator over the envelopes in the sequence with their
+    /// offsets.
+    ///
+    /// The iterator ends after the first envelope that cannot be read, since
+    /// the remainder of the sequence cannot be located.
+    pub fn iter(&self) -> EnvelopeSeqIter<'a, '_> {
+        EnvelopeSeqIter { reader: self, offset: Some(0) }
+    }
+
+    fn get_with_len(&self, offset: u64) -> Result<(Envelope, usize), IngestError> {
+        let data = usize::try_from(offset)
+            .ok()
+            .and_then(|offset| self.body.get(offset..))
+            .ok_or_else(|| IngestError::MalformedCBOR {
+                offset: None,
+                message: format!("offset {} is past the end of the sequence", offset),
+            })?;
+        let len = item_len(data, self.limits.max_depth())?;
+        let envelope = Envelope::ingest_cbor_data(&data[..len], &self.limits)?;
+        Ok((envelope, len))
+    }
+}
+
+impl<'a, 'r> IntoIterator for &'r EnvelopeSeqReader<'a> {
+    type Item = Result<(u64, Envelope), IngestError>;
+    type IntoIter = EnvelopeSeqIter<'a, 'r>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the envelopes of a CBOR sequence, returned by
+/// [`EnvelopeSeqReader::iter`].
+#[derive(Debug, Clone)]
+pub struct EnvelopeSeqIter<'a, 'r> {
+    reader: &'r EnvelopeSeqReader<'a>,
+    offset: Option<u64>,
+}
+
+impl Iterator for EnvelopeSeqIter<'_, '_> {
+    type Item = Result<(u64, Envelope), IngestError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let offset = self.offset?;
+        if offset as usize >= self.reader.body.len() {
+            self.offset = None;
+            return None;
+        }
+        match self.reader.get_with_len(offset) {
+            Ok((envelope, len)) => {
+                self.offset = Some(offset + len as u64);
+                Some(Ok((offset, envelope)))
+            }
+            Err(error) => {
+                self.offset = None;
+                Some(Err(error))
+            }
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use std::ops::RangeBounds;
+
+use anyhow::{bail, Result};
+use dcbor::Date;
+
+use crate::{Envelope, EnvelopeEncodable, EnvelopeError};
+
+/// Support for assertions whose objects are dates.
+///
+/// Dates are compared as instants in UTC, so dates parsed from strings with
+/// different time zone offsets compare correctly.
+impl Envelope {
+    /// Returns a new envelope with an assertion whose object is `date`.
+    pub fn add_date_assertion(&self, predicate: impl EnvelopeEncodable, date: Date) -> Self {
+        self.add_assertion(predicate, date)
+    }
+
+    /// Returns the dates that are the objects of the assertions with the
+    /// given predicate, earliest first.
+    ///
+    /// Returns an error if any of the objects is not a date.
+    pub fn dates_for_predicate(&self, predicate: impl EnvelopeEncodable) -> Result<Vec<Date>> {
+        let mut dates = self
+            .assertions_with_predicate(predicate)
+            .iter()
+            .map(|assertion| match assertion.subject().as_object() {
+                Some(object) => object.extract_subject::<Date>(),
+                None => bail!(EnvelopeError::NotAssertion),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        dates.sort();
+        Ok(dates)
+    }
+
+    /// Returns the assertions with the given predicate whose objects are
+    /// dates within `range`.
+    ///
+    /// Assertions whose objects are not dates, including elided ones, are
+    /// skipped.
+    pub fn assertions_with_date_in_range(
+        &self,
+        predicate: impl EnvelopeEncodable,
+        range: &impl RangeBounds<Date>,
+    ) -> Vec<Self> {
+        self.assertions_with_predicate(predicate)
+            .into_iter()
+            .filter(|assertion| {
+                assertion
+                    .subject()
+                    .as_object()
+                    .and_then(|object| object.extract_subject::<Date>().ok())
+                    .is_some_and(|date| range.contains(&date))
+            })
+            .collect()
+    }
+
+    /// Returns `true` if the envelope has an assertion with the given
+    /// predicate whose object is a date within `range`.
+    pub fn has_date_in_range(&self, predicate: impl EnvelopeEncodable, range: &impl RangeBounds<Date>) -> bool {
+        !self.assertions_with_date_in_range(predicate, range).is_empty()
+    }
+}
+
+/// Returns the envelopes that have an assertion with the given predicate
+/// whose object is a date within `range`, such as the credentials in a
+/// collection that expire this month.
+pub fn envelopes_with_date_in_range<'a, R>(
+    envelopes: impl IntoIterator<Item = &'a Envelope> + 'a,
+    predicate: impl EnvelopeEncodable,
+    range: R,
+) -> impl Iterator<Item = &'a Envelope> + 'a
+where
+    R: RangeBounds<Date> + 'a,
+{
+    let predicate = predicate.into_envelope();
+    envelopes
+        .into_iter()
+        .filter(move |envelope| envelope.has_date_in_range(predicate.clone(), &range))
+}
@@ -1,4 +1,4 @@
-use std::{collections::HashSet, cell::RefCell, borrow::Cow};
+use std::{collections::{HashMap, HashSet}, cell::RefCell, borrow::Cow};
 
 use bc_components::{Digest, DigestProvider};
 
@@ -30,6 +30,11 @@ impl DigestProvider for Envelope {
     }
 }
 
+/// The ancestors of an element within an envelope, starting with the root.
+///
+/// Returned by [`Envelope::find_by_digest`].
+pub type EnvelopePath = Vec<Envelope>;
+
 /// Support for working with the digest tree of an `Envelope`.
 impl Envelope {
     /// Returns the set of digests contained in the envelope's elements, down to the
@@ -50,6 +55,9 @@ impl Envelope {
     ///
     /// * A set of digests down to `levelLimit`.
     pub fn digests(&self, level_limit: usize) -> HashSet<Digest> {
+        if level_limit == usize::MAX {
+            return self.deep_digests_ref().clone();
+        }
         let result = RefCell::new(HashSet::new());
         let visitor = |envelope: Self, level: usize, _: EdgeType, _: Option<&()>| -> _ {
             if level < level_limit {
@@ -64,8 +72,132 @@ impl Envelope {
     }
 
     /// Returns the set of all digests in the envelope.
+    ///
+    /// The set is computed on first use and cached on this envelope only, so
+    /// querying an envelope does not store a set for each of its elements.
+    /// Subtrees that were themselves queried earlier, and so already have a
+    /// cached set, are not walked again.
     pub fn deep_digests(&self) -> HashSet<Digest> {
-        self.digests(usize::MAX)
+        self.deep_digests_ref().clone()
+    }
+
+    /// Returns a reference to the cached set of all digests in the envelope,
+    /// computing it if necessary.
+    pub fn deep_digests_ref(&self) -> &HashSet<Digest> {
+        self.caches().deep_digests.get_or_init(|| {
+            let mut result = HashSet::new();
+            let mut stack = vec![self.clone()];
+            while let Some(envelope) = stack.pop() {
+                if let Some(cached) = envelope.allocated_caches().and_then(|caches| caches.deep_digests.get()) {
+                    result.extend(cached.iter().cloned());
+                    continue;
+                }
+                result.insert(envelope.digest().into_owned());
+                match envelope.case() {
+                    EnvelopeCase::Node { subject, assertions, .. } => {
+                        stack.push(subject.clone());
+                        stack.extend(assertions.iter().cloned());
+                    }
+                    EnvelopeCase::Wrapped { envelope, .. } => stack.push(envelope.clone()),
+                    EnvelopeCase::Assertion(assertion) => {
+                        stack.push(assertion.predicate());
+                        stack.push(assertion.object());
+                    }
+                    _ => {}
+                }
+            }
+            result
+        })
+    }
+
+    /// Returns the element with the given digest, and the path to it.
+    ///
+    /// The path contains the ancestors of the element, starting with this
+    /// envelope and ending with the element's parent, so it is empty if the
+    /// digest is this envelope's own. If more than one element has the digest,
+    /// the first one encountered in a depth-first walk is returned.
+    ///
+    /// The first call builds an index of every element in the envelope, which
+    /// is cached, so later lookups do not need to walk the tree.
+    pub fn find_by_digest(&self, digest: &Digest) -> Option<(Self, EnvelopePath)> {
+        let steps = self.digest_index().get(digest)?;
+        let mut path = Vec::with_capacity(steps.len());
+        let mut element = self.clone();
+        for &step in steps {
+            let child = element.child_at(step)?;
+            path.push(element);
+            element = child;
+        }
+        Some((element, path))
+    }
+
+    /// Returns the path to the element with the given digest as a string of
+    /// edges separated by slashes, such as `subject/assertion[2]/object`, or
+    /// `None` if there is no such element.
+    ///
+    /// Each component is the [`EdgeType::path_component`] of an edge from the
+    /// envelope down to the element, so the path is empty if the digest is
+    /// this envelope's own. Assertions are numbered in the order of their
+    /// digests, so a path is stable for a given envelope, and can be used in
+    /// error messages and logs to say where in an envelope something was
+    /// found.
+    pub fn path_to(&self, digest: &Digest) -> Option<String> {
+        let steps = self.digest_index().get(digest)?;
+        let mut components = Vec::with_capacity(steps.len());
+        let mut element = self.clone();
+        for &step in steps {
+            let edge = match element.case() {
+                EnvelopeCase::Node { .. } if step == 0 => EdgeType::Subject,
+                EnvelopeCase::Node { .. } => EdgeType::Assertion,
+                EnvelopeCase::Wrapped { .. } => EdgeType::Wrapped,
+                EnvelopeCase::Assertion(_) if step == 0 => EdgeType::Predicate,
+                _ => EdgeType::Object,
+            };
+            components.extend(edge.path_component(step.saturating_sub(1)));
+            element = element.child_at(step)?;
+        }
+        Some(components.join("/"))
+    }
+
+    fn digest_index(&self) -> &HashMap<Digest, Vec<usize>> {
+        self.caches().digest_index.get_or_init(|| {
+            let mut index = HashMap::new();
+            self.index_digests(&mut Vec::new(), &mut index);
+            index
+        })
+    }
+
+    fn index_digests(&self, steps: &mut Vec<usize>, index: &mut HashMap<Digest, Vec<usize>>) {
+        index.entry(self.digest().into_owned()).or_insert_with(|| steps.clone());
+        let mut step = 0;
+        while let Some(child) = self.child_at(step) {
+            steps.push(step);
+            child.index_digests(steps, index);
+            steps.pop();
+            step += 1;
+        }
+    }
+
+    /// The child element at `step`: for a node, the subject followed by the
+    /// assertions; for a wrapped envelope, the wrapped envelope; and for an
+    /// assertion, the predicate followed by the object.
+    fn child_at(&self, step: usize) -> Option<Self> {
+        match self.case() {
+            EnvelopeCase::Node { subject, assertions, .. } => {
+                if step == 0 {
+                    Some(subject.clone())
+                } else {
+                    assertions.get(step - 1).cloned()
+                }
+            }
+            EnvelopeCase::Wrapped { envelope, .. } if step == 0 => Some(envelope.clone()),
+            EnvelopeCase::Assertion(assertion) => match step {
+                0 => Some(assertion.predicate()),
+                1 => Some(assertion.object()),
+                _ => None,
+            },
+            _ => None,
+        }
     }
 
     /// Returns the set of all digests in the envelope, down to its second level.
@@ -96,7 +228,16 @@ impl Envelope {
     /// equivalent. It is recommended that envelopes be compared for structural equality
     /// by calling `isIdentical(to:)` as this short-circuits to `false` in cases where
     /// the compared envelopes are not semantically equivalent.
+    ///
+    /// The digest is computed on first use and cached on this envelope.
     pub fn structural_digest(&self) -> Digest {
+        self.caches()
+            .structural_digest
+            .get_or_init(|| self.compute_structural_digest())
+            .clone()
+    }
+
+    fn compute_structural_digest(&self) -> Digest {
         let image = RefCell::new(Vec::new());
         let visitor = |envelope: Self, _: usize, _: EdgeType, _: Option<&()>| -> _ {
             // Add a discriminator to the image for the obscured cases.
@@ -138,6 +279,116 @@ impl Envelope {
         }
         self.structural_digest() == other.structural_digest()
     }
+
+    /// Explains why two envelopes are not semantically equivalent.
+    ///
+    /// Returns `None` if the envelopes are equivalent. Otherwise, descends
+    /// both envelopes in parallel to the first position at which they diverge
+    /// and returns a human-readable description of that position, including
+    /// the path to it, the case and digest of each side, and a snippet of each
+    /// side's formatted form. Elided, encrypted, or compressed elements are
+    /// treated as equivalent to their unobscured forms.
+    pub fn equivalence_failure_hint(&self, other: &Self) -> Option<String> {
+        self.first_divergence(other, "envelope".to_string())
+    }
+
+    fn first_divergence(&self, other: &Self, path: String) -> Option<String> {
+        if self.is_equivalent_to(other) {
+            return None;
+        }
+        match (self.case(), other.case()) {
+            (
+                EnvelopeCase::Node { subject: left_subject, assertions: left_assertions, .. },
+                EnvelopeCase::Node { subject: right_subject, assertions: right_assertions, .. },
+            ) => {
+                if !left_subject.is_equivalent_to(right_subject) {
+                    return left_subject.first_divergence(right_subject, format!("{}.subject", path));
+                }
+                let left_digests: HashSet<Digest> = left_assertions.iter().map(|a| a.digest().into_owned()).collect();
+                let right_digests: HashSet<Digest> = right_assertions.iter().map(|a| a.digest().into_owned()).collect();
+                let left_only: Vec<&Self> = left_assertions.iter().filter(|a| !right_digests.contains(a.digest().as_ref())).collect();
+                let right_only: Vec<&Self> = right_assertions.iter().filter(|a| !left_digests.contains(a.digest().as_ref())).collect();
+                for left in &left_only {
+                    let Some(predicate) = left.as_predicate() else {
+                        continue;
+                    };
+                    let right = right_only.iter().find(|right| {
+                        right.as_predicate().is_some_and(|p| p.is_equivalent_to(&predicate))
+                    });
+                    if let Some(right) = right {
+                        let path = format!("{}.assertion({})", path, predicate.divergence_snippet());
+                        return left.first_divergence(right, path);
+                    }
+                }
+                if let Some(left) = left_only.first() {
+                    return Some(format!("at {}: assertion only in left: {}", path, left.divergence_description()));
+                }
+                if let Some(right) = right_only.first() {
+                    return Some(format!("at {}: assertion only in right: {}", path, right.divergence_description()));
+                }
+            },
+            (EnvelopeCase::Wrapped { envelope: left, .. }, EnvelopeCase::Wrapped { envelope: right, .. }) => {
+                return left.first_divergence(right, format!("{}.wrapped", path));
+            },
+            (EnvelopeCase::Assertion(left), EnvelopeCase::Assertion(right)) => {
+                if !left.predicate().is_equivalent_to(&right.predicate()) {
+                    return left.predicate().first_divergence(&right.predicate(), format!("{}.predicate", path));
+                }
+                return left.object().first_divergence(&right.object(), format!("{}.object", path));
+            },
+            _ => {},
+        }
+        Some(format!(
+            "at {}: left is {}, right is {}",
+            path,
+            self.divergence_description(),
+            other.divergence_description()
+        ))
+    }
+
+    fn divergence_description(&self) -> String {
+        let case = match self.case() {
+            EnvelopeCase::Node { .. } => "node",
+            EnvelopeCase::Leaf { .. } => "leaf",
+            EnvelopeCase::Wrapped { .. } => "wrapped",
+            EnvelopeCase::Assertion(_) => "assertion",
+            EnvelopeCase::Elided(_) => "elided",
+            #[cfg(feature = "known_value")]
+            EnvelopeCase::KnownValue { .. } => "known value",
+            #[cfg(feature = "encrypt")]
+            EnvelopeCase::Encrypted(_) => "encrypted",
+            #[cfg(feature = "compress")]
+            EnvelopeCase::Compressed(_) => "compressed",
+        };
+        format!("{} {} `{}`", case, self.digest().short_description(), self.divergence_snippet())
+    }
+
+    fn divergence_snippet(&self) -> String {
+        const MAX_LENGTH: usize = 60;
+        let formatted = self.format_flat();
+        if formatted.chars().count() <= MAX_LENGTH {
+            formatted
+        } else {
+            formatted.chars().take(MAX_LENGTH - 1).chain(std::iter::once('…')).collect()
+        }
+    }
+}
+
+/// Asserts that two envelopes are semantically equivalent.
+///
+/// On failure, panics with the explanation returned by
+/// [`Envelope::equivalence_failure_hint`], identifying the first position at
+/// which the envelopes diverge.
+#[macro_export]
+macro_rules! assert_equivalent {
+    ($left:expr, $right:expr $(,)?) => {
+        {
+        let (left, right): (&$crate::Envelope, &$crate::Envelope) = (&$left, &$right);
+        if let Some(hint) = left.equivalence_failure_hint(right) {
+            panic!("assertion failed: envelopes are not equivalent\n{}", hint);
+        }
+        }
+    };
 }
 
 /// Implement `PartialEq` for `Envelope` to allow for structural comparison.
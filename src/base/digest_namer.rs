@@ -0,0 +1,80 @@
+use bc_components::Digest;
+
+/// Gives digests memorable names, which are shown next to them in tree
+/// notation.
+///
+/// Set a namer with [`FormatContext::set_digest_namer`](crate::FormatContext::set_digest_namer).
+/// Names are only an aid to comparing trees by eye: different digests may be
+/// given the same name, so names must never be used to identify elements.
+pub trait DigestNamer: Send + Sync {
+    /// The name of `digest`, or `None` to show the digest alone.
+    fn name(&self, digest: &Digest) -> Option<String>;
+}
+
+/// A [`DigestNamer`] that names each digest with an adjective and an animal
+/// chosen by its first two bytes, such as `lucid-otter`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Petnames;
+
+const ADJECTIVES: [&str; 32] = [
+    "agile", "bold", "brave", "calm", "clever", "cosmic", "crisp", "daring",
+    "eager", "fancy", "gentle", "happy", "humble", "jolly", "keen", "lively",
+    "lucid", "lucky", "merry", "mighty", "noble", "proud", "quick", "quiet",
+    "rapid", "shiny", "sleek", "snowy", "sunny", "swift", "vivid", "witty",
+];
+
+const ANIMALS: [&str; 32] = [
+    "badger", "bear", "beaver", "bison", "camel", "crane", "deer", "dolphin",
+    "eagle", "falcon", "ferret", "finch", "fox", "gecko", "heron", "ibis",
+    "jaguar", "koala", "lemur", "lynx", "moose", "newt", "otter", "owl",
+    "panda", "puffin", "quail", "raven", "seal", "tiger", "walrus", "yak",
+];
+
+impl DigestNamer for Petnames {
+    fn name(&self, digest: &Digest) -> Option<String> {
+        let data = digest.data();
+        let adjective = ADJECTIVES[data[0] as usize % ADJECTIVES.len()];
+        let animal = ANIMALS[data[1] as usize % ANIMALS.len()];
+        Some(format!("{}-{}", adjective, animal))
+    }
+}
+
+/// How digests are shown in tree notation and error messages.
+///
+/// Set with [`FormatContext::set_digest_display_format`](crate::FormatContext::set_digest_display_format).
+/// All formats other than [`DigestDisplayFormat::Full`] show only the first
+/// four bytes of the digest, which is enough to compare digests by eye or
+/// read them aloud, but not to identify elements.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum DigestDisplayFormat {
+    /// The first four bytes in hexadecimal, such as `8cc96cdb`.
+    #[default]
+    Short,
+    /// All 32 bytes in hexadecimal.
+    Full,
+    /// The first four bytes as bytewords, such as `luck solo jazz ugly`.
+    Bytewords,
+    /// The first four bytes as minimal bytewords, the first and last letters
+    /// of each word, such as `lksojzuy`.
+    BytewordsMinimal,
+    /// The first four bytes as bytemojis, such as `🟩 👖 🌺 🐹`.
+    Emoji,
+}
+
+impl DigestDisplayFormat {
+    /// Returns `digest` in this format.
+    pub fn format(&self, digest: &Digest) -> String {
+        let prefix: [u8; 4] = digest.data()[..4].try_into().unwrap();
+        match self {
+            DigestDisplayFormat::Short => digest.short_description(),
+            DigestDisplayFormat::Full => hex::encode(digest.data()),
+            DigestDisplayFormat::Bytewords => bc_ur::bytewords::identifier(&prefix),
+            DigestDisplayFormat::BytewordsMinimal => bc_ur::bytewords::identifier(&prefix)
+                .split(' ')
+                .flat_map(|word| [word.chars().next(), word.chars().last()])
+                .flatten()
+                .collect(),
+            DigestDisplayFormat::Emoji => bc_ur::bytewords::bytemoji_identifier(&prefix),
+        }
+    }
+}
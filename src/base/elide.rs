@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use anyhow::{bail, Result};
 use bc_components::{DigestProvider, Digest};
@@ -185,8 +185,28 @@ impl Envelope {
     ///
     /// - Returns: The elided envelope.
     pub fn elide_set_with_action(&self, target: &HashSet<Digest>, is_revealing: bool, action: &ObscureAction) -> Self {
+        self.elide_choosing_action(&|digest| (target.contains(digest) != is_revealing).then_some(action))
+    }
+
+    /// Returns a version of this envelope with each element whose digest is in
+    /// `actions` obscured using the corresponding action.
+    ///
+    /// This allows a single pass to elide some elements, encrypt others, and
+    /// compress still others. An element that is obscured is not descended
+    /// into, so actions for digests inside it are ignored.
+    ///
+    /// - Parameters:
+    ///   - actions: A map from the digests of the elements to obscure to the
+    ///     action (elision, encryption or compression) to perform on each.
+    ///
+    /// - Returns: The obscured envelope.
+    pub fn elide_with_action_map(&self, actions: &HashMap<Digest, ObscureAction>) -> Self {
+        self.elide_choosing_action(&|digest| actions.get(digest))
+    }
+
+    fn elide_choosing_action<'a>(&self, choose: &dyn Fn(&Digest) -> Option<&'a ObscureAction>) -> Self {
         let self_digest = self.digest().into_owned();
-        if target.contains(&self_digest) != is_revealing {
+        if let Some(action) = choose(&self_digest) {
             match action {
                 ObscureAction::Elide => self.elide(),
                 #[cfg(feature = "encrypt")]
@@ -198,22 +218,22 @@ impl Envelope {
                 ObscureAction::Compress => self.compress().unwrap(),
             }
         } else if let EnvelopeCase::Assertion(assertion) = self.case() {
-            let predicate = assertion.predicate().elide_set_with_action(target, is_revealing, action);
-            let object = assertion.object().elide_set_with_action(target, is_revealing, action);
+            let predicate = assertion.predicate().elide_choosing_action(choose);
+            let object = assertion.object().elide_choosing_action(choose);
             let elided_assertion = Assertion::new(predicate, object);
             assert!(&elided_assertion == assertion);
             Self::new_with_assertion(elided_assertion)
         } else if let EnvelopeCase::Node { subject, assertions, ..} = self.case() {
-            let elided_subject = subject.elide_set_with_action(target, is_revealing, action);
+            let elided_subject = subject.elide_choosing_action(choose);
             assert!(elided_subject.digest() == subject.digest());
             let elided_assertions = assertions.iter().map(|assertion| {
-                let elided_assertion = assertion.elide_set_with_action(target, is_revealing, action);
+                let elided_assertion = assertion.elide_choosing_action(choose);
                 assert!(elided_assertion.digest() == assertion.digest());
                 elided_assertion
             }).collect();
             Self::new_with_unchecked_assertions(elided_subject, elided_assertions)
         } else if let EnvelopeCase::Wrapped { envelope, .. } = self.case() {
-            let elided_envelope = envelope.elide_set_with_action(target, is_revealing, action);
+            let elided_envelope = envelope.elide_choosing_action(choose);
             assert!(elided_envelope.digest() == envelope.digest());
             Self::new_wrapped(elided_envelope)
         } else {
@@ -1,3 +1,5 @@
+use std::{collections::{HashMap, HashSet}, sync::OnceLock};
+
 use anyhow::{bail, Result};
 use bc_components::{Digest, DigestProvider};
 #[cfg(feature = "encrypt")]
@@ -10,26 +12,120 @@ use crate::{base::Assertion, EnvelopeEncodable, EnvelopeError};
 use crate::extension::KnownValue;
 
 #[cfg(feature = "multithreaded")]
-use std::sync::Arc as RefCounted;
+pub(crate) use std::sync::Arc as RefCounted;
 
 #[cfg(not(feature = "multithreaded"))]
-use std::rc::Rc as RefCounted;
+pub(crate) use std::rc::Rc as RefCounted;
 
 /// A flexible container for structured data.
 ///
 /// Envelopes are immutable. You create "mutations" by creating new envelopes from old envelopes.
 #[derive(Debug, Clone)]
-pub struct Envelope(RefCounted<EnvelopeCase>);
+pub struct Envelope(RefCounted<EnvelopeStorage>);
+
+/// The shared storage behind an `Envelope`.
+///
+/// Because envelopes are immutable, values derived from the whole subtree
+/// (such as the set of all digests it contains) can be computed once and
+/// cached alongside the case, and are shared by every clone of the envelope
+/// and every envelope that contains it.
+struct EnvelopeStorage {
+    case: EnvelopeCase,
+    caches: OnceLock<Box<EnvelopeCaches>>,
+}
+
+/// The values cached for an envelope.
+///
+/// They are allocated together when the first of them is needed, so an
+/// envelope that is never queried, such as most leaves, carries only an
+/// empty pointer.
+#[derive(Default)]
+pub(crate) struct EnvelopeCaches {
+    pub(crate) deep_digests: OnceLock<HashSet<Digest>>,
+    pub(crate) predicate_index: OnceLock<HashMap<Digest, Vec<usize>>>,
+    pub(crate) digest_index: OnceLock<HashMap<Digest, Vec<usize>>>,
+    pub(crate) structural_digest: OnceLock<Digest>,
+}
+
+impl std::fmt::Debug for EnvelopeStorage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.case.fmt(f)
+    }
+}
+
+// Dropping an envelope would otherwise drop its children recursively, which
+// could exhaust the stack on a deeply nested envelope. Instead, the children
+// that are not shared with another envelope are taken apart here, one at a
+// time, using a stack on the heap.
+impl Drop for EnvelopeStorage {
+    fn drop(&mut self) {
+        if !matches!(self.case, EnvelopeCase::Node { .. } | EnvelopeCase::Wrapped { .. } | EnvelopeCase::Assertion(_)) {
+            return;
+        }
+        let mut stack = Vec::new();
+        take_children(&mut self.case, &mut stack);
+        while let Some(child) = stack.pop() {
+            if let Some(mut storage) = RefCounted::into_inner(child.0) {
+                take_children(&mut storage.case, &mut stack);
+            }
+        }
+    }
+}
+
+/// Moves the children of `case` onto `stack`, leaving a childless case in its
+/// place.
+fn take_children(case: &mut EnvelopeCase, stack: &mut Vec<Envelope>) {
+    match std::mem::replace(case, EnvelopeCase::Elided(Digest::from_data([0; Digest::DIGEST_SIZE]))) {
+        EnvelopeCase::Node { subject, assertions, .. } => {
+            stack.push(subject);
+            stack.extend(assertions);
+        }
+        EnvelopeCase::Wrapped { envelope, .. } => stack.push(envelope),
+        EnvelopeCase::Assertion(assertion) => {
+            stack.push(assertion.predicate());
+            stack.push(assertion.object());
+        }
+        _ => {}
+    }
+}
 
 impl Envelope {
     pub fn case(&self) -> &EnvelopeCase {
-        &self.0
+        &self.0.case
+    }
+
+    /// The envelope's caches, allocating them if this is the first use.
+    pub(crate) fn caches(&self) -> &EnvelopeCaches {
+        self.0.caches.get_or_init(Box::default)
+    }
+
+    /// The envelope's caches, if they have been allocated.
+    pub(crate) fn allocated_caches(&self) -> Option<&EnvelopeCaches> {
+        self.0.caches.get().map(|caches| &**caches)
+    }
+
+    /// The address of the shared storage, which identifies a subtree that may
+    /// be referenced from more than one place.
+    pub(crate) fn storage_ptr(&self) -> *const () {
+        RefCounted::as_ptr(&self.0) as *const ()
+    }
+
+    /// The size of the shared storage allocation, including the reference
+    /// counts.
+    pub(crate) fn storage_size() -> usize {
+        std::mem::size_of::<EnvelopeStorage>() + 2 * std::mem::size_of::<usize>()
     }
 }
 
 impl From<EnvelopeCase> for Envelope {
     fn from(case: EnvelopeCase) -> Self {
-        Self(RefCounted::new(case))
+        let envelope = Self(RefCounted::new(EnvelopeStorage {
+            case,
+            caches: OnceLock::new(),
+        }));
+        #[cfg(debug_assertions)]
+        super::round_trip::check_round_trip_if_enabled(&envelope);
+        envelope
     }
 }
 
@@ -126,6 +222,117 @@ impl Envelope {
     pub fn new_assertion(predicate: impl EnvelopeEncodable, object: impl EnvelopeEncodable) -> Self {
         Self::new_with_assertion(Assertion::new(predicate, object))
     }
+
+    /// Creates an encrypted envelope from a message that was encrypted
+    /// elsewhere, such as by a hardware security module.
+    ///
+    /// The message must be the tagged CBOR of the original envelope encrypted
+    /// with the original envelope's digest as its additional authenticated
+    /// data, as [`Envelope::encrypt_subject`] does, so that the encrypted
+    /// envelope has the same digest as the original.
+    ///
+    /// Returns an error if the message's additional authenticated data is not
+    /// a digest.
+    #[cfg(feature = "encrypt")]
+    pub fn new_with_encrypted(encrypted_message: EncryptedMessage) -> Result<Self> {
+        if !encrypted_message.has_digest() {
+            bail!(EnvelopeError::MissingDigest);
+        }
+        Ok(EnvelopeCase::Encrypted(encrypted_message).into())
+    }
+
+    /// Creates a compressed envelope from data that was compressed
+    /// elsewhere.
+    ///
+    /// The data must be the compressed tagged CBOR of the original envelope,
+    /// with the original envelope's digest, as [`Envelope::compress`] does,
+    /// so that the compressed envelope has the same digest as the original.
+    ///
+    /// Returns an error if the compressed data has no digest.
+    #[cfg(feature = "compress")]
+    pub fn new_with_compressed(compressed: Compressed) -> Result<Self> {
+        if !compressed.has_digest() {
+            bail!(EnvelopeError::MissingDigest);
+        }
+        Ok(EnvelopeCase::Compressed(compressed).into())
+    }
+
+    /// Creates an envelope with a `subject` and `assertions` that are already
+    /// in canonical order, sorted by digest.
+    ///
+    /// This is intended for trusted internal paths that already hold sorted
+    /// assertion sets, and skips the sorting and validity checks performed by
+    /// [`Envelope::add_assertion_envelopes`]. In debug builds it panics if the
+    /// subject is a node, or if the assertions are not strictly sorted, with
+    /// no duplicates, or are not all assertions or obscured assertions; in
+    /// release builds the caller is responsible for this.
+    ///
+    /// If `assertions` is empty, returns `subject`.
+    pub fn with_assertions_unchecked_sorted(subject: Self, assertions: impl IntoIterator<Item = Self>) -> Self {
+        let assertions: Vec<Self> = assertions.into_iter().collect();
+        if assertions.is_empty() {
+            return subject;
+        }
+        debug_assert!(!subject.is_node(), "the subject of a node may not be a node");
+        debug_assert!(
+            assertions.windows(2).all(|pair| pair[0].digest() < pair[1].digest()),
+            "assertions are not sorted by digest, or are duplicated"
+        );
+        debug_assert!(
+            assertions.iter().all(|a| a.is_subject_assertion() || a.is_subject_obscured()),
+            "envelopes that are not assertions may not be added as assertions"
+        );
+        let mut digests = vec![subject.digest().into_owned()];
+        digests.extend(assertions.iter().map(|a| a.digest().into_owned()));
+        let digest = Digest::from_digests(&digests);
+        (EnvelopeCase::Node { subject, assertions, digest }).into()
+    }
+}
+
+/// Constructs an envelope from a literal description.
+///
+/// The subject is followed by an optional brace-delimited list of
+/// `predicate: object` assertions. Any object may itself be followed by a
+/// brace-delimited list of assertions, creating a nested envelope. Subjects,
+/// predicates, and objects may be any single token that implements
+/// `EnvelopeEncodable`, such as a literal or a variable; wrap longer
+/// expressions in parentheses.
+///
+/// ```
+/// # use bc_envelope::prelude::*;
+/// let e = envelope!("Alice" {
+///     "knows": "Bob" {
+///         "knows": "Carol",
+///     },
+///     "age": (30 + 1),
+/// });
+///
+/// let expected = Envelope::new("Alice")
+///     .add_assertion("knows", Envelope::new("Bob").add_assertion("knows", "Carol"))
+///     .add_assertion("age", 31);
+/// assert!(e.is_identical_to(&expected));
+/// ```
+#[macro_export]
+macro_rules! envelope {
+    (@assertions $envelope:ident;) => {};
+    (@assertions $envelope:ident; $predicate:tt : $object:tt { $($inner:tt)* } $(, $($rest:tt)*)?) => {
+        let $envelope = $envelope.add_assertion($predicate, $crate::envelope!($object { $($inner)* }));
+        $crate::envelope!(@assertions $envelope; $($($rest)*)?);
+    };
+    (@assertions $envelope:ident; $predicate:tt : $object:tt $(, $($rest:tt)*)?) => {
+        let $envelope = $envelope.add_assertion($predicate, $object);
+        $crate::envelope!(@assertions $envelope; $($($rest)*)?);
+    };
+    ($subject:tt { $($assertions:tt)* }) => {
+        {
+        let envelope = $crate::Envelope::new($subject);
+        $crate::envelope!(@assertions envelope; $($assertions)*);
+        envelope
+        }
+    };
+    ($subject:tt) => {
+        $crate::Envelope::new($subject)
+    };
 }
 
 /// Internal constructors
@@ -157,22 +364,6 @@ impl Envelope {
         (EnvelopeCase::KnownValue { value, digest }).into()
     }
 
-    #[cfg(feature = "encrypt")]
-    pub(crate) fn new_with_encrypted(encrypted_message: EncryptedMessage) -> Result<Self> {
-        if !encrypted_message.has_digest() {
-            bail!(EnvelopeError::MissingDigest);
-        }
-        Ok(EnvelopeCase::Encrypted(encrypted_message).into())
-    }
-
-    #[cfg(feature = "compress")]
-    pub(crate) fn new_with_compressed(compressed: Compressed) -> Result<Self> {
-        if !compressed.has_digest() {
-            bail!(EnvelopeError::MissingDigest);
-        }
-        Ok(EnvelopeCase::Compressed(compressed).into())
-    }
-
     pub(crate) fn new_elided(digest: Digest) -> Self {
         EnvelopeCase::Elided(digest).into()
     }
@@ -1,4 +1,4 @@
-use bc_components::{Digest, PrivateKeyBase, PublicKeyBase, Reference, SSKRShare, Salt, SealedMessage, Signature, ARID, URI, UUID, XID};
+use bc_components::{Digest, Nonce, PrivateKeyBase, PublicKeyBase, Reference, SSKRShare, Salt, SealedMessage, Signature, ARID, URI, UUID, XID};
 #[cfg(feature = "encrypt")]
 use bc_components::EncryptedMessage;
 #[cfg(feature = "compress")]
@@ -97,6 +97,7 @@ impl_envelope_encodable!(Signature);
 impl_envelope_encodable!(SSKRShare);
 impl_envelope_encodable!(Digest);
 impl_envelope_encodable!(ARID);
+impl_envelope_encodable!(Nonce);
 impl_envelope_encodable!(Salt);
 impl_envelope_encodable!(URI);
 impl_envelope_encodable!(UUID);
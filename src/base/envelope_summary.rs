@@ -1,4 +1,5 @@
 use anyhow::Result;
+use bc_components::tags;
 use dcbor::prelude::*;
 
 use crate::{ FormatContext, string_utils::StringUtils };
@@ -33,7 +34,16 @@ impl EnvelopeSummary for CBOR {
             }
             CBORCase::Map(_) => Ok("Map".to_string()),
             CBORCase::Simple(v) => Ok(v.to_string()),
-            CBORCase::Tagged(_, _) => Ok(self.summary_opt(context)),
+            CBORCase::Tagged(tag, item) => {
+                if tag.value() == tags::TAG_URI {
+                    if let CBORCase::Text(uri) = item.as_case() {
+                        if let Some(abbreviated) = context.abbreviate_uri(uri) {
+                            return Ok(abbreviated);
+                        }
+                    }
+                }
+                Ok(self.summary_opt(context))
+            }
         }
     }
 }
@@ -25,6 +25,9 @@ pub enum EnvelopeError {
     #[error("no assertion matches the predicate")]
     NonexistentPredicate,
 
+    #[error("no assertion has the given digest")]
+    NonexistentAssertion,
+
     #[error("cannot unwrap an envelope that was not wrapped")]
     NotWrapped,
 
@@ -34,6 +37,39 @@ pub enum EnvelopeError {
     #[error("the envelope's subject is not an assertion")]
     NotAssertion,
 
+    #[error("the envelope's subject is not obscured")]
+    NotObscured,
+
+    #[error("the new subject's digest does not match the existing subject's digest")]
+    SubjectDigestMismatch,
+
+    #[error("the envelope is not yet valid")]
+    NotYetValid,
+
+    #[error("the envelope has expired")]
+    Expired,
+
+    #[error("the date is in the future")]
+    DateInFuture,
+
+    #[error("the date is too old")]
+    DateTooOld,
+
+    #[error("this build of bc-envelope does not have the `{0}` feature")]
+    MissingCapability(String),
+
+    #[error("the table has no column named {0:?}")]
+    MissingColumn(String),
+
+    #[error("the cell in row {row} of column {column:?} does not have the column's type")]
+    InvalidCell { row: usize, column: String },
+
+    #[error("the envelope is nested more than {0} levels deep")]
+    TooDeeplyNested(usize),
+
+    #[error("the revealed value does not have the placeholder's shape")]
+    PlaceholderMismatch,
+
 
     //
     // Attachments Extension
@@ -51,6 +87,18 @@ pub enum EnvelopeError {
     #[error("abiguous attachment")]
     AmbiguousAttachment,
 
+    #[cfg(feature = "attachment")]
+    #[error("the envelope has {0} attachments, more than the policy allows")]
+    TooManyAttachments(usize),
+
+    #[cfg(feature = "attachment")]
+    #[error("an attachment payload is {0} bytes, larger than the policy allows")]
+    AttachmentTooLarge(usize),
+
+    #[cfg(feature = "attachment")]
+    #[error("attachment vendor {0:?} is not allowed by the policy")]
+    DisallowedAttachmentVendor(String),
+
 
     //
     // Compression Extension
@@ -86,6 +134,35 @@ pub enum EnvelopeError {
     #[error("the envelope's subject is not a known value")]
     NotKnownValue,
 
+    #[cfg(feature = "known_value")]
+    #[error("known value {0} is in the reserved {1} range")]
+    ReservedKnownValue(u64, crate::extension::KnownValueRange),
+
+    #[cfg(feature = "known_value")]
+    #[error("known value {0} conflicts with an existing registration")]
+    DuplicateKnownValue(u64),
+
+    #[cfg(feature = "known_value")]
+    #[error("known value {0} is not one of the expected values")]
+    UnexpectedKnownValue(u64),
+
+    #[cfg(feature = "known_value")]
+    #[error("translating known values made two assertions identical")]
+    TranslationCollision,
+
+
+    //
+    // Inclusion Proof Extension
+    //
+
+    #[cfg(feature = "proof")]
+    #[error("the envelope has no assertion commitment")]
+    MissingAssertionCommitment,
+
+    #[cfg(feature = "proof")]
+    #[error("the envelope's assertions do not match its assertion commitment")]
+    AssertionCommitmentMismatch,
+
 
     //
     // Public Key Encryption Extension
@@ -95,6 +172,18 @@ pub enum EnvelopeError {
     #[error("unknown recipient")]
     UnknownRecipient,
 
+    #[cfg(feature = "recipient")]
+    #[error("the recipient group has no members")]
+    EmptyRecipientGroup,
+
+    #[cfg(feature = "recipient")]
+    #[error("the envelope is not sealed to any recipient")]
+    NotSealed,
+
+    #[cfg(feature = "recipient")]
+    #[error("the onion route has no recipients")]
+    EmptyOnionRoute,
+
 
     //
     // Public Key Signing Extension
@@ -104,6 +193,22 @@ pub enum EnvelopeError {
     #[error("could not verify a signature")]
     UnverifiedSignature,
 
+    #[cfg(feature = "signature")]
+    #[error("too many documents were resolved while verifying a signature")]
+    ResolutionDepthExceeded,
+
+    #[cfg(feature = "signature")]
+    #[error("invalid key rotation")]
+    InvalidKeyRotation,
+
+    #[cfg(feature = "signature")]
+    #[error("the envelope is not signed")]
+    NotSigned,
+
+    #[cfg(feature = "signature")]
+    #[error("verification panicked: {0}")]
+    VerificationPanicked(String),
+
 
     //
     // SSKR Extension
@@ -113,6 +218,14 @@ pub enum EnvelopeError {
     #[error("invalid SSKR shares")]
     InvalidShares,
 
+    #[cfg(feature = "sskr")]
+    #[error("the SSKR share belongs to a different split")]
+    MismatchedShare,
+
+    #[cfg(feature = "sskr")]
+    #[error("the SSKR share conflicts with a share already received")]
+    ConflictingShare,
+
 
     //
     // Types Extension
@@ -134,4 +247,55 @@ pub enum EnvelopeError {
     #[cfg(feature = "expression")]
     #[error("unexpected response ID")]
     UnexpectedResponseID,
+
+    #[cfg(feature = "expression")]
+    #[error("the request was still processing after {0} attempts")]
+    StillProcessing(usize),
+
+    #[cfg(feature = "expression")]
+    #[error("invalid trace context")]
+    InvalidTraceContext,
+
+    #[cfg(feature = "expression")]
+    #[error("the idempotency key was used for a different request")]
+    IdempotencyKeyReused,
+
+
+    //
+    // Capabilities Extension
+    //
+
+    #[cfg(all(feature = "expression", feature = "signature"))]
+    #[error("invalid capability")]
+    InvalidCapability,
+
+    #[cfg(all(feature = "expression", feature = "signature"))]
+    #[error("the capability does not allow the expression")]
+    CapabilityDenied,
+
+
+    //
+    // Presentations
+    //
+
+    #[cfg(feature = "signature")]
+    #[error("the credential is not bound to a holder key")]
+    NoHolderBinding,
+
+    #[cfg(feature = "signature")]
+    #[error("the presentation is for a different audience")]
+    WrongAudience,
+
+    #[cfg(feature = "signature")]
+    #[error("the presentation has an unexpected nonce")]
+    WrongNonce,
+
+
+    //
+    // Provenance Extension
+    //
+
+    #[cfg(feature = "provenance")]
+    #[error("the edit journal's entries are missing, reordered, or do not chain")]
+    InvalidJournal,
 }
@@ -0,0 +1,135 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::{Error, Result};
+use bc_components::{Digest, DigestProvider};
+
+use crate::{DigestDisplayFormat, Envelope, FormatContext, GLOBAL_FORMAT_CONTEXT};
+
+static ERROR_CONTEXT_LENGTH: AtomicUsize = AtomicUsize::new(0);
+
+/// Enables or disables the attachment of an [`ErrorContext`] to errors
+/// returned when unwrapping, verifying the signatures of, or extracting typed
+/// values from envelopes.
+///
+/// When enabled, such errors describe the element at which the operation
+/// failed, formatted in flat envelope notation and truncated to `max_length`
+/// characters. `None` disables error context, which is the default: some
+/// callers use these errors for control flow, and formatting the element is
+/// relatively expensive.
+pub fn set_error_context_length(max_length: Option<usize>) {
+    ERROR_CONTEXT_LENGTH.store(max_length.map_or(0, |length| length.max(1)), Ordering::Relaxed);
+}
+
+/// Returns the maximum length of the element description in an
+/// [`ErrorContext`], or `None` if error context is disabled.
+pub fn error_context_length() -> Option<usize> {
+    match ERROR_CONTEXT_LENGTH.load(Ordering::Relaxed) {
+        0 => None,
+        length => Some(length),
+    }
+}
+
+/// The element of an envelope at which an operation failed.
+///
+/// Attached as [`anyhow`] context to errors when enabled with
+/// [`set_error_context_length`]. Use the alternate format (`{:#}`) to display
+/// the context followed by the underlying error, and
+/// [`anyhow::Error::downcast_ref`] to recover either one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorContext {
+    path: Vec<Digest>,
+    element: String,
+    digest_format: DigestDisplayFormat,
+}
+
+impl ErrorContext {
+    /// The digests of the elements from the envelope on which the operation
+    /// was invoked down to the element at which it failed.
+    pub fn path(&self) -> &[Digest] {
+        &self.path
+    }
+
+    /// The element at which the operation failed, in flat envelope notation,
+    /// possibly truncated.
+    pub fn element(&self) -> &str {
+        &self.element
+    }
+}
+
+impl std::fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let path = self.path
+            .iter()
+            .map(|digest| self.digest_format.format(digest))
+            .collect::<Vec<_>>()
+            .join("/");
+        write!(f, "at {}: {}", path, self.element)
+    }
+}
+
+impl Envelope {
+    /// Attaches this envelope to `error` as the element at which it occurred,
+    /// or if `error` already has an [`ErrorContext`] from one of this
+    /// envelope's descendants, adds this envelope to the start of its path.
+    pub(crate) fn add_error_context(&self, mut error: Error) -> Error {
+        let Some(max_length) = error_context_length() else {
+            return error;
+        };
+        let digest = self.digest().into_owned();
+        if let Some(context) = error.downcast_mut::<ErrorContext>() {
+            if context.path.first() != Some(&digest) {
+                context.path.insert(0, digest);
+            }
+            return error;
+        }
+        let context = error_context_format_context();
+        let element = truncate(&self.format_opt(Some(&context)), max_length);
+        error.context(ErrorContext { path: vec![digest], element, digest_format: context.digest_display_format() })
+    }
+}
+
+/// Returns the global format context in flat format, falling back to the
+/// default context if the global context is in use, as it is when an error
+/// occurs during formatting.
+fn error_context_format_context() -> FormatContext {
+    let global = GLOBAL_FORMAT_CONTEXT.try_get();
+    global
+        .as_ref()
+        .and_then(|binding| binding.as_ref())
+        .cloned()
+        .unwrap_or_default()
+        .set_flat(true)
+}
+
+pub(crate) trait ErrorContextExt<T> {
+    /// Adds `envelope` to the [`ErrorContext`] of the error, if any.
+    fn error_context(self, envelope: &Envelope) -> Result<T>;
+}
+
+impl<T> ErrorContextExt<T> for Result<T> {
+    fn error_context(self, envelope: &Envelope) -> Result<T> {
+        self.map_err(|error| envelope.add_error_context(error))
+    }
+}
+
+fn truncate(text: &str, max_length: usize) -> String {
+    if text.chars().count() <= max_length {
+        text.to_string()
+    } else {
+        let mut result: String = text.chars().take(max_length - 1).collect();
+        result.push('…');
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("Hello", 5), "Hello");
+        assert_eq!(truncate("Hello, world", 5), "Hell…");
+        assert_eq!(truncate("Hello", 1), "…");
+    }
+}
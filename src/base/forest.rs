@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Error, Result};
+use bc_components::{Digest, DigestProvider};
+use dcbor::prelude::*;
+
+use crate::{Envelope, EnvelopeError, EnvelopeMatcher};
+
+use super::{digest::EnvelopePath, envelope::EnvelopeCase};
+
+/// The kinds of elements a [`Forest`] encodes by reference to their
+/// children.
+const NODE: u64 = 0;
+const WRAPPED: u64 = 1;
+const ASSERTION: u64 = 2;
+
+/// A set of root envelopes that share their common subtrees.
+///
+/// Documents issued from the same templates, or revisions of the same
+/// document, often have many subtrees in common. A forest holds each distinct
+/// subtree once, however many roots contain it, so that the roots share it in
+/// memory, a search examines it once, and its encoding includes it once.
+///
+/// Subtrees are shared only if they are structurally identical, not merely
+/// equivalent: an elided subtree is not shared with the subtree it elides,
+/// even though the two have the same digest.
+#[derive(Debug, Clone, Default)]
+pub struct Forest {
+    /// The distinct elements, each after its children.
+    elements: Vec<Envelope>,
+    /// The indexes of the children of each element.
+    children: Vec<Vec<usize>>,
+    /// The index of each element by its structural identity.
+    index: HashMap<Digest, usize>,
+    roots: Vec<usize>,
+}
+
+impl Forest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a root envelope to the forest, returning its index among the
+    /// roots.
+    pub fn add_root(&mut self, envelope: &Envelope) -> usize {
+        let element = self.intern(envelope, &mut HashMap::new());
+        self.roots.push(element);
+        self.roots.len() - 1
+    }
+
+    /// Adds each element of `envelope` that the forest does not yet have,
+    /// returning the index of the element for `envelope`.
+    ///
+    /// `interned` records the elements already visited by their storage, so
+    /// that a subtree shared within `envelope` is visited once.
+    fn intern(&mut self, envelope: &Envelope, interned: &mut HashMap<*const (), usize>) -> usize {
+        if let Some(&element) = interned.get(&envelope.storage_ptr()) {
+            return element;
+        }
+        let children: Vec<usize> = match envelope.case() {
+            EnvelopeCase::Node { subject, assertions, .. } => std::iter::once(subject)
+                .chain(assertions)
+                .map(|child| self.intern(child, interned))
+                .collect(),
+            EnvelopeCase::Wrapped { envelope, .. } => vec![self.intern(envelope, interned)],
+            EnvelopeCase::Assertion(assertion) => {
+                vec![self.intern(&assertion.predicate(), interned), self.intern(&assertion.object(), interned)]
+            }
+            _ => Vec::new(),
+        };
+        let element = self.insert(envelope, children);
+        interned.insert(envelope.storage_ptr(), element);
+        element
+    }
+
+    /// Adds the element `envelope`, whose children are the elements at the
+    /// indexes `children`, if the forest does not yet have it, returning its
+    /// index.
+    fn insert(&mut self, envelope: &Envelope, children: Vec<usize>) -> usize {
+        let id = {
+            let digest = envelope.digest();
+            let mut parts: Vec<&[u8]> = vec![envelope.case_name().as_bytes(), digest.data()];
+            let child_ids: Vec<[u8; 8]> = children.iter().map(|child| (*child as u64).to_be_bytes()).collect();
+            parts.extend(child_ids.iter().map(|id| id.as_slice()));
+            Digest::from_image_parts(&parts)
+        };
+        if let Some(&element) = self.index.get(&id) {
+            return element;
+        }
+        // Rebuild the element from the forest's copies of its children, so
+        // that the roots share them.
+        let element = match envelope.case() {
+            EnvelopeCase::Node { .. } => Envelope::new_with_unchecked_assertions(
+                self.elements[children[0]].clone(),
+                children[1..].iter().map(|&child| self.elements[child].clone()).collect(),
+            ),
+            EnvelopeCase::Wrapped { .. } => Envelope::new_wrapped(self.elements[children[0]].clone()),
+            EnvelopeCase::Assertion(_) => Envelope::new_assertion(
+                self.elements[children[0]].clone(),
+                self.elements[children[1]].clone(),
+            ),
+            _ => envelope.clone(),
+        };
+        self.elements.push(element);
+        self.children.push(children);
+        self.index.insert(id, self.elements.len() - 1);
+        self.elements.len() - 1
+    }
+
+    /// The number of roots.
+    pub fn len(&self) -> usize {
+        self.roots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.roots.is_empty()
+    }
+
+    /// The root at the given index, if there is one.
+    pub fn root(&self, index: usize) -> Option<&Envelope> {
+        self.roots.get(index).map(|&element| &self.elements[element])
+    }
+
+    /// The roots, in the order they were added.
+    pub fn roots(&self) -> impl Iterator<Item = &Envelope> {
+        self.roots.iter().map(|&element| &self.elements[element])
+    }
+
+    /// The number of distinct elements in all of the roots.
+    pub fn element_count(&self) -> usize {
+        self.elements.len()
+    }
+
+    /// Returns the path to each element of each root that `matcher` matches,
+    /// with the index of the root, in the order of the roots and then in
+    /// depth-first order, as [`Envelope::paths_matching`] does for a single
+    /// envelope.
+    ///
+    /// The matcher is called once for each distinct element, however many
+    /// roots contain it, and subtrees that contain no matches are not walked.
+    pub fn paths_matching(&self, matcher: &impl EnvelopeMatcher) -> Vec<(usize, EnvelopePath)> {
+        // Elements come after their children, so one pass in order finds
+        // which subtrees contain a match.
+        let mut matches = Vec::with_capacity(self.elements.len());
+        let mut contains_match = Vec::with_capacity(self.elements.len());
+        for (element, children) in self.elements.iter().zip(&self.children) {
+            let matched = matcher.matches(element);
+            contains_match.push(matched || children.iter().any(|&child| contains_match[child]));
+            matches.push(matched);
+        }
+        let mut results = Vec::new();
+        for (root_index, &root) in self.roots.iter().enumerate() {
+            if !contains_match[root] {
+                continue;
+            }
+            let mut stack = vec![(root, Vec::new())];
+            while let Some((element, mut path)) = stack.pop() {
+                path.push(self.elements[element].clone());
+                if matches[element] {
+                    results.push((root_index, path.clone()));
+                }
+                for &child in self.children[element].iter().rev() {
+                    if contains_match[child] {
+                        stack.push((child, path.clone()));
+                    }
+                }
+            }
+        }
+        results
+    }
+}
+
+/// A forest is encoded as an array of its distinct elements followed by an
+/// array of the indexes of its roots. Each element without children is
+/// encoded as a tagged envelope, and each node, wrapped envelope, or
+/// assertion as an array of its kind followed by the indexes of its
+/// children, which always precede it.
+impl From<&Forest> for CBOR {
+    fn from(forest: &Forest) -> Self {
+        let elements: Vec<CBOR> = forest
+            .elements
+            .iter()
+            .zip(&forest.children)
+            .map(|(element, children)| {
+                let kind = match element.case() {
+                    EnvelopeCase::Node { .. } => NODE,
+                    EnvelopeCase::Wrapped { .. } => WRAPPED,
+                    EnvelopeCase::Assertion(_) => ASSERTION,
+                    _ => return element.tagged_cbor(),
+                };
+                let mut entry = vec![kind];
+                entry.extend(children.iter().map(|&child| child as u64));
+                entry.into()
+            })
+            .collect();
+        let roots: Vec<u64> = forest.roots.iter().map(|&root| root as u64).collect();
+        vec![CBOR::from(elements), roots.into()].into()
+    }
+}
+
+impl TryFrom<CBOR> for Forest {
+    type Error = Error;
+
+    fn try_from(cbor: CBOR) -> Result<Self> {
+        let [elements, roots]: [CBOR; 2] = cbor.try_into_array()?.try_into().map_err(|_| EnvelopeError::InvalidFormat)?;
+        // The forest is built directly from the table, whose elements each
+        // follow their children, so that each entry is decoded once however
+        // many times it is shared.
+        let mut forest = Self::new();
+        let mut decoded: Vec<usize> = Vec::new();
+        let mut interned = HashMap::new();
+        // The decoded envelopes are kept until the end, so that their
+        // storage is not reused while `interned` refers to it.
+        let mut envelopes = Vec::new();
+        for entry in elements.try_into_array()? {
+            let envelope = if matches!(entry.as_case(), CBORCase::Array(_)) {
+                let entry: Vec<usize> = entry.try_into()?;
+                let Some((&kind, children)) = entry.split_first() else {
+                    bail!(EnvelopeError::InvalidFormat);
+                };
+                let Some(children) = children.iter().map(|&child| decoded.get(child).copied()).collect::<Option<Vec<_>>>() else {
+                    bail!(EnvelopeError::InvalidFormat);
+                };
+                let child = |index: usize| forest.elements[children[index]].clone();
+                match (kind as u64, children.len()) {
+                    (NODE, count) if count >= 2 => {
+                        Envelope::new_with_assertions(child(0), (1..count).map(child).collect())?
+                    }
+                    (WRAPPED, 1) => Envelope::new_wrapped(child(0)),
+                    (ASSERTION, 2) => Envelope::new_assertion(child(0), child(1)),
+                    _ => bail!(EnvelopeError::InvalidFormat),
+                }
+            } else {
+                Envelope::from_tagged_cbor(entry)?
+            };
+            // The children of a decoded element are the forest's own
+            // elements, which are already interned.
+            let element = forest.intern(&envelope, &mut interned);
+            interned.insert(forest.elements[element].storage_ptr(), element);
+            decoded.push(element);
+            envelopes.push(envelope);
+        }
+        for root in Vec::<usize>::try_from(roots)? {
+            let Some(&element) = decoded.get(root) else {
+                bail!(EnvelopeError::InvalidFormat);
+            };
+            forest.roots.push(element);
+        }
+        Ok(forest)
+    }
+}
@@ -1,6 +1,6 @@
 use bc_components::XID;
 use dcbor::prelude::*;
-use crate::{Envelope, Assertion, string_utils::StringUtils, FormatContext, with_format_context};
+use crate::{Envelope, Assertion, string_utils::StringUtils, FormatContext, FormatVersion, with_format_context};
 #[cfg(feature = "known_value")]
 use crate::extension::{KnownValue, known_values};
 
@@ -11,7 +11,11 @@ impl Envelope {
     /// Returns the envelope notation for this envelope.
     pub fn format_opt(&self, context: Option<&FormatContext>) -> String {
         let context = context.cloned().unwrap_or(FormatContext::default());
-        self.format_item(&context).format(context.is_flat()).trim().to_string()
+        // A change to the output is made in a new version, leaving the output
+        // of the earlier versions as it was.
+        match context.format_version() {
+            FormatVersion::V1 => self.format_item(&context).format(context.is_flat()).trim().to_string(),
+        }
     }
 
     /// Returns the envelope notation for this envelope.
@@ -23,6 +27,17 @@ impl Envelope {
         })
     }
 
+    /// Returns the envelope notation for this envelope, pinned to the given
+    /// version.
+    ///
+    /// Uses the current format context. See [`FormatVersion`].
+    pub fn format_versioned(&self, version: FormatVersion) -> String {
+        with_format_context!(|context: &FormatContext| {
+            let context = context.clone().set_format_version(version);
+            self.format_opt(Some(&context))
+        })
+    }
+
     /// Returns the envelope notation for this envelope in flat format.
     ///
     /// In flat format, the envelope is printed on a single line.
@@ -39,7 +54,7 @@ impl Envelope {
     /// for information on CBOR diagnostic notation.
     pub fn diagnostic_annotated(&self) -> String {
         with_format_context!(|context: &FormatContext| {
-            self.tagged_cbor().diagnostic_opt(true, false, false, Some(context.tags()))
+            self.tagged_cbor().diagnostic_opt(true, false, false, Some(context))
         })
     }
 
@@ -59,7 +74,7 @@ impl Envelope {
     /// the CBOR binary format.
     pub fn hex_opt(&self, annotate: bool, context: Option<&FormatContext>) -> String {
         let cbor: CBOR = self.clone().into();
-        cbor.hex_opt(annotate, Some(context.unwrap_or(&FormatContext::default()).tags()))
+        cbor.hex_opt(annotate, Some(context.unwrap_or(&FormatContext::default())))
     }
 
     /// Returns the CBOR hex dump of this envelope.
@@ -392,6 +407,9 @@ impl EnvelopeFormat for Envelope {
                 #[cfg(feature = "known_value")]
                 type_assertion_items.sort();
                 assertion_items.sort();
+                if context.is_sorting_by_predicate_name() {
+                    assertion_items.sort_by_cached_key(|item| predicate_sort_key(item));
+                }
                 #[cfg(feature = "known_value")]
                 assertion_items.splice(0..0, type_assertion_items);
                 #[cfg(feature = "compress")]
@@ -433,6 +451,21 @@ impl EnvelopeFormat for Envelope {
     }
 }
 
+/// Returns the name of the predicate of a formatted assertion, without the
+/// delimiters around it, for sorting assertions by predicate name.
+fn predicate_sort_key(item: &[EnvelopeFormatItem]) -> String {
+    let Some(EnvelopeFormatItem::List(parts)) = item.first() else {
+        return String::new();
+    };
+    let Some(predicate) = parts.first() else {
+        return String::new();
+    };
+    predicate
+        .format_flat()
+        .trim_matches(|c| matches!(c, ' ' | '"' | '\'' | '«' | '»' | '❰' | '❱'))
+        .to_lowercase()
+}
+
 impl EnvelopeFormat for Assertion {
     fn format_item(&self, context: &FormatContext) -> EnvelopeFormatItem {
         EnvelopeFormatItem::List(vec![
@@ -446,13 +479,13 @@ impl EnvelopeFormat for Assertion {
 #[cfg(feature = "known_value")]
 impl EnvelopeFormat for KnownValue {
     fn format_item(&self, context: &FormatContext) -> EnvelopeFormatItem {
-        EnvelopeFormatItem::Item(context
-            .known_values()
-            .assigned_name(self)
-            .map(|s| s.to_string())
-            .unwrap_or_else(|| self.name())
-            .flanked_by("'", "'")
-        )
+        EnvelopeFormatItem::Item(context.format_known_value(self.value(), || {
+            context
+                .known_values()
+                .assigned_name(self)
+                .map(|s| s.to_string())
+                .unwrap_or_else(|| self.name())
+        }))
     }
 }
 
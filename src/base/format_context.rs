@@ -1,25 +1,33 @@
 #[cfg(feature = "expression")]
 use bc_components::tags::*;
 use dcbor::prelude::*;
-#[cfg(feature = "expression")]
 use std::sync::Arc;
 use std::sync::{ Mutex, Once };
+use std::collections::HashMap;
+use super::leaf_tag_adapter::LeafTagAdaptersStore;
+use super::localized_names::LocalizedNames;
+use super::format_version::FormatVersion;
+use super::digest_namer::{DigestDisplayFormat, DigestNamer};
+use bc_components::Digest;
 #[cfg(feature = "known_value")]
 use crate::extension::known_values::{ KnownValuesStore, KNOWN_VALUES };
 
 #[cfg(feature = "expression")]
 use crate::extension::expressions::{
+    Function,
+    Parameter,
     FunctionsStore,
     ParametersStore,
     GLOBAL_FUNCTIONS,
     GLOBAL_PARAMETERS,
 };
-// #[cfg(any(feature = "expression", feature = "known_value"))]
-#[cfg(feature = "expression")]
+#[cfg(feature = "known_value")]
 use crate::KnownValue;
 
+#[cfg(feature = "known_value")]
+use crate::string_utils::StringUtils;
 #[cfg(feature = "expression")]
-use crate::{ string_utils::StringUtils, Envelope };
+use crate::Envelope;
 
 /// The envelope formatting functions take a `FormatContext` as an argument. This type
 /// defines information about CBOR tags, known values, functions and parameters that
@@ -43,15 +51,62 @@ use crate::{ string_utils::StringUtils, Envelope };
 #[derive(Clone)]
 pub struct FormatContext {
     flat: bool,
-    tags: TagsStore,
+    format_version: FormatVersion,
+    sort_by_predicate_name: bool,
+    max_depth: Option<usize>,
+    tags: Arc<TagsStore>,
+    replaced_tags: HashMap<u64, Tag>,
     #[cfg(feature = "known_value")]
-    known_values: KnownValuesStore,
+    known_values: Arc<KnownValuesStore>,
     #[cfg(feature = "expression")]
-    functions: FunctionsStore,
+    functions: Arc<FunctionsStore>,
     #[cfg(feature = "expression")]
-    parameters: ParametersStore,
+    parameters: Arc<ParametersStore>,
+    leaf_tag_adapters: LeafTagAdaptersStore,
+    localized_names: LocalizedNames,
+    #[cfg(feature = "known_value")]
+    known_value_displays: HashMap<u64, String>,
+    uri_prefixes: Vec<(String, String)>,
+    digest_namer: Option<Arc<dyn DigestNamer>>,
+    digest_display_format: DigestDisplayFormat,
+    conflict_resolver: Option<Arc<ConflictResolver>>,
 }
 
+/// The kind of name registered with a [`FormatContext`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RegistrationKind {
+    Tag,
+    #[cfg(feature = "known_value")]
+    KnownValue,
+    #[cfg(feature = "expression")]
+    Function,
+    #[cfg(feature = "expression")]
+    Parameter,
+}
+
+/// A name registered with a [`FormatContext`] for a value that already has a
+/// different name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegistrationConflict {
+    pub kind: RegistrationKind,
+    pub value: u64,
+    pub existing_name: String,
+    pub new_name: String,
+}
+
+/// How a [`RegistrationConflict`] is resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictResolution {
+    /// Keep the existing name.
+    KeepExisting,
+    /// Replace the existing name with the new one.
+    Replace,
+}
+
+/// A callback that resolves registration conflicts. See
+/// [`FormatContext::with_conflict_resolver`].
+pub type ConflictResolver = dyn Fn(&RegistrationConflict) -> ConflictResolution + Send + Sync;
+
 impl FormatContext {
     pub fn new(
         flat: bool,
@@ -62,13 +117,25 @@ impl FormatContext {
     ) -> Self {
         Self {
             flat,
-            tags: tags.cloned().unwrap_or_default(),
+            format_version: FormatVersion::LATEST,
+            sort_by_predicate_name: false,
+            max_depth: None,
+            tags: Arc::new(tags.cloned().unwrap_or_default()),
+            replaced_tags: HashMap::new(),
             #[cfg(feature = "known_value")]
-            known_values: known_values.cloned().unwrap_or_default(),
+            known_values: Arc::new(known_values.cloned().unwrap_or_default()),
             #[cfg(feature = "expression")]
-            functions: functions.cloned().unwrap_or_default(),
+            functions: Arc::new(functions.cloned().unwrap_or_default()),
             #[cfg(feature = "expression")]
-            parameters: parameters.cloned().unwrap_or_default(),
+            parameters: Arc::new(parameters.cloned().unwrap_or_default()),
+            leaf_tag_adapters: LeafTagAdaptersStore::default(),
+            localized_names: LocalizedNames::default(),
+            #[cfg(feature = "known_value")]
+            known_value_displays: HashMap::new(),
+            uri_prefixes: Vec::new(),
+            digest_namer: None,
+            digest_display_format: DigestDisplayFormat::default(),
+            conflict_resolver: None,
         }
     }
 
@@ -81,12 +148,67 @@ impl FormatContext {
         self
     }
 
+    /// The version of the envelope and tree notation produced with this
+    /// context, which is [`FormatVersion::LATEST`] unless another is set.
+    pub fn format_version(&self) -> FormatVersion {
+        self.format_version
+    }
+
+    /// Pins the envelope and tree notation produced with this context to the
+    /// given version.
+    pub fn set_format_version(mut self, format_version: FormatVersion) -> Self {
+        self.format_version = format_version;
+        self
+    }
+
+    /// Whether assertions are listed by the names of their predicates rather
+    /// than in the canonical order.
+    pub fn is_sorting_by_predicate_name(&self) -> bool {
+        self.sort_by_predicate_name
+    }
+
+    /// Lists assertions in envelope notation alphabetically by the names of
+    /// their predicates, ignoring case and the delimiters around them, so
+    /// that large envelopes such as credentials are easier to scan.
+    ///
+    /// This only affects how envelopes are displayed; their digests are
+    /// unchanged. Type assertions are still listed first.
+    pub fn set_sort_by_predicate_name(mut self, sort_by_predicate_name: bool) -> Self {
+        self.sort_by_predicate_name = sort_by_predicate_name;
+        self
+    }
+
+    /// The deepest level shown in tree notation, if limited.
+    pub fn max_depth(&self) -> Option<usize> {
+        self.max_depth
+    }
+
+    /// Limits tree notation to elements at most `max_depth` levels below the
+    /// root. Elements at the limit whose children are hidden are marked with
+    /// `…`.
+    pub fn set_max_depth(&mut self, max_depth: Option<usize>) {
+        self.max_depth = max_depth;
+    }
+
+    /// Returns the context with tree notation limited to `max_depth` levels
+    /// below the root. See [`FormatContext::set_max_depth`].
+    pub fn with_max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.set_max_depth(max_depth);
+        self
+    }
+
+    /// The store of tags registered with the context.
+    ///
+    /// A `TagsStore` cannot rename a tag, so tags renamed by
+    /// [`FormatContext::with_tags`] keep their original names here. The
+    /// context itself is a `TagsStoreTrait` that reflects the new names, and
+    /// is what the formatting functions use.
     pub fn tags(&self) -> &TagsStore {
         &self.tags
     }
 
     pub fn tags_mut(&mut self) -> &mut TagsStore {
-        &mut self.tags
+        Arc::make_mut(&mut self.tags)
     }
 
     #[cfg(feature = "known_value")]
@@ -103,23 +225,305 @@ impl FormatContext {
     pub fn parameters(&self) -> &ParametersStore {
         &self.parameters
     }
+
+    /// Sets the callback that decides which name to keep when a name is
+    /// registered with [`FormatContext::with_tags`],
+    /// `with_known_values`, `with_functions`, or `with_parameters` for a value
+    /// that already has a different name.
+    ///
+    /// Without a resolver, later registrations replace earlier ones, so
+    /// several sources can be combined by registering them in order of
+    /// increasing precedence.
+    pub fn with_conflict_resolver(
+        mut self,
+        resolver: impl Fn(&RegistrationConflict) -> ConflictResolution + Send + Sync + 'static
+    ) -> Self {
+        self.conflict_resolver = Some(Arc::new(resolver));
+        self
+    }
+
+    /// Returns `true` if a name should be registered for `value`, given the
+    /// name it already has, if any.
+    fn should_register(&self, kind: RegistrationKind, value: u64, existing_name: Option<&str>, new_name: &str) -> bool {
+        let (Some(existing_name), Some(resolver)) = (existing_name, self.conflict_resolver.as_ref()) else {
+            return true;
+        };
+        if existing_name == new_name {
+            return true;
+        }
+        let conflict = RegistrationConflict {
+            kind,
+            value,
+            existing_name: existing_name.to_string(),
+            new_name: new_name.to_string(),
+        };
+        resolver(&conflict) == ConflictResolution::Replace
+    }
+
+    /// Returns the context with the standard tags of this crate and its
+    /// dependencies registered, as [`register_tags_in`] does.
+    pub fn with_standard_tags(mut self) -> Self {
+        register_tags_in(&mut self);
+        self
+    }
+
+    /// Returns the context with the given tags registered.
+    pub fn with_tags(mut self, tags: impl IntoIterator<Item = Tag>) -> Self {
+        for tag in tags {
+            let Some(name) = tag.name() else {
+                continue;
+            };
+            let existing_name = self.assigned_name_for_tag(&tag);
+            if !self.should_register(RegistrationKind::Tag, tag.value(), existing_name.as_deref(), &name) {
+                continue;
+            }
+            // Inserting a tag under a different name panics, so a renamed tag
+            // is recorded alongside the store instead.
+            match self.tags.assigned_name_for_tag(&tag) {
+                Some(stored_name) if stored_name != name => {
+                    self.replaced_tags.insert(tag.value(), tag);
+                }
+                _ => {
+                    self.replaced_tags.remove(&tag.value());
+                    self.tags_mut().insert(tag);
+                }
+            }
+        }
+        self
+    }
+
+    /// Returns the context with the given known values registered.
+    #[cfg(feature = "known_value")]
+    pub fn with_known_values(mut self, known_values: impl IntoIterator<Item = KnownValue>) -> Self {
+        for known_value in known_values {
+            let Some(name) = known_value.assigned_name() else {
+                continue;
+            };
+            let existing_name = self.known_values.assigned_name(&known_value);
+            if self.should_register(RegistrationKind::KnownValue, known_value.value(), existing_name, name) {
+                Arc::make_mut(&mut self.known_values).insert(known_value);
+            }
+        }
+        register_summarizers_in(&mut self);
+        self
+    }
+
+    /// Returns the context with the given functions registered.
+    #[cfg(feature = "expression")]
+    pub fn with_functions(mut self, functions: impl IntoIterator<Item = Function>) -> Self {
+        for function in functions {
+            if let Function::Known(value, _) = function {
+                let existing_name = self.functions.assigned_name(&function);
+                if !self.should_register(RegistrationKind::Function, value, existing_name, &function.name()) {
+                    continue;
+                }
+            }
+            Arc::make_mut(&mut self.functions).insert(function);
+        }
+        register_summarizers_in(&mut self);
+        self
+    }
+
+    /// Returns the context with the given parameters registered.
+    #[cfg(feature = "expression")]
+    pub fn with_parameters(mut self, parameters: impl IntoIterator<Item = Parameter>) -> Self {
+        for parameter in parameters {
+            if let Parameter::Known(value, _) = parameter {
+                let existing_name = self.parameters.assigned_name(&parameter);
+                if !self.should_register(RegistrationKind::Parameter, value, existing_name, &parameter.name()) {
+                    continue;
+                }
+            }
+            Arc::make_mut(&mut self.parameters).insert(parameter);
+        }
+        register_summarizers_in(&mut self);
+        self
+    }
+
+    pub fn leaf_tag_adapters(&self) -> &LeafTagAdaptersStore {
+        &self.leaf_tag_adapters
+    }
+
+    pub fn leaf_tag_adapters_mut(&mut self) -> &mut LeafTagAdaptersStore {
+        &mut self.leaf_tag_adapters
+    }
+
+    pub fn localized_names(&self) -> &LocalizedNames {
+        &self.localized_names
+    }
+
+    pub fn localized_names_mut(&mut self) -> &mut LocalizedNames {
+        &mut self.localized_names
+    }
+
+    /// The text displayed in place of the known value with the given raw
+    /// value, if one has been set.
+    #[cfg(feature = "known_value")]
+    pub fn known_value_display(&self, value: u64) -> Option<&str> {
+        self.known_value_displays.get(&value).map(|display| display.as_str())
+    }
+
+    /// Sets the text displayed in place of the known value with the given raw
+    /// value when formatting envelopes.
+    ///
+    /// Unlike a localized name, the text is shown exactly as given, without
+    /// the single quotes that otherwise mark a known value, so for example
+    /// `'isA': "Person"` can be shown as `type: "Person"` to end users. This
+    /// affects only how envelopes are displayed: their encoding and digests
+    /// are unchanged.
+    ///
+    /// The context's tag summarizers are registered again so that they also
+    /// use the new text.
+    #[cfg(feature = "known_value")]
+    pub fn set_known_value_display(&mut self, value: u64, display: impl Into<String>) {
+        self.known_value_displays.insert(value, display.into());
+        register_summarizers_in(self);
+    }
+
+    /// Returns the context with the given text displayed in place of the
+    /// known value with the given raw value.
+    ///
+    /// See [`FormatContext::set_known_value_display`].
+    #[cfg(feature = "known_value")]
+    pub fn with_known_value_display(mut self, value: u64, display: impl Into<String>) -> Self {
+        self.set_known_value_display(value, display);
+        self
+    }
+
+    /// Registers `prefix` as an abbreviation for URIs starting with
+    /// `namespace`, so that for example with the prefix `schema` for the
+    /// namespace `https://schema.org/`, the URI `https://schema.org/name` is
+    /// shown as `schema:name` when formatting envelopes.
+    ///
+    /// Registering a prefix again replaces its namespace. If several
+    /// namespaces match a URI, the longest is used. This affects only how
+    /// envelopes are displayed: their encoding and digests are unchanged.
+    pub fn set_uri_prefix(&mut self, prefix: impl Into<String>, namespace: impl Into<String>) {
+        let prefix = prefix.into();
+        let namespace = namespace.into();
+        match self.uri_prefixes.iter_mut().find(|(existing, _)| *existing == prefix) {
+            Some((_, existing)) => *existing = namespace,
+            None => self.uri_prefixes.push((prefix, namespace)),
+        }
+    }
+
+    /// Returns the context with `prefix` registered as an abbreviation for
+    /// URIs starting with `namespace`.
+    ///
+    /// See [`FormatContext::set_uri_prefix`].
+    pub fn with_uri_prefix(mut self, prefix: impl Into<String>, namespace: impl Into<String>) -> Self {
+        self.set_uri_prefix(prefix, namespace);
+        self
+    }
+
+    /// Returns `uri` abbreviated using the longest registered namespace it
+    /// starts with, or `None` if it starts with none of them.
+    pub fn abbreviate_uri(&self, uri: &str) -> Option<String> {
+        self.uri_prefixes
+            .iter()
+            .filter(|(_, namespace)| uri.starts_with(namespace.as_str()))
+            .max_by_key(|(_, namespace)| namespace.len())
+            .map(|(prefix, namespace)| format!("{}:{}", prefix, &uri[namespace.len()..]))
+    }
+
+    /// Shows the name given by `namer` next to each digest in tree notation,
+    /// such as `8cc96cdb (lucid-otter)`.
+    ///
+    /// See [`DigestNamer`] and [`Petnames`](crate::Petnames).
+    pub fn set_digest_namer(&mut self, namer: impl DigestNamer + 'static) {
+        self.digest_namer = Some(Arc::new(namer));
+    }
+
+    /// Returns the context with the given digest namer.
+    ///
+    /// See [`FormatContext::set_digest_namer`].
+    pub fn with_digest_namer(mut self, namer: impl DigestNamer + 'static) -> Self {
+        self.set_digest_namer(namer);
+        self
+    }
+
+    /// The format in which digests are shown in tree notation and error
+    /// messages.
+    pub fn digest_display_format(&self) -> DigestDisplayFormat {
+        self.digest_display_format
+    }
+
+    /// Shows digests in tree notation, and in the [`ErrorContext`](crate::ErrorContext)
+    /// of errors, in the given format, such as emoji that are easier to
+    /// compare over a voice or video call than hexadecimal.
+    pub fn set_digest_display_format(&mut self, format: DigestDisplayFormat) {
+        self.digest_display_format = format;
+    }
+
+    /// Returns the context with digests shown in the given format. See
+    /// [`FormatContext::set_digest_display_format`].
+    pub fn with_digest_display_format(mut self, format: DigestDisplayFormat) -> Self {
+        self.set_digest_display_format(format);
+        self
+    }
+
+    /// Returns `digest` in the digest display format, followed by its name
+    /// if a digest namer has been set.
+    pub fn describe_digest(&self, digest: &Digest) -> String {
+        let description = self.digest_display_format.format(digest);
+        match self.digest_namer.as_ref().and_then(|namer| namer.name(digest)) {
+            Some(name) => format!("{} ({})", description, name),
+            None => description,
+        }
+    }
+
+    /// Formats the known value with the given raw value, using its display
+    /// text if one has been set, and otherwise its localized name or
+    /// `canonical_name` in single quotes.
+    #[cfg(feature = "known_value")]
+    pub(crate) fn format_known_value(&self, value: u64, canonical_name: impl FnOnce() -> String) -> String {
+        format_known_value(&self.known_value_displays, &self.localized_names, value, canonical_name)
+    }
+}
+
+#[cfg(feature = "known_value")]
+fn format_known_value(
+    displays: &HashMap<u64, String>,
+    localized_names: &LocalizedNames,
+    value: u64,
+    canonical_name: impl FnOnce() -> String
+) -> String {
+    if let Some(display) = displays.get(&value) {
+        return display.clone();
+    }
+    localized_names
+        .known_value_name(value)
+        .map(|name| name.to_string())
+        .unwrap_or_else(canonical_name)
+        .flanked_by("'", "'")
 }
 
 impl TagsStoreTrait for FormatContext {
     fn assigned_name_for_tag(&self, tag: &Tag) -> Option<String> {
-        self.tags.assigned_name_for_tag(tag)
+        self.tag_for_value(tag.value()).and_then(|tag| tag.name())
     }
 
     fn name_for_tag(&self, tag: &Tag) -> String {
-        self.tags.name_for_tag(tag)
+        self.name_for_value(tag.value())
     }
 
     fn tag_for_name(&self, name: &str) -> Option<Tag> {
-        self.tags.tag_for_name(name)
+        self.replaced_tags
+            .values()
+            .find(|tag| tag.name().as_deref() == Some(name))
+            .cloned()
+            .or_else(|| {
+                self.tags
+                    .tag_for_name(name)
+                    .filter(|tag| !self.replaced_tags.contains_key(&tag.value()))
+            })
     }
 
     fn tag_for_value(&self, value: u64) -> Option<Tag> {
-        self.tags.tag_for_value(value)
+        self.replaced_tags
+            .get(&value)
+            .cloned()
+            .or_else(|| self.tags.tag_for_value(value))
     }
 
     fn summarizer(&self, tag: TagValue) -> Option<&CBORSummarizer> {
@@ -127,7 +531,9 @@ impl TagsStoreTrait for FormatContext {
     }
 
     fn name_for_value(&self, value: u64) -> String {
-        self.tags.name_for_value(value)
+        self.tag_for_value(value)
+            .and_then(|tag| tag.name())
+            .unwrap_or_else(|| value.to_string())
     }
 }
 
@@ -150,6 +556,17 @@ pub struct LazyFormatContext {
 
 impl LazyFormatContext {
     pub fn get(&self) -> std::sync::MutexGuard<'_, Option<FormatContext>> {
+        self.initialize();
+        self.data.lock().unwrap()
+    }
+
+    /// Returns the context, or `None` if it is currently locked.
+    pub(crate) fn try_get(&self) -> Option<std::sync::MutexGuard<'_, Option<FormatContext>>> {
+        self.initialize();
+        self.data.try_lock().ok()
+    }
+
+    fn initialize(&self) {
         self.init.call_once(|| {
             bc_components::register_tags();
             let tags_binding = dcbor::GLOBAL_TAGS.get();
@@ -178,7 +595,6 @@ impl LazyFormatContext {
             );
             *self.data.lock().unwrap() = Some(context);
         });
-        self.data.lock().unwrap()
     }
 }
 
@@ -215,17 +631,31 @@ macro_rules! with_format_context_mut {
 
 pub fn register_tags_in(context: &mut FormatContext) {
     bc_components::register_tags_in(context.tags_mut());
+    register_summarizers_in(context);
+}
+
+pub(crate) fn register_summarizers_in(context: &mut FormatContext) {
+    #[cfg(not(feature = "expression"))]
+    let _ = context;
 
     #[cfg(feature = "expression")]
     {
-        use crate::extension::expressions::{ Function, FunctionsStore, Parameter, ParametersStore };
+        use crate::extension::expressions::{ FunctionsStore, ParametersStore };
 
         let functions = context.functions().clone();
+        let localized_names = context.localized_names().clone();
         context.tags_mut().set_summarizer(
             TAG_FUNCTION,
             Arc::new(move |untagged_cbor: CBOR| {
                 let f = Function::from_untagged_cbor(untagged_cbor)?;
-                Ok(FunctionsStore::name_for_function(&f, Some(&functions)).flanked_by("«", "»"))
+                let localized_name = match &f {
+                    Function::Known(value, _) => localized_names.function_name(*value),
+                    Function::Named(_) => None,
+                };
+                let name = localized_name
+                    .map(|name| name.to_string())
+                    .unwrap_or_else(|| FunctionsStore::name_for_function(&f, Some(&functions)));
+                Ok(name.flanked_by("«", "»"))
             })
         );
 
@@ -239,13 +669,19 @@ pub fn register_tags_in(context: &mut FormatContext) {
         );
 
         let known_values = context.known_values().clone();
+        let localized_names = context.localized_names().clone();
+        let known_value_displays = context.known_value_displays.clone();
         context.tags_mut().set_summarizer(
             TAG_KNOWN_VALUE,
             Arc::new(move |untagged_cbor: CBOR| {
+                let known_value = KnownValue::from_untagged_cbor(untagged_cbor)?;
                 Ok(
-                    known_values
-                        .name(KnownValue::from_untagged_cbor(untagged_cbor)?)
-                        .flanked_by("'", "'")
+                    format_known_value(
+                        &known_value_displays,
+                        &localized_names,
+                        known_value.value(),
+                        || known_values.name(known_value)
+                    )
                 )
             })
         );
@@ -0,0 +1,43 @@
+/// A version of the text output of [`Envelope::format`](crate::Envelope::format)
+/// and [`Envelope::tree_format`](crate::Envelope::tree_format).
+///
+/// The default output may improve from release to release, which breaks
+/// golden tests that compare it against fixed text. Tests that need output
+/// that never changes can pin a version with
+/// [`FormatContext::set_format_version`](crate::FormatContext::set_format_version),
+/// or use [`Envelope::format_versioned`](crate::Envelope::format_versioned) and
+/// [`Envelope::tree_format_versioned`](crate::Envelope::tree_format_versioned).
+///
+/// The output of a released version never changes: any change to the output
+/// is made in a new version, which becomes [`FormatVersion::LATEST`] and is
+/// recorded in the version history of the README.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum FormatVersion {
+    /// The output of bc-envelope 0.21.0.
+    V1,
+}
+
+impl FormatVersion {
+    /// The newest version, used unless another version is selected.
+    pub const LATEST: Self = Self::V1;
+
+    /// The version number.
+    pub fn number(&self) -> u32 {
+        match self {
+            Self::V1 => 1,
+        }
+    }
+}
+
+impl Default for FormatVersion {
+    fn default() -> Self {
+        Self::LATEST
+    }
+}
+
+impl std::fmt::Display for FormatVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "v{}", self.number())
+    }
+}
@@ -0,0 +1,88 @@
+use std::{collections::HashSet, ops::Deref};
+
+use bc_components::Digest;
+use bc_ur::prelude::*;
+
+use crate::{base::envelope::RefCounted, Envelope};
+
+/// An envelope with its serializations computed in advance.
+///
+/// Created by [`Envelope::freeze`]. Because envelopes are immutable, the
+/// encoding of a frozen envelope never changes, so a server that returns the
+/// same envelope many times can freeze it once and serve its CBOR or UR
+/// without encoding it again. A frozen envelope dereferences to [`Envelope`],
+/// so all of the read-only envelope APIs are available on it, and clones share
+/// the cached serializations.
+#[derive(Debug, Clone)]
+pub struct FrozenEnvelope {
+    envelope: Envelope,
+    tagged_cbor_data: RefCounted<[u8]>,
+    ur_string: RefCounted<str>,
+}
+
+impl FrozenEnvelope {
+    /// Freezes `envelope`, computing its tagged CBOR encoding, UR string, and
+    /// deep digest set.
+    pub fn new(envelope: Envelope) -> Self {
+        let tagged_cbor_data = envelope.tagged_cbor_data().into();
+        let ur_string = envelope.ur_string().into();
+        envelope.deep_digests_ref();
+        Self { envelope, tagged_cbor_data, ur_string }
+    }
+
+    /// The frozen envelope.
+    pub fn envelope(&self) -> &Envelope {
+        &self.envelope
+    }
+
+    /// The tagged CBOR encoding of the envelope.
+    pub fn tagged_cbor_data(&self) -> &[u8] {
+        &self.tagged_cbor_data
+    }
+
+    /// The envelope encoded as a UR string.
+    pub fn ur_string(&self) -> &str {
+        &self.ur_string
+    }
+
+    /// The digests of all the elements of the envelope.
+    pub fn deep_digests(&self) -> &HashSet<Digest> {
+        self.envelope.deep_digests_ref()
+    }
+}
+
+impl Deref for FrozenEnvelope {
+    type Target = Envelope;
+
+    fn deref(&self) -> &Envelope {
+        &self.envelope
+    }
+}
+
+impl AsRef<Envelope> for FrozenEnvelope {
+    fn as_ref(&self) -> &Envelope {
+        &self.envelope
+    }
+}
+
+impl From<FrozenEnvelope> for Envelope {
+    fn from(frozen: FrozenEnvelope) -> Self {
+        frozen.envelope
+    }
+}
+
+impl From<Envelope> for FrozenEnvelope {
+    fn from(envelope: Envelope) -> Self {
+        Self::new(envelope)
+    }
+}
+
+/// Support for freezing envelopes.
+impl Envelope {
+    /// Returns the envelope with its serializations computed in advance.
+    ///
+    /// See [`FrozenEnvelope`].
+    pub fn freeze(&self) -> FrozenEnvelope {
+        FrozenEnvelope::new(self.clone())
+    }
+}
@@ -0,0 +1,75 @@
+use std::collections::HashSet;
+use std::mem::size_of;
+
+use bc_components::Digest;
+
+use crate::Envelope;
+
+use super::envelope::{EnvelopeCaches, EnvelopeCase};
+
+/// Support for memory usage introspection.
+impl Envelope {
+    /// Returns an estimate of the number of heap bytes retained by this
+    /// envelope.
+    ///
+    /// The estimate includes the envelope's own storage, the storage of every
+    /// element it contains, the payloads of leaves and of encrypted or
+    /// compressed elements, and any digest caches that have been computed.
+    /// Envelopes share identical subtrees when they are cloned or reused, and
+    /// each shared subtree is counted only once. Allocator overhead is not
+    /// included, so the result is approximate, but it is suitable for
+    /// enforcing memory budgets in caches.
+    pub fn heap_size_estimate(&self) -> usize {
+        let mut visited = HashSet::new();
+        self.heap_size_estimate_visiting(&mut visited)
+    }
+
+    fn heap_size_estimate_visiting(&self, visited: &mut HashSet<*const ()>) -> usize {
+        if !visited.insert(self.storage_ptr()) {
+            return 0;
+        }
+        let mut size = Self::storage_size();
+        if let Some(caches) = self.allocated_caches() {
+            size += size_of::<EnvelopeCaches>();
+            if let Some(deep_digests) = caches.deep_digests.get() {
+                size += deep_digests.capacity() * size_of::<Digest>();
+            }
+            for index in [caches.predicate_index.get(), caches.digest_index.get()].into_iter().flatten() {
+                size += index.capacity() * (size_of::<Digest>() + size_of::<Vec<usize>>());
+                size += index.values().map(|indexes| indexes.capacity() * size_of::<usize>()).sum::<usize>();
+            }
+        }
+        match self.case() {
+            EnvelopeCase::Node { subject, assertions, .. } => {
+                size += assertions.capacity() * size_of::<Envelope>();
+                size += subject.heap_size_estimate_visiting(visited);
+                for assertion in assertions {
+                    size += assertion.heap_size_estimate_visiting(visited);
+                }
+            }
+            EnvelopeCase::Leaf { cbor, .. } => {
+                size += cbor.to_cbor_data().len();
+            }
+            EnvelopeCase::Wrapped { envelope, .. } => {
+                size += envelope.heap_size_estimate_visiting(visited);
+            }
+            EnvelopeCase::Assertion(assertion) => {
+                size += assertion.predicate().heap_size_estimate_visiting(visited);
+                size += assertion.object().heap_size_estimate_visiting(visited);
+            }
+            EnvelopeCase::Elided(_) => {}
+            #[cfg(feature = "known_value")]
+            EnvelopeCase::KnownValue { .. } => {}
+            #[cfg(feature = "encrypt")]
+            EnvelopeCase::Encrypted(encrypted_message) => {
+                size += encrypted_message.ciphertext().capacity();
+                size += encrypted_message.aad().capacity();
+            }
+            #[cfg(feature = "compress")]
+            EnvelopeCase::Compressed(compressed) => {
+                size += compressed.compressed_size();
+            }
+        }
+        size
+    }
+}
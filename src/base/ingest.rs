@@ -0,0 +1,286 @@
+use bc_components::tags;
+use bc_ur::bytewords::{self, Style};
+use dcbor::prelude::*;
+use thiserror::Error;
+
+use crate::Envelope;
+
+/// The UR type of an envelope.
+const ENVELOPE_UR_TYPE: &str = "envelope";
+
+/// An upper bound on the number of characters of a UR string that do not
+/// encode the CBOR data, including its checksum, divided by two.
+const MAX_UR_OVERHEAD: usize = 32;
+
+/// Error returned when ingesting an envelope from untrusted data with
+/// [`Envelope::ingest_cbor_data`] or [`Envelope::ingest_ur_string`].
+///
+/// Byte offsets are into the CBOR data, which for a UR is the data decoded
+/// from its bytewords. They are provided where the offending byte is known.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum IngestError {
+    #[error("invalid UR: {0}")]
+    InvalidUR(String),
+
+    #[error("expected UR type `{expected}`, found `{found}`")]
+    URTypeMismatch { expected: String, found: String },
+
+    #[error("malformed CBOR{}: {message}", at(.offset))]
+    MalformedCBOR { offset: Option<usize>, message: String },
+
+    #[error("expected tag {expected}, found {}", tag(.found))]
+    TagMismatch { expected: u64, found: Option<u64> },
+
+    #[error("{limit} limit of {max} exceeded{}", at(.offset))]
+    LimitExceeded { limit: &'static str, max: usize, offset: Option<usize> },
+
+    #[error("invalid envelope: {0}")]
+    InvalidEnvelope(String),
+}
+
+fn at(offset: &Option<usize>) -> String {
+    offset.map_or_else(String::new, |offset| format!(" at byte {}", offset))
+}
+
+fn tag(found: &Option<u64>) -> String {
+    found.map_or_else(|| "untagged data".to_string(), |tag| format!("tag {}", tag))
+}
+
+/// Limits on the untrusted data accepted when ingesting an envelope, and how
+/// leniently it is decoded.
+///
+/// The defaults accept envelopes of up to 16 MiB nested up to 256 levels of
+/// CBOR deep, which is far deeper than any envelope seen in practice but
+/// shallow enough to decode without exhausting the stack. By default, decoding
+/// is strict.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IngestLimits {
+    max_bytes: usize,
+    max_depth: usize,
+    lenient: bool,
+}
+
+impl IngestLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the limits with the given maximum size of the CBOR data.
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Returns the limits with the given maximum nesting depth of CBOR arrays,
+    /// maps, and tags.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Returns the limits with lenient decoding turned on or off.
+    ///
+    /// Lenient decoding accepts envelopes containing elements that this build
+    /// does not support, such as encrypted elements when the `encrypt` feature
+    /// is disabled, or elements with tags introduced by later versions of the
+    /// format, so that older clients can still process the rest of newer
+    /// documents. Unsupported elements with digests are decoded as elided
+    /// elements, and elements with unrecognized tags as opaque leaves, whose
+    /// digests differ from the originals.
+    pub fn with_lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
+    /// The maximum size of the CBOR data.
+    pub fn max_bytes(&self) -> usize {
+        self.max_bytes
+    }
+
+    /// The maximum nesting depth of CBOR arrays, maps, and tags.
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// Whether unsupported elements are decoded rather than rejected.
+    pub fn is_lenient(&self) -> bool {
+        self.lenient
+    }
+}
+
+impl Default for IngestLimits {
+    fn default() -> Self {
+        Self { max_bytes: 16 * 1024 * 1024, max_depth: 256, lenient: false }
+    }
+}
+
+/// Support for decoding envelopes from untrusted data.
+impl Envelope {
+    /// Decodes an envelope from tagged CBOR data received from an untrusted
+    /// source.
+    ///
+    /// Unlike [`CBORTaggedDecodable::from_tagged_cbor_data`], the data is
+    /// checked against `limits` before it is decoded, and every failure is
+    /// reported as an [`IngestError`]. No input causes a panic.
+    pub fn ingest_cbor_data(data: &[u8], limits: &IngestLimits) -> Result<Self, IngestError> {
+        let cbor = decode_cbor(data, limits)?;
+        let CBORCase::Tagged(tag, item) = cbor.into_case() else {
+            return Err(IngestError::TagMismatch { expected: tags::TAG_ENVELOPE, found: None });
+        };
+        if tag.value() != tags::TAG_ENVELOPE {
+            return Err(IngestError::TagMismatch { expected: tags::TAG_ENVELOPE, found: Some(tag.value()) });
+        }
+        decode_envelope(item, limits)
+    }
+
+    /// Decodes an envelope from a UR string received from an untrusted source.
+    ///
+    /// Unlike [`URDecodable::from_ur_string`](bc_ur::URDecodable), this does
+    /// not depend on the global tags being registered, the data is checked
+    /// against `limits` before it is decoded, and every failure is reported as
+    /// an [`IngestError`]. No input causes a panic.
+    pub fn ingest_ur_string(ur_string: &str, limits: &IngestLimits) -> Result<Self, IngestError> {
+        // Each byte is encoded as two characters, so reject strings that are
+        // too long to hold an acceptable envelope before decoding them.
+        if ur_string.len() > limits.max_bytes.saturating_add(MAX_UR_OVERHEAD).saturating_mul(2) {
+            return Err(IngestError::LimitExceeded { limit: "size", max: limits.max_bytes, offset: None });
+        }
+        let ur_string = ur_string.to_lowercase();
+        let Some(body) = ur_string.strip_prefix("ur:") else {
+            return Err(IngestError::InvalidUR("missing `ur:` scheme".to_string()));
+        };
+        let Some((ur_type, bytewords)) = body.split_once('/') else {
+            return Err(IngestError::InvalidUR("missing UR type".to_string()));
+        };
+        if ur_type != ENVELOPE_UR_TYPE {
+            return Err(IngestError::URTypeMismatch {
+                expected: ENVELOPE_UR_TYPE.to_string(),
+                found: ur_type.to_string(),
+            });
+        }
+        if bytewords.contains('/') {
+            return Err(IngestError::InvalidUR("multipart URs are not supported".to_string()));
+        }
+        let data = bytewords::decode(bytewords, Style::Minimal)
+            .map_err(|error| IngestError::InvalidUR(error.to_string()))?;
+        decode_envelope(decode_cbor(&data, limits)?, limits)
+    }
+}
+
+pub(crate) fn decode_cbor(data: &[u8], limits: &IngestLimits) -> Result<CBOR, IngestError> {
+    if data.len() > limits.max_bytes {
+        return Err(IngestError::LimitExceeded { limit: "size", max: limits.max_bytes, offset: None });
+    }
+    scan(data, limits.max_depth)?;
+    CBOR::try_from_data(data).map_err(|error| IngestError::MalformedCBOR { offset: None, message: error.to_string() })
+}
+
+fn decode_envelope(untagged_cbor: CBOR, limits: &IngestLimits) -> Result<Envelope, IngestError> {
+    Envelope::decode(untagged_cbor, limits.lenient, limits.max_depth).map_err(|error| IngestError::InvalidEnvelope(error.to_string()))
+}
+
+/// Checks the structure of CBOR data without decoding it, so that truncated
+/// or excessively nested data is rejected with the offset of the offending
+/// item before the recursive decoder sees it.
+///
+/// Only the item headers and lengths are checked; the decoder performs the
+/// remaining validation.
+fn scan(data: &[u8], max_depth: usize) -> Result<(), IngestError> {
+    let offset = item_len(data, max_depth)?;
+    if offset < data.len() {
+        return Err(IngestError::MalformedCBOR {
+            offset: Some(offset),
+            message: "unexpected data after end of envelope".to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Returns the length of the CBOR item at the start of `data`, checking its
+/// structure as [`scan`] does but allowing data after it, as in a CBOR
+/// sequence.
+pub(crate) fn item_len(data: &[u8], max_depth: usize) -> Result<usize, IngestError> {
+    let malformed = |offset: usize, message: &str| IngestError::MalformedCBOR {
+        offset: Some(offset),
+        message: message.to_string(),
+    };
+    // The number of items remaining in each enclosing array, map, or tag.
+    let mut remaining: Vec<u64> = vec![1];
+    let mut offset = 0;
+    while let Some(count) = remaining.last_mut() {
+        if *count == 0 {
+            remaining.pop();
+            continue;
+        }
+        *count -= 1;
+        let start = offset;
+        let Some(&initial) = data.get(offset) else {
+            return Err(malformed(start, "unexpected end of data"));
+        };
+        offset += 1;
+        let major_type = initial >> 5;
+        let argument = match initial & 0x1f {
+            info @ 0..=23 => info as u64,
+            info @ 24..=27 => {
+                let length = 1 << (info - 24);
+                let Some(bytes) = data.get(offset..offset + length) else {
+                    return Err(malformed(start, "unexpected end of data"));
+                };
+                offset += length;
+                bytes.iter().fold(0, |argument, byte| (argument << 8) | *byte as u64)
+            },
+            _ => return Err(malformed(start, "unsupported value in header")),
+        };
+        let items = match major_type {
+            2 | 3 => {
+                if argument > (data.len() - offset) as u64 {
+                    return Err(malformed(start, "string extends past end of data"));
+                }
+                offset += argument as usize;
+                0
+            },
+            4 => argument,
+            5 => argument.saturating_mul(2),
+            6 => 1,
+            _ => 0,
+        };
+        if items > (data.len() - offset) as u64 {
+            return Err(malformed(start, "container extends past end of data"));
+        }
+        if matches!(major_type, 4..=6) {
+            if remaining.len() > max_depth {
+                return Err(IngestError::LimitExceeded { limit: "depth", max: max_depth, offset: Some(start) });
+            }
+            remaining.push(items);
+        }
+    }
+    Ok(offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan() {
+        assert_eq!(scan(&[0x82, 0x01, 0x02], 1), Ok(()));
+        assert_eq!(
+            scan(&[0x82, 0x01], 1),
+            Err(IngestError::MalformedCBOR { offset: Some(0), message: "container extends past end of data".to_string() })
+        );
+        assert_eq!(
+            scan(&[0x82, 0x01, 0x18], 1),
+            Err(IngestError::MalformedCBOR { offset: Some(2), message: "unexpected end of data".to_string() })
+        );
+        assert_eq!(
+            scan(&[0x81, 0x81, 0x01], 1),
+            Err(IngestError::LimitExceeded { limit: "depth", max: 1, offset: Some(1) })
+        );
+        assert_eq!(
+            scan(&[0x01, 0x02], 1),
+            Err(IngestError::MalformedCBOR { offset: Some(1), message: "unexpected data after end of envelope".to_string() })
+        );
+        assert!(scan(&[0x5b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff], 1).is_err());
+        assert!(scan(&[0x9f], 1).is_err());
+    }
+}
@@ -0,0 +1,237 @@
+use std::{any::Any, collections::HashMap, sync::Arc};
+
+use anyhow::{anyhow, bail, Result};
+use dcbor::prelude::*;
+
+use crate::{Envelope, EnvelopeError, FormatContext};
+
+use super::envelope::EnvelopeCase;
+
+/// A function that checks the untagged content of a leaf with a registered tag.
+pub type LeafValidator = Arc<dyn Fn(&CBOR) -> Result<()> + Send + Sync>;
+
+/// A function that decodes the untagged content of a leaf with a registered
+/// tag into a domain type.
+pub type LeafExtractor = Arc<dyn Fn(CBOR) -> Result<Box<dyn Any + Send + Sync>> + Send + Sync>;
+
+/// Describes how envelopes should treat leaves carrying a particular CBOR tag.
+///
+/// Downstream crates can use adapters to integrate their own tagged types
+/// (for example PSBTs or output descriptors) with envelope formatting,
+/// extraction, and validation, without this crate knowing about the types.
+/// Register an adapter with [`register_leaf_tag_adapter`] or
+/// [`register_leaf_tag_adapter_in`].
+#[derive(Clone)]
+pub struct LeafTagAdapter {
+    tag: Tag,
+    summarizer: Option<CBORSummarizer>,
+    validator: Option<LeafValidator>,
+    extractor: Option<LeafExtractor>,
+}
+
+impl LeafTagAdapter {
+    /// Creates an adapter for the given tag, which should have a name.
+    pub fn new(tag: Tag) -> Self {
+        Self {
+            tag,
+            summarizer: None,
+            validator: None,
+            extractor: None,
+        }
+    }
+
+    /// Sets the function used to summarize leaves with this tag when
+    /// formatting envelopes.
+    pub fn with_summarizer(
+        mut self,
+        summarizer: impl Fn(CBOR) -> Result<String> + Send + Sync + 'static,
+    ) -> Self {
+        self.summarizer = Some(Arc::new(summarizer));
+        self
+    }
+
+    /// Sets the function used to check leaves with this tag in
+    /// [`Envelope::validate_leaf_tags`].
+    pub fn with_validator(
+        mut self,
+        validator: impl Fn(&CBOR) -> Result<()> + Send + Sync + 'static,
+    ) -> Self {
+        self.validator = Some(Arc::new(validator));
+        self
+    }
+
+    /// Sets the function used to decode leaves with this tag in
+    /// [`Envelope::extract_adapted_subject`].
+    pub fn with_extractor<T>(
+        mut self,
+        extractor: impl Fn(CBOR) -> Result<T> + Send + Sync + 'static,
+    ) -> Self
+    where
+        T: Any + Send + Sync,
+    {
+        self.extractor = Some(Arc::new(move |cbor| {
+            Ok(Box::new(extractor(cbor)?) as Box<dyn Any + Send + Sync>)
+        }));
+        self
+    }
+
+    /// The tag this adapter handles.
+    pub fn tag(&self) -> &Tag {
+        &self.tag
+    }
+
+    /// The summarizer, if any.
+    pub fn summarizer(&self) -> Option<&CBORSummarizer> {
+        self.summarizer.as_ref()
+    }
+
+    /// The validator, if any.
+    pub fn validator(&self) -> Option<&LeafValidator> {
+        self.validator.as_ref()
+    }
+
+    /// The extractor, if any.
+    pub fn extractor(&self) -> Option<&LeafExtractor> {
+        self.extractor.as_ref()
+    }
+}
+
+impl std::fmt::Debug for LeafTagAdapter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LeafTagAdapter")
+            .field("tag", &self.tag)
+            .field("summarizer", &self.summarizer.is_some())
+            .field("validator", &self.validator.is_some())
+            .field("extractor", &self.extractor.is_some())
+            .finish()
+    }
+}
+
+/// A type that maps tag values to their registered leaf tag adapters.
+#[derive(Clone, Debug, Default)]
+pub struct LeafTagAdaptersStore {
+    adapters_by_tag_value: HashMap<TagValue, LeafTagAdapter>,
+}
+
+impl LeafTagAdaptersStore {
+    pub fn new<T>(adapters: T) -> Self
+    where
+        T: IntoIterator<Item = LeafTagAdapter>,
+    {
+        let mut store = Self::default();
+        for adapter in adapters {
+            store.insert(adapter);
+        }
+        store
+    }
+
+    pub fn insert(&mut self, adapter: LeafTagAdapter) {
+        self.adapters_by_tag_value.insert(adapter.tag().value(), adapter);
+    }
+
+    pub fn adapter(&self, tag_value: TagValue) -> Option<&LeafTagAdapter> {
+        self.adapters_by_tag_value.get(&tag_value)
+    }
+}
+
+/// Registers a leaf tag adapter in the given format context.
+///
+/// The adapter's tag and summarizer are also registered in the context's tags
+/// store, so formatted envelopes show leaves with the tag using its name and
+/// summarizer.
+pub fn register_leaf_tag_adapter_in(context: &mut FormatContext, adapter: LeafTagAdapter) {
+    context.tags_mut().insert(adapter.tag().clone());
+    if let Some(summarizer) = adapter.summarizer() {
+        context.tags_mut().set_summarizer(adapter.tag().value(), summarizer.clone());
+    }
+    context.leaf_tag_adapters_mut().insert(adapter);
+}
+
+/// Registers a leaf tag adapter in the global format context.
+pub fn register_leaf_tag_adapter(adapter: LeafTagAdapter) {
+    crate::with_format_context_mut!(|context: &mut FormatContext| {
+        register_leaf_tag_adapter_in(context, adapter);
+    });
+}
+
+/// Support for leaves with registered tag adapters.
+impl Envelope {
+    /// Checks every tagged leaf in the envelope that has a registered
+    /// validator in the global format context.
+    ///
+    /// Returns the first validation error encountered.
+    pub fn validate_leaf_tags(&self) -> Result<()> {
+        crate::with_format_context!(|context| self.validate_leaf_tags_opt(Some(context)))
+    }
+
+    /// Checks every tagged leaf in the envelope that has a registered
+    /// validator in the given format context.
+    ///
+    /// Returns the first validation error encountered.
+    pub fn validate_leaf_tags_opt(&self, context: Option<&FormatContext>) -> Result<()> {
+        let Some(context) = context else {
+            return Ok(());
+        };
+        match self.case() {
+            EnvelopeCase::Node { subject, assertions, .. } => {
+                subject.validate_leaf_tags_opt(Some(context))?;
+                for assertion in assertions {
+                    assertion.validate_leaf_tags_opt(Some(context))?;
+                }
+            }
+            EnvelopeCase::Wrapped { envelope, .. } => {
+                envelope.validate_leaf_tags_opt(Some(context))?;
+            }
+            EnvelopeCase::Assertion(assertion) => {
+                assertion.predicate().validate_leaf_tags_opt(Some(context))?;
+                assertion.object().validate_leaf_tags_opt(Some(context))?;
+            }
+            EnvelopeCase::Leaf { cbor, .. } => {
+                if let CBORCase::Tagged(tag, content) = cbor.as_case() {
+                    let validator = context
+                        .leaf_tag_adapters()
+                        .adapter(tag.value())
+                        .and_then(|adapter| adapter.validator());
+                    if let Some(validator) = validator {
+                        validator(content)?;
+                    }
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Decodes the envelope's subject using the extractor registered for its
+    /// tag in the global format context.
+    pub fn extract_adapted_subject<T>(&self) -> Result<T>
+    where
+        T: Any + Send + Sync,
+    {
+        crate::with_format_context!(|context| self.extract_adapted_subject_opt(Some(context)))
+    }
+
+    /// Decodes the envelope's subject using the extractor registered for its
+    /// tag in the given format context.
+    ///
+    /// Returns an error if the subject is not a tagged leaf, if no extractor
+    /// is registered for its tag, or if the extractor produces a type other
+    /// than `T`.
+    pub fn extract_adapted_subject_opt<T>(&self, context: Option<&FormatContext>) -> Result<T>
+    where
+        T: Any + Send + Sync,
+    {
+        let cbor = self.subject().try_leaf()?;
+        let CBORCase::Tagged(tag, content) = cbor.as_case() else {
+            bail!(EnvelopeError::InvalidFormat);
+        };
+        let extractor = context
+            .and_then(|context| context.leaf_tag_adapters().adapter(tag.value()))
+            .and_then(|adapter| adapter.extractor())
+            .ok_or_else(|| anyhow!("no extractor registered for tag {}", tag.value()))?;
+        extractor(content.clone())?
+            .downcast::<T>()
+            .map(|value| *value)
+            .map_err(|_| anyhow!("extractor for tag {} produced a different type", tag.value()))
+    }
+}
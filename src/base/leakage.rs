@@ -0,0 +1,130 @@
+use bc_components::{Digest, DigestProvider};
+use dcbor::prelude::*;
+
+use crate::Envelope;
+
+use super::envelope::EnvelopeCase;
+
+/// What one node of an obscured envelope reveals about its assertions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeLeakage {
+    /// The digest of the node.
+    pub digest: Digest,
+    /// The depth of the node in the envelope's tree, with the root at zero.
+    pub depth: usize,
+    /// The number of assertions on the node, obscured or not.
+    pub assertion_count: usize,
+    /// The number of the node's assertions that are obscured.
+    pub obscured_assertion_count: usize,
+}
+
+/// The structural metadata an obscured envelope still reveals.
+///
+/// Elision hides the content of elements but not the shape of the tree that
+/// holds them: a verifier can count the assertions withheld on each node,
+/// see how deeply the visible elements are nested, and learn the size of
+/// encrypted and compressed elements from their ciphertexts. Returned by
+/// [`Envelope::leakage_report`], so that the leakage of a redaction can be
+/// measured, and reduced, for example by padding nodes with decoy
+/// assertions using [`Envelope::pad_assertions`] before eliding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LeakageReport {
+    encoded_size: usize,
+    max_depth: usize,
+    visible_elements: usize,
+    obscured_elements: usize,
+    nodes: Vec<NodeLeakage>,
+    obscured_sizes: Vec<(Digest, usize)>,
+}
+
+impl LeakageReport {
+    /// The size of the envelope's tagged CBOR encoding.
+    pub fn encoded_size(&self) -> usize {
+        self.encoded_size
+    }
+
+    /// The depth of the most deeply nested element, obscured or not.
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// The number of elements that are not obscured.
+    pub fn visible_elements(&self) -> usize {
+        self.visible_elements
+    }
+
+    /// The number of elided, encrypted, or compressed elements.
+    pub fn obscured_elements(&self) -> usize {
+        self.obscured_elements
+    }
+
+    /// Each visible node, in depth-first order.
+    pub fn nodes(&self) -> &[NodeLeakage] {
+        &self.nodes
+    }
+
+    /// The visible nodes that have obscured assertions, and so reveal how
+    /// many assertions were withheld.
+    pub fn nodes_with_obscured_assertions(&self) -> impl Iterator<Item = &NodeLeakage> {
+        self.nodes.iter().filter(|node| node.obscured_assertion_count > 0)
+    }
+
+    /// The total number of obscured assertions on visible nodes.
+    pub fn obscured_assertions(&self) -> usize {
+        self.nodes.iter().map(|node| node.obscured_assertion_count).sum()
+    }
+
+    /// The digest and encoded size of each encrypted or compressed element,
+    /// whose size reveals the approximate size of its content. Elided
+    /// elements are all the same size, so they are not included.
+    pub fn obscured_sizes(&self) -> &[(Digest, usize)] {
+        &self.obscured_sizes
+    }
+}
+
+/// Support for measuring what obscured envelopes reveal.
+impl Envelope {
+    /// Returns a report of the structural metadata that this envelope
+    /// reveals despite its obscured elements.
+    pub fn leakage_report(&self) -> LeakageReport {
+        let mut report = LeakageReport {
+            encoded_size: self.tagged_cbor_data().len(),
+            max_depth: 0,
+            visible_elements: 0,
+            obscured_elements: 0,
+            nodes: Vec::new(),
+            obscured_sizes: Vec::new(),
+        };
+        let mut stack = vec![(self.clone(), 0)];
+        while let Some((envelope, depth)) = stack.pop() {
+            report.max_depth = report.max_depth.max(depth);
+            if envelope.is_obscured() {
+                report.obscured_elements += 1;
+                if !envelope.is_elided() {
+                    report.obscured_sizes.push((envelope.digest().into_owned(), envelope.tagged_cbor_data().len()));
+                }
+                continue;
+            }
+            report.visible_elements += 1;
+            match envelope.case() {
+                EnvelopeCase::Node { subject, assertions, .. } => {
+                    report.nodes.push(NodeLeakage {
+                        digest: envelope.digest().into_owned(),
+                        depth,
+                        assertion_count: assertions.len(),
+                        obscured_assertion_count: assertions.iter().filter(|assertion| assertion.is_obscured()).count(),
+                    });
+                    stack.extend(assertions.iter().rev().map(|assertion| (assertion.clone(), depth + 1)));
+                    stack.push((subject.clone(), depth + 1));
+                }
+                EnvelopeCase::Assertion(assertion) => {
+                    stack.push((assertion.object().clone(), depth + 1));
+                    stack.push((assertion.predicate().clone(), depth + 1));
+                }
+                EnvelopeCase::Wrapped { envelope: inner, .. } => stack.push((inner.clone(), depth + 1)),
+                _ => {}
+            }
+        }
+        report
+    }
+}
@@ -0,0 +1,64 @@
+use bc_components::{Digest, DigestProvider};
+
+use crate::{Assertion, Envelope, EnvelopeVisitor};
+
+/// A likely mistake found in an envelope by [`Envelope::lint`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnvelopeLint {
+    /// The node with the given digest has assertions in the clear on an
+    /// elided subject.
+    AssertionsOnElidedSubject(Digest),
+}
+
+impl std::fmt::Display for EnvelopeLint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EnvelopeLint::AssertionsOnElidedSubject(digest) => {
+                write!(f, "assertions on elided subject at {}", digest.short_description())
+            }
+        }
+    }
+}
+
+struct Linter {
+    lints: Vec<EnvelopeLint>,
+}
+
+impl EnvelopeVisitor for Linter {
+    fn visit_node(&mut self, envelope: &Envelope, subject: &Envelope, assertions: &[Envelope]) {
+        if subject.is_elided() && assertions.iter().any(|assertion| !assertion.is_obscured()) {
+            self.lints.push(EnvelopeLint::AssertionsOnElidedSubject(envelope.digest().into_owned()));
+        }
+        subject.visit(self);
+        for assertion in assertions {
+            assertion.visit(self);
+        }
+    }
+
+    fn visit_wrapped(&mut self, _envelope: &Envelope, wrapped: &Envelope) {
+        wrapped.visit(self);
+    }
+
+    fn visit_assertion(&mut self, _envelope: &Envelope, assertion: &Assertion) {
+        assertion.predicate().visit(self);
+        assertion.object().visit(self);
+    }
+}
+
+/// Support for detecting likely mistakes in envelopes.
+impl Envelope {
+    /// Returns the likely mistakes found anywhere in the envelope, in
+    /// depth-first order.
+    ///
+    /// Currently this reports each node whose subject is elided but which has
+    /// assertions that are not, which usually means assertions were added
+    /// after the subject was elided rather than before. Such a node is also
+    /// produced by eliding the subject of an envelope while revealing some of
+    /// its assertions, and by [`Envelope::add_assertion_to_obscured`], so a
+    /// lint is not necessarily an error.
+    pub fn lint(&self) -> Vec<EnvelopeLint> {
+        let mut linter = Linter { lints: Vec::new() };
+        self.visit(&mut linter);
+        linter.lints
+    }
+}
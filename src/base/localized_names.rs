@@ -0,0 +1,66 @@
+use std::collections::HashMap;
+
+use crate::FormatContext;
+
+/// Translated display names for known values and functions.
+///
+/// When a [`FormatContext`] carries localized names, formatted envelopes show
+/// them in place of the canonical names, so a user interface can render
+/// `'isA'` or `'issuer'` in the user's language. Localized names are used only
+/// for display: the canonical names are still the ones used to look up known
+/// values and functions by name.
+#[derive(Clone, Debug, Default)]
+pub struct LocalizedNames {
+    known_values: HashMap<u64, String>,
+    functions: HashMap<u64, String>,
+}
+
+impl LocalizedNames {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the display name for the known value with the given raw value.
+    pub fn with_known_value_name(mut self, value: u64, name: impl Into<String>) -> Self {
+        self.known_values.insert(value, name.into());
+        self
+    }
+
+    /// Sets the display name for the known function with the given value.
+    pub fn with_function_name(mut self, value: u64, name: impl Into<String>) -> Self {
+        self.functions.insert(value, name.into());
+        self
+    }
+
+    /// The display name for the known value with the given raw value, if one
+    /// has been set.
+    pub fn known_value_name(&self, value: u64) -> Option<&str> {
+        self.known_values.get(&value).map(|name| name.as_str())
+    }
+
+    /// The display name for the known function with the given value, if one
+    /// has been set.
+    pub fn function_name(&self, value: u64) -> Option<&str> {
+        self.functions.get(&value).map(|name| name.as_str())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.known_values.is_empty() && self.functions.is_empty()
+    }
+}
+
+/// Sets the localized names used by the given format context.
+///
+/// The context's tag summarizers are registered again so that they also use
+/// the localized names.
+pub fn set_localized_names_in(context: &mut FormatContext, names: LocalizedNames) {
+    *context.localized_names_mut() = names;
+    super::format_context::register_summarizers_in(context);
+}
+
+/// Sets the localized names used by the global format context.
+pub fn set_localized_names(names: LocalizedNames) {
+    crate::with_format_context_mut!(|context: &mut FormatContext| {
+        set_localized_names_in(context, names);
+    });
+}
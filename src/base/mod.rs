@@ -1,7 +1,13 @@
 pub mod assertion;
 pub mod assertions;
+pub mod assertion_validator;
+pub use assertion_validator::{
+    validate_assertion,
+    AssertionValidator,
+};
 pub mod cbor;
 pub mod digest;
+pub use digest::EnvelopePath;
 pub mod envelope;
 
 /// Types dealing with elision.
@@ -10,6 +16,8 @@ pub mod envelope;
 pub mod elide;
 
 pub mod error;
+pub mod error_context;
+pub use error_context::{error_context_length, set_error_context_length, ErrorContext};
 
 pub mod envelope_encodable;
 pub use envelope_encodable::EnvelopeEncodable;
@@ -18,10 +26,25 @@ pub mod envelope_decodable;
 
 pub mod queries;
 
+pub mod leaf_tag_adapter;
+pub use leaf_tag_adapter::{
+    register_leaf_tag_adapter,
+    register_leaf_tag_adapter_in,
+    LeafTagAdapter,
+    LeafTagAdaptersStore,
+};
+
+pub mod localized_names;
+pub use localized_names::{set_localized_names, set_localized_names_in, LocalizedNames};
+
 /// Types dealing with formatting envelopes.
 pub mod format;
 pub mod format_context;
 pub use format_context::*;
+pub mod format_version;
+pub use format_version::FormatVersion;
+pub mod digest_namer;
+pub use digest_namer::{DigestDisplayFormat, DigestNamer, Petnames};
 pub mod tree_format;
 
 /// Types dealing with recursive walking of envelopes.
@@ -29,6 +52,52 @@ pub mod tree_format;
 /// The [`Envelope`] type itself has functions for walking envelopes.
 pub mod walk;
 
+pub mod frozen;
+pub use frozen::FrozenEnvelope;
+
+pub mod heap_size;
+pub mod ingest;
+pub use ingest::{IngestError, IngestLimits};
+pub mod cbor_seq;
+pub use cbor_seq::{write_indexed_envelope_seq, EnvelopeSeqIter, EnvelopeSeqReader, EnvelopeSeqWriter};
+pub mod lint;
+pub use lint::EnvelopeLint;
+pub mod spec;
+pub use spec::{SpecFinding, SpecRequirement};
+pub mod reveal_token;
+pub use reveal_token::RevealToken;
+pub mod leakage;
+pub use leakage::{LeakageReport, NodeLeakage};
+pub mod multipart;
+pub use multipart::{MultipartEnvelopeDecoder, MultipartProgress};
+pub mod placeholder;
+pub use placeholder::{LeafKind, LeafShape, PlaceholderDetail, PlaceholderPolicy};
+pub mod structural_fingerprint;
+pub use structural_fingerprint::StructuralFingerprint;
+
+pub mod transform;
+pub mod store;
+pub mod forest;
+pub use forest::Forest;
+pub use store::{search_store, EnvelopeMatcher, EnvelopeStore};
+pub mod pattern;
+pub use pattern::{DigestPattern, NodePattern, ObscuredPattern, Pattern};
+pub mod query_cache;
+pub use query_cache::QueryCache;
+pub mod table;
+pub use table::{ColumnType, TableMapping};
+pub mod dates;
+pub use dates::envelopes_with_date_in_range;
+pub mod time_policy;
+pub use time_policy::{Clock, FixedClock, SystemClock, TimePolicy};
+
+pub mod capabilities;
+pub use capabilities::{capabilities, has_capability, require_capability};
+
+pub mod round_trip;
+pub use round_trip::{is_round_trip_checking, set_round_trip_checking};
+
+pub mod streaming_bytes;
 pub mod wrap;
 pub mod envelope_summary;
 
@@ -37,3 +106,4 @@ pub use envelope::Envelope;
 pub use error::EnvelopeError;
 pub use format_context::{FormatContext, GLOBAL_FORMAT_CONTEXT};
 pub use envelope_summary::EnvelopeSummary;
+pub use walk::{EdgeType, EnvelopeVisitor, WalkEvent};
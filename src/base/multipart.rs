@@ -0,0 +1,156 @@
+use std::collections::HashSet;
+
+use anyhow::{bail, Error, Result};
+use bc_ur::{MultipartDecoder, UR};
+use dcbor::prelude::*;
+
+use crate::{Envelope, EnvelopeError};
+
+/// The UR type of an envelope.
+const ENVELOPE_UR_TYPE: &str = "envelope";
+
+/// How far a [`MultipartEnvelopeDecoder`] has got in assembling an envelope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MultipartProgress {
+    /// The number of distinct parts received.
+    pub parts_received: usize,
+    /// The number of fragments the envelope was split into, which is the
+    /// fewest parts that can complete it, or `None` if no part has been
+    /// received.
+    pub parts_needed: Option<usize>,
+    /// Whether the envelope has been assembled.
+    pub is_complete: bool,
+}
+
+impl MultipartProgress {
+    /// The estimated fraction of the envelope received, from 0.0 to 1.0.
+    ///
+    /// Fountain-coded parts each combine several fragments, so a scan may
+    /// need somewhat more parts than `parts_needed`; the estimate stays just
+    /// short of 1.0 until the envelope is complete.
+    pub fn fraction(&self) -> f64 {
+        if self.is_complete {
+            return 1.0;
+        }
+        match self.parts_needed {
+            Some(needed) if needed > 0 => (self.parts_received as f64 / needed as f64).min(0.99),
+            _ => 0.0,
+        }
+    }
+
+    /// The estimated percentage of the envelope received, from 0 to 100.
+    pub fn percent_complete(&self) -> f64 {
+        self.fraction() * 100.0
+    }
+}
+
+/// Assembles an envelope from the parts of a multipart `ur:envelope`, such as
+/// the frames of an animated QR code, reporting its progress as it goes.
+///
+/// The decoder's state is the parts it has received, so it can be saved by
+/// converting it to CBOR and restored by converting the CBOR back, letting a
+/// long scan resume after the app is relaunched.
+#[derive(Default)]
+pub struct MultipartEnvelopeDecoder {
+    decoder: MultipartDecoder,
+    parts: Vec<String>,
+    sequence_numbers: HashSet<u64>,
+    parts_needed: Option<usize>,
+    envelope: Option<Envelope>,
+}
+
+impl std::fmt::Debug for MultipartEnvelopeDecoder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MultipartEnvelopeDecoder")
+            .field("progress", &self.progress())
+            .finish()
+    }
+}
+
+impl MultipartEnvelopeDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Receives a part, returning the progress made so far.
+    ///
+    /// A single-part `ur:envelope` completes the decoder at once. Parts
+    /// received again, and parts received after the envelope is complete,
+    /// are ignored. Returns an error if the part is not a `ur:envelope` part,
+    /// or belongs to a different envelope than the parts before it.
+    pub fn receive(&mut self, part: &str) -> Result<MultipartProgress> {
+        if self.envelope.is_some() {
+            return Ok(self.progress());
+        }
+        let part = part.trim().to_lowercase();
+        let Some((ur_type, rest)) = part.strip_prefix("ur:").and_then(|body| body.split_once('/')) else {
+            bail!(EnvelopeError::InvalidFormat);
+        };
+        if ur_type != ENVELOPE_UR_TYPE {
+            bail!(EnvelopeError::InvalidFormat);
+        }
+        let Some((sequence, _)) = rest.split_once('/') else {
+            let ur = UR::from_ur_string(&part)?;
+            self.envelope = Some(Envelope::from_untagged_cbor(ur.cbor())?);
+            self.parts.push(part);
+            return Ok(self.progress());
+        };
+        let Some((number, count)) = sequence
+            .split_once('-')
+            .and_then(|(number, count)| Some((number.parse::<u64>().ok()?, count.parse::<usize>().ok()?)))
+        else {
+            bail!(EnvelopeError::InvalidFormat);
+        };
+        if self.parts_needed.is_some_and(|needed| needed != count) {
+            bail!(EnvelopeError::InvalidFormat);
+        }
+        if self.sequence_numbers.contains(&number) {
+            return Ok(self.progress());
+        }
+        self.decoder.receive(&part)?;
+        self.sequence_numbers.insert(number);
+        self.parts_needed = Some(count);
+        self.parts.push(part);
+        if let Some(ur) = self.decoder.message()? {
+            self.envelope = Some(Envelope::from_untagged_cbor(ur.cbor())?);
+        }
+        Ok(self.progress())
+    }
+
+    /// The progress made so far.
+    pub fn progress(&self) -> MultipartProgress {
+        MultipartProgress {
+            parts_received: self.parts.len(),
+            parts_needed: if self.envelope.is_some() { Some(self.parts_needed.unwrap_or(1)) } else { self.parts_needed },
+            is_complete: self.envelope.is_some(),
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.envelope.is_some()
+    }
+
+    /// The assembled envelope, or `None` if it is not yet complete.
+    pub fn envelope(&self) -> Option<&Envelope> {
+        self.envelope.as_ref()
+    }
+}
+
+impl From<&MultipartEnvelopeDecoder> for CBOR {
+    fn from(decoder: &MultipartEnvelopeDecoder) -> Self {
+        decoder.parts.clone().into()
+    }
+}
+
+impl TryFrom<CBOR> for MultipartEnvelopeDecoder {
+    type Error = Error;
+
+    fn try_from(cbor: CBOR) -> Result<Self> {
+        let parts: Vec<String> = cbor.try_into()?;
+        let mut decoder = Self::new();
+        for part in parts {
+            decoder.receive(&part)?;
+        }
+        Ok(decoder)
+    }
+}
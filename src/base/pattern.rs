@@ -0,0 +1,213 @@
+use std::ops::{Bound, RangeBounds};
+
+use bc_components::{Digest, DigestProvider};
+
+use crate::{Envelope, EnvelopeMatcher};
+
+use super::envelope::EnvelopeCase;
+
+/// A pattern matching nodes by the number of their assertions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodePattern {
+    min: usize,
+    max: Option<usize>,
+}
+
+impl NodePattern {
+    /// Matches any node.
+    pub fn any() -> Self {
+        Self { min: 0, max: None }
+    }
+
+    /// Matches nodes with exactly `count` assertions.
+    pub fn with_assertions_count(count: usize) -> Self {
+        Self { min: count, max: Some(count) }
+    }
+
+    /// Matches nodes whose number of assertions is within `range`.
+    pub fn with_assertions_range(range: impl RangeBounds<usize>) -> Self {
+        let min = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start.saturating_add(1),
+            Bound::Unbounded => 0,
+        };
+        let max = match range.end_bound() {
+            Bound::Included(&end) => Some(end),
+            Bound::Excluded(&end) => Some(end.saturating_sub(1)),
+            Bound::Unbounded => None,
+        };
+        Self { min, max }
+    }
+}
+
+impl std::fmt::Display for NodePattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.min, self.max) {
+            (0, None) => write!(f, "node"),
+            (min, Some(max)) if min == max => write!(f, "node({{{}}})", min),
+            (min, Some(max)) => write!(f, "node({{{},{}}})", min, max),
+            (min, None) => write!(f, "node({{{},}})", min),
+        }
+    }
+}
+
+impl EnvelopeMatcher for NodePattern {
+    fn matches(&self, element: &Envelope) -> bool {
+        let EnvelopeCase::Node { assertions, .. } = element.case() else {
+            return false;
+        };
+        assertions.len() >= self.min && self.max.is_none_or(|max| assertions.len() <= max)
+    }
+}
+
+/// A pattern matching elements by their digests.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DigestPattern {
+    /// Matches the element with exactly this digest.
+    Digest(Digest),
+    /// Matches elements whose digests begin with these bytes.
+    Prefix(Vec<u8>),
+}
+
+impl std::fmt::Display for DigestPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DigestPattern::Digest(digest) => write!(f, "digest({})", hex::encode(digest.data())),
+            DigestPattern::Prefix(prefix) => write!(f, "digest({})", hex::encode(prefix)),
+        }
+    }
+}
+
+impl EnvelopeMatcher for DigestPattern {
+    fn matches(&self, element: &Envelope) -> bool {
+        match self {
+            DigestPattern::Digest(digest) => element.digest().as_ref() == digest,
+            DigestPattern::Prefix(prefix) => element.digest().data().starts_with(prefix),
+        }
+    }
+}
+
+/// A pattern matching obscured elements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObscuredPattern {
+    /// Matches elided, encrypted, or compressed elements.
+    Any,
+    /// Matches elided elements.
+    Elided,
+    /// Matches encrypted elements.
+    #[cfg(feature = "encrypt")]
+    Encrypted,
+    /// Matches compressed elements.
+    #[cfg(feature = "compress")]
+    Compressed,
+}
+
+impl std::fmt::Display for ObscuredPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ObscuredPattern::Any => write!(f, "obscured"),
+            ObscuredPattern::Elided => write!(f, "elided"),
+            #[cfg(feature = "encrypt")]
+            ObscuredPattern::Encrypted => write!(f, "encrypted"),
+            #[cfg(feature = "compress")]
+            ObscuredPattern::Compressed => write!(f, "compressed"),
+        }
+    }
+}
+
+impl EnvelopeMatcher for ObscuredPattern {
+    fn matches(&self, element: &Envelope) -> bool {
+        match self {
+            ObscuredPattern::Any => element.is_obscured(),
+            ObscuredPattern::Elided => element.is_elided(),
+            #[cfg(feature = "encrypt")]
+            ObscuredPattern::Encrypted => element.is_encrypted(),
+            #[cfg(feature = "compress")]
+            ObscuredPattern::Compressed => element.is_compressed(),
+        }
+    }
+}
+
+/// A pattern matching the structure of envelope elements.
+///
+/// This covers the structural queries of the `bc-envelope-pattern` crate
+/// that are most often needed, so that they can be used without it. Patterns
+/// are [`EnvelopeMatcher`]s, so they can be used with
+/// [`Envelope::paths_matching`] and [`search_store`](crate::search_store).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pattern {
+    Node(NodePattern),
+    Digest(DigestPattern),
+    Obscured(ObscuredPattern),
+}
+
+impl Pattern {
+    /// Matches any node.
+    pub fn any_node() -> Self {
+        Pattern::Node(NodePattern::any())
+    }
+
+    /// Matches nodes with exactly `count` assertions.
+    pub fn node_with_assertions_count(count: usize) -> Self {
+        Pattern::Node(NodePattern::with_assertions_count(count))
+    }
+
+    /// Matches nodes whose number of assertions is within `range`.
+    pub fn node_with_assertions_range(range: impl RangeBounds<usize>) -> Self {
+        Pattern::Node(NodePattern::with_assertions_range(range))
+    }
+
+    /// Matches the element with exactly this digest.
+    pub fn digest(digest: Digest) -> Self {
+        Pattern::Digest(DigestPattern::Digest(digest))
+    }
+
+    /// Matches elements whose digests begin with `prefix`.
+    pub fn digest_prefix(prefix: impl AsRef<[u8]>) -> Self {
+        Pattern::Digest(DigestPattern::Prefix(prefix.as_ref().to_vec()))
+    }
+
+    /// Matches elided, encrypted, or compressed elements.
+    pub fn obscured() -> Self {
+        Pattern::Obscured(ObscuredPattern::Any)
+    }
+
+    /// Matches elided elements.
+    pub fn elided() -> Self {
+        Pattern::Obscured(ObscuredPattern::Elided)
+    }
+
+    /// Matches encrypted elements.
+    #[cfg(feature = "encrypt")]
+    pub fn encrypted() -> Self {
+        Pattern::Obscured(ObscuredPattern::Encrypted)
+    }
+
+    /// Matches compressed elements.
+    #[cfg(feature = "compress")]
+    pub fn compressed() -> Self {
+        Pattern::Obscured(ObscuredPattern::Compressed)
+    }
+}
+
+/// Patterns are displayed in the syntax of `bc-envelope-pattern`, which also
+/// identifies them to a [`QueryCache`](crate::QueryCache).
+impl std::fmt::Display for Pattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Pattern::Node(pattern) => pattern.fmt(f),
+            Pattern::Digest(pattern) => pattern.fmt(f),
+            Pattern::Obscured(pattern) => pattern.fmt(f),
+        }
+    }
+}
+
+impl EnvelopeMatcher for Pattern {
+    fn matches(&self, element: &Envelope) -> bool {
+        match self {
+            Pattern::Node(pattern) => pattern.matches(element),
+            Pattern::Digest(pattern) => pattern.matches(element),
+            Pattern::Obscured(pattern) => pattern.matches(element),
+        }
+    }
+}
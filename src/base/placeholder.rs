@@ -0,0 +1,312 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+use bc_components::{Digest, DigestProvider};
+use dcbor::{prelude::*, Simple};
+
+use crate::{Envelope, EnvelopeError};
+
+use super::envelope::EnvelopeCase;
+
+/// The predicate of the assertion on a placeholder whose object is the kind
+/// of value withheld, such as `"string"` or `"integer"`.
+pub const PLACEHOLDER: &str = "placeholderKind";
+
+/// The predicate of the assertion on a placeholder whose object is the exact
+/// length of the value withheld.
+pub const PLACEHOLDER_LENGTH: &str = "placeholderLength";
+
+/// The predicate of the assertion on a placeholder whose object is a bound on
+/// the length of the value withheld.
+pub const PLACEHOLDER_MAX_LENGTH: &str = "placeholderMaxLength";
+
+/// The predicate of the assertion on a placeholder whose object is the CBOR
+/// tag of a tagged value withheld.
+pub const PLACEHOLDER_TAG: &str = "placeholderTag";
+
+/// The kind of value withheld by a placeholder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LeafKind {
+    Integer,
+    Bytes,
+    String,
+    Array,
+    Map,
+    /// A tagged value, with its tag.
+    Tagged(u64),
+    Boolean,
+    Null,
+    Float,
+    KnownValue,
+}
+
+impl LeafKind {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Integer => "integer",
+            Self::Bytes => "bytes",
+            Self::String => "string",
+            Self::Array => "array",
+            Self::Map => "map",
+            Self::Tagged(_) => "tagged",
+            Self::Boolean => "boolean",
+            Self::Null => "null",
+            Self::Float => "float",
+            Self::KnownValue => "knownValue",
+        }
+    }
+
+    fn from_name(name: &str, tag: Option<u64>) -> Result<Self> {
+        let kind = match name {
+            "integer" => Self::Integer,
+            "bytes" => Self::Bytes,
+            "string" => Self::String,
+            "array" => Self::Array,
+            "map" => Self::Map,
+            "tagged" => match tag {
+                Some(tag) => Self::Tagged(tag),
+                None => bail!(EnvelopeError::InvalidFormat),
+            },
+            "boolean" => Self::Boolean,
+            "null" => Self::Null,
+            "float" => Self::Float,
+            "knownValue" => Self::KnownValue,
+            _ => bail!(EnvelopeError::InvalidFormat),
+        };
+        Ok(kind)
+    }
+}
+
+/// How much a placeholder discloses about the value it withholds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaceholderDetail {
+    /// Only the kind of value.
+    Kind,
+
+    /// The kind of value and its exact length, for strings, byte strings,
+    /// arrays, and maps.
+    Length,
+
+    /// The kind of value and its length rounded up to a multiple of the given
+    /// bucket size, so that a verifier can check an upper bound on the length
+    /// without learning it exactly.
+    LengthBound(usize),
+}
+
+/// Which leaves [`Envelope::elide_with_placeholders`] replaces, and how much
+/// each placeholder discloses.
+#[derive(Debug, Clone, Default)]
+pub struct PlaceholderPolicy {
+    targets: HashMap<Digest, PlaceholderDetail>,
+}
+
+impl PlaceholderPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces `target` with a placeholder disclosing `detail`.
+    pub fn with_target(mut self, target: &dyn DigestProvider, detail: PlaceholderDetail) -> Self {
+        self.targets.insert(target.digest().into_owned(), detail);
+        self
+    }
+
+    /// The detail disclosed for the element with the given digest, if it is
+    /// a target.
+    pub fn detail(&self, digest: &Digest) -> Option<PlaceholderDetail> {
+        self.targets.get(digest).copied()
+    }
+}
+
+/// The shape of a value withheld by a placeholder: its kind, and what is
+/// disclosed about its length.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LeafShape {
+    kind: LeafKind,
+    length: Option<usize>,
+    max_length: Option<usize>,
+}
+
+impl LeafShape {
+    /// The shape of `envelope` disclosing `detail`, or `None` if it is not a
+    /// leaf or known value.
+    pub fn of(envelope: &Envelope, detail: PlaceholderDetail) -> Option<Self> {
+        let (kind, length) = match envelope.case() {
+            EnvelopeCase::Leaf { cbor, .. } => match cbor.as_case() {
+                CBORCase::Unsigned(_) | CBORCase::Negative(_) => (LeafKind::Integer, None),
+                CBORCase::ByteString(bytes) => (LeafKind::Bytes, Some(bytes.len())),
+                CBORCase::Text(text) => (LeafKind::String, Some(text.chars().count())),
+                CBORCase::Array(array) => (LeafKind::Array, Some(array.len())),
+                CBORCase::Map(map) => (LeafKind::Map, Some(map.len())),
+                CBORCase::Tagged(tag, _) => (LeafKind::Tagged(tag.value()), None),
+                CBORCase::Simple(Simple::True | Simple::False) => (LeafKind::Boolean, None),
+                CBORCase::Simple(Simple::Null) => (LeafKind::Null, None),
+                CBORCase::Simple(Simple::Float(_)) => (LeafKind::Float, None),
+            },
+            #[cfg(feature = "known_value")]
+            EnvelopeCase::KnownValue { .. } => (LeafKind::KnownValue, None),
+            _ => return None,
+        };
+        let (length, max_length) = match detail {
+            PlaceholderDetail::Kind => (None, None),
+            PlaceholderDetail::Length => (length, None),
+            PlaceholderDetail::LengthBound(bucket) => {
+                (None, length.map(|length| length.max(1).div_ceil(bucket.max(1)) * bucket.max(1)))
+            }
+        };
+        Some(Self { kind, length, max_length })
+    }
+
+    pub fn kind(&self) -> LeafKind {
+        self.kind
+    }
+
+    /// The exact length of the value, if disclosed.
+    pub fn length(&self) -> Option<usize> {
+        self.length
+    }
+
+    /// A bound on the length of the value, if disclosed.
+    pub fn max_length(&self) -> Option<usize> {
+        self.max_length
+    }
+
+    /// Whether `envelope` has this shape, as when checking a value revealed
+    /// later against its placeholder.
+    pub fn matches(&self, envelope: &Envelope) -> bool {
+        let Some(shape) = Self::of(envelope, PlaceholderDetail::Length) else {
+            return false;
+        };
+        shape.kind == self.kind
+            && self.length.is_none_or(|length| shape.length == Some(length))
+            && self.max_length.is_none_or(|max| shape.length.is_some_and(|length| length <= max))
+    }
+
+    /// A description of the shape, such as `"string of length 24"`.
+    pub fn description(&self) -> String {
+        let kind = match self.kind {
+            LeafKind::Tagged(tag) => format!("value tagged {}", tag),
+            kind => kind.name().to_string(),
+        };
+        match (self.length, self.max_length) {
+            (Some(length), _) => format!("{} of length {}", kind, length),
+            (None, Some(max)) => format!("{} of length at most {}", kind, max),
+            (None, None) => kind,
+        }
+    }
+}
+
+impl std::fmt::Display for LeafShape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.description())
+    }
+}
+
+/// Support for eliding leaves behind placeholders that disclose their shape.
+impl Envelope {
+    /// Returns a version of this envelope with each leaf targeted by `policy`
+    /// replaced by a placeholder: the elided leaf, wrapped, with assertions
+    /// disclosing the kind of value it was and, as the policy allows, its
+    /// length.
+    ///
+    /// ```text
+    /// "Alice" [
+    ///     "email": {
+    ///         ELIDED
+    ///     } [
+    ///         "placeholderKind": "string"
+    ///         "placeholderLength": 17
+    ///     ]
+    /// ]
+    /// ```
+    ///
+    /// A targeted subject is wrapped once more, so that the placeholder's
+    /// assertions are never mixed with the assertions on the subject.
+    /// Targets that are not leaves or known values are elided as usual.
+    ///
+    /// The shapes are claims made by whoever elides the envelope, usually its
+    /// holder, and nothing authenticates them. Adding the placeholder
+    /// assertions also changes the digests of all of the placeholders'
+    /// ancestors, so the issuer's signatures will no longer verify. A
+    /// verifier can read a shape with [`Envelope::placeholder_shape`], but
+    /// should only rely on it once the value is revealed and checked with
+    /// [`Envelope::verify_placeholder`].
+    pub fn elide_with_placeholders(&self, policy: &PlaceholderPolicy) -> Self {
+        if let Some(detail) = policy.detail(&self.digest()) {
+            return match LeafShape::of(self, detail) {
+                Some(shape) => self.elide().wrap_envelope().add_placeholder_assertions(&shape),
+                None => self.elide(),
+            };
+        }
+        match self.case() {
+            EnvelopeCase::Node { subject, assertions, .. } => {
+                let subject = subject.elide_with_placeholders(policy);
+                let subject = if subject.is_node() { subject.wrap_envelope() } else { subject };
+                let assertions: Vec<_> =
+                    assertions.iter().map(|assertion| assertion.elide_with_placeholders(policy)).collect();
+                Self::new_with_unchecked_assertions(subject, assertions)
+            }
+            EnvelopeCase::Assertion(assertion) => Self::new_assertion(
+                assertion.predicate().elide_with_placeholders(policy),
+                assertion.object().elide_with_placeholders(policy),
+            ),
+            EnvelopeCase::Wrapped { envelope, .. } => Self::new_wrapped(envelope.elide_with_placeholders(policy)),
+            _ => self.clone(),
+        }
+    }
+
+    fn add_placeholder_assertions(&self, shape: &LeafShape) -> Self {
+        let tag = match shape.kind {
+            LeafKind::Tagged(tag) => Some(tag),
+            _ => None,
+        };
+        self.add_assertion(PLACEHOLDER, shape.kind.name())
+            .add_optional_assertion(PLACEHOLDER_TAG, tag)
+            .add_optional_assertion(PLACEHOLDER_LENGTH, shape.length)
+            .add_optional_assertion(PLACEHOLDER_MAX_LENGTH, shape.max_length)
+    }
+
+    /// Returns `true` if this envelope is a placeholder made by
+    /// [`Envelope::elide_with_placeholders`].
+    pub fn is_placeholder(&self) -> bool {
+        self.subject().unwrap_envelope().is_ok_and(|inner| inner.is_elided())
+            && self.assertion_with_predicate(PLACEHOLDER).is_ok()
+    }
+
+    /// Returns the shape disclosed by this placeholder, or `None` if it is
+    /// not a placeholder.
+    ///
+    /// Returns an error if its placeholder assertions are malformed.
+    pub fn placeholder_shape(&self) -> Result<Option<LeafShape>> {
+        if !self.is_placeholder() {
+            return Ok(None);
+        }
+        let name: String = self.extract_object_for_predicate(PLACEHOLDER)?;
+        let tag = self.extract_optional_object_for_predicate(PLACEHOLDER_TAG)?;
+        Ok(Some(LeafShape {
+            kind: LeafKind::from_name(&name, tag)?,
+            length: self.extract_optional_object_for_predicate(PLACEHOLDER_LENGTH)?,
+            max_length: self.extract_optional_object_for_predicate(PLACEHOLDER_MAX_LENGTH)?,
+        }))
+    }
+
+    /// Checks that `revealed` is the value withheld by this placeholder: that
+    /// it has the placeholder's digest and the shape the placeholder
+    /// discloses.
+    ///
+    /// - Throws: Throws `EnvelopeError::InvalidDigest` if `revealed` is a
+    ///   different value, or `EnvelopeError::PlaceholderMismatch` if the
+    ///   placeholder misrepresents its shape.
+    pub fn verify_placeholder(&self, revealed: &Envelope) -> Result<()> {
+        let Some(shape) = self.placeholder_shape()? else {
+            bail!(EnvelopeError::NotObscured);
+        };
+        if self.subject().unwrap_envelope()?.digest() != revealed.digest() {
+            bail!(EnvelopeError::InvalidDigest);
+        }
+        if !shape.matches(revealed) {
+            bail!(EnvelopeError::PlaceholderMismatch);
+        }
+        Ok(())
+    }
+}
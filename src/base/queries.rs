@@ -5,13 +5,14 @@ use bc_components::EncryptedMessage;
 #[cfg(feature = "compress")]
 use bc_components::Compressed;
 use dcbor::prelude::*;
-use std::any::{Any, TypeId};
+use std::{any::{Any, TypeId}, collections::HashMap};
 
 use crate::{Assertion, Envelope, EnvelopeEncodable, EnvelopeError};
 #[cfg(feature = "known_value")]
 use crate::extension::KnownValue;
 
 use super::envelope::EnvelopeCase;
+use super::error_context::ErrorContextExt;
 
 /// Support for various queries on envelopes.
 impl Envelope {
@@ -33,6 +34,20 @@ impl Envelope {
         }
     }
 
+    /// Returns an iterator over the envelope's assertions.
+    ///
+    /// Unlike [`Envelope::assertions`], this does not allocate.
+    pub fn iter_assertions(&self) -> std::slice::Iter<'_, Self> {
+        self.assertions_slice().iter()
+    }
+
+    fn assertions_slice(&self) -> &[Self] {
+        match self.case() {
+            EnvelopeCase::Node { assertions, .. } => assertions,
+            _ => &[],
+        }
+    }
+
     /// `true` if the envelope has at least one assertion, `false` otherwise.
     pub fn has_assertions(&self) -> bool {
         match self.case() {
@@ -256,13 +271,10 @@ impl Envelope {
             }
         }
 
-        match self.case() {
+        let result = match self.case() {
             EnvelopeCase::Wrapped { envelope, .. } => extract_type::<T, Self>(envelope),
             EnvelopeCase::Node { subject, .. } => subject.extract_subject::<T>(),
-            EnvelopeCase::Leaf { cbor, .. } => {
-                let from_cbor = T::try_from(cbor.clone())?;
-                Ok(from_cbor)
-            },
+            EnvelopeCase::Leaf { cbor, .. } => T::try_from(cbor.clone()),
             EnvelopeCase::Assertion(assertion) => extract_type::<T, Assertion>(assertion),
             EnvelopeCase::Elided(digest) => extract_type::<T, Digest>(digest),
             #[cfg(feature = "known_value")]
@@ -271,22 +283,53 @@ impl Envelope {
             EnvelopeCase::Encrypted(encrypted_message) => extract_type::<T, EncryptedMessage>(encrypted_message),
             #[cfg(feature = "compress")]
             EnvelopeCase::Compressed(compressed) => extract_type::<T, Compressed>(compressed),
-        }
+        };
+        result.error_context(self)
     }
 
     /// Returns all assertions with the given predicate. Match by comparing digests.
+    ///
+    /// The first query on a node builds an index from predicate digests to
+    /// assertions, so subsequent queries take constant time regardless of the
+    /// number of assertions.
     pub fn assertions_with_predicate(&self, predicate: impl EnvelopeEncodable) -> Vec<Self> {
-        let predicate = Envelope::new(predicate);
-        self.assertions()
-            .into_iter()
-            .filter(|assertion| {
-                assertion
-                    .subject()
-                    .as_predicate()
-                    .map(|p| p.digest() == predicate.digest())
-                    .unwrap_or(false)
-            })
-            .collect()
+        self.iter_assertions_with_predicate(predicate).cloned().collect()
+    }
+
+    /// Returns an iterator over the assertions with the given predicate.
+    ///
+    /// Unlike [`Envelope::assertions_with_predicate`], this does not allocate
+    /// once the envelope's predicate index has been built.
+    pub fn iter_assertions_with_predicate(&self, predicate: impl EnvelopeEncodable) -> AssertionsWithPredicate<'_> {
+        let assertions = self.assertions_slice();
+        let indexes = if assertions.is_empty() {
+            &[]
+        } else {
+            let predicate = Envelope::new(predicate);
+            self.predicate_index()
+                .get(predicate.digest().as_ref())
+                .map_or(&[][..], |indexes| indexes.as_slice())
+        };
+        AssertionsWithPredicate { assertions, indexes: indexes.iter() }
+    }
+
+    /// Returns the index from predicate digests to the positions of the
+    /// assertions in this node that have that predicate, building it on first
+    /// use. Assertions that themselves have assertions, such as salted ones,
+    /// are indexed by the predicate of their subject. Obscured assertions are
+    /// not indexed.
+    fn predicate_index(&self) -> &HashMap<Digest, Vec<usize>> {
+        self.caches().predicate_index.get_or_init(|| {
+            let mut index: HashMap<Digest, Vec<usize>> = HashMap::new();
+            if let EnvelopeCase::Node { assertions, .. } = self.case() {
+                for (i, assertion) in assertions.iter().enumerate() {
+                    if let Some(predicate) = assertion.subject().as_predicate() {
+                        index.entry(predicate.digest().into_owned()).or_default().push(i);
+                    }
+                }
+            }
+            index
+        })
     }
 
     /// Returns the assertion with the given predicate.
@@ -343,8 +386,9 @@ impl Envelope {
     /// Returns an error if the envelope is not an assertion.
     /// Returns an error if the encoded type doesn't match the given type.
     pub fn extract_object<T: TryFrom<CBOR, Error = Error> + 'static>(&self) -> Result<T> {
-        self.try_object()?
-            .extract_subject()
+        self.try_object()
+            .and_then(|object| object.extract_subject())
+            .error_context(self)
     }
 
     /// Returns the predicate of the assertion, decoded as the given type.
@@ -352,8 +396,9 @@ impl Envelope {
     /// Returns an error if the envelope is not an assertion.
     /// Returns an error if the encoded type doesn't match the given type.
     pub fn extract_predicate<T: TryFrom<CBOR, Error = Error> + 'static>(&self) -> Result<T> {
-        self.try_predicate()?
-            .extract_subject()
+        self.try_predicate()
+            .and_then(|predicate| predicate.extract_subject())
+            .error_context(self)
     }
 
     /// Returns the object of the assertion with the given predicate, decoded as the given type.
@@ -361,16 +406,18 @@ impl Envelope {
     /// Returns an error if there is no matching predicate or multiple matching predicates.
     /// Returns an error if the encoded type doesn't match the given type.
     pub fn extract_object_for_predicate<T: TryFrom<CBOR, Error = Error> + 'static>(&self, predicate: impl EnvelopeEncodable) -> Result<T> {
-        self.assertion_with_predicate(predicate)?
-            .extract_object()
+        self.assertion_with_predicate(predicate)
+            .and_then(|assertion| assertion.extract_object())
+            .error_context(self)
     }
 
     /// Returns the object of the assertion with the given predicate, or `None` if there is no matching predicate.
     ///
     /// Returns an error if there are multiple matching predicates.
     pub fn extract_optional_object_for_predicate<T: TryFrom<CBOR, Error = Error> + 'static>(&self, predicate: impl EnvelopeEncodable) -> Result<Option<T>> {
-        self.optional_object_for_predicate(predicate)?
-            .map_or(Ok(None), |o| Ok(Some(o.extract_subject()?)))
+        self.optional_object_for_predicate(predicate)
+            .and_then(|object| object.map_or(Ok(None), |o| Ok(Some(o.extract_subject()?))))
+            .error_context(self)
     }
 
     /// Returns the object of the assertion with the given predicate, or a default value if there is no matching predicate.
@@ -381,10 +428,16 @@ impl Envelope {
 
     /// Returns the objects of all assertions with the matching predicate.
     pub fn objects_for_predicate(&self, predicate: impl EnvelopeEncodable) -> Vec<Self> {
-        self.assertions_with_predicate(predicate)
-            .into_iter()
-            .map(|a| a.as_object().unwrap())
-            .collect()
+        self.iter_objects_for_predicate(predicate).collect()
+    }
+
+    /// Returns an iterator over the objects of all assertions with the matching
+    /// predicate.
+    ///
+    /// Unlike [`Envelope::objects_for_predicate`], this does not collect the
+    /// matching assertions or objects into vectors.
+    pub fn iter_objects_for_predicate(&self, predicate: impl EnvelopeEncodable) -> ObjectsForPredicate<'_> {
+        ObjectsForPredicate(self.iter_assertions_with_predicate(predicate))
     }
 
     /// Returns the objects of all assertions with the matching predicate,
@@ -396,6 +449,7 @@ impl Envelope {
             .into_iter()
             .map(|a| a.extract_subject::<T>())
             .collect::<Result<Vec<T>>>()
+            .error_context(self)
     }
 
     /// Returns the number of structural elements in the envelope, including itself.
@@ -430,4 +484,84 @@ impl Envelope {
 
         result
     }
+
+    /// Returns the distinct predicates used anywhere in the envelope, with the
+    /// number of assertions in which each is used.
+    ///
+    /// Assertions within wrapped envelopes, predicates, and objects are
+    /// included. Because envelopes are not `Eq`, the inventory is keyed by each
+    /// predicate's digest and holds the predicate itself along with its count.
+    /// Elided predicates are counted under their digests; the contents of
+    /// elided, encrypted, and compressed elements are not included.
+    pub fn predicate_inventory(&self) -> HashMap<Digest, (Self, usize)> {
+        fn _inventory(envelope: &Envelope, result: &mut HashMap<Digest, (Envelope, usize)>) {
+            match envelope.case() {
+                EnvelopeCase::Node { subject, assertions, .. } => {
+                    _inventory(subject, result);
+                    for assertion in assertions {
+                        _inventory(assertion, result);
+                    }
+                }
+                EnvelopeCase::Assertion(assertion) => {
+                    let predicate = assertion.predicate();
+                    result
+                        .entry(predicate.digest().into_owned())
+                        .or_insert_with(|| (predicate.clone(), 0))
+                        .1 += 1;
+                    _inventory(&predicate, result);
+                    _inventory(&assertion.object(), result);
+                }
+                EnvelopeCase::Wrapped { envelope, .. } => _inventory(envelope, result),
+                _ => {}
+            }
+        }
+
+        let mut result = HashMap::new();
+        _inventory(self, &mut result);
+        result
+    }
+}
+
+/// An iterator over the assertions of an envelope that have a given predicate.
+///
+/// Returned by [`Envelope::iter_assertions_with_predicate`].
+#[derive(Debug, Clone)]
+pub struct AssertionsWithPredicate<'a> {
+    assertions: &'a [Envelope],
+    indexes: std::slice::Iter<'a, usize>,
+}
+
+impl<'a> Iterator for AssertionsWithPredicate<'a> {
+    type Item = &'a Envelope;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.indexes.next().map(|&i| &self.assertions[i])
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.indexes.size_hint()
+    }
 }
+
+impl ExactSizeIterator for AssertionsWithPredicate<'_> {}
+
+/// An iterator over the objects of the assertions of an envelope that have a
+/// given predicate.
+///
+/// Returned by [`Envelope::iter_objects_for_predicate`].
+#[derive(Debug, Clone)]
+pub struct ObjectsForPredicate<'a>(AssertionsWithPredicate<'a>);
+
+impl Iterator for ObjectsForPredicate<'_> {
+    type Item = Envelope;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|a| a.as_object().unwrap())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl ExactSizeIterator for ObjectsForPredicate<'_> {}
@@ -0,0 +1,111 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+};
+
+use bc_components::Digest;
+
+use crate::{base::digest::EnvelopePath, Envelope, EnvelopeMatcher, Pattern};
+
+type QueryKey = (Digest, String);
+
+#[derive(Debug, Default)]
+struct Entries {
+    results: HashMap<QueryKey, Vec<EnvelopePath>>,
+    order: VecDeque<QueryKey>,
+    hits: u64,
+    misses: u64,
+}
+
+/// A cache of the results of [`Envelope::paths_matching`], keyed by the
+/// structural digest of the envelope queried and the text of the query.
+///
+/// Envelopes are immutable and their structural digests identify their
+/// structure, so a query run again on a structurally identical envelope has
+/// the same result. The ordinary digest is not enough: an elided copy of an
+/// envelope has the same digest but different elements, so it is cached
+/// separately. See [`Envelope::structural_digest`].
+/// The structural digest is cached on the envelope once computed, so looking
+/// up a result for the same envelope again does not walk it.
+/// This is useful to servers that answer the same queries about the same
+/// envelopes repeatedly. When the cache is full, the oldest result is
+/// evicted.
+///
+/// The query text must identify the matcher: two matchers given the same
+/// text are assumed to match the same elements. The text is compared exactly,
+/// since texts that differ only in whitespace, such as within a quoted
+/// string, may not mean the same thing. [`Pattern`]s supply their own
+/// canonical text, so [`QueryCache::paths_matching_pattern`] needs none.
+#[derive(Debug)]
+pub struct QueryCache {
+    capacity: usize,
+    entries: Mutex<Entries>,
+}
+
+impl QueryCache {
+    /// Creates a cache holding at most `capacity` results.
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity, entries: Mutex::new(Entries::default()) }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The number of results in the cache.
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().results.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The number of queries answered from the cache and the number that
+    /// were not, since the cache was created or last cleared.
+    pub fn stats(&self) -> (u64, u64) {
+        let entries = self.entries.lock().unwrap();
+        (entries.hits, entries.misses)
+    }
+
+    /// Removes every result from the cache and resets its statistics.
+    pub fn clear(&self) {
+        *self.entries.lock().unwrap() = Entries::default();
+    }
+
+    /// Returns the result of [`Envelope::paths_matching`] for `envelope` and
+    /// `matcher`, which is identified by `query`, from the cache if possible.
+    pub fn paths_matching(&self, envelope: &Envelope, query: &str, matcher: &impl EnvelopeMatcher) -> Vec<EnvelopePath> {
+        let key = (envelope.structural_digest(), query.to_string());
+        {
+            let mut entries = self.entries.lock().unwrap();
+            if let Some(paths) = entries.results.get(&key).cloned() {
+                entries.hits += 1;
+                return paths;
+            }
+            entries.misses += 1;
+        }
+
+        // The lock is not held while querying, so other queries can proceed.
+        let paths = envelope.paths_matching(matcher);
+        if self.capacity > 0 {
+            let mut entries = self.entries.lock().unwrap();
+            if !entries.results.contains_key(&key) {
+                if entries.results.len() >= self.capacity {
+                    if let Some(oldest) = entries.order.pop_front() {
+                        entries.results.remove(&oldest);
+                    }
+                }
+                entries.order.push_back(key.clone());
+                entries.results.insert(key, paths.clone());
+            }
+        }
+        paths
+    }
+
+    /// Returns the paths to the elements of `envelope` that `pattern`
+    /// matches, from the cache if possible.
+    pub fn paths_matching_pattern(&self, envelope: &Envelope, pattern: &Pattern) -> Vec<EnvelopePath> {
+        self.paths_matching(envelope, &pattern.to_string(), pattern)
+    }
+}
@@ -0,0 +1,144 @@
+use std::collections::HashSet;
+
+use anyhow::{bail, Result};
+use bc_components::{Digest, DigestProvider};
+use bc_ur::UR;
+use dcbor::prelude::*;
+
+use crate::{Envelope, EnvelopeError};
+
+/// A compact description of a projection of an envelope.
+///
+/// A reveal token identifies a set of elements to leave revealed within a
+/// particular root envelope. Rather than listing their digests, it records the
+/// root's digest and a bitmap over the root's digests in sorted order, so it is
+/// small enough to hand to the holder of the envelope as a UR. The holder then
+/// produces the projection with [`Envelope::apply_reveal_token`], without the
+/// issuer needing to transfer a second envelope.
+///
+/// Obscuring elements of an envelope keeps its root digest but changes the
+/// digests within it, so the token also records a digest of the sorted list
+/// the bitmap indexes, and applies only to a copy of the root obscured in
+/// the same way.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RevealToken {
+    root: Digest,
+    digests: Digest,
+    bitmap: Vec<u8>,
+}
+
+impl RevealToken {
+    /// The UR type of an encoded reveal token.
+    pub const UR_TYPE: &'static str = "reveal-token";
+
+    /// Creates a token that reveals the elements of `root` whose digests are
+    /// in `target`.
+    ///
+    /// Digests in `target` that are not in `root` are ignored.
+    pub fn new(root: &Envelope, target: &HashSet<Digest>) -> Self {
+        let digests = Self::sorted_digests(root);
+        let mut bitmap = vec![0u8; digests.len().div_ceil(8)];
+        for (index, digest) in digests.iter().enumerate() {
+            if target.contains(*digest) {
+                bitmap[index / 8] |= 1 << (index % 8);
+            }
+        }
+        while bitmap.last() == Some(&0) {
+            bitmap.pop();
+        }
+        Self { root: root.digest().into_owned(), digests: Self::digest_of(&digests), bitmap }
+    }
+
+    /// The digest of the envelope the token applies to.
+    pub fn root(&self) -> &Digest {
+        &self.root
+    }
+
+    /// The set of digests the token reveals within `root`.
+    ///
+    /// Returns an error if `root` is not the envelope the token was created
+    /// for, or is a copy of it with different elements obscured.
+    pub fn target(&self, root: &Envelope) -> Result<HashSet<Digest>> {
+        if *root.digest() != self.root {
+            bail!(EnvelopeError::InvalidDigest);
+        }
+        let digests = Self::sorted_digests(root);
+        if Self::digest_of(&digests) != self.digests {
+            bail!(EnvelopeError::InvalidDigest);
+        }
+        if self.bitmap.len() > digests.len().div_ceil(8) {
+            bail!(EnvelopeError::InvalidFormat);
+        }
+        Ok(digests
+            .into_iter()
+            .enumerate()
+            .filter(|(index, _)| self.bitmap.get(index / 8).is_some_and(|byte| byte & (1 << (index % 8)) != 0))
+            .map(|(_, digest)| digest.clone())
+            .collect())
+    }
+
+    /// The token encoded as a UR string.
+    pub fn ur_string(&self) -> String {
+        UR::new(Self::UR_TYPE, self.clone()).unwrap().string()
+    }
+
+    /// Decodes a token from a UR string.
+    pub fn from_ur_string(ur_string: impl Into<String>) -> Result<Self> {
+        let ur = UR::from_ur_string(ur_string)?;
+        ur.check_type(Self::UR_TYPE)?;
+        Self::try_from(ur.cbor())
+    }
+
+    fn sorted_digests(root: &Envelope) -> Vec<&Digest> {
+        let mut digests: Vec<&Digest> = root.deep_digests_ref().iter().collect();
+        digests.sort();
+        digests
+    }
+
+    fn digest_of(digests: &[&Digest]) -> Digest {
+        let parts: Vec<&[u8]> = digests.iter().map(|digest| digest.data().as_slice()).collect();
+        Digest::from_image_parts(&parts)
+    }
+}
+
+impl From<RevealToken> for CBOR {
+    fn from(value: RevealToken) -> Self {
+        vec![CBOR::from(value.root), CBOR::from(value.digests), CBOR::to_byte_string(value.bitmap)].into()
+    }
+}
+
+impl TryFrom<CBOR> for RevealToken {
+    type Error = anyhow::Error;
+
+    fn try_from(cbor: CBOR) -> Result<Self> {
+        let CBORCase::Array(elements) = cbor.into_case() else {
+            bail!(EnvelopeError::InvalidFormat);
+        };
+        let [root, digests, bitmap] = <[CBOR; 3]>::try_from(elements).map_err(|_| EnvelopeError::InvalidFormat)?;
+        Ok(Self {
+            root: Digest::try_from(root)?,
+            digests: Digest::try_from(digests)?,
+            bitmap: bitmap.try_into_byte_string()?,
+        })
+    }
+}
+
+/// Support for selective disclosure using reveal tokens.
+impl Envelope {
+    /// Returns a reveal token for the elements of this envelope whose digests
+    /// are in `target`.
+    ///
+    /// As with [`Envelope::elide_revealing_set`], the target must include the
+    /// digests of every element on the path to each element to be revealed.
+    pub fn reveal_token(&self, target: &HashSet<Digest>) -> RevealToken {
+        RevealToken::new(self, target)
+    }
+
+    /// Returns the projection of this envelope described by `token`, with all
+    /// elements except those the token reveals elided.
+    ///
+    /// Returns an error if this is not the envelope the token was created for.
+    pub fn apply_reveal_token(&self, token: &RevealToken) -> Result<Self> {
+        Ok(self.elide_revealing_set(&token.target(self)?))
+    }
+}
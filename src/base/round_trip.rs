@@ -0,0 +1,62 @@
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::{bail, Result};
+use bc_components::DigestProvider;
+use dcbor::prelude::*;
+
+use crate::{Envelope, EnvelopeError};
+
+static ROUND_TRIP_CHECKING: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    static IN_ROUND_TRIP_CHECK: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Enables or disables round-trip checking of every envelope constructed.
+///
+/// When enabled in a debug build, each envelope is encoded to CBOR and decoded
+/// again as it is constructed, and construction panics if the result is not
+/// the same envelope. This catches canonicalization bugs in code that builds
+/// envelopes as early as possible. It is very slow, and has no effect in
+/// release builds.
+pub fn set_round_trip_checking(enabled: bool) {
+    ROUND_TRIP_CHECKING.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns whether round-trip checking of constructed envelopes is enabled.
+pub fn is_round_trip_checking() -> bool {
+    cfg!(debug_assertions) && ROUND_TRIP_CHECKING.load(Ordering::Relaxed)
+}
+
+#[cfg(debug_assertions)]
+pub(crate) fn check_round_trip_if_enabled(envelope: &Envelope) {
+    if !is_round_trip_checking() || IN_ROUND_TRIP_CHECK.with(|in_check| in_check.get()) {
+        return;
+    }
+    // Decoding constructs envelopes too, so don't check those.
+    IN_ROUND_TRIP_CHECK.with(|in_check| in_check.set(true));
+    let result = envelope.verify_round_trip();
+    IN_ROUND_TRIP_CHECK.with(|in_check| in_check.set(false));
+    if let Err(error) = result {
+        panic!("envelope does not round-trip through its CBOR encoding: {}", error);
+    }
+}
+
+/// Support for checking envelope encoding.
+impl Envelope {
+    /// Checks that the envelope survives a round trip through its CBOR
+    /// encoding.
+    ///
+    /// The envelope is encoded and then decoded, and the decoded envelope must
+    /// have the same digest as the original.
+    ///
+    /// Returns the envelope itself, so that the check can be chained.
+    pub fn verify_round_trip(&self) -> Result<Self> {
+        let restored = Envelope::from_tagged_cbor(self.tagged_cbor())?;
+        if self.digest() != restored.digest() {
+            bail!(EnvelopeError::InvalidDigest);
+        }
+        Ok(self.clone())
+    }
+}
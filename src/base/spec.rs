@@ -0,0 +1,203 @@
+use bc_components::{tags, Compressed, DigestProvider, EncryptedMessage};
+use dcbor::prelude::*;
+
+use crate::{base::cbor::MAX_DECODE_DEPTH, Envelope};
+
+/// The sections of
+/// [draft-mcnally-envelope](https://datatracker.ietf.org/doc/draft-mcnally-envelope/)
+/// that findings refer to.
+pub mod sections {
+    /// Envelopes are encoded in deterministic CBOR (dCBOR).
+    pub const DETERMINISTIC_ENCODING: &str = "3";
+    /// An envelope is tagged `#6.200`.
+    pub const TOP_LEVEL: &str = "3.1";
+    /// The leaf case is tagged `#6.201`.
+    pub const LEAF: &str = "3.2";
+    /// The elided case is a 32-byte digest.
+    pub const ELIDED: &str = "3.3";
+    /// The node case is an array of a subject and its sorted assertions.
+    pub const NODE: &str = "3.4";
+    /// The assertion case is a single-element map.
+    pub const ASSERTION: &str = "3.7";
+    /// The encrypted case is a `crypto-msg` authenticating its digest.
+    pub const ENCRYPTED: &str = "3.8";
+    /// The compressed case is a `compressed` carrying its digest.
+    pub const COMPRESSED: &str = "3.9";
+}
+
+/// The strength of a requirement of the specification, as defined by
+/// RFC 2119.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SpecRequirement {
+    Must,
+    Should,
+}
+
+impl std::fmt::Display for SpecRequirement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SpecRequirement::Must => write!(f, "MUST"),
+            SpecRequirement::Should => write!(f, "SHOULD"),
+        }
+    }
+}
+
+/// A requirement of the specification that an encoded envelope does not
+/// meet, found by [`Envelope::check_spec_conformance`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpecFinding {
+    /// The section of the draft stating the requirement, one of those in
+    /// [`sections`].
+    pub section: &'static str,
+    pub requirement: SpecRequirement,
+    pub message: String,
+}
+
+impl std::fmt::Display for SpecFinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "§{} {}: {}", self.section, self.requirement, self.message)
+    }
+}
+
+#[derive(Default)]
+struct Checker {
+    findings: Vec<SpecFinding>,
+}
+
+impl Checker {
+    fn must(&mut self, section: &'static str, message: impl Into<String>) {
+        self.push(section, SpecRequirement::Must, message);
+    }
+
+    fn should(&mut self, section: &'static str, message: impl Into<String>) {
+        self.push(section, SpecRequirement::Should, message);
+    }
+
+    fn push(&mut self, section: &'static str, requirement: SpecRequirement, message: impl Into<String>) {
+        self.findings.push(SpecFinding { section, requirement, message: message.into() });
+    }
+
+    fn check_envelope(&mut self, cbor: &CBOR) {
+        match cbor.as_case() {
+            CBORCase::Tagged(tag, item) if tag.value() == tags::TAG_ENVELOPE => self.check_case(item),
+            _ => {
+                self.must(sections::TOP_LEVEL, "envelope is not tagged #6.200");
+                self.check_case(cbor);
+            }
+        }
+    }
+
+    fn check_case(&mut self, cbor: &CBOR) {
+        match cbor.as_case() {
+            CBORCase::Tagged(tag, item) => match tag.value() {
+                tags::TAG_LEAF => {}
+                tags::TAG_ENCODED_CBOR => {
+                    self.should(sections::LEAF, "leaf is tagged #6.24 rather than #6.201");
+                }
+                tags::TAG_ENVELOPE => self.check_case(item),
+                tags::TAG_ENCRYPTED => match EncryptedMessage::from_untagged_cbor(item.clone()) {
+                    Ok(message) if message.opt_digest().is_none() => {
+                        self.must(sections::ENCRYPTED, "encrypted element does not authenticate its digest");
+                    }
+                    Ok(_) => {}
+                    Err(_) => self.must(sections::ENCRYPTED, "encrypted element is malformed"),
+                },
+                tags::TAG_COMPRESSED => match Compressed::from_untagged_cbor(item.clone()) {
+                    Ok(compressed) if compressed.digest_ref_opt().is_none() => {
+                        self.must(sections::COMPRESSED, "compressed element does not carry its digest");
+                    }
+                    Ok(_) => {}
+                    Err(_) => self.must(sections::COMPRESSED, "compressed element is malformed"),
+                },
+                value => self.must(sections::TOP_LEVEL, format!("element has unknown tag #6.{}", value)),
+            },
+            CBORCase::ByteString(bytes) => {
+                if bytes.len() != 32 {
+                    self.must(sections::ELIDED, format!("elided digest is {} bytes rather than 32", bytes.len()));
+                }
+            }
+            CBORCase::Array(elements) => self.check_node(elements),
+            CBORCase::Map(map) => {
+                if map.len() != 1 {
+                    self.must(sections::ASSERTION, format!("assertion map has {} entries rather than 1", map.len()));
+                }
+                for (predicate, object) in map.iter() {
+                    self.check_case(predicate);
+                    self.check_case(object);
+                }
+            }
+            CBORCase::Unsigned(_) => {}
+            _ => self.must(sections::TOP_LEVEL, "element is not any envelope case"),
+        }
+    }
+
+    fn check_node(&mut self, elements: &[CBOR]) {
+        let Some((subject, assertions)) = elements.split_first() else {
+            self.must(sections::NODE, "node has no subject");
+            return;
+        };
+        if assertions.is_empty() {
+            self.must(sections::NODE, "node has no assertions");
+        }
+        if matches!(subject.as_case(), CBORCase::Array(_)) {
+            self.must(sections::NODE, "node subject is itself a node");
+        }
+        self.check_case(subject);
+        for assertion in assertions {
+            if !is_assertion_or_obscured(assertion) {
+                self.must(sections::NODE, "node assertion is neither an assertion nor obscured");
+            }
+            self.check_case(assertion);
+        }
+        let digests = assertions
+            .iter()
+            .map(|assertion| Envelope::decode(assertion.clone(), true, MAX_DECODE_DEPTH).map(|envelope| envelope.digest().into_owned()))
+            .collect::<Result<Vec<_>, _>>();
+        if let Ok(digests) = digests {
+            if digests.windows(2).any(|pair| pair[0] == pair[1]) {
+                self.must(sections::NODE, "node has duplicate assertions");
+            }
+            if digests.windows(2).any(|pair| pair[0] > pair[1]) {
+                self.must(sections::NODE, "node assertions are not sorted by digest");
+            }
+        }
+    }
+}
+
+fn is_assertion_or_obscured(cbor: &CBOR) -> bool {
+    match cbor.as_case() {
+        CBORCase::Map(_) | CBORCase::ByteString(_) => true,
+        CBORCase::Tagged(tag, _) => matches!(tag.value(), tags::TAG_ENCRYPTED | tags::TAG_COMPRESSED),
+        _ => false,
+    }
+}
+
+/// Support for checking conformance to the Gordian Envelope specification.
+impl Envelope {
+    /// Returns the requirements of the specification that the encoding of
+    /// this envelope does not meet.
+    ///
+    /// Envelopes built with this crate always conform, so this is mostly
+    /// useful as a reference for other implementations; see
+    /// [`Envelope::check_data_spec_conformance`] to check their output.
+    pub fn check_spec_conformance(&self) -> Vec<SpecFinding> {
+        let mut checker = Checker::default();
+        checker.check_envelope(&self.tagged_cbor());
+        checker.findings
+    }
+
+    /// Returns the requirements of the specification that the given encoded
+    /// envelope does not meet, in the order they are found.
+    ///
+    /// Unlike decoding, which accepts some non-conforming encodings such as
+    /// unsorted assertions, this reports every problem it finds. If the data
+    /// is not deterministic CBOR nothing else is checked.
+    pub fn check_data_spec_conformance(data: impl AsRef<[u8]>) -> Vec<SpecFinding> {
+        let mut checker = Checker::default();
+        match CBOR::try_from_data(data) {
+            Ok(cbor) => checker.check_envelope(&cbor),
+            Err(error) => checker.must(sections::DETERMINISTIC_ENCODING, format!("not deterministic CBOR: {}", error)),
+        }
+        checker.findings
+    }
+}
@@ -0,0 +1,162 @@
+use std::{cell::RefCell, collections::HashMap, hash::BuildHasher};
+
+use bc_components::{Digest, DigestProvider};
+
+use crate::{Assertion, Envelope};
+
+use super::{digest::EnvelopePath, envelope::EnvelopeCase};
+
+/// A collection of envelopes, each addressed by its digest.
+///
+/// Implemented for `HashMap<Digest, Envelope>`. Stores backed by files or
+/// databases need only be able to list their digests and fetch envelopes on
+/// demand, so that searches need not hold the whole collection in memory.
+pub trait EnvelopeStore {
+    /// Returns the envelope with the given digest, if the store has it.
+    fn get(&self, digest: &Digest) -> Option<Envelope>;
+
+    /// Returns the digests of the envelopes in the store.
+    fn digests(&self) -> Box<dyn Iterator<Item = Digest> + '_>;
+}
+
+impl<S: BuildHasher> EnvelopeStore for HashMap<Digest, Envelope, S> {
+    fn get(&self, digest: &Digest) -> Option<Envelope> {
+        HashMap::get(self, digest).cloned()
+    }
+
+    fn digests(&self) -> Box<dyn Iterator<Item = Digest> + '_> {
+        Box::new(self.keys().cloned())
+    }
+}
+
+/// Decides which elements of an envelope a search matches.
+///
+/// Implemented for functions and closures that take an element and return
+/// whether it matches.
+pub trait EnvelopeMatcher {
+    /// Returns `true` if `element` matches.
+    fn matches(&self, element: &Envelope) -> bool;
+}
+
+impl<F: Fn(&Envelope) -> bool> EnvelopeMatcher for F {
+    fn matches(&self, element: &Envelope) -> bool {
+        self(element)
+    }
+}
+
+/// Searches every envelope in a store for elements that match.
+///
+/// Yields the digest of each stored envelope that has a matching element,
+/// with the path from the envelope to the element. Unlike the paths returned
+/// by [`Envelope::find_by_digest`], these paths end with the matching element
+/// itself. Envelopes are fetched from the store one at a time as the results
+/// are consumed, and the same matcher is used for all of them.
+pub fn search_store<'a>(
+    store: &'a impl EnvelopeStore,
+    matcher: &'a impl EnvelopeMatcher,
+) -> impl Iterator<Item = (Digest, EnvelopePath)> + 'a {
+    store
+        .digests()
+        .filter_map(|digest| store.get(&digest).map(|envelope| (digest, envelope)))
+        .flat_map(|(digest, envelope)| {
+            envelope
+                .paths_matching(matcher)
+                .into_iter()
+                .map(move |path| (digest.clone(), path))
+        })
+}
+
+impl Envelope {
+    /// Returns the path to each element of the envelope that `matcher`
+    /// matches, in depth-first order, where each path starts with this
+    /// envelope and ends with the matching element.
+    pub fn paths_matching(&self, matcher: &impl EnvelopeMatcher) -> Vec<EnvelopePath> {
+        let paths = RefCell::new(Vec::new());
+        let visitor = |element: Envelope, _: usize, _, parent: Option<EnvelopePath>| -> Option<EnvelopePath> {
+            let mut path = parent.unwrap_or_default();
+            let matched = matcher.matches(&element);
+            path.push(element);
+            if matched {
+                paths.borrow_mut().push(path.clone());
+            }
+            Some(path)
+        };
+        self.walk(false, &visitor);
+        paths.into_inner()
+    }
+}
+
+/// Support for restoring elided elements from a store.
+impl Envelope {
+    /// Returns a new envelope with each elided element that `store` has an
+    /// envelope for replaced by that envelope, including elided elements
+    /// within restored ones.
+    ///
+    /// Elided elements the store does not have are left in place, so a
+    /// document received in redacted form can be progressively
+    /// reconstructed as more of its parts become known. An envelope from the
+    /// store is only used if its digest is the one it was fetched by, so
+    /// the digest of the result is always that of this envelope.
+    pub fn unelide_from_store(&self, store: &impl EnvelopeStore) -> Self {
+        self.restore_from_store(store).unwrap_or_else(|| self.clone())
+    }
+
+    /// Returns the envelope with elements restored from `store`, or `None`
+    /// if nothing was restored, so that unchanged subtrees are shared with
+    /// the original.
+    fn restore_from_store(&self, store: &impl EnvelopeStore) -> Option<Self> {
+        match self.case() {
+            EnvelopeCase::Elided(digest) => {
+                let restored = store
+                    .get(digest)
+                    .filter(|restored| !restored.is_elided() && restored.digest().as_ref() == digest)?;
+                Some(restored.unelide_from_store(store))
+            }
+            EnvelopeCase::Node { subject, assertions, .. } => {
+                let new_subject = subject.restore_from_store(store);
+                let new_assertions: Vec<_> = assertions
+                    .iter()
+                    .map(|assertion| assertion.restore_from_store(store))
+                    .collect();
+                if new_subject.is_none() && new_assertions.iter().all(Option::is_none) {
+                    return None;
+                }
+                let new_assertions = new_assertions
+                    .into_iter()
+                    .zip(assertions)
+                    .map(|(new, old)| new.unwrap_or_else(|| old.clone()))
+                    .collect();
+                Some(Self::new_with_unchecked_assertions(
+                    new_subject.unwrap_or_else(|| subject.clone()),
+                    new_assertions,
+                ))
+            }
+            EnvelopeCase::Wrapped { envelope, .. } => Some(Self::new_wrapped(envelope.restore_from_store(store)?)),
+            EnvelopeCase::Assertion(assertion) => {
+                let predicate = assertion.predicate();
+                let object = assertion.object();
+                let new_predicate = predicate.restore_from_store(store);
+                let new_object = object.restore_from_store(store);
+                if new_predicate.is_none() && new_object.is_none() {
+                    return None;
+                }
+                Some(Self::new_with_assertion(Assertion::new(
+                    new_predicate.unwrap_or(predicate),
+                    new_object.unwrap_or(object),
+                )))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns a new envelope with each elided element whose digest is that
+    /// of one of `envelopes` replaced by that envelope, as
+    /// [`Envelope::unelide_from_store`] does.
+    pub fn walk_unelide(&self, envelopes: &[Envelope]) -> Self {
+        let store: HashMap<Digest, Envelope> = envelopes
+            .iter()
+            .map(|envelope| (envelope.digest().into_owned(), envelope.clone()))
+            .collect();
+        self.unelide_from_store(&store)
+    }
+}
@@ -0,0 +1,87 @@
+use std::io::Read;
+
+use anyhow::{bail, Result};
+use bc_components::Digest;
+use sha2::{Digest as _, Sha256};
+
+use crate::{Envelope, EnvelopeError};
+
+/// Support for envelopes whose subject is too large to hold in memory.
+impl Envelope {
+    /// Creates an elided envelope standing in for a byte string subject of
+    /// `len` bytes read from `reader`.
+    ///
+    /// The payload is hashed as it is read and never held in memory in its
+    /// entirety. The returned envelope has exactly the digest that
+    /// `Envelope::new(ByteString::from(payload))` would have, so assertions,
+    /// including signatures, can be added to it and will remain valid for the
+    /// full envelope. The holder of the payload can later restore it using
+    /// [`Envelope::unelide`].
+    ///
+    /// Returns an error if `reader` yields fewer than `len` bytes.
+    pub fn new_streaming_bytes(reader: impl Read, len: u64) -> Result<Self> {
+        let mut hasher = Sha256::new();
+        hasher.update(byte_string_header(len));
+        let copied = std::io::copy(&mut reader.take(len), &mut HashWriter(&mut hasher))?;
+        if copied != len {
+            bail!(EnvelopeError::InvalidFormat);
+        }
+        let digest = Digest::from_data(hasher.finalize().into());
+        Ok(Self::new_elided(digest))
+    }
+}
+
+/// The CBOR header of a byte string of the given length.
+fn byte_string_header(len: u64) -> Vec<u8> {
+    const MAJOR_TYPE: u8 = 2 << 5;
+    if len < 24 {
+        vec![MAJOR_TYPE | len as u8]
+    } else if len <= u8::MAX as u64 {
+        vec![MAJOR_TYPE | 24, len as u8]
+    } else if len <= u16::MAX as u64 {
+        [&[MAJOR_TYPE | 25][..], &(len as u16).to_be_bytes()].concat()
+    } else if len <= u32::MAX as u64 {
+        [&[MAJOR_TYPE | 26][..], &(len as u32).to_be_bytes()].concat()
+    } else {
+        [&[MAJOR_TYPE | 27][..], &len.to_be_bytes()].concat()
+    }
+}
+
+struct HashWriter<'a>(&'a mut Sha256);
+
+impl std::io::Write for HashWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bc_components::DigestProvider;
+    use dcbor::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn test_streaming_bytes_digest() {
+        for len in [0usize, 23, 24, 255, 256, 65535, 65536, 100_000] {
+            let payload: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let expected = Envelope::new(ByteString::from(payload.clone()));
+            let streamed = Envelope::new_streaming_bytes(payload.as_slice(), len as u64).unwrap();
+            assert_eq!(streamed.digest(), expected.digest(), "len {}", len);
+            assert!(streamed.is_elided());
+            assert!(streamed.unelide(&expected).unwrap().is_leaf());
+        }
+    }
+
+    #[test]
+    fn test_streaming_bytes_short_read() {
+        let payload = [1u8, 2, 3];
+        assert!(Envelope::new_streaming_bytes(payload.as_slice(), 4).is_err());
+    }
+}
@@ -0,0 +1,146 @@
+use bc_components::Digest;
+
+use crate::Envelope;
+
+use super::envelope::EnvelopeCase;
+
+/// A locality-sensitive fingerprint of the shape of an envelope's tree.
+///
+/// Returned by [`Envelope::structural_fingerprint`]. The fingerprint depends
+/// only on how the elements of an envelope are arranged, not on the values of
+/// its leaves, so envelopes with the same shape have the same fingerprint,
+/// and envelopes with similar shapes have fingerprints that differ in few
+/// bits. This lets similar kinds of document be clustered without inspecting
+/// their content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StructuralFingerprint(u64);
+
+impl StructuralFingerprint {
+    pub fn value(&self) -> u64 {
+        self.0
+    }
+
+    /// The number of bits in which this fingerprint differs from `other`.
+    pub fn distance(&self, other: &Self) -> u32 {
+        (self.0 ^ other.0).count_ones()
+    }
+
+    /// The similarity of the shapes with this fingerprint and `other`, from
+    /// 0.0 to 1.0, where 1.0 means the fingerprints are equal.
+    pub fn similarity(&self, other: &Self) -> f64 {
+        1.0 - self.distance(other) as f64 / u64::BITS as f64
+    }
+}
+
+impl std::fmt::Display for StructuralFingerprint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+/// The position of an element relative to its parent.
+#[derive(Debug, Clone, Copy)]
+enum Role {
+    Root,
+    Subject,
+    Assertion,
+    Predicate,
+    Object,
+    Content,
+}
+
+impl Role {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Root => "root",
+            Self::Subject => "subject",
+            Self::Assertion => "assertion",
+            Self::Predicate => "predicate",
+            Self::Object => "object",
+            Self::Content => "content",
+        }
+    }
+}
+
+/// Support for fingerprinting the shape of envelopes.
+impl Envelope {
+    /// Returns a fingerprint of the shape of this envelope's tree that is
+    /// stable under changes to the values of its leaves.
+    ///
+    /// Each element contributes features describing its kind, its position
+    /// relative to its parent, its depth, and the shape of the subtree below
+    /// it, which are combined by SimHash. Adding or removing an assertion
+    /// changes only a few bits of the fingerprint, while envelopes of
+    /// unrelated shapes have fingerprints about 32 bits apart; compare them
+    /// with [`StructuralFingerprint::distance`].
+    ///
+    /// Leaves, known values, and obscured elements are distinguished from one
+    /// another, but not by their values, so the fingerprint reveals nothing
+    /// about the envelope's content beyond its shape.
+    pub fn structural_fingerprint(&self) -> StructuralFingerprint {
+        let mut weights = [0i64; u64::BITS as usize];
+        self.add_shape_features(Role::Root, 0, &mut weights);
+        let bits = weights
+            .iter()
+            .enumerate()
+            .filter(|(_, weight)| **weight > 0)
+            .fold(0u64, |bits, (bit, _)| bits | (1 << bit));
+        StructuralFingerprint(bits)
+    }
+
+    /// Adds the features of this element and its descendants to `weights`,
+    /// returning the digest of the shape of its subtree.
+    fn add_shape_features(&self, role: Role, depth: usize, weights: &mut [i64]) -> Digest {
+        let mut children = match self.case() {
+            EnvelopeCase::Node { subject, assertions, .. } => {
+                let mut children = vec![subject.add_shape_features(Role::Subject, depth + 1, weights)];
+                children.extend(
+                    assertions.iter().map(|assertion| assertion.add_shape_features(Role::Assertion, depth + 1, weights)),
+                );
+                children
+            }
+            EnvelopeCase::Assertion(assertion) => vec![
+                assertion.predicate().add_shape_features(Role::Predicate, depth + 1, weights),
+                assertion.object().add_shape_features(Role::Object, depth + 1, weights),
+            ],
+            EnvelopeCase::Wrapped { envelope, .. } => {
+                vec![envelope.add_shape_features(Role::Content, depth + 1, weights)]
+            }
+            _ => Vec::new(),
+        };
+        // The order of assertions depends on their content, so the shapes of
+        // the assertions of a node are sorted before they are combined.
+        if self.is_node() {
+            children[1..].sort();
+        }
+        let kind = self.shape_kind();
+        let mut parts: Vec<&[u8]> = vec![kind.as_bytes()];
+        parts.extend(children.iter().map(|child| child.data().as_slice()));
+        let shape = Digest::from_image_parts(&parts);
+
+        let position = format!("{}/{}/{}", kind, role.name(), depth);
+        for feature in [Digest::from_image(position), shape.clone()] {
+            let hash = u64::from_be_bytes(feature.data()[..8].try_into().unwrap());
+            for (bit, weight) in weights.iter_mut().enumerate() {
+                *weight += if hash & (1 << bit) != 0 { 1 } else { -1 };
+            }
+        }
+        shape
+    }
+
+    fn shape_kind(&self) -> &'static str {
+        match self.case() {
+            EnvelopeCase::Node { .. } => "node",
+            EnvelopeCase::Leaf { .. } => "leaf",
+            EnvelopeCase::Wrapped { .. } => "wrapped",
+            EnvelopeCase::Assertion(_) => "assertion",
+            EnvelopeCase::Elided(_) => "elided",
+            #[cfg(feature = "known_value")]
+            EnvelopeCase::KnownValue { .. } => "knownValue",
+            #[cfg(feature = "encrypt")]
+            EnvelopeCase::Encrypted(_) => "encrypted",
+            #[cfg(feature = "compress")]
+            EnvelopeCase::Compressed(_) => "compressed",
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use anyhow::{bail, Result};
+use dcbor::Date;
+
+use crate::{Envelope, EnvelopeEncodable, EnvelopeError};
+
+/// How the text of a table cell is converted to an envelope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    /// The cell is used as a string.
+    Text,
+    /// The cell is parsed as an integer.
+    Integer,
+    /// The cell is parsed as a floating-point number.
+    Float,
+    /// The cell is parsed as `true` or `false`.
+    Boolean,
+    /// The cell is parsed as an ISO 8601 date.
+    Date,
+}
+
+impl ColumnType {
+    fn parse(&self, cell: &str) -> Option<Envelope> {
+        Some(match self {
+            ColumnType::Text => Envelope::new(cell),
+            ColumnType::Integer => Envelope::new(cell.parse::<i64>().ok()?),
+            ColumnType::Float => Envelope::new(cell.parse::<f64>().ok()?),
+            ColumnType::Boolean => Envelope::new(cell.parse::<bool>().ok()?),
+            ColumnType::Date => Envelope::new(Date::from_string(cell).ok()?),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ColumnMapping {
+    column: String,
+    predicate: Envelope,
+    column_type: ColumnType,
+}
+
+/// Maps the rows of a table, such as a CSV file, to envelopes.
+///
+/// Each mapped column becomes an assertion whose predicate is given by the
+/// mapping and whose object is the cell converted according to its
+/// [`ColumnType`]. Empty cells are skipped, and columns that are not mapped
+/// are ignored. The subject of each envelope is taken from the subject
+/// column, if there is one, and is `null` otherwise.
+///
+/// This is useful for issuing credentials in bulk from a spreadsheet.
+#[derive(Debug, Clone, Default)]
+pub struct TableMapping {
+    subject: Option<(String, ColumnType)>,
+    columns: Vec<ColumnMapping>,
+}
+
+impl TableMapping {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Takes the subject of each envelope from `column`.
+    pub fn with_subject_column(mut self, column: impl Into<String>, column_type: ColumnType) -> Self {
+        self.subject = Some((column.into(), column_type));
+        self
+    }
+
+    /// Maps `column` to assertions with the given predicate.
+    pub fn with_column(mut self, column: impl Into<String>, predicate: impl EnvelopeEncodable, column_type: ColumnType) -> Self {
+        self.columns.push(ColumnMapping {
+            column: column.into(),
+            predicate: predicate.into_envelope(),
+            column_type,
+        });
+        self
+    }
+
+    /// Returns an envelope for each row of a table with the given header.
+    ///
+    /// Returns an error if a mapped column is missing from the header, or if
+    /// a cell cannot be converted to its column's type.
+    pub fn envelopes<R, S>(&self, header: &[S], rows: impl IntoIterator<Item = R>) -> Result<Vec<Envelope>>
+    where
+        R: AsRef<[S]>,
+        S: AsRef<str>,
+    {
+        let index = |column: &str| {
+            header
+                .iter()
+                .position(|name| name.as_ref() == column)
+                .ok_or_else(|| EnvelopeError::MissingColumn(column.to_string()))
+        };
+        let subject = self
+            .subject
+            .as_ref()
+            .map(|(column, column_type)| Ok::<_, EnvelopeError>((index(column)?, column.as_str(), *column_type)))
+            .transpose()?;
+        let columns = self
+            .columns
+            .iter()
+            .map(|mapping| Ok((index(&mapping.column)?, mapping)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut envelopes = Vec::new();
+        for (row_index, row) in rows.into_iter().enumerate() {
+            let row = row.as_ref();
+            let cell = |index: usize| row.get(index).map_or("", |cell| cell.as_ref());
+            let parse = |index: usize, column: &str, column_type: ColumnType| {
+                column_type.parse(cell(index)).ok_or_else(|| EnvelopeError::InvalidCell {
+                    row: row_index,
+                    column: column.to_string(),
+                })
+            };
+            let mut envelope = match subject {
+                Some((index, column, column_type)) => parse(index, column, column_type)?,
+                None => Envelope::null(),
+            };
+            for (index, mapping) in &columns {
+                if cell(*index).is_empty() {
+                    continue;
+                }
+                let object = parse(*index, &mapping.column, mapping.column_type)?;
+                envelope = envelope.add_assertion(mapping.predicate.clone(), object);
+            }
+            envelopes.push(envelope);
+        }
+        Ok(envelopes)
+    }
+
+    /// Returns an envelope for each row of CSV text whose first line is the
+    /// header.
+    ///
+    /// Fields may be quoted with `"`, and a quote within a quoted field is
+    /// written as `""`, as described in RFC 4180.
+    pub fn envelopes_from_csv(&self, csv: &str) -> Result<Vec<Envelope>> {
+        let mut rows = parse_csv(csv)?.into_iter();
+        let header = rows.next().unwrap_or_default();
+        self.envelopes(&header, rows)
+    }
+
+    /// Returns a single envelope with the given subject and an assertion
+    /// with the given predicate for each row of the table.
+    pub fn collection<R, S>(
+        &self,
+        subject: impl EnvelopeEncodable,
+        predicate: impl EnvelopeEncodable,
+        header: &[S],
+        rows: impl IntoIterator<Item = R>,
+    ) -> Result<Envelope>
+    where
+        R: AsRef<[S]>,
+        S: AsRef<str>,
+    {
+        let predicate = predicate.into_envelope();
+        Ok(self
+            .envelopes(header, rows)?
+            .into_iter()
+            .fold(subject.into_envelope(), |collection, row| collection.add_assertion(predicate.clone(), row)))
+    }
+}
+
+fn parse_csv(csv: &str) -> Result<Vec<Vec<String>>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = csv.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, in_quotes) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            ('"', true) => in_quotes = false,
+            ('"', false) if field.is_empty() => in_quotes = true,
+            (',', false) => row.push(std::mem::take(&mut field)),
+            ('\r', false) if chars.peek() == Some(&'\n') => {}
+            ('\n', false) => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            (c, _) => field.push(c),
+        }
+    }
+    if in_quotes {
+        bail!(EnvelopeError::InvalidFormat);
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    Ok(rows)
+}
@@ -0,0 +1,164 @@
+use std::{fmt::Debug, sync::Arc, time::Duration};
+
+use anyhow::{bail, Result};
+use dcbor::Date;
+
+use crate::EnvelopeError;
+#[cfg(feature = "known_value")]
+use crate::{extension::known_values, Envelope};
+
+/// A source of the current time for a [`TimePolicy`].
+pub trait Clock: Debug + Send + Sync {
+    /// The current time.
+    fn now(&self) -> Date;
+}
+
+/// A [`Clock`] that reads the system clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Date {
+        Date::now()
+    }
+}
+
+/// A [`Clock`] that always returns the same time, for testing.
+#[derive(Debug, Clone)]
+pub struct FixedClock(Date);
+
+impl FixedClock {
+    pub fn new(now: Date) -> Self {
+        Self(now)
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> Date {
+        self.0.clone()
+    }
+}
+
+/// How dates in envelopes are checked against the current time.
+///
+/// A policy tolerates clocks that disagree by up to its maximum skew, and
+/// optionally rejects dates older than a maximum age. It is consumed by
+/// [`Envelope::check_validity`], [`Envelope::verify_signature_from_with_policy`],
+/// [`Request::check_date`](crate::Request::check_date), and
+/// [`ClaimsSet::check_validity`](crate::ClaimsSet::check_validity), so that
+/// all time checks are made the same way, and can be tested with a
+/// [`FixedClock`].
+///
+/// The default policy reads the system clock, tolerates no skew, and accepts
+/// dates of any age.
+#[derive(Debug, Clone)]
+pub struct TimePolicy {
+    clock: Arc<dyn Clock>,
+    max_skew: Duration,
+    max_age: Option<Duration>,
+}
+
+impl TimePolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the policy with the given clock.
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Returns the policy tolerating clocks that disagree by up to `max_skew`.
+    pub fn with_max_skew(mut self, max_skew: Duration) -> Self {
+        self.max_skew = max_skew;
+        self
+    }
+
+    /// Returns the policy rejecting dates older than `max_age`.
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// The current time according to the policy's clock.
+    pub fn now(&self) -> Date {
+        self.clock.now()
+    }
+
+    pub fn max_skew(&self) -> Duration {
+        self.max_skew
+    }
+
+    pub fn max_age(&self) -> Option<Duration> {
+        self.max_age
+    }
+
+    // Times are compared as timestamps rather than by adding durations to
+    // dates, which panics if the result is out of range, so that any skew or
+    // age can be given.
+
+    /// Checks that something valid from `not_before` is valid now.
+    pub fn check_not_before(&self, not_before: &Date) -> Result<()> {
+        if self.now().timestamp() + self.max_skew.as_secs_f64() < not_before.timestamp() {
+            bail!(EnvelopeError::NotYetValid);
+        }
+        Ok(())
+    }
+
+    /// Checks that something valid until `not_after` is still valid now.
+    pub fn check_not_after(&self, not_after: &Date) -> Result<()> {
+        if self.now().timestamp() - self.max_skew.as_secs_f64() > not_after.timestamp() {
+            bail!(EnvelopeError::Expired);
+        }
+        Ok(())
+    }
+
+    /// Checks that something dated `date`, such as a request or a signature,
+    /// was not dated in the future, nor longer ago than the maximum age.
+    pub fn check_date(&self, date: &Date) -> Result<()> {
+        let now = self.now().timestamp();
+        let skew = self.max_skew.as_secs_f64();
+        if now + skew < date.timestamp() {
+            bail!(EnvelopeError::DateInFuture);
+        }
+        if let Some(max_age) = self.max_age {
+            if now - skew - max_age.as_secs_f64() > date.timestamp() {
+                bail!(EnvelopeError::DateTooOld);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for TimePolicy {
+    fn default() -> Self {
+        Self {
+            clock: Arc::new(SystemClock),
+            max_skew: Duration::ZERO,
+            max_age: None,
+        }
+    }
+}
+
+/// Support for checking the dates in envelopes.
+#[cfg(feature = "known_value")]
+impl Envelope {
+    /// Checks the envelope's `'validFrom'`, `'validUntil'`, and `'date'`
+    /// assertions, if present, against the current time.
+    ///
+    /// Returns an error if any of them is not a single date, or if the policy
+    /// rejects it.
+    pub fn check_validity(&self, policy: &TimePolicy) -> Result<()> {
+        if let Some(not_before) = self.extract_optional_object_for_predicate::<Date>(known_values::VALID_FROM)? {
+            policy.check_not_before(&not_before)?;
+        }
+        if let Some(not_after) = self.extract_optional_object_for_predicate::<Date>(known_values::VALID_UNTIL)? {
+            policy.check_not_after(&not_after)?;
+        }
+        if let Some(date) = self.extract_optional_object_for_predicate::<Date>(known_values::DATE)? {
+            policy.check_date(&date)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::HashSet;
+
+use anyhow::{bail, Result};
+use bc_components::{Digest, DigestProvider};
+use dcbor::prelude::*;
+
+use crate::{Assertion, Envelope, EnvelopeEncodable, EnvelopeError};
+
+use super::envelope::EnvelopeCase;
+
+/// Support for transforming the elements of envelopes.
+///
+/// Each of these methods rebuilds only the elements on the path from the root
+/// to the elements that change. Unchanged subtrees are shared with the
+/// original envelope, along with their cached digests.
+impl Envelope {
+    /// Returns a new envelope with each leaf replaced by the result of `f`.
+    ///
+    /// Leaves inside obscured elements are not visited. Leaves for which `f`
+    /// returns the same CBOR are left unchanged. Assertions that become
+    /// identical are merged.
+    pub fn map_leaves<F>(&self, mut f: F) -> Result<Self>
+    where
+        F: FnMut(&CBOR) -> CBOR,
+    {
+        self.map_elements(&mut |element| match element.case() {
+            EnvelopeCase::Leaf { cbor, .. } => Some(Ok(Envelope::new_leaf(f(cbor)))),
+            _ => None,
+        })
+    }
+
+    /// Returns a new envelope with the object of each assertion on this
+    /// envelope with the given predicate replaced by the result of `f`.
+    ///
+    /// Only the envelope's own assertions are visited, as with
+    /// [`Envelope::objects_for_predicate`]. Assertions that become identical
+    /// are merged.
+    pub fn map_objects_for_predicate<F>(&self, predicate: impl EnvelopeEncodable, mut f: F) -> Self
+    where
+        F: FnMut(Envelope) -> Envelope,
+    {
+        let predicate = predicate.into_envelope();
+        let EnvelopeCase::Node { subject, assertions, .. } = self.case() else {
+            return self.clone();
+        };
+        let mut changed = false;
+        let assertions = assertions
+            .iter()
+            .map(|assertion| {
+                let assertion_subject = assertion.subject();
+                (assertion, assertion_subject.as_predicate().zip(assertion_subject.as_object()))
+            })
+            .map(|(assertion, parts)| match parts {
+                Some((assertion_predicate, object)) if assertion_predicate.digest() == predicate.digest() => {
+                    let new_object = f(object.clone());
+                    if new_object.digest() == object.digest() {
+                        return assertion.clone();
+                    }
+                    changed = true;
+                    let new_assertion = Envelope::new_assertion(assertion_predicate, new_object);
+                    match assertion.case() {
+                        // Keep any assertions on the assertion, such as salt.
+                        EnvelopeCase::Node { assertions, .. } => {
+                            Envelope::new_with_unchecked_assertions(new_assertion, assertions.clone())
+                        }
+                        _ => new_assertion,
+                    }
+                }
+                _ => assertion.clone(),
+            })
+            .collect();
+        if changed {
+            Envelope::new_with_unchecked_assertions(subject.clone(), distinct(assertions))
+        } else {
+            self.clone()
+        }
+    }
+
+    /// Returns a new envelope with each element whose digest is `target`
+    /// replaced by the result of `f`.
+    ///
+    /// Returns an error if there is no element with the digest, or if the
+    /// replacement cannot appear where the element was, such as a leaf in
+    /// place of an assertion.
+    pub fn map_subtree<F>(&self, target: &Digest, mut f: F) -> Result<Self>
+    where
+        F: FnMut(Envelope) -> Envelope,
+    {
+        let mut found = false;
+        let result = self.map_elements(&mut |element| {
+            if *element.digest() == *target {
+                found = true;
+                Some(Ok(f(element.clone())))
+            } else {
+                None
+            }
+        })?;
+        if !found {
+            bail!(EnvelopeError::MissingDigest);
+        }
+        Ok(result)
+    }
+
+    /// Rebuilds the envelope, replacing each element for which `replace`
+    /// returns a replacement, and descending into the others.
+    fn map_elements(&self, replace: &mut dyn FnMut(&Envelope) -> Option<Result<Envelope>>) -> Result<Self> {
+        if let Some(replacement) = replace(self) {
+            return replacement;
+        }
+        match self.case() {
+            EnvelopeCase::Node { subject, assertions, .. } => {
+                let new_subject = subject.map_elements(replace)?;
+                let new_assertions = assertions
+                    .iter()
+                    .map(|assertion| assertion.map_elements(replace))
+                    .collect::<Result<Vec<_>>>()?;
+                if new_subject.digest() == subject.digest()
+                    && new_assertions.iter().zip(assertions).all(|(new, old)| new.digest() == old.digest())
+                {
+                    return Ok(self.clone());
+                }
+                Envelope::new_with_assertions(new_subject, distinct(new_assertions))
+            }
+            EnvelopeCase::Wrapped { envelope, .. } => {
+                let new_envelope = envelope.map_elements(replace)?;
+                if new_envelope.digest() == envelope.digest() {
+                    return Ok(self.clone());
+                }
+                Ok(Envelope::new_wrapped(new_envelope))
+            }
+            EnvelopeCase::Assertion(assertion) => {
+                let predicate = assertion.predicate();
+                let object = assertion.object();
+                let new_predicate = predicate.map_elements(replace)?;
+                let new_object = object.map_elements(replace)?;
+                if new_predicate.digest() == predicate.digest() && new_object.digest() == object.digest() {
+                    return Ok(self.clone());
+                }
+                Ok(Envelope::new_with_assertion(Assertion::new(new_predicate, new_object)))
+            }
+            _ => Ok(self.clone()),
+        }
+    }
+}
+
+/// Removes the assertions with the same digest as an earlier one.
+fn distinct(assertions: Vec<Envelope>) -> Vec<Envelope> {
+    let mut seen = HashSet::new();
+    assertions.into_iter().filter(|assertion| seen.insert(assertion.digest().into_owned())).collect()
+}
@@ -2,9 +2,9 @@ use std::{collections::HashSet, cell::RefCell};
 
 use bc_components::{Digest, DigestProvider};
 
-use crate::{Envelope, with_format_context, FormatContext};
+use crate::{Envelope, with_format_context, FormatContext, FormatVersion};
 #[cfg(feature = "known_value")]
-use crate::{string_utils::StringUtils, extension::KnownValuesStore};
+use crate::extension::KnownValuesStore;
 
 use super::{walk::EdgeType, EnvelopeSummary, envelope::EnvelopeCase};
 
@@ -20,23 +20,73 @@ impl Envelope {
         })
     }
 
+    /// Returns the tree notation for this envelope, pinned to the given
+    /// version.
+    ///
+    /// Uses the current format context. See [`FormatVersion`].
+    pub fn tree_format_versioned(&self, hide_nodes: bool, version: FormatVersion) -> String {
+        with_format_context!(|context: &FormatContext| {
+            let context = context.clone().set_format_version(version);
+            self.tree_format_opt(hide_nodes, Some(&context))
+        })
+    }
+
     pub fn tree_format_with_target_opt(&self, hide_nodes: bool, highlighting_target: &HashSet<Digest>, context: Option<&FormatContext>) -> String {
+        let context = context.cloned().unwrap_or_default();
+        self.tree_elements(hide_nodes, highlighting_target, &context)
+            .iter()
+            .map(|e| e.string(&context))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Returns one page of the tree notation for this envelope, so that very
+    /// large envelopes can be displayed incrementally.
+    ///
+    /// Page `page_index` holds lines `page_index * page_size` up to
+    /// `(page_index + 1) * page_size` of the output of
+    /// [`Envelope::tree_format_opt`]. Only the lines of the requested page
+    /// are formatted. Returns an empty string if the page is past the end.
+    /// See [`Envelope::tree_format_page_count`].
+    pub fn tree_format_paged(&self, hide_nodes: bool, context: Option<&FormatContext>, page_size: usize, page_index: usize) -> String {
+        let context = context.cloned().unwrap_or_default();
+        self.tree_elements(hide_nodes, &HashSet::new(), &context)
+            .iter()
+            .skip(page_index.saturating_mul(page_size))
+            .take(page_size)
+            .map(|e| e.string(&context))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Returns the number of pages of `page_size` lines in the tree notation
+    /// for this envelope. See [`Envelope::tree_format_paged`].
+    pub fn tree_format_page_count(&self, hide_nodes: bool, context: Option<&FormatContext>, page_size: usize) -> usize {
+        let context = context.cloned().unwrap_or_default();
+        self.tree_elements(hide_nodes, &HashSet::new(), &context).len().div_ceil(page_size.max(1))
+    }
+
+    fn tree_elements(&self, hide_nodes: bool, highlighting_target: &HashSet<Digest>, context: &FormatContext) -> Vec<TreeElement> {
+        let max_depth = context.max_depth();
         let elements: RefCell<Vec<TreeElement>> = RefCell::new(Vec::new());
         let visitor = |envelope: Self, level: usize, incoming_edge: EdgeType, _: Option<&()>| -> _ {
+            if max_depth.is_some_and(|max_depth| level > max_depth) {
+                return None;
+            }
+            let is_truncated = max_depth == Some(level) && envelope.is_internal();
             let elem = TreeElement::new(
                 level,
                 envelope.clone(),
                 incoming_edge,
                 !hide_nodes,
                 highlighting_target.contains(&envelope.digest()),
+                is_truncated,
             );
             elements.borrow_mut().push(elem);
             None
         };
-        let s = self.clone();
-        s.walk(hide_nodes, &visitor);
-        let elements = elements.borrow();
-        elements.iter().map(|e| e.string(context.unwrap_or(&FormatContext::default()))).collect::<Vec<_>>().join("\n")
+        self.walk(hide_nodes, &visitor);
+        elements.into_inner()
     }
 
     pub fn tree_format_with_target(&self, hide_nodes: bool, highlighting_target: &HashSet<Digest>) -> String {
@@ -60,8 +110,9 @@ impl Envelope {
             EnvelopeCase::Elided(_) => "ELIDED".to_string(),
             #[cfg(feature = "known_value")]
             EnvelopeCase::KnownValue { value, .. } => {
-                let known_value = KnownValuesStore::known_value_for_raw_value(value.value(), Some(context.known_values()));
-                known_value.to_string().flanked_by("'", "'",)
+                context.format_known_value(value.value(), || {
+                    KnownValuesStore::known_value_for_raw_value(value.value(), Some(context.known_values())).to_string()
+                })
             },
             #[cfg(feature = "encrypt")]
             EnvelopeCase::Encrypted(_) => "ENCRYPTED".to_string(),
@@ -78,21 +129,29 @@ struct TreeElement {
     incoming_edge: EdgeType,
     show_id: bool,
     is_highlighted: bool,
+    is_truncated: bool,
 }
 
 impl TreeElement {
-    fn new(level: usize, envelope: Envelope, incoming_edge: EdgeType, show_id: bool, is_highlighted: bool) -> Self {
-        Self { level, envelope, incoming_edge, show_id, is_highlighted }
+    fn new(level: usize, envelope: Envelope, incoming_edge: EdgeType, show_id: bool, is_highlighted: bool, is_truncated: bool) -> Self {
+        Self { level, envelope, incoming_edge, show_id, is_highlighted, is_truncated }
     }
 
     fn string(&self, context: &FormatContext) -> String {
-        let line = vec![
-            if self.is_highlighted { Some("*".to_string()) } else { None },
-            if self.show_id { Some(self.envelope.short_id()) } else { None },
-            self.incoming_edge.label().map(|s| s.to_string()),
-            Some(self.envelope.summary(40, context)),
-        ].into_iter().flatten().collect::<Vec<_>>().join(" ");
-        let indent = " ".repeat(self.level * 4);
-        format!("{}{}", indent, line)
+        // A change to the output is made in a new version, leaving the output
+        // of the earlier versions as it was.
+        match context.format_version() {
+            FormatVersion::V1 => {
+                let line = vec![
+                    if self.is_highlighted { Some("*".to_string()) } else { None },
+                    if self.show_id { Some(context.describe_digest(&self.envelope.digest())) } else { None },
+                    self.incoming_edge.label().map(|s| s.to_string()),
+                    Some(self.envelope.summary(40, context)),
+                    if self.is_truncated { Some("…".to_string()) } else { None },
+                ].into_iter().flatten().collect::<Vec<_>>().join(" ");
+                let indent = " ".repeat(self.level * 4);
+                format!("{}{}", indent, line)
+            }
+        }
     }
 }
@@ -1,4 +1,13 @@
-use crate::Envelope;
+use bc_components::{Digest, DigestProvider};
+#[cfg(feature = "encrypt")]
+use bc_components::EncryptedMessage;
+#[cfg(feature = "compress")]
+use bc_components::Compressed;
+use dcbor::prelude::*;
+
+use crate::{Assertion, Envelope};
+#[cfg(feature = "known_value")]
+use crate::extension::KnownValue;
 
 use super::envelope::EnvelopeCase;
 
@@ -41,60 +50,265 @@ impl Envelope {
         }
     }
 
+    // The walks keep their own stacks rather than recursing, so that walking
+    // a deeply nested envelope cannot exhaust the call stack.
+
     fn walk_structure<Parent: Clone>(&self, visit: &Visitor<'_, Parent>) {
-        self._walk_structure(0, EdgeType::None, None, visit);
+        let mut stack = vec![(self.clone(), 0, EdgeType::None, None)];
+        while let Some((envelope, level, incoming_edge, parent)) = stack.pop() {
+            let parent = visit(envelope.clone(), level, incoming_edge, parent);
+            let next_level = level + 1;
+            match envelope.case() {
+                EnvelopeCase::Node { subject, assertions, .. } => {
+                    for assertion in assertions.iter().rev() {
+                        stack.push((assertion.clone(), next_level, EdgeType::Assertion, parent.clone()));
+                    }
+                    stack.push((subject.clone(), next_level, EdgeType::Subject, parent));
+                },
+                EnvelopeCase::Wrapped { envelope, .. } => {
+                    stack.push((envelope.clone(), next_level, EdgeType::Wrapped, parent));
+                },
+                EnvelopeCase::Assertion(assertion) => {
+                    stack.push((assertion.object(), next_level, EdgeType::Object, parent.clone()));
+                    stack.push((assertion.predicate(), next_level, EdgeType::Predicate, parent));
+                },
+                _ => {},
+            }
+        }
     }
 
-    fn _walk_structure<Parent: Clone>(&self, level: usize, incoming_edge: EdgeType, parent: Option<Parent>, visit: &Visitor<'_, Parent>) {
-        let parent = visit(self.clone(), level, incoming_edge, parent);
-        let next_level = level + 1;
-        match self.case() {
-            EnvelopeCase::Node { subject, assertions, .. } => {
-                subject._walk_structure(next_level, EdgeType::Subject, parent.clone(), visit);
-                for assertion in assertions {
-                    assertion._walk_structure(next_level, EdgeType::Assertion, parent.clone(), visit);
+    fn walk_tree<Parent: Clone>(&self, visit: &Visitor<'_, Parent>) {
+        let mut stack = vec![(self.clone(), 0, None)];
+        while let Some((envelope, level, parent)) = stack.pop() {
+            if let EnvelopeCase::Node { subject, assertions, .. } = envelope.case() {
+                // The subject is visited at the node's level, and its result
+                // is the parent of the assertions, which come after the
+                // subject's children.
+                let assertion_level = level + 1;
+                if subject.is_node() {
+                    for assertion in assertions.iter().rev() {
+                        stack.push((assertion.clone(), assertion_level, parent.clone()));
+                    }
+                    stack.push((subject.clone(), level, parent));
+                } else {
+                    let assertion_parent = visit(subject.clone(), level, EdgeType::None, parent);
+                    for assertion in assertions.iter().rev() {
+                        stack.push((assertion.clone(), assertion_level, assertion_parent.clone()));
+                    }
+                    push_tree_children(&mut stack, subject, level + 1, assertion_parent);
                 }
-            },
-            EnvelopeCase::Wrapped { envelope, .. } => {
-                envelope._walk_structure(next_level, EdgeType::Wrapped, parent, visit);
-            },
-            EnvelopeCase::Assertion(assertion) => {
-                assertion.predicate()._walk_structure(next_level, EdgeType::Predicate, parent.clone(), visit);
-                assertion.object()._walk_structure(next_level, EdgeType::Object, parent, visit);
-            },
-            _ => {},
+            } else {
+                let parent = visit(envelope.clone(), level, EdgeType::None, parent);
+                push_tree_children(&mut stack, &envelope, level + 1, parent);
+            }
         }
     }
+}
 
-    fn walk_tree<Parent: Clone>(&self, visit: &Visitor<'_, Parent>)
-    {
-        self._walk_tree(0, None, visit);
+/// Pushes the children of an element that is not a node onto the stack of
+/// [`Envelope::walk`], so that they are visited in order.
+fn push_tree_children<Parent: Clone>(stack: &mut Vec<(Envelope, usize, Option<Parent>)>, envelope: &Envelope, level: usize, parent: Option<Parent>) {
+    match envelope.case() {
+        EnvelopeCase::Wrapped { envelope, .. } => {
+            stack.push((envelope.clone(), level, parent));
+        },
+        EnvelopeCase::Assertion(assertion) => {
+            stack.push((assertion.object(), level, parent.clone()));
+            stack.push((assertion.predicate(), level, parent));
+        },
+        _ => {},
     }
+}
 
-    fn _walk_tree<Parent: Clone>(&self, level: usize, parent: Option<Parent>, visit: &Visitor<'_, Parent>) -> Option<Parent> {
-        let mut parent = parent;
-        let mut subject_level = level;
-        if !self.is_node() {
-            parent = visit(self.clone(), level, EdgeType::None, parent);
-            subject_level = level + 1;
+/// An event in the depth-first traversal of an envelope produced by
+/// [`Envelope::walk_events`].
+///
+/// Each element with children produces an `Enter` event, followed by the
+/// events of its children, followed by an `Exit` event. Each element without
+/// children produces a single `Leaf` event. The `case` of an event is the name
+/// of the element's case, such as `"node"`, `"assertion"`, or `"elided"`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WalkEvent {
+    Enter { digest: Digest, case: &'static str, level: usize, edge: EdgeType },
+    Leaf { digest: Digest, case: &'static str, level: usize, edge: EdgeType },
+    Exit { digest: Digest, case: &'static str, level: usize },
+}
+
+impl WalkEvent {
+    /// The event as a single line of JSON, so that a sequence of events can be
+    /// written as JSON Lines for analysis by external tools.
+    ///
+    /// Digests are encoded as hexadecimal strings, and the `edge` of an `Exit`
+    /// event is omitted.
+    pub fn to_json(&self) -> String {
+        let (event, digest, case, level, edge) = match self {
+            WalkEvent::Enter { digest, case, level, edge } => ("enter", digest, case, level, Some(edge)),
+            WalkEvent::Leaf { digest, case, level, edge } => ("leaf", digest, case, level, Some(edge)),
+            WalkEvent::Exit { digest, case, level } => ("exit", digest, case, level, None),
+        };
+        let edge = edge
+            .map(|edge| format!(r#","edge":"{}""#, edge.name()))
+            .unwrap_or_default();
+        format!(
+            r#"{{"event":"{}","digest":"{}","case":"{}","level":{}{}}}"#,
+            event, digest.hex(), case, level, edge
+        )
+    }
+}
+
+impl EdgeType {
+    /// The component of a path string, such as `subject/assertion[2]/object`,
+    /// for an element reached by this edge, or `None` for the root.
+    ///
+    /// `index` is the position of an assertion among its node's assertions,
+    /// which are ordered by digest, and is ignored for other edges.
+    pub fn path_component(&self, index: usize) -> Option<String> {
+        match self {
+            EdgeType::None => None,
+            EdgeType::Assertion => Some(format!("assertion[{}]", index)),
+            edge => Some(edge.name().to_string()),
         }
-        match self.case() {
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            EdgeType::None => "none",
+            EdgeType::Subject => "subject",
+            EdgeType::Assertion => "assertion",
+            EdgeType::Predicate => "predicate",
+            EdgeType::Object => "object",
+            EdgeType::Wrapped => "wrapped",
+        }
+    }
+}
+
+enum WalkStep {
+    Visit(Envelope, usize, EdgeType),
+    Exit(Envelope, usize),
+}
+
+/// An iterator over the [`WalkEvent`]s of an envelope.
+///
+/// Created by [`Envelope::walk_events`].
+pub struct WalkEvents {
+    stack: Vec<WalkStep>,
+}
+
+impl Iterator for WalkEvents {
+    type Item = WalkEvent;
+
+    fn next(&mut self) -> Option<WalkEvent> {
+        let (envelope, level, edge) = match self.stack.pop()? {
+            WalkStep::Exit(envelope, level) => {
+                let digest = envelope.digest().into_owned();
+                return Some(WalkEvent::Exit { digest, case: envelope.case_name(), level });
+            },
+            WalkStep::Visit(envelope, level, edge) => (envelope, level, edge),
+        };
+        let digest = envelope.digest().into_owned();
+        let case = envelope.case_name();
+        let next_level = level + 1;
+        let children: Vec<WalkStep> = match envelope.case() {
             EnvelopeCase::Node { subject, assertions, .. } => {
-                let assertion_parent = subject._walk_tree(subject_level, parent.clone(), visit);
-                let assertion_level = subject_level + 1;
-                for assertion in assertions {
-                    assertion._walk_tree(assertion_level, assertion_parent.clone(), visit);
-                }
+                std::iter::once(WalkStep::Visit(subject.clone(), next_level, EdgeType::Subject))
+                    .chain(assertions.iter().map(|assertion| WalkStep::Visit(assertion.clone(), next_level, EdgeType::Assertion)))
+                    .collect()
             },
             EnvelopeCase::Wrapped { envelope, .. } => {
-                envelope._walk_tree(subject_level, parent.clone(), visit);
+                vec![WalkStep::Visit(envelope.clone(), next_level, EdgeType::Wrapped)]
             },
-            EnvelopeCase::Assertion(assertion) => {
-                assertion.predicate()._walk_tree(subject_level, parent.clone(), visit);
-                assertion.object()._walk_tree(subject_level, parent.clone(), visit);
-            },
-            _ => {},
+            EnvelopeCase::Assertion(assertion) => vec![
+                WalkStep::Visit(assertion.predicate(), next_level, EdgeType::Predicate),
+                WalkStep::Visit(assertion.object(), next_level, EdgeType::Object),
+            ],
+            _ => return Some(WalkEvent::Leaf { digest, case, level, edge }),
+        };
+        self.stack.push(WalkStep::Exit(envelope, level));
+        self.stack.extend(children.into_iter().rev());
+        Some(WalkEvent::Enter { digest, case, level, edge })
+    }
+}
+
+impl Envelope {
+    /// Returns an iterator over the events of a depth-first traversal of the
+    /// envelope, visiting elements in the same order as [`Envelope::walk`].
+    pub fn walk_events(&self) -> WalkEvents {
+        WalkEvents { stack: vec![WalkStep::Visit(self.clone(), 0, EdgeType::None)] }
+    }
+
+    pub(crate) fn case_name(&self) -> &'static str {
+        match self.case() {
+            EnvelopeCase::Node { .. } => "node",
+            EnvelopeCase::Leaf { .. } => "leaf",
+            EnvelopeCase::Wrapped { .. } => "wrapped",
+            EnvelopeCase::Assertion(_) => "assertion",
+            EnvelopeCase::Elided(_) => "elided",
+            #[cfg(feature = "known_value")]
+            EnvelopeCase::KnownValue { .. } => "knownValue",
+            #[cfg(feature = "encrypt")]
+            EnvelopeCase::Encrypted(_) => "encrypted",
+            #[cfg(feature = "compress")]
+            EnvelopeCase::Compressed(_) => "compressed",
+        }
+    }
+}
+
+/// A visitor that is dispatched on the case of a single envelope.
+///
+/// Every method has a default no-op implementation, so implementors only need
+/// to override the cases they care about. Each method receives the envelope
+/// being visited along with the contents of its case. Using this trait via
+/// [`Envelope::visit`] rather than matching on [`EnvelopeCase`] directly
+/// insulates transformations from cases added by future versions or enabled
+/// by other features.
+#[allow(unused_variables)]
+pub trait EnvelopeVisitor {
+    /// Called for an envelope with one or more assertions.
+    fn visit_node(&mut self, envelope: &Envelope, subject: &Envelope, assertions: &[Envelope]) {}
+
+    /// Called for an envelope containing encoded CBOR data.
+    fn visit_leaf(&mut self, envelope: &Envelope, cbor: &CBOR) {}
+
+    /// Called for an envelope that wraps another envelope.
+    fn visit_wrapped(&mut self, envelope: &Envelope, wrapped: &Envelope) {}
+
+    /// Called for an assertion envelope.
+    fn visit_assertion(&mut self, envelope: &Envelope, assertion: &Assertion) {}
+
+    /// Called for an elided envelope.
+    fn visit_elided(&mut self, envelope: &Envelope, digest: &Digest) {}
+
+    /// Called for a known value envelope.
+    #[cfg(feature = "known_value")]
+    fn visit_known_value(&mut self, envelope: &Envelope, value: &KnownValue) {}
+
+    /// Called for an encrypted envelope.
+    #[cfg(feature = "encrypt")]
+    fn visit_encrypted(&mut self, envelope: &Envelope, message: &EncryptedMessage) {}
+
+    /// Called for a compressed envelope.
+    #[cfg(feature = "compress")]
+    fn visit_compressed(&mut self, envelope: &Envelope, compressed: &Compressed) {}
+}
+
+impl Envelope {
+    /// Calls the method of `visitor` that corresponds to this envelope's case.
+    ///
+    /// This does not recurse into the envelope's children; visitors that need
+    /// to descend can call `visit` on the children they are given.
+    pub fn visit(&self, visitor: &mut impl EnvelopeVisitor) {
+        match self.case() {
+            EnvelopeCase::Node { subject, assertions, .. } => visitor.visit_node(self, subject, assertions),
+            EnvelopeCase::Leaf { cbor, .. } => visitor.visit_leaf(self, cbor),
+            EnvelopeCase::Wrapped { envelope, .. } => visitor.visit_wrapped(self, envelope),
+            EnvelopeCase::Assertion(assertion) => visitor.visit_assertion(self, assertion),
+            EnvelopeCase::Elided(digest) => visitor.visit_elided(self, digest),
+            #[cfg(feature = "known_value")]
+            EnvelopeCase::KnownValue { value, .. } => visitor.visit_known_value(self, value),
+            #[cfg(feature = "encrypt")]
+            EnvelopeCase::Encrypted(message) => visitor.visit_encrypted(self, message),
+            #[cfg(feature = "compress")]
+            EnvelopeCase::Compressed(compressed) => visitor.visit_compressed(self, compressed),
         }
-        parent
     }
 }
@@ -1,9 +1,13 @@
-use anyhow::{bail, Result};
+use anyhow::Result;
 
-use crate::{Envelope, EnvelopeError};
+use crate::{Envelope, EnvelopeError, EnvelopeMatcher};
 
 use super::envelope::EnvelopeCase;
 
+/// The most wrapping layers that [`Envelope::unwrap_all`] and
+/// [`Envelope::unwrap_until`] will remove.
+pub const MAX_UNWRAP_DEPTH: usize = 64;
+
 /// Support for wrapping and unwrapping envelopes.
 impl Envelope {
     /// Return a new envelope which wraps the current envelope.
@@ -15,9 +19,71 @@ impl Envelope {
     ///
     /// Returns an error if this is not a wrapped envelope.
     pub fn unwrap_envelope(&self) -> Result<Self> {
-        match self.subject().case() {
+        let subject = self.subject();
+        match subject.case() {
             EnvelopeCase::Wrapped { envelope, .. } => Ok(envelope.clone()),
-            _ => bail!(EnvelopeError::NotWrapped),
+            _ => Err(self.add_error_context(subject.add_error_context(EnvelopeError::NotWrapped.into()))),
+        }
+    }
+
+    /// Unwraps `n` layers of wrapping and returns the inner envelope.
+    ///
+    /// This replaces chains of [`Envelope::unwrap_envelope`] calls, such as
+    /// when verifying a document that was signed and then countersigned.
+    /// Returns an error if the envelope has fewer than `n` layers.
+    pub fn unwrap_n(&self, n: usize) -> Result<Self> {
+        let mut envelope = self.clone();
+        for _ in 0..n {
+            envelope = envelope.unwrap_envelope()?;
+        }
+        Ok(envelope)
+    }
+
+    /// Unwraps as many layers of wrapping as the envelope has, up to
+    /// [`MAX_UNWRAP_DEPTH`], and returns the innermost envelope with the
+    /// number of layers removed.
+    pub fn unwrap_all(&self) -> (Self, usize) {
+        self.unwrap_all_to_depth(MAX_UNWRAP_DEPTH)
+    }
+
+    /// Unwraps as many layers of wrapping as the envelope has, up to
+    /// `max_depth`, and returns the innermost envelope reached with the
+    /// number of layers removed.
+    pub fn unwrap_all_to_depth(&self, max_depth: usize) -> (Self, usize) {
+        let mut envelope = self.clone();
+        let mut count = 0;
+        while count < max_depth {
+            let Ok(inner) = envelope.unwrap_envelope() else {
+                break;
+            };
+            envelope = inner;
+            count += 1;
+        }
+        (envelope, count)
+    }
+
+    /// Unwraps layers of wrapping until reaching an envelope that `matcher`
+    /// matches, and returns it with the number of layers removed.
+    ///
+    /// The envelope itself is checked first. Returns `None` if no envelope
+    /// within [`MAX_UNWRAP_DEPTH`] layers matches.
+    pub fn unwrap_until(&self, matcher: &impl EnvelopeMatcher) -> Option<(Self, usize)> {
+        self.unwrap_until_depth(matcher, MAX_UNWRAP_DEPTH)
+    }
+
+    /// Unwraps layers of wrapping until reaching an envelope that `matcher`
+    /// matches, and returns it with the number of layers removed.
+    ///
+    /// The envelope itself is checked first. Returns `None` if no envelope
+    /// within `max_depth` layers matches.
+    pub fn unwrap_until_depth(&self, matcher: &impl EnvelopeMatcher, max_depth: usize) -> Option<(Self, usize)> {
+        let mut envelope = self.clone();
+        for count in 0..=max_depth {
+            if matcher.matches(&envelope) {
+                return Some((envelope, count));
+            }
+            envelope = envelope.unwrap_envelope().ok()?;
         }
+        None
     }
 }
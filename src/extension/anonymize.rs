@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+use bc_components::{Digest, DigestProvider, Salt};
+use bc_rand::{RandomNumberGenerator, SecureRandomNumberGenerator};
+use dcbor::prelude::*;
+
+use crate::{base::envelope::EnvelopeCase, Assertion, Envelope, EnvelopeEncodable, EnvelopeError};
+
+/// What to put in place of a value removed by [`Envelope::anonymize`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnonymizeAction {
+    /// Replace the value with its elided form. Elided values keep their
+    /// original digest, so an attacker who can guess the value can confirm the
+    /// guess.
+    Elide,
+
+    /// Replace the value with a leaf containing a salted hash of the value.
+    /// Identical values produce different hashes, so replacements cannot be
+    /// correlated with each other or confirmed by guessing.
+    SaltedHash,
+}
+
+/// A function that decides whether the text of a string leaf is removed.
+pub type ValueMatcher<'a> = dyn Fn(&str) -> bool + 'a;
+
+/// A policy describing which values [`Envelope::anonymize`] removes.
+///
+/// Values can be selected either by the predicate of the assertion they are
+/// the object of, or by testing the text of string leaves (for example with a
+/// regular expression that recognizes email addresses).
+#[derive(Default)]
+pub struct AnonymizePolicy<'a> {
+    predicates: Vec<(Digest, AnonymizeAction)>,
+    matchers: Vec<(Box<ValueMatcher<'a>>, AnonymizeAction)>,
+}
+
+impl<'a> AnonymizePolicy<'a> {
+    /// Creates a new policy that doesn't remove anything.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Removes the object of every assertion with the given predicate.
+    pub fn anonymize_predicate(mut self, predicate: impl EnvelopeEncodable, action: AnonymizeAction) -> Self {
+        self.predicates.push((predicate.into_envelope().digest().into_owned(), action));
+        self
+    }
+
+    /// Removes every string leaf whose text satisfies `matcher`.
+    pub fn anonymize_matching(mut self, matcher: impl Fn(&str) -> bool + 'a, action: AnonymizeAction) -> Self {
+        self.matchers.push((Box::new(matcher), action));
+        self
+    }
+
+    fn action_for_predicate(&self, predicate: &Envelope) -> Option<AnonymizeAction> {
+        let digest = predicate.digest();
+        self.predicates
+            .iter()
+            .find(|(d, _)| d == digest.as_ref())
+            .map(|(_, action)| *action)
+    }
+
+    fn action_for_leaf(&self, cbor: &CBOR) -> Option<AnonymizeAction> {
+        let CBORCase::Text(text) = cbor.as_case() else {
+            return None;
+        };
+        self.matchers
+            .iter()
+            .find(|(matcher, _)| matcher(text))
+            .map(|(_, action)| *action)
+    }
+}
+
+/// Support for anonymizing envelopes.
+impl Envelope {
+    /// Returns an anonymized copy of this envelope and a mapping envelope that
+    /// can be used to re-identify it.
+    ///
+    /// Every value selected by `policy` is replaced as specified by its
+    /// [`AnonymizeAction`]. The mapping envelope has the digest of the
+    /// anonymized envelope as its subject, and one assertion per replaced
+    /// value whose predicate is the digest of the replacement and whose object
+    /// is the original value. It should be shared only with parties authorized
+    /// to re-identify the document, using [`Envelope::reidentify`].
+    pub fn anonymize(&self, policy: &AnonymizePolicy<'_>) -> (Self, Self) {
+        let mut rng = SecureRandomNumberGenerator;
+        self.anonymize_using(policy, &mut rng)
+    }
+
+    #[doc(hidden)]
+    /// Returns an anonymized copy of this envelope and a mapping envelope that
+    /// can be used to re-identify it, using the given random number generator
+    /// to produce salt.
+    ///
+    /// Only used for testing.
+    pub fn anonymize_using(&self, policy: &AnonymizePolicy<'_>, rng: &mut impl RandomNumberGenerator) -> (Self, Self) {
+        let mut replacements = Vec::new();
+        let result = self.anonymize_element(policy, rng, &mut replacements);
+        let mapping = replacements
+            .into_iter()
+            .fold(Envelope::new(result.digest().into_owned()), |mapping, (digest, original)| {
+                mapping.add_assertion(digest, original)
+            });
+        (result, mapping)
+    }
+
+    /// Restores the values removed by [`Envelope::anonymize`], given the
+    /// mapping envelope it returned.
+    ///
+    /// Entries of the mapping that have been obscured are skipped, so their
+    /// values stay anonymized. Returns an error if the mapping was not
+    /// produced for this envelope, or an entry is malformed.
+    pub fn reidentify(&self, mapping: &Envelope) -> Result<Self> {
+        let subject: Digest = mapping.extract_subject()?;
+        if &subject != self.digest().as_ref() {
+            bail!(EnvelopeError::InvalidDigest);
+        }
+        let mut originals = HashMap::new();
+        for assertion in mapping.assertions() {
+            if assertion.is_obscured() {
+                continue;
+            }
+            // Look through any assertions on the entry, such as salt.
+            let assertion = assertion.subject();
+            let (Some(predicate), Some(object)) = (assertion.as_predicate(), assertion.as_object()) else {
+                bail!(EnvelopeError::InvalidFormat);
+            };
+            originals.insert(predicate.extract_subject::<Digest>()?, object);
+        }
+        Ok(self.reidentify_element(&originals))
+    }
+
+    fn anonymize_element(
+        &self,
+        policy: &AnonymizePolicy<'_>,
+        rng: &mut impl RandomNumberGenerator,
+        replacements: &mut Vec<(Digest, Envelope)>,
+    ) -> Self {
+        match self.case() {
+            EnvelopeCase::Node { subject, assertions, .. } => {
+                let subject = subject.anonymize_element(policy, rng, replacements);
+                let assertions = assertions
+                    .iter()
+                    .map(|assertion| assertion.anonymize_element(policy, rng, replacements))
+                    .collect();
+                Self::new_with_unchecked_assertions(subject, assertions)
+            }
+            EnvelopeCase::Wrapped { envelope, .. } => {
+                Self::new_wrapped(envelope.anonymize_element(policy, rng, replacements))
+            }
+            EnvelopeCase::Assertion(assertion) => {
+                let predicate = assertion.predicate();
+                let object = match policy.action_for_predicate(&predicate) {
+                    Some(action) => assertion.object().anonymize_replacing(action, rng, replacements),
+                    None => assertion.object().anonymize_element(policy, rng, replacements),
+                };
+                let predicate = predicate.anonymize_element(policy, rng, replacements);
+                Self::new_with_assertion(Assertion::new(predicate, object))
+            }
+            EnvelopeCase::Leaf { cbor, .. } => match policy.action_for_leaf(cbor) {
+                Some(action) => self.anonymize_replacing(action, rng, replacements),
+                None => self.clone(),
+            },
+            _ => self.clone(),
+        }
+    }
+
+    fn anonymize_replacing(
+        &self,
+        action: AnonymizeAction,
+        rng: &mut impl RandomNumberGenerator,
+        replacements: &mut Vec<(Digest, Envelope)>,
+    ) -> Self {
+        let replacement = match action {
+            AnonymizeAction::Elide => self.elide(),
+            AnonymizeAction::SaltedHash => {
+                let data = self.tagged_cbor().to_cbor_data();
+                let salt = Salt::new_for_size_using(data.len(), rng);
+                Envelope::new(Digest::from_image_parts(&[salt.data().as_slice(), data.as_slice()]))
+            }
+        };
+        replacements.push((replacement.digest().into_owned(), self.clone()));
+        replacement
+    }
+
+    fn reidentify_element(&self, originals: &HashMap<Digest, Envelope>) -> Self {
+        if let Some(original) = originals.get(self.digest().as_ref()) {
+            return original.clone();
+        }
+        match self.case() {
+            EnvelopeCase::Node { subject, assertions, .. } => {
+                let subject = subject.reidentify_element(originals);
+                let assertions = assertions
+                    .iter()
+                    .map(|assertion| assertion.reidentify_element(originals))
+                    .collect();
+                Self::new_with_unchecked_assertions(subject, assertions)
+            }
+            EnvelopeCase::Wrapped { envelope, .. } => {
+                Self::new_wrapped(envelope.reidentify_element(originals))
+            }
+            EnvelopeCase::Assertion(assertion) => {
+                let predicate = assertion.predicate().reidentify_element(originals);
+                let object = assertion.object().reidentify_element(originals);
+                Self::new_with_assertion(Assertion::new(predicate, object))
+            }
+            _ => self.clone(),
+        }
+    }
+}
@@ -1,4 +1,6 @@
 use anyhow::{bail, Result};
+use dcbor::prelude::*;
+use bc_components::{Digest, DigestProvider};
 
 use crate::{base::envelope::EnvelopeCase, extension::known_values, Assertion, Envelope, EnvelopeEncodable, EnvelopeError};
 
@@ -6,7 +8,15 @@ impl Assertion {
     /// Creates an attachment assertion. See:
     /// [BCR-2023-006](https://github.com/BlockchainCommons/Research/blob/master/papers/bcr-2023-006-envelope-attachment.md)
     pub fn new_attachment(payload: impl EnvelopeEncodable, vendor: &str, conforms_to: Option<&str>) -> Self {
+        Self::new_attachment_with_id(payload, vendor, conforms_to, None)
+    }
+
+    /// Creates an attachment assertion with an optional `'id': String`
+    /// assertion, which distinguishes attachments that have the same `vendor`
+    /// and `conformsTo`.
+    pub fn new_attachment_with_id(payload: impl EnvelopeEncodable, vendor: &str, conforms_to: Option<&str>, id: Option<&str>) -> Self {
         let conforms_to: Option<String> = conforms_to.map(|c| c.to_string());
+        let id: Option<String> = id.map(|i| i.to_string());
         Self::new(
             known_values::ATTACHMENT,
             payload
@@ -14,6 +24,7 @@ impl Assertion {
                 .wrap_envelope()
                 .add_assertion(known_values::VENDOR, vendor.to_string())
                 .add_optional_assertion(known_values::CONFORMS_TO, conforms_to)
+                .add_optional_assertion(known_values::ID, id)
         )
     }
 
@@ -32,6 +43,11 @@ impl Assertion {
         self.object().extract_optional_object_for_predicate(known_values::CONFORMS_TO)
     }
 
+    /// Returns the `id` of the given attachment assertion.
+    pub fn attachment_id(&self) -> Result<Option<String>> {
+        self.object().extract_optional_object_for_predicate(known_values::ID)
+    }
+
     /// Validates the given attachment assertion.
     ///
     /// Ensures:
@@ -39,11 +55,13 @@ impl Assertion {
     /// - The attachment assertion's object is an envelope.
     /// - The attachment assertion's object has a `'vendor': String` assertion.
     /// - The attachment assertion's object has an optional `'conformsTo': String` assertion.
+    /// - The attachment assertion's object has an optional `'id': String` assertion.
     pub fn validate_attachment(&self) -> Result<()> {
         let payload = self.attachment_payload()?;
         let vendor = self.attachment_vendor()?;
         let conforms_to: Option<String> = self.attachment_conforms_to()?;
-        let assertion = Assertion::new_attachment(payload, vendor.as_str(), conforms_to.as_deref());
+        let id: Option<String> = self.attachment_id()?;
+        let assertion = Assertion::new_attachment_with_id(payload, vendor.as_str(), conforms_to.as_deref(), id.as_deref());
         let e: Envelope = assertion.to_envelope();
         if !e.is_equivalent_to(&self.clone().to_envelope()) {
             bail!(EnvelopeError::InvalidAttachment);
@@ -62,6 +80,13 @@ impl Envelope {
         Assertion::new_attachment(payload, vendor, conforms_to).to_envelope()
     }
 
+    /// Returns a new attachment envelope with an optional `'id': String`
+    /// assertion.
+    pub fn new_attachment_with_id(payload: impl EnvelopeEncodable, vendor: &str, conforms_to: Option<&str>, id: Option<&str>) -> Self
+    {
+        Assertion::new_attachment_with_id(payload, vendor, conforms_to, id).to_envelope()
+    }
+
     /// Returns a new envelope with an added `'attachment': Envelope` assertion.
     ///
     /// The payload envelope has a `'vendor': String` assertion and an optional
@@ -71,6 +96,31 @@ impl Envelope {
             Assertion::new_attachment(payload, vendor, conforms_to)
         ).unwrap()
     }
+
+    /// Returns a new envelope with an added `'attachment': Envelope` assertion
+    /// whose payload envelope also has an `'id': String` assertion.
+    ///
+    /// The `id` distinguishes attachments that have the same `vendor` and
+    /// `conformsTo`.
+    pub fn add_attachment_with_id(&self, payload: impl EnvelopeEncodable, vendor: &str, conforms_to: Option<&str>, id: &str) -> Self {
+        self.add_assertion_envelope(
+            Assertion::new_attachment_with_id(payload, vendor, conforms_to, Some(id))
+        ).unwrap()
+    }
+
+    /// Returns a new envelope with `attachment` added to the assertion with the
+    /// digest `assertion`, rather than to the envelope's subject.
+    ///
+    /// Adding the attachment changes the digest of the assertion, and so of the
+    /// envelope. Returns an error if `attachment` is not a valid attachment
+    /// envelope, or if the envelope has no assertion with the given digest.
+    pub fn add_attachment_to_assertion(&self, assertion: &Digest, attachment: Self) -> Result<Self> {
+        attachment.validate_attachment()?;
+        let Some(target) = self.assertions().into_iter().find(|a| a.digest().as_ref() == assertion) else {
+            bail!(EnvelopeError::NonexistentAssertion);
+        };
+        self.replace_assertion(target.clone(), target.add_assertion_envelope(attachment)?)
+    }
 }
 
 impl Envelope {
@@ -101,6 +151,15 @@ impl Envelope {
         }
     }
 
+    /// Returns the `id` of the given attachment envelope.
+    pub fn attachment_id(&self) -> Result<Option<String>> {
+        if let EnvelopeCase::Assertion(assertion) = self.case() {
+            Ok(assertion.attachment_id()?)
+        } else {
+            bail!(EnvelopeError::InvalidAttachment);
+        }
+    }
+
     /// Searches the envelope's attachments for any that match the given
     /// `vendor` and `conformsTo`.
     ///
@@ -183,4 +242,160 @@ impl Envelope {
         }
         Ok(attachments.first().unwrap().clone())
     }
+
+    /// Returns the attachment with the given `id`.
+    ///
+    /// Returns an error if no attachment or more than one attachment has the
+    /// `id`, or if any of the attachments are invalid.
+    pub fn attachment_with_id(&self, id: &str) -> Result<Self> {
+        let mut attachments = Vec::new();
+        for attachment in self.attachments()? {
+            if attachment.attachment_id()?.as_deref() == Some(id) {
+                attachments.push(attachment);
+            }
+        }
+        if attachments.is_empty() {
+            bail!(EnvelopeError::NonexistentAttachment);
+        }
+        if attachments.len() > 1 {
+            bail!(EnvelopeError::AmbiguousAttachment);
+        }
+        Ok(attachments.remove(0))
+    }
+
+    /// Validates the attachments on the envelope's subject and on each of its
+    /// assertions against `policy`.
+    ///
+    /// Services that accept envelopes from untrusted sources can use this to
+    /// reject envelopes carrying too many or too large attachments before
+    /// processing them further. The attachments are counted before any of
+    /// them is decoded, so an envelope with too many is rejected without
+    /// decoding them. Returns an error if any of the attachments are invalid
+    /// or if the policy is violated.
+    pub fn validate_attachments(&self, policy: &AttachmentPolicy) -> Result<()> {
+        if let Some(max_count) = policy.max_count {
+            let count = self.attachment_count();
+            if count > max_count {
+                bail!(EnvelopeError::TooManyAttachments(count));
+            }
+        }
+        for info in &self.attachment_infos()? {
+            if let Some(allowed_vendors) = &policy.allowed_vendors {
+                if !allowed_vendors.iter().any(|v| v == info.vendor()) {
+                    bail!(EnvelopeError::DisallowedAttachmentVendor(info.vendor().to_string()));
+                }
+            }
+            if let Some(max_payload_bytes) = policy.max_payload_bytes {
+                let size = info.payload().tagged_cbor_data().len();
+                if size > max_payload_bytes {
+                    bail!(EnvelopeError::AttachmentTooLarge(size));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns a description of each attachment on the envelope's subject and
+    /// on each of its assertions.
+    ///
+    /// Returns an error if any of the attachments are invalid.
+    pub fn attachment_infos(&self) -> Result<Vec<AttachmentInfo>> {
+        let mut infos = Vec::new();
+        self.collect_attachment_infos(&mut infos)?;
+        for assertion in self.assertions() {
+            if assertion.subject().as_predicate().is_some_and(|p| p.digest() == known_values::ATTACHMENT.digest()) {
+                continue;
+            }
+            assertion.collect_attachment_infos(&mut infos)?;
+        }
+        Ok(infos)
+    }
+
+    /// The number of `'attachment'` assertions on the envelope's subject and
+    /// on each of its assertions, counted without decoding them.
+    fn attachment_count(&self) -> usize {
+        let mut count = 0;
+        for assertion in self.assertions() {
+            if assertion.subject().as_predicate().is_some_and(|p| p.digest() == known_values::ATTACHMENT.digest()) {
+                count += 1;
+            } else {
+                count += assertion.assertions_with_predicate(known_values::ATTACHMENT).len();
+            }
+        }
+        count
+    }
+
+    fn collect_attachment_infos(&self, infos: &mut Vec<AttachmentInfo>) -> Result<()> {
+        let target = self.subject().digest().into_owned();
+        for attachment in self.attachments()? {
+            infos.push(AttachmentInfo {
+                target: target.clone(),
+                payload: attachment.attachment_payload()?,
+                vendor: attachment.attachment_vendor()?,
+                conforms_to: attachment.attachment_conforms_to()?,
+                id: attachment.attachment_id()?,
+                attachment,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Limits on the attachments an envelope may carry, enforced by
+/// [`Envelope::validate_attachments`].
+///
+/// Each limit that is `None` is not enforced, so the default policy accepts
+/// any valid attachments.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AttachmentPolicy {
+    /// The maximum number of attachments.
+    pub max_count: Option<usize>,
+    /// The maximum size of the tagged CBOR encoding of each payload.
+    pub max_payload_bytes: Option<usize>,
+    /// The vendors whose attachments are accepted.
+    pub allowed_vendors: Option<Vec<String>>,
+}
+
+/// A description of an attachment, returned by [`Envelope::attachment_infos`].
+#[derive(Debug, Clone)]
+pub struct AttachmentInfo {
+    attachment: Envelope,
+    target: Digest,
+    payload: Envelope,
+    vendor: String,
+    conforms_to: Option<String>,
+    id: Option<String>,
+}
+
+impl AttachmentInfo {
+    /// The `'attachment': Envelope` assertion.
+    pub fn attachment(&self) -> &Envelope {
+        &self.attachment
+    }
+
+    /// The digest of the subject or assertion to which the attachment is
+    /// attached.
+    pub fn target(&self) -> &Digest {
+        &self.target
+    }
+
+    /// The payload of the attachment.
+    pub fn payload(&self) -> &Envelope {
+        &self.payload
+    }
+
+    /// The `vendor` of the attachment.
+    pub fn vendor(&self) -> &str {
+        &self.vendor
+    }
+
+    /// The `conformsTo` of the attachment, if any.
+    pub fn conforms_to(&self) -> Option<&str> {
+        self.conforms_to.as_deref()
+    }
+
+    /// The `id` of the attachment, if any.
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
 }
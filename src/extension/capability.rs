@@ -0,0 +1,286 @@
+use anyhow::{bail, Result};
+use bc_components::{PublicKeyBase, Signer, Verifier, ARID};
+use dcbor::Date;
+
+use crate::{
+    extension::known_values, Envelope, EnvelopeError, Expression, ExpressionBehavior, Function, Parameter,
+    TimePolicy,
+};
+
+/// The predicate of the assertions naming the functions a capability allows.
+pub const ALLOW_FUNCTION: &str = "allowFunction";
+
+/// The predicate of the assertions naming the parameters a capability allows.
+pub const ALLOW_PARAMETER: &str = "allowParameter";
+
+/// The predicate of the assertion limiting how many times a capability may be
+/// exercised.
+pub const MAX_USES: &str = "maxUses";
+
+/// The predicate of the assertion whose object is the public key of the
+/// holder of a capability.
+pub const HOLDER: &str = "holder";
+
+/// An object capability: the authority to invoke functions on a target.
+///
+/// A capability is issued as a signed envelope whose subject is the ARID of
+/// the target, with assertions restricting the functions and parameters that
+/// may be used, and caveats limiting when and how often it may be exercised.
+///
+/// ```text
+/// {
+///     ARID(target) [
+///         "allowFunction": «function»
+///         "allowParameter": ❰parameter❱
+///         "holder": PublicKeyBase
+///         "maxUses": 10
+///         'validUntil': Date
+///     ]
+/// } [
+///     'signed': Signature
+/// ]
+/// ```
+///
+/// The holder of a capability may delegate it by wrapping the signed
+/// capability in a new one, possibly with further restrictions and a new
+/// holder, and signing that. A delegated capability can only narrow the
+/// authority of its parent.
+///
+/// A capability with no `allowFunction` or `allowParameter` assertions
+/// allows any function or parameter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Capability {
+    target: ARID,
+    functions: Vec<Function>,
+    parameters: Vec<Parameter>,
+    expiration: Option<Date>,
+    max_uses: Option<u64>,
+    holder: Option<PublicKeyBase>,
+}
+
+impl Capability {
+    /// Creates a capability for the given target that allows everything.
+    pub fn new(target: ARID) -> Self {
+        Self {
+            target,
+            functions: Vec::new(),
+            parameters: Vec::new(),
+            expiration: None,
+            max_uses: None,
+            holder: None,
+        }
+    }
+
+    /// Adds a function that the capability allows.
+    pub fn with_function(mut self, function: impl Into<Function>) -> Self {
+        self.functions.push(function.into());
+        self
+    }
+
+    /// Adds a parameter that the capability allows.
+    pub fn with_parameter(mut self, parameter: impl Into<Parameter>) -> Self {
+        self.parameters.push(parameter.into());
+        self
+    }
+
+    /// Sets the date after which the capability may no longer be exercised.
+    pub fn with_expiration(mut self, expiration: Date) -> Self {
+        self.expiration = Some(expiration);
+        self
+    }
+
+    /// Sets the number of times the capability may be exercised.
+    pub fn with_max_uses(mut self, max_uses: u64) -> Self {
+        self.max_uses = Some(max_uses);
+        self
+    }
+
+    /// Sets the holder of the capability, who may exercise or delegate it.
+    pub fn with_holder(mut self, holder: PublicKeyBase) -> Self {
+        self.holder = Some(holder);
+        self
+    }
+
+    pub fn target(&self) -> &ARID {
+        &self.target
+    }
+
+    pub fn functions(&self) -> &[Function] {
+        &self.functions
+    }
+
+    pub fn parameters(&self) -> &[Parameter] {
+        &self.parameters
+    }
+
+    pub fn expiration(&self) -> Option<&Date> {
+        self.expiration.as_ref()
+    }
+
+    /// The number of times the capability may be exercised.
+    ///
+    /// Counting uses requires state, so it is left to the service that
+    /// accepts the capability.
+    pub fn max_uses(&self) -> Option<u64> {
+        self.max_uses
+    }
+
+    pub fn holder(&self) -> Option<&PublicKeyBase> {
+        self.holder.as_ref()
+    }
+
+    /// Returns `true` if the capability allows `function`.
+    pub fn allows_function(&self, function: &Function) -> bool {
+        self.functions.is_empty() || self.functions.contains(function)
+    }
+
+    /// Returns `true` if the capability allows `parameter`.
+    pub fn allows_parameter(&self, parameter: &Parameter) -> bool {
+        self.parameters.is_empty() || self.parameters.contains(parameter)
+    }
+
+    /// Checks that the capability allows `expression`: its function and
+    /// every one of its parameters must be allowed, and the capability must
+    /// not have expired according to `policy`.
+    pub fn check_expression(&self, expression: &Expression, policy: &TimePolicy) -> Result<()> {
+        if let Some(expiration) = &self.expiration {
+            policy.check_not_after(expiration)?;
+        }
+        if !self.allows_function(expression.function()) {
+            bail!(EnvelopeError::CapabilityDenied);
+        }
+        for assertion in expression.expression_envelope().assertions() {
+            let parameter: Parameter = assertion.try_predicate()?.extract_subject()?;
+            if !self.allows_parameter(&parameter) {
+                bail!(EnvelopeError::CapabilityDenied);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the capability as an envelope signed by `issuer`.
+    pub fn issue(&self, issuer: &dyn Signer) -> Envelope {
+        self.add_restrictions(Envelope::new(self.target.clone())).sign(issuer)
+    }
+
+    /// Delegates the capability `parent` with the restrictions of this
+    /// capability, returning a new capability signed by `delegator`, who must
+    /// be the holder of `parent` for it to verify.
+    ///
+    /// Returns an error if `parent` is not a capability for the same target.
+    pub fn delegate(&self, parent: &Envelope, delegator: &dyn Signer) -> Result<Envelope> {
+        let (_, root) = capability_layers(parent)?.pop().unwrap();
+        if root.extract_subject::<ARID>()? != self.target {
+            bail!(EnvelopeError::InvalidCapability);
+        }
+        Ok(self.add_restrictions(parent.wrap_envelope()).sign(delegator))
+    }
+
+    fn add_restrictions(&self, mut envelope: Envelope) -> Envelope {
+        for function in &self.functions {
+            envelope = envelope.add_assertion(ALLOW_FUNCTION, function.clone());
+        }
+        for parameter in &self.parameters {
+            envelope = envelope.add_assertion(ALLOW_PARAMETER, parameter.clone());
+        }
+        envelope
+            .add_optional_assertion(HOLDER, self.holder.clone())
+            .add_optional_assertion(MAX_USES, self.max_uses)
+            .add_optional_assertion(known_values::VALID_UNTIL, self.expiration.clone())
+    }
+
+    fn from_restrictions(target: ARID, body: &Envelope) -> Result<Self> {
+        // Eliding a restriction keeps the signature valid, but would widen
+        // the authority granted, so no assertion may be obscured.
+        if body.assertions().iter().any(|assertion| assertion.is_obscured()) {
+            bail!(EnvelopeError::InvalidCapability);
+        }
+        Ok(Self {
+            target,
+            functions: body.extract_objects_for_predicate(ALLOW_FUNCTION)?,
+            parameters: body.extract_objects_for_predicate(ALLOW_PARAMETER)?,
+            expiration: body.extract_optional_object_for_predicate(known_values::VALID_UNTIL)?,
+            max_uses: body.extract_optional_object_for_predicate(MAX_USES)?,
+            holder: body.extract_optional_object_for_predicate(HOLDER)?,
+        })
+    }
+
+    /// Returns this capability narrowed by the restrictions of a delegated
+    /// one, which may not allow anything this one does not.
+    fn narrow(self, delegated: Self) -> Result<Self> {
+        let functions = narrow_list(self.functions, delegated.functions)?;
+        let parameters = narrow_list(self.parameters, delegated.parameters)?;
+        Ok(Self {
+            target: self.target,
+            functions,
+            parameters,
+            expiration: min_option(self.expiration, delegated.expiration),
+            max_uses: min_option(self.max_uses, delegated.max_uses),
+            holder: delegated.holder,
+        })
+    }
+}
+
+fn narrow_list<T: PartialEq>(parent: Vec<T>, delegated: Vec<T>) -> Result<Vec<T>> {
+    if delegated.is_empty() {
+        return Ok(parent);
+    }
+    if !parent.is_empty() && delegated.iter().any(|item| !parent.contains(item)) {
+        bail!(EnvelopeError::InvalidCapability);
+    }
+    Ok(delegated)
+}
+
+fn min_option<T: Ord>(a: Option<T>, b: Option<T>) -> Option<T> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Returns the signed layers of a capability chain and their unwrapped
+/// bodies, outermost first, so that the last is the root capability.
+fn capability_layers(envelope: &Envelope) -> Result<Vec<(Envelope, Envelope)>> {
+    let mut layers = Vec::new();
+    let mut signed = envelope.clone();
+    loop {
+        let body = signed.unwrap_envelope()?;
+        let subject = body.subject();
+        layers.push((signed, body));
+        if !subject.is_wrapped() {
+            return Ok(layers);
+        }
+        signed = subject.unwrap_envelope()?;
+    }
+}
+
+/// Support for object capabilities.
+impl Envelope {
+    /// Verifies a capability issued with [`Capability::issue`] and possibly
+    /// delegated with [`Capability::delegate`], returning the authority it
+    /// grants.
+    ///
+    /// The root capability must be signed by `root_trust`, and each
+    /// delegation by the holder of the capability it delegates. The returned
+    /// capability combines the restrictions of every link in the chain, and
+    /// its holder is the holder named by the last delegation.
+    ///
+    /// Returns an error if a signature cannot be verified, if any link in the
+    /// chain has an obscured assertion, or if a delegation tries to allow
+    /// more than the capability it delegates.
+    pub fn verify_capability_chain(&self, root_trust: &dyn Verifier) -> Result<Capability> {
+        let mut layers = capability_layers(self)?.into_iter().rev();
+        let (root, body) = layers.next().unwrap();
+        root.verify_signature_from(root_trust)?;
+        let mut capability = Capability::from_restrictions(body.extract_subject()?, &body)?;
+        for (signed, body) in layers {
+            let Some(holder) = capability.holder() else {
+                bail!(EnvelopeError::InvalidCapability);
+            };
+            signed.verify_signature_from(holder)?;
+            let delegated = Capability::from_restrictions(capability.target.clone(), &body)?;
+            capability = capability.narrow(delegated)?;
+        }
+        Ok(capability)
+    }
+}
@@ -0,0 +1,215 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::{bail, Result};
+use dcbor::{prelude::*, Date};
+
+use crate::{Envelope, EnvelopeError, TimePolicy};
+use crate::extension::{known_values, KnownValue};
+
+/// The predicate used for the `aud` (audience) claim, which has no known
+/// value counterpart.
+///
+/// It is namespaced so that it doesn't collide with a private claim named
+/// `aud`.
+pub const CLAIM_AUDIENCE: &str = "jwt:aud";
+
+/// A set of JWT/CWT claims.
+///
+/// The registered claims of RFC 7519 and RFC 8392 are represented by typed
+/// fields. All other claims are kept by name.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ClaimsSet {
+    issuer: Option<String>,
+    subject: Option<String>,
+    audience: BTreeSet<String>,
+    expiration: Option<Date>,
+    not_before: Option<Date>,
+    issued_at: Option<Date>,
+    id: Option<String>,
+    private_claims: BTreeMap<String, CBOR>,
+}
+
+impl ClaimsSet {
+    /// Creates a new, empty claims set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the `iss` (issuer) claim.
+    pub fn with_issuer(mut self, issuer: impl Into<String>) -> Self {
+        self.issuer = Some(issuer.into());
+        self
+    }
+
+    /// Sets the `sub` (subject) claim.
+    pub fn with_subject(mut self, subject: impl Into<String>) -> Self {
+        self.subject = Some(subject.into());
+        self
+    }
+
+    /// Adds a recipient to the `aud` (audience) claim.
+    pub fn with_audience(mut self, audience: impl Into<String>) -> Self {
+        self.audience.insert(audience.into());
+        self
+    }
+
+    /// Sets the `exp` (expiration time) claim.
+    pub fn with_expiration(mut self, expiration: Date) -> Self {
+        self.expiration = Some(expiration);
+        self
+    }
+
+    /// Sets the `nbf` (not before) claim.
+    pub fn with_not_before(mut self, not_before: Date) -> Self {
+        self.not_before = Some(not_before);
+        self
+    }
+
+    /// Sets the `iat` (issued at) claim.
+    pub fn with_issued_at(mut self, issued_at: Date) -> Self {
+        self.issued_at = Some(issued_at);
+        self
+    }
+
+    /// Sets the `jti`/`cti` (token identifier) claim.
+    pub fn with_id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Sets a claim that is not one of the registered claims.
+    pub fn with_private_claim(mut self, name: impl Into<String>, value: impl Into<CBOR>) -> Self {
+        self.private_claims.insert(name.into(), value.into());
+        self
+    }
+
+    pub fn issuer(&self) -> Option<&str> {
+        self.issuer.as_deref()
+    }
+
+    pub fn subject(&self) -> Option<&str> {
+        self.subject.as_deref()
+    }
+
+    pub fn audience(&self) -> &BTreeSet<String> {
+        &self.audience
+    }
+
+    pub fn expiration(&self) -> Option<&Date> {
+        self.expiration.as_ref()
+    }
+
+    pub fn not_before(&self) -> Option<&Date> {
+        self.not_before.as_ref()
+    }
+
+    pub fn issued_at(&self) -> Option<&Date> {
+        self.issued_at.as_ref()
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    pub fn private_claims(&self) -> &BTreeMap<String, CBOR> {
+        &self.private_claims
+    }
+
+    /// Checks the `nbf`, `exp`, and `iat` claims, if present, against the
+    /// given policy.
+    pub fn check_validity(&self, policy: &TimePolicy) -> Result<()> {
+        if let Some(not_before) = &self.not_before {
+            policy.check_not_before(not_before)?;
+        }
+        if let Some(expiration) = &self.expiration {
+            policy.check_not_after(expiration)?;
+        }
+        if let Some(issued_at) = &self.issued_at {
+            policy.check_date(issued_at)?;
+        }
+        Ok(())
+    }
+}
+
+/// Support for converting between JWT/CWT claims and envelopes.
+impl Envelope {
+    /// Creates an envelope from a set of claims.
+    ///
+    /// The `sub` claim becomes the subject of the envelope, or the subject is
+    /// `null` if there is no `sub` claim. Registered claims map to known value
+    /// assertions:
+    ///
+    /// | Claim | Predicate      |
+    /// |-------|----------------|
+    /// | `iss` | `'issuer'`     |
+    /// | `exp` | `'validUntil'` |
+    /// | `nbf` | `'validFrom'`  |
+    /// | `iat` | `'date'`       |
+    /// | `jti` | `'id'`         |
+    ///
+    /// Each `aud` recipient becomes a `"jwt:aud"` assertion, and each private
+    /// claim an assertion whose predicate is the claim name as a string.
+    /// Because each claim is a separate assertion, individual claims can then
+    /// be elided.
+    pub fn from_claims(claims: &ClaimsSet) -> Self {
+        let mut envelope = Self::new_or_null(claims.subject.clone())
+            .add_optional_assertion(known_values::ISSUER, claims.issuer.clone())
+            .add_optional_assertion(known_values::VALID_UNTIL, claims.expiration.clone())
+            .add_optional_assertion(known_values::VALID_FROM, claims.not_before.clone())
+            .add_optional_assertion(known_values::DATE, claims.issued_at.clone())
+            .add_optional_assertion(known_values::ID, claims.id.clone());
+        for audience in &claims.audience {
+            envelope = envelope.add_assertion(CLAIM_AUDIENCE, audience.as_str());
+        }
+        for (name, value) in &claims.private_claims {
+            envelope = envelope.add_assertion(name.as_str(), value.clone());
+        }
+        envelope
+    }
+
+    /// Recovers the set of claims from an envelope created with
+    /// [`Envelope::from_claims`].
+    ///
+    /// Obscured claims, such as those elided from a redacted credential, are
+    /// skipped.
+    ///
+    /// Returns an error if any revealed assertion has a predicate that is
+    /// neither a string nor one of the mapped known values, or has an object
+    /// of the wrong type.
+    pub fn to_claims(&self) -> Result<ClaimsSet> {
+        let subject = self.subject();
+        let mut claims = ClaimsSet::new();
+        if !subject.is_null() {
+            claims.subject = Some(subject.extract_subject()?);
+        }
+        for assertion in self.assertions() {
+            if assertion.is_obscured() {
+                continue;
+            }
+            let predicate = assertion.try_predicate()?;
+            let object = assertion.try_object()?;
+            if predicate.is_obscured() || object.is_obscured() {
+                continue;
+            }
+            if let Ok(known_value) = predicate.extract_subject::<KnownValue>() {
+                match known_value.value() {
+                    known_values::ISSUER_RAW => claims.issuer = Some(object.extract_subject()?),
+                    known_values::VALID_UNTIL_RAW => claims.expiration = Some(object.extract_subject()?),
+                    known_values::VALID_FROM_RAW => claims.not_before = Some(object.extract_subject()?),
+                    known_values::DATE_RAW => claims.issued_at = Some(object.extract_subject()?),
+                    known_values::ID_RAW => claims.id = Some(object.extract_subject()?),
+                    _ => bail!(EnvelopeError::InvalidFormat),
+                }
+            } else if let Ok(name) = predicate.extract_subject::<String>() {
+                if name == CLAIM_AUDIENCE {
+                    claims.audience.insert(object.extract_subject()?);
+                } else {
+                    claims.private_claims.insert(name, object.try_leaf()?);
+                }
+            } else {
+                bail!(EnvelopeError::InvalidFormat);
+            }
+        }
+        Ok(claims)
+    }
+}
@@ -2,7 +2,7 @@ use anyhow::{bail, Result};
 use bc_components::{Compressed, DigestProvider};
 use dcbor::prelude::*;
 
-use crate::{Envelope, EnvelopeError, base::envelope::EnvelopeCase};
+use crate::{Assertion, Envelope, EnvelopeError, base::envelope::EnvelopeCase};
 
 /// Support for compressing and uncompressing envelopes.
 impl Envelope {
@@ -68,4 +68,127 @@ impl Envelope {
             Ok(self.clone())
         }
     }
+
+    /// Checks that every compressed element of this envelope decompresses to
+    /// an envelope with the digest the element declares.
+    ///
+    /// Compressed elements inside decompressed content are checked too. The
+    /// decompressed content is discarded once checked, so this can be used to
+    /// validate a compressed envelope received from an untrusted source before
+    /// storing or forwarding it. Encrypted and elided elements cannot be
+    /// inspected and are skipped.
+    ///
+    /// Returns the envelope itself, so that the check can be chained.
+    pub fn verify_compressed_integrity(&self) -> Result<Self> {
+        self.check_compressed_integrity()?;
+        Ok(self.clone())
+    }
+
+    fn check_compressed_integrity(&self) -> Result<()> {
+        match self.case() {
+            EnvelopeCase::Node { subject, assertions, .. } => {
+                subject.check_compressed_integrity()?;
+                for assertion in assertions {
+                    assertion.check_compressed_integrity()?;
+                }
+            }
+            EnvelopeCase::Wrapped { envelope, .. } => envelope.check_compressed_integrity()?,
+            EnvelopeCase::Assertion(assertion) => {
+                assertion.predicate().check_compressed_integrity()?;
+                assertion.object().check_compressed_integrity()?;
+            }
+            EnvelopeCase::Compressed(_) => self.uncompress()?.check_compressed_integrity()?,
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// The sizes measured by [`Envelope::optimize_for_compression_with_report`],
+/// in bytes of tagged CBOR.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompressionReport {
+    /// The size of the envelope before optimizing.
+    pub original_size: usize,
+    /// The size of the envelope with every compressed element uncompressed.
+    pub uncompressed_size: usize,
+    /// The size of the optimized envelope.
+    pub optimized_size: usize,
+}
+
+impl CompressionReport {
+    /// The size of the optimized envelope as a fraction of its uncompressed
+    /// size.
+    pub fn ratio(&self) -> f64 {
+        self.optimized_size as f64 / self.uncompressed_size as f64
+    }
+}
+
+/// Support for choosing how an envelope is compressed.
+impl Envelope {
+    /// Returns the smallest encoding of this envelope that this crate can
+    /// produce by compressing and uncompressing its elements.
+    ///
+    /// The order of an envelope's assertions, and of the entries in its leaf
+    /// maps, is fixed by the deterministic encoding rules, so compression
+    /// cannot be improved by reordering them. What can change is where the
+    /// compressed boundaries fall: elements compressed separately each pay
+    /// the compressor's overhead and cannot share repeated text, such as the
+    /// similar leaves of a set of attachments. This uncompresses every
+    /// compressed element and compresses the result as a whole, keeping that
+    /// only if it is smaller than both the original and the uncompressed
+    /// envelope.
+    ///
+    /// The result has the same digest as this envelope. Encrypted and elided
+    /// elements are left as they are.
+    pub fn optimize_for_compression(&self) -> Result<Self> {
+        Ok(self.optimize_for_compression_with_report()?.0)
+    }
+
+    /// Returns the result of [`Envelope::optimize_for_compression`], and the
+    /// sizes measured while choosing it.
+    pub fn optimize_for_compression_with_report(&self) -> Result<(Self, CompressionReport)> {
+        let size = |envelope: &Envelope| envelope.tagged_cbor().to_cbor_data().len();
+        let original_size = size(self);
+        let uncompressed = self.uncompress_all()?;
+        let uncompressed_size = size(&uncompressed);
+
+        let mut best = (self.clone(), original_size);
+        if uncompressed_size < best.1 {
+            best = (uncompressed.clone(), uncompressed_size);
+        }
+        if !uncompressed.is_obscured() {
+            let compressed = uncompressed.compress()?;
+            let compressed_size = size(&compressed);
+            if compressed_size < best.1 {
+                best = (compressed, compressed_size);
+            }
+        }
+        let (optimized, optimized_size) = best;
+        Ok((optimized, CompressionReport { original_size, uncompressed_size, optimized_size }))
+    }
+
+    /// Returns this envelope with every compressed element, including those
+    /// within compressed content, uncompressed.
+    fn uncompress_all(&self) -> Result<Self> {
+        let result = match self.case() {
+            EnvelopeCase::Compressed(_) => return self.uncompress()?.uncompress_all(),
+            EnvelopeCase::Node { subject, assertions, .. } => {
+                let subject = subject.uncompress_all()?;
+                let assertions = assertions
+                    .iter()
+                    .map(|assertion| assertion.uncompress_all())
+                    .collect::<Result<Vec<_>>>()?;
+                Self::new_with_unchecked_assertions(subject, assertions)
+            }
+            EnvelopeCase::Wrapped { envelope, .. } => Self::new_wrapped(envelope.uncompress_all()?),
+            EnvelopeCase::Assertion(assertion) => {
+                let predicate = assertion.predicate().uncompress_all()?;
+                let object = assertion.object().uncompress_all()?;
+                Self::new_with_assertion(Assertion::new(predicate, object))
+            }
+            _ => return Ok(self.clone()),
+        };
+        Ok(result)
+    }
 }
@@ -3,8 +3,55 @@ use std::borrow::Cow;
 use anyhow::{bail, Result};
 use bc_components::{SymmetricKey, Nonce, Digest, DigestProvider, tags};
 use dcbor::prelude::*;
+use zeroize::Zeroizing;
 
-use crate::{Envelope, EnvelopeError, base::envelope::EnvelopeCase};
+use crate::{Assertion, Envelope, EnvelopeError, base::envelope::EnvelopeCase};
+
+/// The decrypted content of an envelope's subject, which is zeroized when it
+/// is dropped.
+///
+/// Returned by [`Envelope::decrypt_subject_to_secret`] for callers that need
+/// to control how long decrypted data stays in memory. The content is the
+/// tagged CBOR encoding of the subject, which is not checked against the
+/// subject's digest until it is decoded with
+/// [`SecretEnvelopeContent::to_envelope`].
+pub struct SecretEnvelopeContent {
+    cbor_data: Zeroizing<Vec<u8>>,
+    digest: Digest,
+}
+
+impl SecretEnvelopeContent {
+    /// The tagged CBOR encoding of the decrypted subject.
+    pub fn cbor_data(&self) -> &[u8] {
+        &self.cbor_data
+    }
+
+    /// The digest of the decrypted subject, as recorded in the encrypted
+    /// message.
+    pub fn digest(&self) -> &Digest {
+        &self.digest
+    }
+
+    /// Decodes the decrypted subject, checking it against its digest.
+    ///
+    /// The returned envelope is an ordinary envelope whose memory is not
+    /// zeroized when it is dropped.
+    pub fn to_envelope(&self) -> Result<Envelope> {
+        let subject = Envelope::from_tagged_cbor_data(self.cbor_data.as_slice())?;
+        if *subject.digest() != self.digest {
+            bail!(EnvelopeError::InvalidDigest);
+        }
+        Ok(subject)
+    }
+}
+
+impl std::fmt::Debug for SecretEnvelopeContent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SecretEnvelopeContent")
+            .field("digest", &self.digest)
+            .finish_non_exhaustive()
+    }
+}
 
 /// Support for encrypting and decrypting envelopes.
 impl Envelope {
@@ -85,26 +132,34 @@ impl Envelope {
     }
 
     /// Returns a new envelope with its subject decrypted.
+    ///
+    /// The decrypted plaintext is zeroized once it has been decoded.
     pub fn decrypt_subject(&self, key: &SymmetricKey) -> Result<Self> {
-        match self.subject().case() {
-            EnvelopeCase::Encrypted(message) => {
-                let encoded_cbor = key.decrypt(message)?;
-                let subject_digest = message.opt_digest().ok_or(EnvelopeError::MissingDigest)?;
-                let cbor = CBOR::try_from_data(encoded_cbor)?;
-                let result_subject = Self::from_tagged_cbor(cbor)?;
-                if *result_subject.digest() != subject_digest {
+        let result_subject = self.decrypt_subject_to_secret(key)?.to_envelope()?;
+        match self.case() {
+            EnvelopeCase::Node { assertions, digest, .. } => {
+                let result = Self::new_with_unchecked_assertions(result_subject, assertions.clone());
+                if *result.digest() != *digest {
                     bail!(EnvelopeError::InvalidDigest);
                 }
-                match self.case() {
-                    EnvelopeCase::Node { assertions, digest, .. } => {
-                        let result = Self::new_with_unchecked_assertions(result_subject, assertions.clone());
-                        if *result.digest() != *digest {
-                            bail!(EnvelopeError::InvalidDigest);
-                        }
-                        Ok(result)
-                    }
-                    _ => Ok(result_subject)
-                }
+                Ok(result)
+            }
+            _ => Ok(result_subject)
+        }
+    }
+
+    /// Returns the decrypted content of the envelope's subject, which is
+    /// zeroized when it is dropped.
+    ///
+    /// Unlike [`Envelope::decrypt_subject`], the content is not decoded, so
+    /// no copy of it is left in memory that the caller does not control. See
+    /// [`SecretEnvelopeContent`].
+    pub fn decrypt_subject_to_secret(&self, key: &SymmetricKey) -> Result<SecretEnvelopeContent> {
+        match self.subject().case() {
+            EnvelopeCase::Encrypted(message) => {
+                let cbor_data = Zeroizing::new(key.decrypt(message)?);
+                let digest = message.opt_digest().ok_or(EnvelopeError::MissingDigest)?;
+                Ok(SecretEnvelopeContent { cbor_data, digest })
             },
             _ => bail!(EnvelopeError::NotEncrypted)
         }
@@ -125,3 +180,114 @@ impl Envelope {
             .unwrap_envelope()
     }
 }
+
+/// Why an encrypted element was left in place by
+/// [`Envelope::walk_decrypt_with_report`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecryptionFailure {
+    /// None of the keys authenticated the element's ciphertext.
+    ///
+    /// Authenticated encryption cannot tell a wrong key from a corrupted
+    /// ciphertext, so this is also the result for an element that has been
+    /// tampered with.
+    NoMatchingKey,
+    /// A key authenticated the ciphertext, but the plaintext is not an
+    /// envelope with the digest recorded in the encrypted message.
+    InvalidPlaintext,
+    /// The encrypted message does not record the digest of its plaintext.
+    MissingDigest,
+}
+
+/// The outcome of [`Envelope::walk_decrypt_with_report`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DecryptionReport {
+    /// The digests of the elements that were decrypted, in the order they
+    /// were found.
+    pub decrypted: Vec<Digest>,
+    /// The digests of the encrypted elements that remain, and why each could
+    /// not be decrypted.
+    pub remaining: Vec<(Digest, DecryptionFailure)>,
+}
+
+impl DecryptionReport {
+    /// Returns `true` if no encrypted elements remain.
+    pub fn is_complete(&self) -> bool {
+        self.remaining.is_empty()
+    }
+}
+
+/// Support for decrypting every encrypted element of an envelope.
+impl Envelope {
+    /// Returns a new envelope with each encrypted element that one of `keys`
+    /// decrypts replaced by its plaintext, including encrypted elements
+    /// within decrypted ones.
+    ///
+    /// Elements that cannot be decrypted are left in place. Use
+    /// [`Envelope::walk_decrypt_with_report`] to find out which, and why.
+    pub fn walk_decrypt(&self, keys: &[SymmetricKey]) -> Self {
+        self.walk_decrypt_with_report(keys).0
+    }
+
+    /// Returns the result of [`Envelope::walk_decrypt`], and a report of the
+    /// elements that were decrypted and those that remain encrypted.
+    ///
+    /// Decrypting an element does not change its digest, so the digests in
+    /// the report identify elements in both the original and the returned
+    /// envelope.
+    pub fn walk_decrypt_with_report(&self, keys: &[SymmetricKey]) -> (Self, DecryptionReport) {
+        let mut report = DecryptionReport::default();
+        let result = self.walk_decrypt_element(keys, &mut report);
+        (result, report)
+    }
+
+    fn walk_decrypt_element(&self, keys: &[SymmetricKey], report: &mut DecryptionReport) -> Self {
+        let decrypted_count = report.decrypted.len();
+        let result = match self.case() {
+            EnvelopeCase::Encrypted(message) => match Self::decrypt_message_with_keys(message, keys) {
+                Ok(plaintext) => {
+                    report.decrypted.push(self.digest().into_owned());
+                    return plaintext.walk_decrypt_element(keys, report);
+                }
+                Err(failure) => {
+                    report.remaining.push((self.digest().into_owned(), failure));
+                    return self.clone();
+                }
+            },
+            EnvelopeCase::Node { subject, assertions, .. } => {
+                let subject = subject.walk_decrypt_element(keys, report);
+                let assertions = assertions
+                    .iter()
+                    .map(|assertion| assertion.walk_decrypt_element(keys, report))
+                    .collect();
+                Self::new_with_unchecked_assertions(subject, assertions)
+            }
+            EnvelopeCase::Wrapped { envelope, .. } => Self::new_wrapped(envelope.walk_decrypt_element(keys, report)),
+            EnvelopeCase::Assertion(assertion) => {
+                let predicate = assertion.predicate().walk_decrypt_element(keys, report);
+                let object = assertion.object().walk_decrypt_element(keys, report);
+                Self::new_with_assertion(Assertion::new(predicate, object))
+            }
+            _ => return self.clone(),
+        };
+        // Share subtrees in which nothing was decrypted with the original.
+        if report.decrypted.len() == decrypted_count {
+            self.clone()
+        } else {
+            result
+        }
+    }
+
+    fn decrypt_message_with_keys(
+        message: &bc_components::EncryptedMessage,
+        keys: &[SymmetricKey],
+    ) -> std::result::Result<Self, DecryptionFailure> {
+        let Some(digest) = message.opt_digest() else {
+            return Err(DecryptionFailure::MissingDigest);
+        };
+        let Some(cbor_data) = keys.iter().find_map(|key| key.decrypt(message).ok()) else {
+            return Err(DecryptionFailure::NoMatchingKey);
+        };
+        let content = SecretEnvelopeContent { cbor_data: Zeroizing::new(cbor_data), digest };
+        content.to_envelope().map_err(|_| DecryptionFailure::InvalidPlaintext)
+    }
+}
@@ -0,0 +1,121 @@
+use anyhow::{bail, Result};
+use bc_components::SymmetricKey;
+use bc_rand::{RandomNumberGenerator, SecureRandomNumberGenerator};
+use bc_ur::UR;
+use dcbor::prelude::*;
+
+use crate::{Envelope, EnvelopeError};
+
+/// The UR type of an encrypted envelope.
+pub const ENCRYPTED_ENVELOPE_UR_TYPE: &str = "crypto-envelope";
+
+/// The predicate of the assertion naming the function used to derive the key
+/// of a password-protected UR.
+pub const KEY_DERIVATION: &str = "keyDerivation";
+
+/// The predicate of the assertion holding the salt of the key derivation.
+pub const KEY_DERIVATION_SALT: &str = "keyDerivationSalt";
+
+/// The predicate of the assertion holding the iteration count of the key
+/// derivation.
+pub const KEY_DERIVATION_ITERATIONS: &str = "keyDerivationIterations";
+
+/// The only key derivation function currently supported.
+const PBKDF2_HMAC_SHA256: &str = "pbkdf2-hmac-sha256";
+
+/// The default number of PBKDF2 iterations for password-protected URs.
+pub const DEFAULT_PASSWORD_ITERATIONS: u32 = 600_000;
+
+/// The most PBKDF2 iterations accepted when parsing, so that a malicious UR
+/// cannot make the parser spin.
+const MAX_PASSWORD_ITERATIONS: u32 = 10_000_000;
+
+const SALT_LEN: usize = 16;
+
+/// The secret that protects an encrypted UR.
+#[derive(Debug, Clone, Copy)]
+pub enum URSecret<'a> {
+    /// A symmetric key, used directly.
+    Key(&'a SymmetricKey),
+    /// A password, from which a key is derived with PBKDF2-HMAC-SHA256.
+    Password(&'a str),
+}
+
+impl<'a> From<&'a SymmetricKey> for URSecret<'a> {
+    fn from(key: &'a SymmetricKey) -> Self {
+        URSecret::Key(key)
+    }
+}
+
+impl<'a> From<&'a str> for URSecret<'a> {
+    fn from(password: &'a str) -> Self {
+        URSecret::Password(password)
+    }
+}
+
+/// Support for encrypted URs, which keep backups of envelopes encrypted even
+/// when they are pasted into places that are not secret, such as notes apps.
+impl Envelope {
+    /// Returns a `ur:crypto-envelope` string holding this envelope encrypted
+    /// with the given key or password.
+    ///
+    /// The UR holds the envelope wrapped and then encrypted, as with
+    /// [`Envelope::encrypt`]. If a password is given, a key is derived from
+    /// it with PBKDF2-HMAC-SHA256 and a random salt, which are recorded in
+    /// assertions on the encrypted envelope.
+    pub fn to_encrypted_ur<'a>(&self, secret: impl Into<URSecret<'a>>) -> Result<String> {
+        self.to_encrypted_ur_opt(secret, DEFAULT_PASSWORD_ITERATIONS, &mut SecureRandomNumberGenerator)
+    }
+
+    #[doc(hidden)]
+    pub fn to_encrypted_ur_opt<'a>(
+        &self,
+        secret: impl Into<URSecret<'a>>,
+        iterations: u32,
+        rng: &mut impl RandomNumberGenerator,
+    ) -> Result<String> {
+        let encrypted = match secret.into() {
+            URSecret::Key(key) => self.encrypt(key),
+            URSecret::Password(password) => {
+                let salt = rng.random_data(SALT_LEN);
+                let key = derive_key(password, &salt, iterations);
+                self.encrypt(&key)
+                    .add_assertion(KEY_DERIVATION, PBKDF2_HMAC_SHA256)
+                    .add_assertion(KEY_DERIVATION_SALT, ByteString::from(salt))
+                    .add_assertion(KEY_DERIVATION_ITERATIONS, iterations)
+            }
+        };
+        Ok(UR::new(ENCRYPTED_ENVELOPE_UR_TYPE, encrypted.untagged_cbor())?.string())
+    }
+
+    /// Decrypts the envelope in a `ur:crypto-envelope` string made by
+    /// [`Envelope::to_encrypted_ur`] with the same key or password.
+    ///
+    /// Returns an error if the UR is not an encrypted envelope, or if the
+    /// key or password is wrong.
+    pub fn from_encrypted_ur<'a>(ur_string: &str, secret: impl Into<URSecret<'a>>) -> Result<Self> {
+        let ur = UR::from_ur_string(ur_string)?;
+        ur.check_type(ENCRYPTED_ENVELOPE_UR_TYPE)?;
+        let encrypted = Envelope::from_untagged_cbor(ur.cbor())?;
+        match secret.into() {
+            URSecret::Key(key) => encrypted.decrypt(key),
+            URSecret::Password(password) => {
+                if encrypted.extract_object_for_predicate::<String>(KEY_DERIVATION)? != PBKDF2_HMAC_SHA256 {
+                    bail!(EnvelopeError::InvalidFormat);
+                }
+                let salt: ByteString = encrypted.extract_object_for_predicate(KEY_DERIVATION_SALT)?;
+                let iterations: u32 = encrypted.extract_object_for_predicate(KEY_DERIVATION_ITERATIONS)?;
+                if iterations == 0 || iterations > MAX_PASSWORD_ITERATIONS {
+                    bail!(EnvelopeError::InvalidFormat);
+                }
+                let key = derive_key(password, salt.data(), iterations);
+                encrypted.decrypt(&key)
+            }
+        }
+    }
+}
+
+fn derive_key(password: &str, salt: &[u8], iterations: u32) -> SymmetricKey {
+    let data = bc_crypto::pbkdf2_hmac_sha256(password, salt, iterations, SymmetricKey::SYMMETRIC_KEY_SIZE);
+    SymmetricKey::from_data_ref(data).unwrap()
+}
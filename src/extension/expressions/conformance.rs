@@ -0,0 +1,270 @@
+//! A scripted peer for testing other implementations of envelope requests and
+//! responses.
+//!
+//! The peer exchanges one envelope per line, encoded as a UR string, over any
+//! transport: a child process's stdin and stdout, or a `TcpStream` with a
+//! `BufReader` over a clone of it. It follows a script of
+//! [`ConformanceCase`]s, such as [`standard_cases`], in one of two roles:
+//!
+//! * [`run_client`] sends each case's request and checks that the response
+//!   matches the case's response, for testing a server.
+//! * [`run_server`] checks that each request it receives matches the case's
+//!   request, and replies with the case's response, for testing a client.
+//!
+//! Requests and responses are compared by digest, so the implementation under
+//! test must produce envelopes that are equivalent to the scripted ones.
+//!
+//! Sealing requests and responses is outside the scope of this crate, so the
+//! peer exchanges them in the clear. A sealed transport can be tested by
+//! sealing and unsealing around these functions.
+
+use std::io::{BufRead, Write};
+
+use anyhow::{bail, Result};
+use bc_components::{DigestProvider, ARID};
+use bc_ur::UR;
+use dcbor::prelude::*;
+
+use crate::{Envelope, IngestLimits};
+
+use super::{ExpressionBehavior, Request, Response, ResponseBehavior};
+
+/// One exchange in a conformance script.
+#[derive(Debug, Clone)]
+pub struct ConformanceCase {
+    name: String,
+    request: Request,
+    response: Response,
+}
+
+impl ConformanceCase {
+    pub fn new(name: impl Into<String>, request: Request, response: Response) -> Self {
+        Self { name: name.into(), request, response }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn request(&self) -> &Request {
+        &self.request
+    }
+
+    pub fn response(&self) -> &Response {
+        &self.response
+    }
+}
+
+/// The outcome of running a conformance script.
+#[derive(Debug, Clone, Default)]
+pub struct ConformanceReport {
+    results: Vec<(String, Option<String>)>,
+}
+
+impl ConformanceReport {
+    /// The name of each case that was run, with a description of the failure
+    /// if it failed.
+    pub fn results(&self) -> &[(String, Option<String>)] {
+        &self.results
+    }
+
+    /// The cases that failed, with a description of each failure.
+    pub fn failures(&self) -> Vec<(&str, &str)> {
+        self.results
+            .iter()
+            .filter_map(|(name, failure)| failure.as_deref().map(|failure| (name.as_str(), failure)))
+            .collect()
+    }
+
+    /// `true` if every case passed.
+    pub fn passed(&self) -> bool {
+        self.results.iter().all(|(_, failure)| failure.is_none())
+    }
+
+    fn record(&mut self, case: &ConformanceCase, outcome: Result<()>) {
+        self.results.push((case.name.clone(), outcome.err().map(|error| error.to_string())));
+    }
+}
+
+fn case_id(n: u8) -> ARID {
+    ARID::from_data([n; 32])
+}
+
+/// The standard conformance script.
+///
+/// | Case              | Function            | Parameters                 | Response                   |
+/// |-------------------|---------------------|----------------------------|----------------------------|
+/// | `echo`            | `"echo"`            | `"value"`: `"Hello."`      | result `"Hello."`          |
+/// | `add`             | `"add"`             | `"lhs"`: `2`, `"rhs"`: `3` | result `5`                 |
+/// | `ping`            | `"ping"`            |                            | result `'OK'`              |
+/// | `unknownFunction` | `"unknownFunction"` |                            | error `"unknown function"` |
+///
+/// Case `n`, counting from 1, has the request ID whose 32 bytes are all `n`.
+pub fn standard_cases() -> Vec<ConformanceCase> {
+    vec![
+        ConformanceCase::new(
+            "echo",
+            Request::new("echo", case_id(1)).with_parameter("value", "Hello."),
+            Response::new_success(case_id(1)).with_result("Hello."),
+        ),
+        ConformanceCase::new(
+            "add",
+            Request::new("add", case_id(2)).with_parameter("lhs", 2).with_parameter("rhs", 3),
+            Response::new_success(case_id(2)).with_result(5),
+        ),
+        ConformanceCase::new(
+            "ping",
+            Request::new("ping", case_id(3)),
+            Response::new_success(case_id(3)),
+        ),
+        ConformanceCase::new(
+            "unknownFunction",
+            Request::new("unknownFunction", case_id(4)),
+            Response::new_failure(case_id(4)).with_error("unknown function"),
+        ),
+    ]
+}
+
+/// Acts as a client: sends the request of each case and checks the response.
+///
+/// Returns an error only if the transport fails. Each case's outcome is
+/// recorded in the report; a missing response ends the script.
+pub fn run_client(cases: &[ConformanceCase], mut input: impl BufRead, mut output: impl Write) -> Result<ConformanceReport> {
+    let mut report = ConformanceReport::default();
+    for case in cases {
+        write_envelope(&mut output, &case.request.clone().into())?;
+        let outcome = match read_envelope(&mut input)? {
+            Some(response) => check_response(case, response),
+            None => {
+                report.record(case, Err(anyhow::anyhow!("no response")));
+                break;
+            }
+        };
+        report.record(case, outcome);
+    }
+    Ok(report)
+}
+
+/// Acts as a server: checks each request received against the request of
+/// the next case, and replies with that case's response.
+///
+/// Returns an error only if the transport fails. A request that does not
+/// match is answered with an early failure response. The script ends when
+/// the input ends.
+pub fn run_server(cases: &[ConformanceCase], mut input: impl BufRead, mut output: impl Write) -> Result<ConformanceReport> {
+    let mut report = ConformanceReport::default();
+    for case in cases {
+        let Some(request) = read_envelope(&mut input)? else {
+            report.record(case, Err(anyhow::anyhow!("no request")));
+            break;
+        };
+        let outcome = check_request(case, request);
+        let response = if outcome.is_ok() {
+            case.response.clone()
+        } else {
+            Response::new_early_failure().with_error("unexpected request")
+        };
+        write_envelope(&mut output, &response.into())?;
+        report.record(case, outcome);
+    }
+    Ok(report)
+}
+
+fn check_response(case: &ConformanceCase, received: Result<Envelope>) -> Result<()> {
+    let received = Response::try_from(received?)?;
+    if received.id() != case.response.id() {
+        bail!("expected response to {:?}, received response to {:?}", case.response.id(), received.id());
+    }
+    let expected: Envelope = case.response.clone().into();
+    let received: Envelope = received.into();
+    if received.digest() != expected.digest() {
+        bail!("expected envelope {}, received envelope {}", expected.short_id(), received.short_id());
+    }
+    Ok(())
+}
+
+fn check_request(case: &ConformanceCase, received: Result<Envelope>) -> Result<()> {
+    let received = received?;
+    let expected: Envelope = case.request.clone().into();
+    if received.digest() != expected.digest() {
+        bail!("expected envelope {}, received envelope {}", expected.short_id(), received.short_id());
+    }
+    Ok(())
+}
+
+/// Reads the envelope on the next line, or `None` at the end of the input.
+///
+/// Transport errors are returned as the outer error, and malformed envelopes
+/// as the inner one.
+fn read_envelope(input: &mut impl BufRead) -> Result<Option<Result<Envelope>>> {
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(Envelope::ingest_ur_string(line.trim(), &IngestLimits::default()).map_err(Into::into)))
+}
+
+fn write_envelope(output: &mut impl Write, envelope: &Envelope) -> Result<()> {
+    let ur = UR::new("envelope", envelope.untagged_cbor())?;
+    writeln!(output, "{}", ur.string())?;
+    output.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn requests(cases: &[ConformanceCase]) -> Vec<u8> {
+        let mut data = Vec::new();
+        for case in cases {
+            write_envelope(&mut data, &case.request.clone().into()).unwrap();
+        }
+        data
+    }
+
+    #[test]
+    fn test_conformance_peers() {
+        let cases = standard_cases();
+
+        // The scripted server accepts the scripted client's requests...
+        let mut responses = Vec::new();
+        let report = run_server(&cases, Cursor::new(requests(&cases)), &mut responses).unwrap();
+        assert!(report.passed());
+        assert_eq!(report.results().len(), cases.len());
+
+        // ...and the scripted client accepts the scripted server's responses.
+        let mut sent = Vec::new();
+        let report = run_client(&cases, Cursor::new(responses), &mut sent).unwrap();
+        assert!(report.passed(), "{:?}", report.failures());
+        assert_eq!(sent, requests(&cases));
+    }
+
+    #[test]
+    fn test_conformance_failures() {
+        let cases = standard_cases();
+
+        // Requests out of order are rejected with early failures.
+        let mut reordered = cases.clone();
+        reordered.swap(0, 1);
+        let mut responses = Vec::new();
+        let report = run_server(&cases, Cursor::new(requests(&reordered)), &mut responses).unwrap();
+        let failures = report.failures();
+        assert_eq!(failures.len(), 2);
+        assert_eq!(failures[0].0, "echo");
+
+        // The client notices that those responses are not the expected ones.
+        let report = run_client(&cases, Cursor::new(responses), Vec::new()).unwrap();
+        assert_eq!(report.failures().len(), 2);
+
+        // The client notices when the server stops responding.
+        let report = run_client(&cases, Cursor::new(Vec::new()), Vec::new()).unwrap();
+        assert_eq!(report.failures(), vec![("echo", "no response")]);
+
+        // Garbage is reported rather than ending the script.
+        let report = run_client(&cases[..1], Cursor::new(b"not a UR\n".to_vec()), Vec::new()).unwrap();
+        assert_eq!(report.failures().len(), 1);
+    }
+}
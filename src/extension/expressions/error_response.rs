@@ -0,0 +1,250 @@
+use anyhow::{bail, Error, Result};
+
+use crate::{Envelope, EnvelopeEncodable};
+
+/// The predicate for the numeric error code of an `ErrorResponse`.
+pub const ERROR_CODE: &str = "code";
+
+/// The predicate for the retryability flag of an `ErrorResponse`.
+pub const ERROR_RETRYABLE: &str = "retryable";
+
+/// The predicate for the nested cause of an `ErrorResponse`.
+pub const ERROR_CAUSE: &str = "cause";
+
+/// A structured error suitable for use as the `'error'` value of a `Response`.
+///
+/// The human-readable message is the subject of the envelope. The error code,
+/// retryability flag, and nested cause are carried as assertions:
+///
+/// ```text
+/// "Rate limit exceeded" [
+///     "cause": "Too many requests from this peer"
+///     "code": 429
+///     "retryable": true
+/// ]
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorResponse {
+    message: String,
+    code: Option<u64>,
+    retryable: bool,
+    cause: Option<Envelope>,
+}
+
+impl ErrorResponse {
+    /// Creates a new error with the given human-readable message.
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            code: None,
+            retryable: false,
+            cause: None,
+        }
+    }
+
+    /// Sets the numeric error code.
+    pub fn with_code(mut self, code: u64) -> Self {
+        self.code = Some(code);
+        self
+    }
+
+    /// Sets whether the client may retry the request that caused this error.
+    pub fn with_retryable(mut self, retryable: bool) -> Self {
+        self.retryable = retryable;
+        self
+    }
+
+    /// Sets the underlying cause of this error.
+    ///
+    /// The cause may be any envelope, including another `ErrorResponse`.
+    pub fn with_cause(mut self, cause: impl EnvelopeEncodable) -> Self {
+        self.cause = Some(cause.into_envelope());
+        self
+    }
+
+    /// Returns the human-readable message.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns the numeric error code, if any.
+    pub fn code(&self) -> Option<u64> {
+        self.code
+    }
+
+    /// Returns `true` if the client may retry the request.
+    pub fn is_retryable(&self) -> bool {
+        self.retryable
+    }
+
+    /// Returns the underlying cause of this error, if any.
+    pub fn cause(&self) -> Option<&Envelope> {
+        self.cause.as_ref()
+    }
+
+    /// Returns the underlying cause of this error decoded as an
+    /// `ErrorResponse`, if any.
+    ///
+    /// Returns an error if there is a cause but it is not a structured error.
+    pub fn cause_details(&self) -> Result<Option<ErrorResponse>> {
+        self.cause.clone().map(ErrorResponse::try_from).transpose()
+    }
+}
+
+impl std::fmt::Display for ErrorResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(code) = self.code {
+            write!(f, "{} ({})", self.message, code)
+        } else {
+            write!(f, "{}", self.message)
+        }
+    }
+}
+
+impl From<ErrorResponse> for Envelope {
+    fn from(error: ErrorResponse) -> Self {
+        Envelope::new(error.message)
+            .add_optional_assertion(ERROR_CODE, error.code)
+            .add_assertion_if(error.retryable, ERROR_RETRYABLE, true)
+            .add_optional_assertion(ERROR_CAUSE, error.cause)
+    }
+}
+
+impl TryFrom<Envelope> for ErrorResponse {
+    type Error = Error;
+
+    fn try_from(envelope: Envelope) -> Result<Self> {
+        let message: String = match envelope.extract_subject() {
+            Ok(message) => message,
+            Err(_) => bail!("error response subject must be a message string"),
+        };
+        Ok(Self {
+            message,
+            code: envelope.extract_optional_object_for_predicate(ERROR_CODE)?,
+            retryable: envelope.extract_object_for_predicate_with_default(ERROR_RETRYABLE, false)?,
+            cause: envelope.optional_object_for_predicate(ERROR_CAUSE)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Response, ResponseBehavior};
+    use bc_components::ARID;
+    use hex_literal::hex;
+
+    fn request_id() -> ARID {
+        ARID::from_data(hex!("c66be27dbad7cd095ca77647406d07976dc0f35f0d4d654bb0e96dd227a1e9fc"))
+    }
+
+    #[test]
+    fn test_error_response() -> Result<()> {
+        let error = ErrorResponse::new("Rate limit exceeded")
+            .with_code(429)
+            .with_retryable(true)
+            .with_cause(ErrorResponse::new("Too many requests").with_code(1));
+
+        let response = Response::new_failure(request_id())
+            .with_error(error.clone());
+        let envelope: Envelope = response.into();
+
+        let parsed_response = Response::try_from(envelope)?;
+        let details = parsed_response.error_details()?;
+        assert_eq!(details, error);
+        assert_eq!(details.message(), "Rate limit exceeded");
+        assert_eq!(details.code(), Some(429));
+        assert!(details.is_retryable());
+
+        let cause = details.cause_details()?.unwrap();
+        assert_eq!(cause.message(), "Too many requests");
+        assert_eq!(cause.code(), Some(1));
+        assert!(!cause.is_retryable());
+        assert!(cause.cause().is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_free_form_error() {
+        let response = Response::new_failure(request_id())
+            .with_error(42);
+        assert!(response.error_details().is_err());
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Receipt {
+        amount: u64,
+    }
+
+    impl From<Receipt> for Envelope {
+        fn from(receipt: Receipt) -> Self {
+            Envelope::new("Receipt").add_assertion("amount", receipt.amount)
+        }
+    }
+
+    impl TryFrom<Envelope> for Receipt {
+        type Error = Error;
+
+        fn try_from(envelope: Envelope) -> Result<Self> {
+            Ok(Self { amount: envelope.extract_object_for_predicate("amount")? })
+        }
+    }
+
+    #[derive(Debug)]
+    enum TransferError {
+        InsufficientFunds,
+        Rejected(String),
+        Protocol(Error),
+    }
+
+    impl From<TransferError> for ErrorResponse {
+        fn from(error: TransferError) -> Self {
+            match error {
+                TransferError::InsufficientFunds => ErrorResponse::new("Insufficient funds").with_code(402),
+                TransferError::Rejected(message) => ErrorResponse::new(message),
+                TransferError::Protocol(error) => ErrorResponse::new(error.to_string()),
+            }
+        }
+    }
+
+    impl From<Error> for TransferError {
+        fn from(error: Error) -> Self {
+            Self::Protocol(error)
+        }
+    }
+
+    fn transfer_error(error: ErrorResponse) -> TransferError {
+        match error.code() {
+            Some(402) => TransferError::InsufficientFunds,
+            _ => TransferError::Rejected(error.message().to_string()),
+        }
+    }
+
+    fn round_trip(response: Response) -> Response {
+        Response::try_from(Envelope::from(response)).unwrap()
+    }
+
+    #[test]
+    fn test_extract_result_as() {
+        let success = round_trip(Response::from_result(request_id(), Ok::<_, TransferError>(Receipt { amount: 100 })));
+        let receipt: Receipt = success.extract_result_as(transfer_error).unwrap();
+        assert_eq!(receipt, Receipt { amount: 100 });
+
+        let failure = round_trip(Response::from_result(request_id(), Err::<Receipt, _>(TransferError::InsufficientFunds)));
+        let result: Result<Receipt, TransferError> = failure.extract_result_as(transfer_error);
+        assert!(matches!(result, Err(TransferError::InsufficientFunds)));
+
+        let rejected = round_trip(Response::new_failure(request_id()).with_error("Account closed"));
+        let result: Result<Receipt, TransferError> = rejected.extract_result_as(transfer_error);
+        assert!(matches!(result, Err(TransferError::Rejected(message)) if message == "Account closed"));
+
+        // A result that is not the expected value, or an error that is not
+        // structured, is a protocol error.
+        let unexpected = Response::new_success(request_id()).with_result("Done");
+        let result: Result<Receipt, TransferError> = unexpected.extract_result_as(transfer_error);
+        assert!(matches!(result, Err(TransferError::Protocol(_))));
+        let result: Result<Receipt, TransferError> = Response::new_early_failure().extract_result_as(transfer_error);
+        assert!(matches!(result, Err(TransferError::Protocol(_))));
+    }
+}
@@ -18,6 +18,74 @@ impl Expression {
             envelope: Envelope::new(function),
         }
     }
+
+    /// Returns the expressions nested in the parameters of this expression,
+    /// at any depth, in an order in which they can be evaluated.
+    ///
+    /// A nested expression is a parameter whose argument is itself an
+    /// expression, such as the `sha256` call in
+    /// `verifySignature(key, sha256(data))`. Each nested expression is
+    /// returned after all the expressions nested within it, so an evaluator
+    /// can evaluate them in order and substitute each result into the
+    /// expression that depends on it. The outermost expression, which
+    /// depends on all of them, is not included. Sibling expressions are
+    /// returned in the order of their parameters' assertions.
+    pub fn nested_expressions(&self) -> Vec<NestedExpression> {
+        let mut result = Vec::new();
+        self.collect_nested_expressions(&mut Vec::new(), &mut result);
+        result
+    }
+
+    fn collect_nested_expressions(&self, path: &mut Vec<Parameter>, result: &mut Vec<NestedExpression>) {
+        for assertion in self.envelope.assertions() {
+            let (Some(predicate), Some(object)) = (assertion.as_predicate(), assertion.as_object()) else {
+                continue;
+            };
+            let Ok(parameter) = predicate.extract_subject::<Parameter>() else {
+                continue;
+            };
+            let Ok(expression) = Expression::try_from(object) else {
+                continue;
+            };
+            path.push(parameter);
+            expression.collect_nested_expressions(path, result);
+            result.push(NestedExpression { path: path.clone(), expression });
+            path.pop();
+        }
+    }
+}
+
+/// An expression nested in the parameters of another expression, returned by
+/// [`Expression::nested_expressions`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct NestedExpression {
+    path: Vec<Parameter>,
+    expression: Expression,
+}
+
+impl NestedExpression {
+    /// The parameters leading from the outermost expression to this one,
+    /// outermost first.
+    pub fn path(&self) -> &[Parameter] {
+        &self.path
+    }
+
+    /// The parameter of the enclosing expression whose argument is this
+    /// expression.
+    pub fn parameter(&self) -> &Parameter {
+        self.path.last().unwrap()
+    }
+
+    /// How deeply this expression is nested: 1 for an argument of the
+    /// outermost expression, 2 for an argument of one of those, and so on.
+    pub fn depth(&self) -> usize {
+        self.path.len()
+    }
+
+    /// The nested expression.
+    pub fn expression(&self) -> &Expression {
+        &self.expression
+    }
 }
 
 impl std::fmt::Display for Expression {
@@ -265,4 +333,30 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_nested_expressions() -> Result<()> {
+        let sha256 = Expression::new("sha256")
+            .with_parameter("data", Expression::new("concat").with_parameter("lhs", "a").with_parameter("rhs", "b"));
+        let expression = Expression::new("verifySignature")
+            .with_parameter("key", "Alice")
+            .with_parameter("message", sha256.clone());
+
+        let nested = expression.nested_expressions();
+        assert_eq!(nested.len(), 2);
+
+        // The innermost expression is evaluated first.
+        assert_eq!(nested[0].expression().function(), &Function::from("concat"));
+        assert_eq!(nested[0].path(), [Parameter::from("message"), Parameter::from("data")]);
+        assert_eq!(nested[0].depth(), 2);
+
+        assert_eq!(nested[1].expression(), &sha256);
+        assert_eq!(nested[1].parameter(), &Parameter::from("message"));
+        assert_eq!(nested[1].depth(), 1);
+
+        assert!(sha256.nested_expressions()[0].path() == [Parameter::from("data")]);
+        assert!(Expression::new("foo").with_parameter("bar", 1).nested_expressions().is_empty());
+
+        Ok(())
+    }
 }
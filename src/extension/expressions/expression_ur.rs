@@ -0,0 +1,86 @@
+use anyhow::{Error, Result};
+use bc_ur::UR;
+use dcbor::prelude::*;
+
+use crate::Envelope;
+
+use super::{Request, Response};
+
+/// A request or response that is encoded as a UR of its own type, whose body
+/// is its envelope.
+///
+/// A dedicated UR type lets a request or response be told apart from an
+/// arbitrary `ur:envelope` at the boundary, before it is parsed.
+pub trait ExpressionUR: Clone + Into<Envelope> + TryFrom<Envelope, Error = Error> {
+    /// The UR type of the encoding.
+    const UR_TYPE: &'static str;
+
+    /// Returns the value encoded as a UR of type [`ExpressionUR::UR_TYPE`].
+    fn ur_string(&self) -> String {
+        UR::new(Self::UR_TYPE, self.clone().into().untagged_cbor()).unwrap().string()
+    }
+
+    /// Decodes a value from a UR string.
+    ///
+    /// Returns an error if the UR has any other type, including
+    /// `ur:envelope`, or if its envelope is not a value of this type.
+    fn from_ur_string(ur_string: impl Into<String>) -> Result<Self> {
+        let ur = UR::from_ur_string(ur_string)?;
+        ur.check_type(Self::UR_TYPE)?;
+        Self::try_from(Envelope::from_untagged_cbor(ur.cbor())?)
+    }
+}
+
+impl ExpressionUR for Request {
+    const UR_TYPE: &'static str = "request";
+}
+
+impl ExpressionUR for Response {
+    const UR_TYPE: &'static str = "response";
+}
+
+#[cfg(test)]
+mod tests {
+    use bc_components::ARID;
+
+    use super::*;
+    use crate::{ExpressionBehavior, RequestBehavior, ResponseBehavior};
+
+    fn envelope_ur_string(envelope: Envelope) -> String {
+        UR::new("envelope", envelope.untagged_cbor()).unwrap().string()
+    }
+
+    #[test]
+    fn test_request_ur() -> Result<()> {
+        let request = Request::new("test", ARID::new())
+            .with_parameter("param1", 42)
+            .with_note("This is a test");
+        let ur_string = request.ur_string();
+        assert!(ur_string.starts_with("ur:request/"));
+        assert_eq!(Request::from_ur_string(ur_string)?, request);
+
+        // Other UR types are rejected at the boundary, even when they hold a
+        // request.
+        assert!(Request::from_ur_string(envelope_ur_string(Envelope::from(request.clone()))).is_err());
+        let response = Response::new_success(request.id());
+        assert!(Request::from_ur_string(response.ur_string()).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_response_ur() -> Result<()> {
+        let success = Response::new_success(ARID::new()).with_result("It works!");
+        let ur_string = success.ur_string();
+        assert!(ur_string.starts_with("ur:response/"));
+        assert_eq!(Response::from_ur_string(ur_string)?, success);
+
+        let failure = Response::new_early_failure();
+        assert_eq!(Response::from_ur_string(failure.ur_string())?, failure);
+
+        assert!(Response::from_ur_string(envelope_ur_string(Envelope::from(success))).is_err());
+        assert!(Response::from_ur_string(envelope_ur_string(Envelope::new("Hello"))).is_err());
+
+        Ok(())
+    }
+}
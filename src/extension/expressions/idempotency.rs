@@ -0,0 +1,224 @@
+use std::collections::{hash_map::Entry, HashMap};
+use std::sync::Mutex;
+
+use anyhow::{bail, Result};
+use bc_components::{Digest, DigestProvider, ARID};
+
+use crate::{EnvelopeError, ExpressionBehavior};
+
+use super::{Request, RequestBehavior, Response};
+
+/// The predicate of the assertion carrying a request's idempotency key.
+pub const IDEMPOTENCY_KEY: &str = "idempotencyKey";
+
+/// A server-side store of the responses to requests that carried idempotency
+/// keys.
+///
+/// A client that does not receive a response may retry its request. If the
+/// request has an idempotency key, a server that responds through
+/// [`IdempotencyCache::respond`] performs the request only once, and replies
+/// to each retry with the response it gave the first time. A retry that
+/// arrives while the first request is still being performed gets a
+/// processing response, as made by [`Response::new_processing`].
+///
+/// Each response is stored with the digest of the body of the request it
+/// answered, so that a key reused for a different request is detected.
+///
+/// The cache is shared by the requests being performed concurrently, so its
+/// methods take `&self`, and implementations must make [`reserve`] atomic.
+///
+/// Implementations decide how long responses are retained.
+///
+/// [`reserve`]: IdempotencyCache::reserve
+pub trait IdempotencyCache {
+    /// Reserves the idempotency key `key` for a request with a body with the
+    /// digest `body`, unless it is already in use.
+    ///
+    /// Returns `None` if the key was reserved. Otherwise returns the digest
+    /// of the body of the request that holds the key, and the response given
+    /// to it, or `None` if that request is still being performed.
+    fn reserve(&self, key: &ARID, body: &Digest) -> Option<(Digest, Option<Response>)>;
+
+    /// Records `response` as the response to the request that reserved the
+    /// idempotency key `key`.
+    fn complete(&self, key: &ARID, response: &Response);
+
+    /// Releases the reservation of the idempotency key `key` by a request
+    /// that was not performed.
+    fn release(&self, key: &ARID);
+
+    /// Returns the response to `request`.
+    ///
+    /// If the request has an idempotency key for which a response has been
+    /// cached, returns the cached response, addressed to this request,
+    /// without calling `handler`. If a request with the key is still being
+    /// performed, returns a processing response. Otherwise calls `handler`
+    /// to perform the request, and caches the response if the request has an
+    /// idempotency key.
+    ///
+    /// Returns an error if the idempotency key was used before for a request
+    /// with a different body.
+    fn respond(&self, request: &Request, handler: impl FnOnce(&Request) -> Response) -> Result<Response>
+    where
+        Self: Sized,
+    {
+        let Some(key) = request.idempotency_key() else {
+            return Ok(handler(request));
+        };
+        let body = request.body().expression_envelope().digest().into_owned();
+        if let Some((reserved_body, response)) = self.reserve(key, &body) {
+            if reserved_body != body {
+                bail!(EnvelopeError::IdempotencyKeyReused);
+            }
+            return Ok(match response {
+                Some(response) => response.readdressed(request.id(), request.trace_context()),
+                None => Response::new_processing(request.id()),
+            });
+        }
+        let reservation = Reservation { cache: self, key, completed: false };
+        let response = handler(request);
+        reservation.complete(&response);
+        Ok(response)
+    }
+}
+
+/// Releases a reserved idempotency key if the handler unwinds before the
+/// response is cached.
+struct Reservation<'a, C: IdempotencyCache> {
+    cache: &'a C,
+    key: &'a ARID,
+    completed: bool,
+}
+
+impl<C: IdempotencyCache> Reservation<'_, C> {
+    fn complete(mut self, response: &Response) {
+        self.cache.complete(self.key, response);
+        self.completed = true;
+    }
+}
+
+impl<C: IdempotencyCache> Drop for Reservation<'_, C> {
+    fn drop(&mut self) {
+        if !self.completed {
+            self.cache.release(self.key);
+        }
+    }
+}
+
+/// An in-memory cache that retains every response.
+///
+/// Each entry holds the digest of the request body, and the response, or
+/// `None` while the request is being performed.
+impl IdempotencyCache for Mutex<HashMap<ARID, (Digest, Option<Response>)>> {
+    fn reserve(&self, key: &ARID, body: &Digest) -> Option<(Digest, Option<Response>)> {
+        match self.lock().unwrap().entry(key.clone()) {
+            Entry::Occupied(entry) => Some(entry.get().clone()),
+            Entry::Vacant(entry) => {
+                entry.insert((body.clone(), None));
+                None
+            }
+        }
+    }
+
+    fn complete(&self, key: &ARID, response: &Response) {
+        if let Some((_, cached)) = self.lock().unwrap().get_mut(key) {
+            *cached = Some(response.clone());
+        }
+    }
+
+    fn release(&self, key: &ARID) {
+        self.lock().unwrap().remove(key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Envelope, ResponseBehavior};
+
+    #[test]
+    fn test_idempotency_key_round_trip() {
+        let key = ARID::new();
+        let request = Request::new("transfer", ARID::new())
+            .with_idempotency_key(&key);
+        assert_eq!(request.idempotency_key(), Some(&key));
+        let envelope: Envelope = request.clone().into();
+        let parsed = Request::try_from(envelope).unwrap();
+        assert_eq!(parsed, request);
+        assert_eq!(parsed.idempotency_key(), Some(&key));
+    }
+
+    #[test]
+    fn test_idempotency_cache() {
+        let cache: Mutex<HashMap<ARID, (Digest, Option<Response>)>> = Mutex::default();
+        let mut calls = 0;
+        let mut handler = |request: &Request| {
+            calls += 1;
+            Response::new_success(request.id()).with_result(calls)
+        };
+
+        let key = ARID::new();
+        let request = Request::new("transfer", ARID::new())
+            .with_idempotency_key(&key);
+        let first = cache.respond(&request, &mut handler).unwrap();
+        let retry = cache.respond(&request, &mut handler).unwrap();
+        assert_eq!(first, retry);
+        assert_eq!(retry.extract_result::<i32>().unwrap(), 1);
+
+        // A retry with a new request ID gets the cached response addressed
+        // to it.
+        let resent = Request::new("transfer", ARID::new())
+            .with_idempotency_key(&key);
+        let response = cache.respond(&resent, &mut handler).unwrap();
+        assert_eq!(response.id(), Some(resent.id()));
+        assert_eq!(response.extract_result::<i32>().unwrap(), 1);
+
+        // The key cannot be reused for a different request.
+        let other = Request::new("transfer", ARID::new())
+            .with_parameter("amount", 10)
+            .with_idempotency_key(&key);
+        let error = cache.respond(&other, &mut handler).unwrap_err();
+        assert_eq!(error.to_string(), "the idempotency key was used for a different request");
+        let other = Request::new("refund", ARID::new())
+            .with_idempotency_key(&key);
+        assert!(cache.respond(&other, &mut handler).is_err());
+
+        // Requests without an idempotency key are always performed.
+        let request = Request::new("transfer", ARID::new());
+        cache.respond(&request, &mut handler).unwrap();
+        let response = cache.respond(&request, &mut handler).unwrap();
+        assert_eq!(response.extract_result::<i32>().unwrap(), 3);
+        assert_eq!(cache.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_idempotency_cache_in_flight() {
+        let cache: Mutex<HashMap<ARID, (Digest, Option<Response>)>> = Mutex::default();
+        let key = ARID::new();
+        let request = Request::new("transfer", ARID::new())
+            .with_idempotency_key(&key);
+
+        // A retry that arrives while the request is being performed is not
+        // performed again.
+        let mut calls = 0;
+        let response = cache.respond(&request, |request| {
+            calls += 1;
+            let retry = cache.respond(request, |_| unreachable!()).unwrap();
+            assert!(retry.is_processing());
+            Response::new_success(request.id()).with_result(1)
+        }).unwrap();
+        assert_eq!(calls, 1);
+        assert_eq!(response.extract_result::<i32>().unwrap(), 1);
+        let retry = cache.respond(&request, |_| unreachable!()).unwrap();
+        assert_eq!(retry, response);
+
+        // A request whose handler panics releases its key.
+        let other_key = ARID::new();
+        let other = Request::new("transfer", ARID::new())
+            .with_idempotency_key(&other_key);
+        let result = std::panic::catch_unwind(|| cache.respond(&other, |_| panic!("failed")));
+        assert!(result.is_err());
+        let response = cache.respond(&other, |request| Response::new_success(request.id())).unwrap();
+        assert!(!response.is_processing());
+    }
+}
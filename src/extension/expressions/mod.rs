@@ -25,6 +25,7 @@ pub use expression::{
     Expression,
     ExpressionBehavior,
     IntoExpression,
+    NestedExpression,
 };
 
 pub mod request;
@@ -39,6 +40,28 @@ pub use response::{
     ResponseBehavior,
 };
 
+pub mod expression_ur;
+pub use expression_ur::ExpressionUR;
+
+pub mod idempotency;
+pub use idempotency::IdempotencyCache;
+
+pub mod polling;
+pub use polling::PollPolicy;
+
+pub mod tracing;
+pub use tracing::{
+    CallTrace,
+    SpanStatus,
+    TraceContext,
+    TraceSpan,
+};
+
+pub mod conformance;
+
+pub mod error_response;
+pub use error_response::ErrorResponse;
+
 pub mod event;
 pub use event::{
     Event,
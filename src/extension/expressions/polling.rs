@@ -0,0 +1,114 @@
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+
+use crate::EnvelopeError;
+
+use super::{Response, ResponseBehavior};
+
+/// How a client polls for the outcome of a request whose responses say it
+/// is still processing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PollPolicy {
+    /// The most times the request is sent, including the first.
+    pub max_attempts: usize,
+    /// How long to wait when a processing response has no `"retryAfter"`.
+    pub default_delay: Duration,
+    /// The longest the client waits between attempts, whatever the server
+    /// asks for.
+    pub max_delay: Duration,
+}
+
+impl Default for PollPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 10,
+            default_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+        }
+    }
+}
+
+impl PollPolicy {
+    /// Returns how long to wait before asking again after `response`, or
+    /// `None` if the response is not a processing response.
+    pub fn delay_after(&self, response: &Response) -> Option<Duration> {
+        if !response.is_processing() {
+            return None;
+        }
+        Some(response.retry_after().unwrap_or(self.default_delay).min(self.max_delay))
+    }
+
+    /// Sends a request with `send` until its response is no longer a
+    /// processing response, calling `sleep` with the delay the server asked
+    /// for between attempts, and returns the final response.
+    ///
+    /// `send` and `sleep` are supplied by the caller so that any transport,
+    /// and any runtime, may be used. Returns an error if `send` fails, or if
+    /// the request is still processing after `max_attempts` attempts.
+    pub fn poll(
+        &self,
+        mut send: impl FnMut() -> Result<Response>,
+        mut sleep: impl FnMut(Duration),
+    ) -> Result<Response> {
+        for attempt in 1..=self.max_attempts {
+            let response = send()?;
+            match self.delay_after(&response) {
+                None => return Ok(response),
+                Some(delay) if attempt < self.max_attempts => sleep(delay),
+                Some(_) => {}
+            }
+        }
+        bail!(EnvelopeError::StillProcessing(self.max_attempts))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bc_components::ARID;
+
+    use super::*;
+
+    #[test]
+    fn test_poll() {
+        let id = ARID::new();
+        let responses = [
+            Response::new_processing(&id).with_retry_after(Duration::from_millis(250)).with_progress(0.5),
+            Response::new_processing(&id).with_retry_after(Duration::from_secs(3600)),
+            Response::new_processing(&id),
+            Response::new_success(&id).with_result("done"),
+        ];
+        assert!(responses[0].is_processing());
+        assert_eq!(responses[0].retry_after(), Some(Duration::from_millis(250)));
+        assert_eq!(responses[0].progress(), Some(0.5));
+        assert_eq!(responses[2].retry_after(), None);
+        assert!(!responses[3].is_processing());
+        assert_eq!(responses[3].retry_after(), None);
+
+        let policy = PollPolicy::default();
+        let mut sent = responses.iter().cloned();
+        let mut delays = Vec::new();
+        let response = policy.poll(|| Ok(sent.next().unwrap()), |delay| delays.push(delay)).unwrap();
+        assert_eq!(response.extract_result::<String>().unwrap(), "done");
+        assert_eq!(delays, [Duration::from_millis(250), policy.max_delay, policy.default_delay]);
+
+        // The server never finishes.
+        let policy = PollPolicy { max_attempts: 3, ..PollPolicy::default() };
+        let mut sleeps = 0;
+        let result = policy.poll(|| Ok(Response::new_processing(&id)), |_| sleeps += 1);
+        assert!(result.is_err());
+        assert_eq!(sleeps, 2);
+    }
+
+    #[test]
+    fn test_processing_round_trip() {
+        let response = Response::new_processing(ARID::new())
+            .with_retry_after(Duration::from_secs(5))
+            .with_progress(0.25);
+        let envelope: crate::Envelope = response.clone().into();
+        let parsed = Response::try_from(envelope).unwrap();
+        assert_eq!(parsed, response);
+        assert_eq!(parsed.retry_after(), Some(Duration::from_secs(5)));
+        assert_eq!(parsed.progress(), Some(0.25));
+    }
+}
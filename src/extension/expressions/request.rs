@@ -2,7 +2,9 @@ use anyhow::{Error, Result};
 use bc_components::{tags, ARID};
 use dcbor::{Date, prelude::*};
 
-use crate::{known_values, Envelope, EnvelopeEncodable, Expression, ExpressionBehavior, Function, Parameter};
+use crate::{known_values, Envelope, EnvelopeEncodable, Expression, ExpressionBehavior, Function, Parameter, TimePolicy};
+
+use super::{idempotency::IDEMPOTENCY_KEY, TraceContext};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Request {
@@ -10,6 +12,8 @@ pub struct Request {
     id: ARID,
     note: String,
     date: Option<Date>,
+    idempotency_key: Option<ARID>,
+    trace_context: Option<TraceContext>,
 }
 
 impl std::fmt::Display for Request {
@@ -22,6 +26,14 @@ impl Request {
     pub fn summary(&self) -> String {
         format!("id: {}, body: {}", self.id.short_description(), self.body.expression_envelope().format_flat())
     }
+
+    /// Checks the request's date, if it has one, against the given policy.
+    ///
+    /// Servers can use this to reject requests that are stale or dated in the
+    /// future.
+    pub fn check_date(&self, policy: &TimePolicy) -> Result<()> {
+        self.date.as_ref().map_or(Ok(()), |date| policy.check_date(date))
+    }
 }
 
 pub trait RequestBehavior: ExpressionBehavior {
@@ -35,6 +47,17 @@ pub trait RequestBehavior: ExpressionBehavior {
     /// Adds a date to the request.
     fn with_date(self, date: impl AsRef<Date>) -> Self;
 
+    /// Adds an idempotency key to the request.
+    ///
+    /// A client that retries a request with the same idempotency key is
+    /// asking for the operation to be performed at most once. See
+    /// [`IdempotencyCache`](super::IdempotencyCache).
+    fn with_idempotency_key(self, key: impl AsRef<ARID>) -> Self;
+
+    /// Adds a trace context to the request, placing it in a distributed
+    /// trace. See [`TraceContext`].
+    fn with_trace_context(self, context: TraceContext) -> Self;
+
     //
     // Parsing
     //
@@ -50,6 +73,12 @@ pub trait RequestBehavior: ExpressionBehavior {
 
     /// Returns the date of the request.
     fn date(&self) -> Option<&Date>;
+
+    /// Returns the idempotency key of the request.
+    fn idempotency_key(&self) -> Option<&ARID>;
+
+    /// Returns the trace context of the request.
+    fn trace_context(&self) -> Option<&TraceContext>;
 }
 
 impl Request {
@@ -59,6 +88,8 @@ impl Request {
             id: id.as_ref().clone(),
             note: String::new(),
             date: None,
+            idempotency_key: None,
+            trace_context: None,
         }
     }
 
@@ -126,6 +157,18 @@ impl RequestBehavior for Request {
         self
     }
 
+    /// Adds an idempotency key to the request.
+    fn with_idempotency_key(mut self, key: impl AsRef<ARID>) -> Self {
+        self.idempotency_key = Some(key.as_ref().clone());
+        self
+    }
+
+    /// Adds a trace context to the request.
+    fn with_trace_context(mut self, context: TraceContext) -> Self {
+        self.trace_context = Some(context);
+        self
+    }
+
     /// Returns the body of the request.
     fn body(&self) -> &Expression {
         &self.body
@@ -145,6 +188,16 @@ impl RequestBehavior for Request {
     fn date(&self) -> Option<&Date> {
         self.date.as_ref()
     }
+
+    /// Returns the idempotency key of the request.
+    fn idempotency_key(&self) -> Option<&ARID> {
+        self.idempotency_key.as_ref()
+    }
+
+    /// Returns the trace context of the request.
+    fn trace_context(&self) -> Option<&TraceContext> {
+        self.trace_context.as_ref()
+    }
 }
 
 impl From<Request> for Expression {
@@ -155,10 +208,15 @@ impl From<Request> for Expression {
 
 impl From<Request> for Envelope {
     fn from(request: Request) -> Self {
-        Envelope::new(CBOR::to_tagged_value(tags::TAG_REQUEST, request.id))
+        let envelope = Envelope::new(CBOR::to_tagged_value(tags::TAG_REQUEST, request.id))
             .add_assertion(known_values::BODY, request.body.into_envelope())
             .add_assertion_if(!request.note.is_empty(), known_values::NOTE, request.note)
             .add_optional_assertion(known_values::DATE, request.date)
+            .add_optional_assertion(IDEMPOTENCY_KEY, request.idempotency_key);
+        match request.trace_context {
+            Some(context) => envelope.add_trace_context(&context),
+            None => envelope,
+        }
     }
 }
 
@@ -174,6 +232,8 @@ impl TryFrom<(Envelope, Option<&Function>)> for Request {
                 .try_into()?,
             note: envelope.extract_object_for_predicate_with_default(known_values::NOTE, "".to_string())?,
             date: envelope.extract_optional_object_for_predicate(known_values::DATE)?,
+            idempotency_key: envelope.extract_optional_object_for_predicate(IDEMPOTENCY_KEY)?,
+            trace_context: envelope.trace_context()?,
         })
     }
 }
@@ -261,4 +321,20 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_request_check_date() -> Result<()> {
+        let request_date = Date::try_from("2024-07-04T11:11:11Z")?;
+        let request = Request::new("test", request_id()).with_date(&request_date);
+        let policy = |now: &str| crate::TimePolicy::new()
+            .with_clock(crate::FixedClock::new(Date::try_from(now).unwrap()))
+            .with_max_age(std::time::Duration::from_secs(60));
+
+        assert!(request.check_date(&policy("2024-07-04T11:11:30Z")).is_ok());
+        assert!(request.check_date(&policy("2024-07-04T11:11:00Z")).is_err());
+        assert!(request.check_date(&policy("2024-07-04T11:13:00Z")).is_err());
+        assert!(Request::new("test", request_id()).check_date(&policy("2024-07-04T11:13:00Z")).is_ok());
+
+        Ok(())
+    }
 }
@@ -1,13 +1,24 @@
 use core::panic;
+use std::time::Duration;
 
 use anyhow::{bail, Error, Result};
 use bc_components::{tags, ARID};
-use dcbor::CBOR;
+use dcbor::prelude::*;
 
 use crate::{known_values, Envelope, EnvelopeEncodable, KnownValue};
 
+use super::{ErrorResponse, TraceContext};
+
+/// The predicate of the assertion on the result of a processing response
+/// giving the number of seconds the client should wait before asking again.
+pub const RETRY_AFTER: &str = "retryAfter";
+
+/// The predicate of the assertion on the result of a processing response
+/// giving the fraction of the work completed.
+pub const PROGRESS: &str = "progress";
+
 #[derive(Debug, Clone, PartialEq)]
-pub struct Response (Result<(ARID, Envelope), (Option<ARID>, Envelope)>);
+pub struct Response (Result<(ARID, Envelope), (Option<ARID>, Envelope)>, Option<TraceContext>);
 
 impl std::fmt::Display for Response {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -38,6 +49,10 @@ impl Envelope {
     pub fn ok() -> Self {
         known_values::OK_VALUE.into_envelope()
     }
+
+    pub fn processing() -> Self {
+        known_values::PROCESSING_VALUE.into_envelope()
+    }
 }
 
 impl Response {
@@ -46,7 +61,43 @@ impl Response {
     //
 
     pub fn new_success(id: impl AsRef<ARID>) -> Self {
-        Self(Ok((id.as_ref().clone(), Envelope::ok())))
+        Self(Ok((id.as_ref().clone(), Envelope::ok())), None)
+    }
+
+    /// A processing response tells the client that the request was accepted
+    /// but has not yet completed, and that it should ask again later.
+    ///
+    /// The result is `'Processing'`, optionally with `"retryAfter"` and
+    /// `"progress"` assertions added by [`Response::with_retry_after`] and
+    /// [`Response::with_progress`].
+    pub fn new_processing(id: impl AsRef<ARID>) -> Self {
+        Self(Ok((id.as_ref().clone(), Envelope::processing())), None)
+    }
+
+    /// Adds a `"retryAfter"` assertion to the result of a processing
+    /// response, telling the client how long to wait before asking again.
+    ///
+    /// The delay is recorded in seconds.
+    pub fn with_retry_after(self, delay: Duration) -> Self {
+        self.with_processing_assertion(RETRY_AFTER, delay.as_secs_f64())
+    }
+
+    /// Adds a `"progress"` assertion to the result of a processing response,
+    /// giving the fraction of the work completed, from 0.0 to 1.0.
+    pub fn with_progress(self, progress: f64) -> Self {
+        self.with_processing_assertion(PROGRESS, progress.clamp(0.0, 1.0))
+    }
+
+    fn with_processing_assertion(mut self, predicate: &str, object: f64) -> Self {
+        match self.0 {
+            Ok((id, result)) if result.subject().as_known_value() == Some(&known_values::PROCESSING_VALUE) => {
+                self.0 = Ok((id, result.add_assertion(predicate, object)));
+                self
+            }
+            _ => {
+                panic!("Cannot add processing assertions to a response that is not processing");
+            }
+        }
     }
 
     //
@@ -54,13 +105,40 @@ impl Response {
     //
 
     pub fn new_failure(id: impl AsRef<ARID>) -> Self {
-        Self(Err((Some(id.as_ref().clone()), Envelope::unknown())))
+        Self(Err((Some(id.as_ref().clone()), Envelope::unknown())), None)
     }
 
     /// An early failure takes place before the message has been decrypted,
     /// and therefore the ID is not known.
     pub fn new_early_failure() -> Self {
-        Self(Err((None, Envelope::unknown())))
+        Self(Err((None, Envelope::unknown())), None)
+    }
+
+    /// Returns this response addressed to the request with the given ID and
+    /// trace context, as when replaying it to a retried request.
+    pub(crate) fn readdressed(self, id: &ARID, trace_context: Option<&TraceContext>) -> Self {
+        let result = match self.0 {
+            Ok((_, result)) => Ok((id.clone(), result)),
+            Err((_, error)) => Err((Some(id.clone()), error)),
+        };
+        Self(result, trace_context.cloned())
+    }
+
+    /// Creates a successful response with the value of `result`, or a failed
+    /// response whose error is the structured [`ErrorResponse`] it converts
+    /// to.
+    ///
+    /// A client can decode the response with
+    /// [`ResponseBehavior::extract_result_as`].
+    pub fn from_result<T, E>(id: impl AsRef<ARID>, result: Result<T, E>) -> Self
+    where
+        T: EnvelopeEncodable,
+        E: Into<ErrorResponse>,
+    {
+        match result {
+            Ok(value) => Self::new_success(id).with_result(value),
+            Err(error) => Self::new_failure(id).with_error(error.into()),
+        }
     }
 }
 
@@ -123,6 +201,66 @@ pub trait ResponseBehavior {
     {
         self.error()?.extract_subject()
     }
+
+    /// Returns `true` if this is a processing response, whose request has not
+    /// yet completed.
+    fn is_processing(&self) -> bool {
+        self.result()
+            .is_ok_and(|result| result.subject().as_known_value() == Some(&known_values::PROCESSING_VALUE))
+    }
+
+    /// Returns how long the server asked the client to wait before asking
+    /// again, if this is a processing response with a valid `"retryAfter"`
+    /// assertion.
+    fn retry_after(&self) -> Option<Duration> {
+        if !self.is_processing() {
+            return None;
+        }
+        let seconds: f64 = self.result().ok()?.extract_object_for_predicate(RETRY_AFTER).ok()?;
+        Duration::try_from_secs_f64(seconds).ok()
+    }
+
+    /// Returns the fraction of the work completed, from 0.0 to 1.0, if this
+    /// is a processing response with a `"progress"` assertion.
+    fn progress(&self) -> Option<f64> {
+        if !self.is_processing() {
+            return None;
+        }
+        let progress: f64 = self.result().ok()?.extract_object_for_predicate(PROGRESS).ok()?;
+        Some(progress.clamp(0.0, 1.0))
+    }
+
+    /// Adds a trace context to the response, normally that of the request it
+    /// answers. See [`TraceContext`].
+    fn with_trace_context(self, context: TraceContext) -> Self;
+
+    /// Returns the trace context of the response.
+    fn trace_context(&self) -> Option<&TraceContext>;
+
+    /// Returns the error value decoded as a structured `ErrorResponse`.
+    ///
+    /// Returns an error if the response is successful, or if the error value
+    /// is not a structured error.
+    fn error_details(&self) -> Result<ErrorResponse> {
+        ErrorResponse::try_from(self.error()?.clone())
+    }
+
+    /// Returns the result decoded as a structured value, or the error decoded
+    /// as an `ErrorResponse` and mapped to the caller's error type by
+    /// `convert`.
+    ///
+    /// Failures to decode either are returned through the error type's
+    /// `From<anyhow::Error>` conversion.
+    fn extract_result_as<T, E>(&self, convert: impl FnOnce(ErrorResponse) -> E) -> Result<T, E>
+    where
+        T: TryFrom<Envelope, Error = Error>,
+        E: From<Error>,
+    {
+        if self.is_err() {
+            return Err(convert(self.error_details()?));
+        }
+        Ok(T::try_from(self.result()?.clone())?)
+    }
 }
 
 impl ResponseBehavior for Response {
@@ -190,11 +328,19 @@ impl ResponseBehavior for Response {
         }
     }
 
+    fn with_trace_context(mut self, context: TraceContext) -> Self {
+        self.1 = Some(context);
+        self
+    }
+
+    fn trace_context(&self) -> Option<&TraceContext> {
+        self.1.as_ref()
+    }
 }
 
 impl From<Response> for Envelope {
     fn from(value: Response) -> Self {
-        match value.0 {
+        let envelope = match value.0 {
             Ok((id, result)) => {
                 Envelope::new(CBOR::to_tagged_value(tags::TAG_RESPONSE, id)).add_assertion(known_values::RESULT, result)
             }
@@ -207,6 +353,10 @@ impl From<Response> for Envelope {
                 }
                 subject.add_assertion(known_values::ERROR, error)
             }
+        };
+        match value.1 {
+            Some(context) => envelope.add_trace_context(&context),
+            None => envelope,
         }
     }
 }
@@ -228,7 +378,7 @@ impl TryFrom<Envelope> for Response {
                 .try_into_expected_tagged_value(tags::TAG_RESPONSE)?
                 .try_into()?;
             let result = envelope.object_for_predicate(known_values::RESULT)?;
-            return Ok(Response(Ok((id, result))));
+            return Ok(Response(Ok((id, result)), envelope.trace_context()?));
         }
 
         if error.is_ok() {
@@ -247,7 +397,7 @@ impl TryFrom<Envelope> for Response {
                 id = Some(id_value.try_into()?);
             }
             let error = envelope.object_for_predicate(known_values::ERROR)?;
-            return Ok(Response(Err((id, error))));
+            return Ok(Response(Err((id, error)), envelope.trace_context()?));
         }
 
         bail!("Invalid response")
@@ -0,0 +1,372 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+use bc_components::ARID;
+use dcbor::{prelude::*, Date};
+
+use crate::{Envelope, EnvelopeError};
+
+use super::{ExpressionBehavior, Request, RequestBehavior, Response, ResponseBehavior};
+
+/// The predicate of the assertion giving the ID of a trace.
+pub const TRACE_ID: &str = "traceID";
+
+/// The predicate of the assertion giving the ID of a span.
+pub const SPAN_ID: &str = "spanID";
+
+/// The predicate of the assertion giving the ID of the parent of a span.
+pub const PARENT_SPAN_ID: &str = "parentSpanID";
+
+/// The identifiers that place a request or response in a distributed trace,
+/// as in the W3C Trace Context used by OpenTelemetry.
+///
+/// A client starts a trace with [`TraceContext::new_root`] and adds it to its
+/// request with [`RequestBehavior::with_trace_context`]. Each party that
+/// makes further requests on the request's behalf gives them a
+/// [`child`](TraceContext::child) of the request's context, and a server
+/// answers with a response carrying the request's context, so that the
+/// envelopes exchanged can be assembled into a [`CallTrace`].
+///
+/// In an envelope, the context is a `"traceID"` assertion, a `"spanID"`
+/// assertion, and a `"parentSpanID"` assertion if the span has a parent, each
+/// with a byte string object.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TraceContext {
+    trace_id: [u8; 16],
+    span_id: [u8; 8],
+    parent_span_id: Option<[u8; 8]>,
+}
+
+impl TraceContext {
+    /// Creates a context with the given identifiers.
+    pub fn new(trace_id: [u8; 16], span_id: [u8; 8], parent_span_id: Option<[u8; 8]>) -> Self {
+        Self { trace_id, span_id, parent_span_id }
+    }
+
+    /// Creates the context of the first span of a new trace, with random
+    /// identifiers.
+    pub fn new_root() -> Self {
+        let mut trace_id = [0; 16];
+        bc_rand::fill_random_data(&mut trace_id);
+        Self { trace_id, span_id: random_span_id(), parent_span_id: None }
+    }
+
+    /// Creates the context of a new span in the same trace, whose parent is
+    /// this span.
+    pub fn child(&self) -> Self {
+        Self { trace_id: self.trace_id, span_id: random_span_id(), parent_span_id: Some(self.span_id) }
+    }
+
+    pub fn trace_id(&self) -> &[u8; 16] {
+        &self.trace_id
+    }
+
+    pub fn span_id(&self) -> &[u8; 8] {
+        &self.span_id
+    }
+
+    pub fn parent_span_id(&self) -> Option<&[u8; 8]> {
+        self.parent_span_id.as_ref()
+    }
+
+    /// Returns the context as the value of a W3C `traceparent` header, so
+    /// that it can be propagated over other transports.
+    ///
+    /// The parent span is not part of the header, and the span is always
+    /// marked as sampled.
+    pub fn to_traceparent(&self) -> String {
+        format!("00-{}-{}-01", hex::encode(self.trace_id), hex::encode(self.span_id))
+    }
+
+    /// Parses the value of a W3C `traceparent` header as the context of a
+    /// span whose children will be created with [`TraceContext::child`].
+    pub fn from_traceparent(traceparent: &str) -> Result<Self> {
+        let parts: Vec<&str> = traceparent.trim().split('-').collect();
+        let [version, trace_id, span_id, _flags] = parts[..] else {
+            bail!(EnvelopeError::InvalidTraceContext);
+        };
+        let (Ok(trace_id), Ok(span_id)) = (hex::decode(trace_id), hex::decode(span_id)) else {
+            bail!(EnvelopeError::InvalidTraceContext);
+        };
+        let (Ok(trace_id), Ok(span_id)) = (trace_id.try_into(), span_id.try_into()) else {
+            bail!(EnvelopeError::InvalidTraceContext);
+        };
+        if version != "00" || trace_id == [0; 16] || span_id == [0; 8] {
+            bail!(EnvelopeError::InvalidTraceContext);
+        }
+        Ok(Self { trace_id, span_id, parent_span_id: None })
+    }
+}
+
+fn random_span_id() -> [u8; 8] {
+    let mut span_id = [0; 8];
+    bc_rand::fill_random_data(&mut span_id);
+    span_id
+}
+
+/// Support for trace context assertions.
+impl Envelope {
+    /// Returns the envelope with the assertions of `context`.
+    pub fn add_trace_context(&self, context: &TraceContext) -> Self {
+        self.add_assertion(TRACE_ID, ByteString::from(context.trace_id))
+            .add_assertion(SPAN_ID, ByteString::from(context.span_id))
+            .add_optional_assertion(
+                PARENT_SPAN_ID,
+                context.parent_span_id.map(ByteString::from),
+            )
+    }
+
+    /// Returns the trace context in the envelope's assertions, or `None` if
+    /// it has no `"traceID"` assertion.
+    pub fn trace_context(&self) -> Result<Option<TraceContext>> {
+        let Some(trace_id) = self.extract_optional_object_for_predicate::<ByteString>(TRACE_ID)? else {
+            return Ok(None);
+        };
+        let span_id: ByteString = self.extract_object_for_predicate(SPAN_ID)?;
+        let parent_span_id: Option<ByteString> = self.extract_optional_object_for_predicate(PARENT_SPAN_ID)?;
+        let invalid = || anyhow::Error::from(EnvelopeError::InvalidTraceContext);
+        Ok(Some(TraceContext {
+            trace_id: trace_id.data().try_into().map_err(|_| invalid())?,
+            span_id: span_id.data().try_into().map_err(|_| invalid())?,
+            parent_span_id: parent_span_id
+                .map(|id| id.data().try_into().map_err(|_| invalid()))
+                .transpose()?,
+        }))
+    }
+}
+
+/// The outcome of a traced request, as recorded in a [`TraceSpan`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpanStatus {
+    /// No response to the request was found.
+    Unset,
+    /// The request succeeded.
+    Ok,
+    /// The request failed.
+    Error,
+}
+
+/// One request in a [`CallTrace`], and the outcome of its response.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceSpan {
+    /// The request's trace context.
+    pub context: TraceContext,
+    /// The name of the function the request called.
+    pub name: String,
+    /// The ID of the request.
+    pub request_id: ARID,
+    /// The date of the request, if it has one.
+    pub start: Option<Date>,
+    /// The outcome of the request's response.
+    pub status: SpanStatus,
+    /// The number of ancestors of the span in the trace.
+    pub depth: usize,
+}
+
+impl TraceSpan {
+    /// The span as a single line of JSON with the field names of an
+    /// OpenTelemetry span, so that a trace can be written as JSON Lines for
+    /// import into tracing tools.
+    ///
+    /// Identifiers are hexadecimal strings, and the start time is in
+    /// nanoseconds since the Unix epoch.
+    pub fn to_json(&self) -> String {
+        let parent = self
+            .context
+            .parent_span_id
+            .map(|id| format!(r#","parentSpanId":"{}""#, hex::encode(id)))
+            .unwrap_or_default();
+        let start = self
+            .start
+            .as_ref()
+            .map(|date| format!(r#","startTimeUnixNano":{}"#, (date.timestamp() * 1e9) as i64))
+            .unwrap_or_default();
+        let status = match self.status {
+            SpanStatus::Unset => "STATUS_CODE_UNSET",
+            SpanStatus::Ok => "STATUS_CODE_OK",
+            SpanStatus::Error => "STATUS_CODE_ERROR",
+        };
+        format!(
+            r#"{{"traceId":"{}","spanId":"{}"{},"name":"{}","requestId":"{}"{},"status":"{}"}}"#,
+            hex::encode(self.context.trace_id),
+            hex::encode(self.context.span_id),
+            parent,
+            self.name.replace('\\', "\\\\").replace('"', "\\\""),
+            self.request_id.hex(),
+            start,
+            status,
+        )
+    }
+}
+
+/// The tree of requests in one trace, assembled from the request and
+/// response envelopes exchanged while handling it.
+#[derive(Debug, Clone)]
+pub struct CallTrace {
+    spans: Vec<TraceSpan>,
+}
+
+impl CallTrace {
+    /// Assembles the trace with the given ID from `envelopes`.
+    ///
+    /// Each request carrying a trace context with the ID becomes a span, and
+    /// each response is matched to its request by request ID. Envelopes that
+    /// are neither, or that belong to other traces, are ignored.
+    pub fn from_envelopes(trace_id: &[u8; 16], envelopes: &[Envelope]) -> Self {
+        let mut requests = Vec::new();
+        let mut statuses = HashMap::new();
+        for envelope in envelopes {
+            let Ok(Some(context)) = envelope.trace_context() else {
+                continue;
+            };
+            if context.trace_id != *trace_id {
+                continue;
+            }
+            if let Ok(request) = Request::try_from(envelope.clone()) {
+                requests.push((context, request));
+            } else if let Ok(response) = Response::try_from(envelope.clone()) {
+                if let Some(id) = response.id() {
+                    let status = if response.is_ok() { SpanStatus::Ok } else { SpanStatus::Error };
+                    statuses.insert(id.clone(), status);
+                }
+            }
+        }
+
+        // Order the spans depth first, each after its parent, with siblings
+        // in the order their requests were given.
+        let mut children: HashMap<Option<[u8; 8]>, Vec<usize>> = HashMap::new();
+        let span_ids: Vec<[u8; 8]> = requests.iter().map(|(context, _)| context.span_id).collect();
+        for (index, (context, _)) in requests.iter().enumerate() {
+            let parent = context.parent_span_id.filter(|parent| span_ids.contains(parent));
+            children.entry(parent).or_default().push(index);
+        }
+        let mut spans = Vec::new();
+        let mut stack: Vec<(usize, usize)> = children
+            .get(&None)
+            .into_iter()
+            .flatten()
+            .rev()
+            .map(|&index| (index, 0))
+            .collect();
+        while let Some((index, depth)) = stack.pop() {
+            let (context, request) = &requests[index];
+            spans.push(TraceSpan {
+                context: context.clone(),
+                name: request.function().named_name().unwrap_or_else(|| request.function().name()),
+                request_id: request.id().clone(),
+                start: request.date().cloned(),
+                status: statuses.get(request.id()).copied().unwrap_or(SpanStatus::Unset),
+                depth,
+            });
+            if let Some(children) = children.get(&Some(context.span_id)) {
+                stack.extend(children.iter().rev().map(|&child| (child, depth + 1)));
+            }
+        }
+        Self { spans }
+    }
+
+    /// The spans of the trace, each after its parent.
+    pub fn spans(&self) -> &[TraceSpan] {
+        &self.spans
+    }
+
+    /// The trace as JSON Lines, one span per line.
+    pub fn to_json_lines(&self) -> String {
+        self.spans.iter().map(|span| span.to_json() + "\n").collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{functions, Response};
+
+    use super::*;
+
+    #[test]
+    fn test_trace_context_round_trip() {
+        let root = TraceContext::new_root();
+        let child = root.child();
+        assert_eq!(child.trace_id(), root.trace_id());
+        assert_eq!(child.parent_span_id(), Some(root.span_id()));
+        assert_ne!(child.span_id(), root.span_id());
+
+        let request = Request::new(functions::ADD, ARID::new()).with_trace_context(child.clone());
+        let parsed = Request::try_from(Envelope::from(request.clone())).unwrap();
+        assert_eq!(parsed, request);
+        assert_eq!(parsed.trace_context(), Some(&child));
+
+        let response = Response::new_failure(ARID::new()).with_error("failed").with_trace_context(root.clone());
+        let parsed = Response::try_from(Envelope::from(response.clone())).unwrap();
+        assert_eq!(parsed, response);
+        assert_eq!(parsed.trace_context(), Some(&root));
+
+        let untraced = Request::new(functions::ADD, ARID::new());
+        assert_eq!(Request::try_from(Envelope::from(untraced)).unwrap().trace_context(), None);
+    }
+
+    #[test]
+    fn test_traceparent() {
+        let traceparent = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+        let context = TraceContext::from_traceparent(traceparent).unwrap();
+        assert_eq!(hex::encode(context.trace_id()), "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(hex::encode(context.span_id()), "00f067aa0ba902b7");
+        assert_eq!(context.to_traceparent(), traceparent);
+        assert_eq!(context.child().to_traceparent()[..36], traceparent[..36]);
+
+        assert!(TraceContext::from_traceparent("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7").is_err());
+        assert!(TraceContext::from_traceparent("00-00000000000000000000000000000000-00f067aa0ba902b7-01").is_err());
+        assert!(TraceContext::from_traceparent("00-4bf92f35-00f067aa0ba902b7-01").is_err());
+        assert!(TraceContext::from_traceparent("ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01").is_err());
+    }
+
+    #[test]
+    fn test_call_trace() {
+        let root = TraceContext::new([1; 16], [1; 8], None);
+        let lookup = root.child();
+        let store = root.child();
+        let audit = store.child();
+
+        let root_request = Request::new("transfer", ARID::new())
+            .with_date(Date::from_timestamp(1.5))
+            .with_trace_context(root.clone());
+        let lookup_request = Request::new("lookup", ARID::new()).with_trace_context(lookup.clone());
+        let store_request = Request::new("store", ARID::new()).with_trace_context(store.clone());
+        let audit_request = Request::new("audit", ARID::new()).with_trace_context(audit.clone());
+        let other_request = Request::new("other", ARID::new()).with_trace_context(TraceContext::new_root());
+
+        // Envelopes in the order they were observed, children before parents.
+        let envelopes: Vec<Envelope> = vec![
+            audit_request.clone().into(),
+            lookup_request.clone().into(),
+            Response::new_success(lookup_request.id()).with_trace_context(lookup.clone()).into(),
+            store_request.clone().into(),
+            Response::new_failure(store_request.id()).with_trace_context(store.clone()).into(),
+            other_request.into(),
+            Envelope::new("unrelated").add_trace_context(&root),
+            root_request.clone().into(),
+            Response::new_success(root_request.id()).with_trace_context(root.clone()).into(),
+        ];
+        let trace = CallTrace::from_envelopes(root.trace_id(), &envelopes);
+        let summary: Vec<_> = trace
+            .spans()
+            .iter()
+            .map(|span| (span.name.as_str(), span.depth, span.status))
+            .collect();
+        assert_eq!(summary, [
+            ("transfer", 0, SpanStatus::Ok),
+            ("lookup", 1, SpanStatus::Ok),
+            ("store", 1, SpanStatus::Error),
+            ("audit", 2, SpanStatus::Unset),
+        ]);
+
+        let json_lines = trace.to_json_lines();
+        let lines: Vec<&str> = json_lines.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], format!(
+            r#"{{"traceId":"{}","spanId":"0101010101010101","name":"transfer","requestId":"{}","startTimeUnixNano":1500000000,"status":"STATUS_CODE_OK"}}"#,
+            "01".repeat(16),
+            root_request.id().hex(),
+        ));
+        assert!(lines[3].contains(&format!(r#""parentSpanId":"{}""#, hex::encode(store.span_id()))));
+    }
+}
@@ -0,0 +1,430 @@
+//! A C ABI for the core envelope operations.
+//!
+//! Envelopes are passed across the boundary as opaque [`BcEnvelope`] handles,
+//! which the caller owns and must release with [`bc_envelope_free`]. Byte
+//! strings returned to the caller are [`BcBuffer`]s, which must be released
+//! with [`bc_buffer_free`]. Handles are immutable: each operation that changes
+//! an envelope returns a new handle through its `out` parameter and leaves its
+//! input unchanged. Handles must not be shared between threads.
+//!
+//! Every fallible function returns a [`BcEnvelopeStatus`]. On failure nothing
+//! is written to the `out` parameter, and [`bc_envelope_last_error`] describes
+//! the failure. The numeric values of the status codes are stable: codes may
+//! be added in later releases, but existing codes will not change meaning.
+//!
+//! Panics are caught at the boundary and reported as
+//! [`BcEnvelopeStatus::Panic`] rather than unwinding into foreign code.
+//!
+//! To link the functions into a C program, build this crate with the `ffi`
+//! feature as a `staticlib` or `cdylib`, for example from a small crate that
+//! depends on it and sets `crate-type` accordingly.
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, CStr, CString},
+    panic::{catch_unwind, AssertUnwindSafe},
+    ptr, slice,
+};
+
+use anyhow::{bail, Error, Result};
+use bc_components::{Digest, DigestProvider, PrivateKeyBase, PublicKeyBase, PublicKeyBaseProvider};
+use bc_ur::UR;
+use dcbor::prelude::*;
+
+use crate::{Envelope, EnvelopeError};
+
+/// The result of an FFI call.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BcEnvelopeStatus {
+    /// The call succeeded.
+    Ok = 0,
+    /// A required pointer argument was null.
+    NullArgument = 1,
+    /// A string argument was not valid UTF-8.
+    InvalidUtf8 = 2,
+    /// Data to be decoded was not a valid envelope, or an envelope did not
+    /// have the form the operation requires.
+    InvalidData = 3,
+    /// A key was not valid.
+    InvalidKey = 4,
+    /// A signature could not be verified.
+    UnverifiedSignature = 5,
+    /// The call panicked.
+    Panic = 6,
+    /// The call failed for another reason, described by
+    /// [`bc_envelope_last_error`].
+    Error = 7,
+}
+
+impl std::fmt::Display for BcEnvelopeStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let description = match self {
+            Self::Ok => "ok",
+            Self::NullArgument => "a required argument was null",
+            Self::InvalidUtf8 => "a string argument was not valid UTF-8",
+            Self::InvalidData => "invalid envelope data",
+            Self::InvalidKey => "invalid key",
+            Self::UnverifiedSignature => "could not verify a signature",
+            Self::Panic => "panicked",
+            Self::Error => "error",
+        };
+        write!(f, "{}", description)
+    }
+}
+
+impl std::error::Error for BcEnvelopeStatus {}
+
+impl BcEnvelopeStatus {
+    fn of(error: &Error) -> Self {
+        if let Some(status) = error.downcast_ref::<Self>() {
+            return *status;
+        }
+        if error.downcast_ref::<dcbor::CBORError>().is_some() {
+            return Self::InvalidData;
+        }
+        match error.downcast_ref::<EnvelopeError>() {
+            Some(EnvelopeError::UnverifiedSignature) => Self::UnverifiedSignature,
+            Some(EnvelopeError::InvalidFormat | EnvelopeError::NotWrapped | EnvelopeError::InvalidDigest) => {
+                Self::InvalidData
+            }
+            _ => Self::Error,
+        }
+    }
+}
+
+/// An opaque handle to an envelope.
+pub struct BcEnvelope(Envelope);
+
+/// A byte string owned by the caller, which must release it with
+/// [`bc_buffer_free`].
+#[repr(C)]
+#[derive(Debug)]
+pub struct BcBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+impl BcBuffer {
+    fn new(data: Vec<u8>) -> Self {
+        let data = Box::into_raw(data.into_boxed_slice());
+        Self { data: data as *mut u8, len: data.len() }
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<CString> = RefCell::new(CString::default());
+}
+
+fn set_last_error(message: String) {
+    let message = CString::new(message.replace('\0', "")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = message);
+}
+
+/// Runs `body`, converting its errors and panics to status codes.
+fn run(body: impl FnOnce() -> Result<()>) -> BcEnvelopeStatus {
+    match catch_unwind(AssertUnwindSafe(body)) {
+        Ok(Ok(())) => {
+            set_last_error(String::new());
+            BcEnvelopeStatus::Ok
+        }
+        Ok(Err(error)) => {
+            set_last_error(error.to_string());
+            BcEnvelopeStatus::of(&error)
+        }
+        Err(panic) => {
+            let message = panic
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+            set_last_error(format!("panicked: {}", message));
+            BcEnvelopeStatus::Panic
+        }
+    }
+}
+
+unsafe fn envelope_arg<'a>(envelope: *const BcEnvelope) -> Result<&'a Envelope> {
+    match envelope.as_ref() {
+        Some(envelope) => Ok(&envelope.0),
+        None => bail!(BcEnvelopeStatus::NullArgument),
+    }
+}
+
+unsafe fn str_arg<'a>(text: *const c_char) -> Result<&'a str> {
+    if text.is_null() {
+        bail!(BcEnvelopeStatus::NullArgument);
+    }
+    CStr::from_ptr(text).to_str().map_err(|_| BcEnvelopeStatus::InvalidUtf8.into())
+}
+
+unsafe fn bytes_arg<'a>(data: *const u8, len: usize) -> Result<&'a [u8]> {
+    if len == 0 {
+        return Ok(&[]);
+    }
+    if data.is_null() {
+        bail!(BcEnvelopeStatus::NullArgument);
+    }
+    Ok(slice::from_raw_parts(data, len))
+}
+
+unsafe fn put_envelope(out: *mut *mut BcEnvelope, envelope: Envelope) -> Result<()> {
+    if out.is_null() {
+        bail!(BcEnvelopeStatus::NullArgument);
+    }
+    *out = Box::into_raw(Box::new(BcEnvelope(envelope)));
+    Ok(())
+}
+
+unsafe fn put_buffer(out: *mut BcBuffer, data: Vec<u8>) -> Result<()> {
+    if out.is_null() {
+        bail!(BcEnvelopeStatus::NullArgument);
+    }
+    *out = BcBuffer::new(data);
+    Ok(())
+}
+
+/// The UR type of a private key base.
+const PRIVATE_KEY_BASE_UR_TYPE: &str = "crypto-prvkeys";
+
+/// Decodes a private key base from its tagged CBOR encoding or its UR
+/// string.
+///
+/// The UR type is checked directly, so that callers need not register the
+/// tags.
+fn private_key_arg(data: &[u8]) -> Result<PrivateKeyBase> {
+    let private_key = match std::str::from_utf8(data) {
+        Ok(ur) if ur.starts_with("ur:") => UR::from_ur_string(ur)
+            .ok()
+            .filter(|ur| ur.ur_type_str() == PRIVATE_KEY_BASE_UR_TYPE)
+            .and_then(|ur| PrivateKeyBase::from_untagged_cbor(ur.cbor()).ok()),
+        _ => CBOR::try_from_data(data).ok().and_then(|cbor| PrivateKeyBase::try_from(cbor).ok()),
+    };
+    match private_key {
+        Some(private_key) if !private_key.data().is_empty() => Ok(private_key),
+        _ => bail!(BcEnvelopeStatus::InvalidKey),
+    }
+}
+
+fn public_key_arg(data: &[u8]) -> Result<PublicKeyBase> {
+    let Ok(cbor) = CBOR::try_from_data(data) else {
+        bail!(BcEnvelopeStatus::InvalidKey);
+    };
+    PublicKeyBase::try_from(cbor).map_err(|_| BcEnvelopeStatus::InvalidKey.into())
+}
+
+/// Returns a description of the last failure on the calling thread, or an
+/// empty string if the last call succeeded.
+///
+/// The string is owned by the library and is valid until the next call on the
+/// same thread.
+#[no_mangle]
+pub extern "C" fn bc_envelope_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ptr())
+}
+
+/// Creates an envelope whose subject is a UTF-8 string.
+///
+/// # Safety
+///
+/// `text` must be a valid NUL-terminated string, and `out` must be valid for
+/// writing a handle.
+#[no_mangle]
+pub unsafe extern "C" fn bc_envelope_new_string(text: *const c_char, out: *mut *mut BcEnvelope) -> BcEnvelopeStatus {
+    run(|| put_envelope(out, Envelope::new(str_arg(text)?)))
+}
+
+/// Creates an envelope whose subject is a byte string.
+///
+/// # Safety
+///
+/// `data` must be valid for reading `len` bytes, and `out` must be valid for
+/// writing a handle.
+#[no_mangle]
+pub unsafe extern "C" fn bc_envelope_new_bytes(data: *const u8, len: usize, out: *mut *mut BcEnvelope) -> BcEnvelopeStatus {
+    run(|| put_envelope(out, Envelope::new(ByteString::from(bytes_arg(data, len)?))))
+}
+
+/// Releases an envelope handle. Does nothing if `envelope` is null.
+///
+/// # Safety
+///
+/// `envelope` must be null or a handle returned by this library that has not
+/// already been released.
+#[no_mangle]
+pub unsafe extern "C" fn bc_envelope_free(envelope: *mut BcEnvelope) {
+    if !envelope.is_null() {
+        drop(Box::from_raw(envelope));
+    }
+}
+
+/// Releases a buffer returned by this library. Does nothing if its data is
+/// null.
+///
+/// # Safety
+///
+/// `buffer` must have been returned by this library and not already been
+/// released.
+#[no_mangle]
+pub unsafe extern "C" fn bc_buffer_free(buffer: BcBuffer) {
+    if !buffer.data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(buffer.data, buffer.len)));
+    }
+}
+
+/// Adds an assertion with the given predicate and object envelopes.
+///
+/// # Safety
+///
+/// `envelope`, `predicate`, and `object` must be valid handles, and `out` must
+/// be valid for writing a handle.
+#[no_mangle]
+pub unsafe extern "C" fn bc_envelope_add_assertion(
+    envelope: *const BcEnvelope,
+    predicate: *const BcEnvelope,
+    object: *const BcEnvelope,
+    out: *mut *mut BcEnvelope,
+) -> BcEnvelopeStatus {
+    run(|| {
+        let assertion = Envelope::new_assertion(envelope_arg(predicate)?.clone(), envelope_arg(object)?.clone());
+        put_envelope(out, envelope_arg(envelope)?.add_assertion_envelope(assertion)?)
+    })
+}
+
+/// Adds an assertion whose predicate and object are UTF-8 strings.
+///
+/// # Safety
+///
+/// `envelope` must be a valid handle, `predicate` and `object` must be valid
+/// NUL-terminated strings, and `out` must be valid for writing a handle.
+#[no_mangle]
+pub unsafe extern "C" fn bc_envelope_add_string_assertion(
+    envelope: *const BcEnvelope,
+    predicate: *const c_char,
+    object: *const c_char,
+    out: *mut *mut BcEnvelope,
+) -> BcEnvelopeStatus {
+    run(|| put_envelope(out, envelope_arg(envelope)?.add_assertion(str_arg(predicate)?, str_arg(object)?)))
+}
+
+/// Writes the tagged CBOR encoding of the public key for `private_key`, for
+/// use with [`bc_envelope_verify`].
+///
+/// `private_key` is the tagged CBOR encoding of a private key base, or its UR
+/// string (`ur:crypto-prvkeys/...`) without a terminating NUL.
+///
+/// # Safety
+///
+/// `private_key` must be valid for reading `len` bytes, and `out` must be
+/// valid for writing a buffer.
+#[no_mangle]
+pub unsafe extern "C" fn bc_envelope_public_key(private_key: *const u8, len: usize, out: *mut BcBuffer) -> BcEnvelopeStatus {
+    run(|| {
+        let private_key = private_key_arg(bytes_arg(private_key, len)?)?;
+        put_buffer(out, private_key.public_key_base().to_cbor_data())
+    })
+}
+
+/// Wraps the envelope and signs it with `private_key`, as [`Envelope::sign`]
+/// does.
+///
+/// `private_key` is encoded as for [`bc_envelope_public_key`].
+///
+/// # Safety
+///
+/// `envelope` must be a valid handle, `private_key` must be valid for reading
+/// `len` bytes, and `out` must be valid for writing a handle.
+#[no_mangle]
+pub unsafe extern "C" fn bc_envelope_sign(
+    envelope: *const BcEnvelope,
+    private_key: *const u8,
+    len: usize,
+    out: *mut *mut BcEnvelope,
+) -> BcEnvelopeStatus {
+    run(|| {
+        let envelope = envelope_arg(envelope)?;
+        let private_key = private_key_arg(bytes_arg(private_key, len)?)?;
+        put_envelope(out, envelope.sign(&private_key))
+    })
+}
+
+/// Verifies the signature on a signed envelope with the tagged CBOR encoding
+/// of a public key, and unwraps it, as [`Envelope::verify`] does.
+///
+/// # Safety
+///
+/// `envelope` must be a valid handle, `public_key` must be valid for reading
+/// `len` bytes, and `out` must be valid for writing a handle.
+#[no_mangle]
+pub unsafe extern "C" fn bc_envelope_verify(
+    envelope: *const BcEnvelope,
+    public_key: *const u8,
+    len: usize,
+    out: *mut *mut BcEnvelope,
+) -> BcEnvelopeStatus {
+    run(|| {
+        let envelope = envelope_arg(envelope)?;
+        let public_key = public_key_arg(bytes_arg(public_key, len)?)?;
+        put_envelope(out, envelope.verify(&public_key)?)
+    })
+}
+
+/// Writes the 32-byte digest of the envelope to `out`.
+///
+/// # Safety
+///
+/// `envelope` must be a valid handle, and `out` must be valid for writing 32
+/// bytes.
+#[no_mangle]
+pub unsafe extern "C" fn bc_envelope_digest(envelope: *const BcEnvelope, out: *mut u8) -> BcEnvelopeStatus {
+    run(|| {
+        let digest = envelope_arg(envelope)?.digest();
+        if out.is_null() {
+            bail!(BcEnvelopeStatus::NullArgument);
+        }
+        ptr::copy_nonoverlapping(digest.data().as_ptr(), out, Digest::DIGEST_SIZE);
+        Ok(())
+    })
+}
+
+/// Elides the element of the envelope with the 32-byte digest `target`, as
+/// [`Envelope::elide_removing_target`] does.
+///
+/// # Safety
+///
+/// `envelope` must be a valid handle, `target` must be valid for reading 32
+/// bytes, and `out` must be valid for writing a handle.
+#[no_mangle]
+pub unsafe extern "C" fn bc_envelope_elide_removing(
+    envelope: *const BcEnvelope,
+    target: *const u8,
+    out: *mut *mut BcEnvelope,
+) -> BcEnvelopeStatus {
+    run(|| {
+        let envelope = envelope_arg(envelope)?;
+        let target = Digest::from_data_ref(bytes_arg(target, Digest::DIGEST_SIZE)?)?;
+        put_envelope(out, envelope.elide_removing_target(&target))
+    })
+}
+
+/// Writes the tagged CBOR encoding of the envelope.
+///
+/// # Safety
+///
+/// `envelope` must be a valid handle, and `out` must be valid for writing a
+/// buffer.
+#[no_mangle]
+pub unsafe extern "C" fn bc_envelope_to_cbor(envelope: *const BcEnvelope, out: *mut BcBuffer) -> BcEnvelopeStatus {
+    run(|| put_buffer(out, envelope_arg(envelope)?.tagged_cbor_data()))
+}
+
+/// Decodes an envelope from its tagged CBOR encoding.
+///
+/// # Safety
+///
+/// `data` must be valid for reading `len` bytes, and `out` must be valid for
+/// writing a handle.
+#[no_mangle]
+pub unsafe extern "C" fn bc_envelope_from_cbor(data: *const u8, len: usize, out: *mut *mut BcEnvelope) -> BcEnvelopeStatus {
+    run(|| put_envelope(out, Envelope::from_tagged_cbor_data(bytes_arg(data, len)?)?))
+}
@@ -0,0 +1,98 @@
+//! Test Vectors 1.0.
+//!
+//! Canonical example envelopes, constructed deterministically so that their
+//! digests and encodings are the same on every platform and in every release.
+//! Downstream crates and implementations in other languages can assert against
+//! these envelopes without copying their encodings into their own test suites.
+//!
+//! The envelopes and their digests are frozen: a release that changes any of
+//! them is a breaking change, and new examples are added as a new version of
+//! the test vectors rather than by modifying these.
+//!
+//! For larger families of related envelopes, such as credentials issued to
+//! several holders and presented with varying redactions, use a
+//! [`ScenarioBuilder`].
+
+use std::{cell::RefCell, rc::Rc};
+
+use bc_components::{PrivateKeyBase, SigningOptions, ARID};
+use bc_rand::make_fake_random_number_generator;
+use dcbor::{prelude::*, Date};
+
+use crate::{
+    extension::known_values,
+    Envelope,
+    ExpressionBehavior,
+    Request,
+    RequestBehavior,
+    Response,
+    ResponseBehavior,
+};
+
+pub mod scenario;
+pub use scenario::{Scenario, ScenarioBuilder, ScenarioCredential, ScenarioIssuer, ScenarioPresentation};
+
+/// The version of the test vectors provided by this module.
+pub const TEST_VECTORS_VERSION: &str = "1.0";
+
+/// The private key of Alice, who signs the example credential.
+pub fn alice_private_key() -> PrivateKeyBase {
+    PrivateKeyBase::from_data(hex::decode("82f32c855d3d542256180810797e0073").unwrap())
+}
+
+/// An example credential issued to James Maxwell, wrapped and signed by Alice.
+///
+/// The Schnorr signature is made using a fake random number generator, so
+/// the signature is deterministic.
+pub fn alice_credential() -> Envelope {
+    let rng = Rc::new(RefCell::new(make_fake_random_number_generator()));
+    let options = SigningOptions::Schnorr { rng };
+    Envelope::new(ARID::from_hex("4676635a6e6068c2ef3ffd8ff726dd401fd341036e920f136a1d8af5e829496d"))
+        .add_assertion(known_values::IS_A, "Certificate of Completion")
+        .add_assertion(known_values::ISSUER, "Example Electrical Engineering Board")
+        .add_assertion(known_values::CONTROLLER, "Example Electrical Engineering Board")
+        .add_assertion("firstName", "James")
+        .add_assertion("lastName", "Maxwell")
+        .add_assertion("issueDate", Date::from_string("2020-01-01").unwrap())
+        .add_assertion("expirationDate", Date::from_string("2028-01-01").unwrap())
+        .add_assertion("photo", "This is James Maxwell's photo.")
+        .add_assertion("certificateNumber", "123-456-789")
+        .add_assertion("subject", "RF and Microwave Engineering")
+        .add_assertion("continuingEducationUnits", 1)
+        .add_assertion("professionalDevelopmentHours", 15)
+        .add_assertion("topics", vec!["Subject 1", "Subject 2"].to_cbor())
+        .wrap_envelope()
+        .add_signature_opt(&alice_private_key(), Some(options), None)
+        .add_assertion(known_values::NOTE, "Signed by Example Electrical Engineering Board")
+}
+
+/// An example backup of a seed, with its creation date, name, and note.
+pub fn seed_backup() -> Envelope {
+    Envelope::new(CBOR::to_byte_string(hex::decode("82f32c855d3d542256180810797e0073").unwrap()))
+        .add_assertion(known_values::IS_A, known_values::SEED_TYPE)
+        .add_assertion(known_values::DATE, Date::from_string("2021-02-24").unwrap())
+        .add_assertion(known_values::NAME, "Alice's Seed")
+        .add_assertion(known_values::NOTE, "This is the note.")
+}
+
+/// The ID shared by the example request and response.
+pub fn request_id() -> ARID {
+    ARID::from_hex("c66be27dbad7cd095ca77647406d07976dc0f35f0d4d654bb0e96dd227a1e9fc")
+}
+
+/// An example request to call the function `"test"` with two parameters.
+pub fn request() -> Envelope {
+    Request::new("test", request_id())
+        .with_parameter("param1", 42)
+        .with_parameter("param2", "hello")
+        .with_note("This should be a simple test.")
+        .with_date(Date::from_string("2024-07-04T11:11:11Z").unwrap())
+        .into()
+}
+
+/// An example successful response to [`request`].
+pub fn response() -> Envelope {
+    Response::new_success(request_id())
+        .with_result("Hello, world!")
+        .into()
+}
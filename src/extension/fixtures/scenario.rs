@@ -0,0 +1,332 @@
+use std::{cell::RefCell, collections::HashSet, rc::Rc};
+
+use anyhow::{bail, Result};
+use bc_components::{Digest, DigestProvider, PrivateKeyBase, PublicKeyBase, PublicKeyBaseProvider, SigningOptions, ARID};
+use bc_rand::make_fake_random_number_generator;
+use dcbor::{prelude::*, Date};
+
+use crate::{extension::known_values, Envelope};
+
+/// An issuer in a [`Scenario`], with its keys and its public document.
+#[derive(Debug, Clone)]
+pub struct ScenarioIssuer {
+    name: String,
+    private_key: PrivateKeyBase,
+    document: Envelope,
+}
+
+impl ScenarioIssuer {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn private_key(&self) -> &PrivateKeyBase {
+        &self.private_key
+    }
+
+    pub fn public_key(&self) -> PublicKeyBase {
+        self.private_key.public_key_base()
+    }
+
+    /// The issuer's public document: its ID, with its name and public key.
+    pub fn document(&self) -> &Envelope {
+        &self.document
+    }
+}
+
+/// A credential in a [`Scenario`], issued to a holder and signed by its
+/// issuer.
+///
+/// The credential binds its holder's public key with a `'holder'` assertion,
+/// so that the holder can present it with [`present`](crate::present).
+#[derive(Debug, Clone)]
+pub struct ScenarioCredential {
+    issuer: String,
+    holder: String,
+    holder_private_key: PrivateKeyBase,
+    kind: String,
+    claims: Vec<String>,
+    envelope: Envelope,
+}
+
+impl ScenarioCredential {
+    /// The name of the issuer.
+    pub fn issuer(&self) -> &str {
+        &self.issuer
+    }
+
+    /// The name of the holder.
+    pub fn holder(&self) -> &str {
+        &self.holder
+    }
+
+    /// The holder's private key, derived from the scenario's seed and the
+    /// holder's name.
+    pub fn holder_private_key(&self) -> &PrivateKeyBase {
+        &self.holder_private_key
+    }
+
+    /// The holder's public key, which the credential binds.
+    pub fn holder_public_key(&self) -> PublicKeyBase {
+        self.holder_private_key.public_key_base()
+    }
+
+    /// The type of the credential, given by its `isA` assertion.
+    pub fn kind(&self) -> &str {
+        &self.kind
+    }
+
+    /// The predicates of the credential's claims, in the order given.
+    pub fn claims(&self) -> &[String] {
+        &self.claims
+    }
+
+    /// The signed credential.
+    pub fn envelope(&self) -> &Envelope {
+        &self.envelope
+    }
+}
+
+/// A presentation in a [`Scenario`]: a credential with some of its claims
+/// elided.
+#[derive(Debug, Clone)]
+pub struct ScenarioPresentation {
+    credential: usize,
+    revealed: Vec<String>,
+    envelope: Envelope,
+}
+
+impl ScenarioPresentation {
+    /// The index of the presented credential in [`Scenario::credentials`].
+    pub fn credential(&self) -> usize {
+        self.credential
+    }
+
+    /// The predicates of the claims revealed.
+    pub fn revealed(&self) -> &[String] {
+        &self.revealed
+    }
+
+    /// The presented credential, whose issuer's signature still verifies.
+    pub fn envelope(&self) -> &Envelope {
+        &self.envelope
+    }
+}
+
+/// A family of related envelopes for integration tests, built by a
+/// [`ScenarioBuilder`].
+#[derive(Debug, Clone)]
+pub struct Scenario {
+    issuers: Vec<ScenarioIssuer>,
+    credentials: Vec<ScenarioCredential>,
+    presentations: Vec<ScenarioPresentation>,
+}
+
+impl Scenario {
+    pub fn issuers(&self) -> &[ScenarioIssuer] {
+        &self.issuers
+    }
+
+    /// The issuer with the given name, if there is one.
+    pub fn issuer(&self, name: &str) -> Option<&ScenarioIssuer> {
+        self.issuers.iter().find(|issuer| issuer.name == name)
+    }
+
+    pub fn credentials(&self) -> &[ScenarioCredential] {
+        &self.credentials
+    }
+
+    /// The credentials issued to the given holder.
+    pub fn credentials_of<'a>(&'a self, holder: &'a str) -> impl Iterator<Item = &'a ScenarioCredential> {
+        self.credentials.iter().filter(move |credential| credential.holder == holder)
+    }
+
+    pub fn presentations(&self) -> &[ScenarioPresentation] {
+        &self.presentations
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CredentialSpec {
+    issuer: String,
+    holder: String,
+    kind: String,
+    claims: Vec<(String, CBOR)>,
+}
+
+/// Builds a [`Scenario`] of issuers, the credentials they issue to holders,
+/// and presentations of those credentials with varying redactions.
+///
+/// ```ignore
+/// let scenario = ScenarioBuilder::new("degrees")
+///     .issuer("Example University")
+///     .credential("Example University", "Alice", "Degree", [
+///         ("degree", "BSc".to_cbor()),
+///         ("year", 2020.to_cbor()),
+///     ])
+///     .presentation(0, &["degree"])
+///     .build()?;
+/// ```
+///
+/// Everything in a scenario is derived from its seed: keys, IDs, and
+/// signatures are the same every time a scenario with the same seed and
+/// description is built, on every platform. Projects that build the same
+/// scenario can therefore exchange its envelopes, or assert against their
+/// digests. Unlike the rest of the test vectors, the envelopes a scenario
+/// produces are not frozen, and may change between releases.
+#[derive(Debug, Clone)]
+pub struct ScenarioBuilder {
+    seed: String,
+    date: Date,
+    issuers: Vec<String>,
+    credentials: Vec<CredentialSpec>,
+    presentations: Vec<(usize, Vec<String>)>,
+}
+
+impl ScenarioBuilder {
+    /// Creates a builder for a scenario derived from `seed`.
+    pub fn new(seed: impl Into<String>) -> Self {
+        Self {
+            seed: seed.into(),
+            date: Date::from_string("2024-01-01").unwrap(),
+            issuers: Vec::new(),
+            credentials: Vec::new(),
+            presentations: Vec::new(),
+        }
+    }
+
+    /// Sets the issue date of the credentials, which is 2024-01-01 by
+    /// default.
+    pub fn with_date(mut self, date: Date) -> Self {
+        self.date = date;
+        self
+    }
+
+    /// Adds an issuer with the given name.
+    pub fn issuer(mut self, name: impl Into<String>) -> Self {
+        self.issuers.push(name.into());
+        self
+    }
+
+    /// Adds a credential of type `kind` issued by the named issuer to
+    /// `holder`, with a claim for each predicate and value.
+    pub fn credential<'a>(
+        mut self,
+        issuer: impl Into<String>,
+        holder: impl Into<String>,
+        kind: impl Into<String>,
+        claims: impl IntoIterator<Item = (&'a str, CBOR)>,
+    ) -> Self {
+        self.credentials.push(CredentialSpec {
+            issuer: issuer.into(),
+            holder: holder.into(),
+            kind: kind.into(),
+            claims: claims.into_iter().map(|(predicate, value)| (predicate.to_string(), value)).collect(),
+        });
+        self
+    }
+
+    /// Adds a presentation of the credential at index `credential`, in the
+    /// order credentials were added, revealing only the claims with the
+    /// given predicates.
+    pub fn presentation(mut self, credential: usize, revealing: &[&str]) -> Self {
+        self.presentations.push((credential, revealing.iter().map(|predicate| predicate.to_string()).collect()));
+        self
+    }
+
+    /// Adds a presentation of the credential at index `credential` for each
+    /// of its claims, revealing only that claim, and one revealing none of
+    /// its claims.
+    pub fn presentation_per_claim(mut self, credential: usize) -> Self {
+        self.presentations.push((credential, Vec::new()));
+        if let Some(spec) = self.credentials.get(credential) {
+            let presentations: Vec<_> = spec.claims.iter().map(|(predicate, _)| (credential, vec![predicate.clone()])).collect();
+            self.presentations.extend(presentations);
+        }
+        self
+    }
+
+    /// Builds the scenario.
+    ///
+    /// Returns an error if a credential names an issuer that was not added,
+    /// or a presentation names a credential or claim that was not added.
+    pub fn build(&self) -> Result<Scenario> {
+        let issuers: Vec<ScenarioIssuer> = self.issuers.iter().map(|name| self.build_issuer(name)).collect();
+        let credentials = self
+            .credentials
+            .iter()
+            .map(|spec| {
+                let Some(issuer) = issuers.iter().find(|issuer| issuer.name == spec.issuer) else {
+                    bail!("unknown issuer {:?}", spec.issuer);
+                };
+                Ok(self.build_credential(issuer, spec))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let presentations = self
+            .presentations
+            .iter()
+            .map(|(index, revealed)| {
+                let Some(credential) = credentials.get(*index) else {
+                    bail!("unknown credential {}", index);
+                };
+                Self::build_presentation(*index, credential, revealed)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Scenario { issuers, credentials, presentations })
+    }
+
+    fn derive(&self, role: &str, name: &str) -> Digest {
+        Digest::from_image(format!("{}/{}/{}", self.seed, role, name))
+    }
+
+    fn build_issuer(&self, name: &str) -> ScenarioIssuer {
+        let private_key = PrivateKeyBase::from_data(&self.derive("key", name).data()[..16]);
+        let document = Envelope::new(ARID::from_data(*self.derive("issuer", name).data()))
+            .add_assertion(known_values::NAME, name)
+            .add_assertion(known_values::KEY, private_key.public_key_base());
+        ScenarioIssuer { name: name.to_string(), private_key, document }
+    }
+
+    fn build_credential(&self, issuer: &ScenarioIssuer, spec: &CredentialSpec) -> ScenarioCredential {
+        let id = self.derive("credential", &format!("{}/{}/{}", spec.issuer, spec.holder, spec.kind));
+        let holder_private_key = PrivateKeyBase::from_data(&self.derive("holder-key", &spec.holder).data()[..16]);
+        let credential = spec.claims.iter().fold(
+            Envelope::new(ARID::from_data(*id.data()))
+                .add_assertion(known_values::IS_A, spec.kind.as_str())
+                .add_assertion(known_values::ISSUER, issuer.document.subject())
+                .add_assertion(known_values::HOLDER, holder_private_key.public_key_base())
+                .add_assertion("issueDate", self.date.clone()),
+            |credential, (predicate, value)| credential.add_assertion(predicate.as_str(), value.clone()),
+        );
+        // Schnorr signatures made with a fake random number generator are
+        // deterministic.
+        let rng = Rc::new(RefCell::new(make_fake_random_number_generator()));
+        let envelope = credential
+            .wrap_envelope()
+            .add_signature_opt(&issuer.private_key, Some(SigningOptions::Schnorr { rng }), None);
+        ScenarioCredential {
+            issuer: spec.issuer.clone(),
+            holder: spec.holder.clone(),
+            holder_private_key,
+            kind: spec.kind.clone(),
+            claims: spec.claims.iter().map(|(predicate, _)| predicate.clone()).collect(),
+            envelope,
+        }
+    }
+
+    fn build_presentation(index: usize, credential: &ScenarioCredential, revealed: &[String]) -> Result<ScenarioPresentation> {
+        if let Some(unknown) = revealed.iter().find(|predicate| !credential.claims.contains(predicate)) {
+            bail!("unknown claim {:?}", unknown);
+        }
+        let content = credential.envelope.subject().unwrap_envelope()?;
+        let mut hidden = HashSet::new();
+        for predicate in credential.claims.iter().filter(|predicate| !revealed.contains(predicate)) {
+            hidden.insert(content.assertion_with_predicate(predicate.as_str())?.digest().into_owned());
+        }
+        Ok(ScenarioPresentation {
+            credential: index,
+            revealed: revealed.to_vec(),
+            envelope: credential.envelope.elide_removing_set(&hidden),
+        })
+    }
+}
@@ -0,0 +1,53 @@
+use anyhow::Result;
+use bc_components::SymmetricKey;
+use dcbor::prelude::*;
+
+use crate::Envelope;
+
+/// The predicate of the assertion identifying the content key that encrypted
+/// the subject of an envelope, so that the receiver's [`KeyProvider`] can
+/// supply it again.
+pub const CONTENT_KEY_ID: &str = "contentKeyID";
+
+/// A source of per-message content keys, such as a ratchet or an MLS group.
+///
+/// Secure messaging systems derive a fresh key for every message and retire
+/// it once the message has been read. Implementing this trait lets them use
+/// envelopes as their message container: the sender calls
+/// [`next_content_key`](KeyProvider::next_content_key) for each message it
+/// encrypts, and the receiver calls
+/// [`content_key`](KeyProvider::content_key) with the identifier recorded in
+/// the message.
+pub trait KeyProvider {
+    /// Returns the key for the next message to be sent, and the identifier
+    /// that the receiver passes to [`content_key`](KeyProvider::content_key)
+    /// to obtain the same key, such as an epoch and generation.
+    fn next_content_key(&mut self) -> Result<(Vec<u8>, SymmetricKey)>;
+
+    /// Returns the key for a received message with the given identifier.
+    ///
+    /// Returns an error if the key is unknown or has already been used.
+    fn content_key(&mut self, key_id: &[u8]) -> Result<SymmetricKey>;
+}
+
+/// Support for encrypting with keys from a [`KeyProvider`].
+impl Envelope {
+    /// Returns a new envelope with its subject encrypted with the next key from
+    /// `provider`, and a `contentKeyID` assertion identifying the key.
+    pub fn encrypt_subject_with_provider(&self, provider: &mut dyn KeyProvider) -> Result<Self> {
+        let (key_id, key) = provider.next_content_key()?;
+        Ok(self
+            .encrypt_subject(&key)?
+            .add_assertion(CONTENT_KEY_ID, ByteString::from(key_id)))
+    }
+
+    /// Returns a new envelope with its subject decrypted with the key that
+    /// `provider` supplies for its `contentKeyID` assertion, which is
+    /// removed.
+    pub fn decrypt_subject_with_provider(&self, provider: &mut dyn KeyProvider) -> Result<Self> {
+        let key_id: ByteString = self.extract_object_for_predicate(CONTENT_KEY_ID)?;
+        let key = provider.content_key(key_id.data())?;
+        let decrypted = self.decrypt_subject(&key)?;
+        Ok(decrypted.remove_assertion(Envelope::new_assertion(CONTENT_KEY_ID, key_id)))
+    }
+}
@@ -0,0 +1,126 @@
+use crate::{Envelope, EnvelopeError};
+
+use super::KnownValue;
+
+/// An enum whose variants are each represented by a known value.
+///
+/// Implemented by enums declared with [`known_value_enum!`](crate::known_value_enum),
+/// which are useful for status-like assertions whose objects come from a
+/// small, fixed set of known values.
+pub trait KnownValueEnum: Sized + Copy + 'static {
+    /// Every variant of the enum, in declaration order.
+    const VARIANTS: &'static [Self];
+
+    /// Returns the known value representing this variant.
+    fn known_value(&self) -> KnownValue;
+
+    /// Returns the variant represented by `known_value`, if any.
+    fn from_known_value(known_value: &KnownValue) -> Option<Self> {
+        Self::VARIANTS
+            .iter()
+            .copied()
+            .find(|variant| variant.known_value().value() == known_value.value())
+    }
+
+    /// Returns the variant represented by the known value that is the
+    /// subject of `envelope`.
+    ///
+    /// Returns an error if the subject is not a known value, or is not one of
+    /// the enum's known values.
+    fn from_envelope(envelope: &Envelope) -> Result<Self, EnvelopeError> {
+        let subject = envelope.subject();
+        let known_value = subject.as_known_value().ok_or(EnvelopeError::NotKnownValue)?;
+        Self::from_known_value(known_value).ok_or(EnvelopeError::UnexpectedKnownValue(known_value.value()))
+    }
+}
+
+/// A macro that declares an enum whose variants are represented by known
+/// values.
+///
+/// Each variant is given the value of its known value, and optionally the
+/// name under which it is formatted, which defaults to the variant's name.
+/// The enum derives `Debug`, `Clone`, `Copy`, `PartialEq`, `Eq`, and `Hash`,
+/// and implements [`KnownValueEnum`], `EnvelopeEncodable`, and
+/// `TryFrom<Envelope>`.
+///
+/// ```
+/// use bc_envelope::prelude::*;
+///
+/// known_value_enum! {
+///     pub enum Status {
+///         Ok = 103 => "OK",
+///         Processing = 104,
+///     }
+/// }
+///
+/// assert_eq!(KnownValue::from(Status::Ok).name(), "OK");
+/// assert_eq!(KnownValue::from(Status::Processing).value(), 104);
+///
+/// let envelope = Envelope::new("Request").add_assertion("status", Status::Processing);
+/// let status = envelope.object_for_predicate("status").unwrap();
+/// assert_eq!(Status::try_from(status).unwrap(), Status::Processing);
+/// ```
+#[macro_export]
+macro_rules! known_value_enum {
+    (@name $variant:ident) => {
+        stringify!($variant)
+    };
+    (@name $variant:ident $name:literal) => {
+        $name
+    };
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $enum_name:ident {
+            $(
+                $(#[$variant_meta:meta])*
+                $variant:ident = $value:literal $(=> $name:literal)?
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        $vis enum $enum_name {
+            $(
+                $(#[$variant_meta])*
+                $variant,
+            )*
+        }
+
+        impl $crate::extension::known_values::KnownValueEnum for $enum_name {
+            const VARIANTS: &'static [Self] = &[$(Self::$variant),*];
+
+            fn known_value(&self) -> $crate::extension::known_values::KnownValue {
+                match self {
+                    $(
+                        Self::$variant => $crate::extension::known_values::KnownValue::new_with_static_name(
+                            $value,
+                            $crate::known_value_enum!(@name $variant $($name)?),
+                        ),
+                    )*
+                }
+            }
+        }
+
+        impl From<$enum_name> for $crate::extension::known_values::KnownValue {
+            fn from(value: $enum_name) -> Self {
+                $crate::extension::known_values::KnownValueEnum::known_value(&value)
+            }
+        }
+
+        impl $crate::EnvelopeEncodable for $enum_name {
+            fn into_envelope(self) -> $crate::Envelope {
+                $crate::EnvelopeEncodable::into_envelope(
+                    $crate::extension::known_values::KnownValueEnum::known_value(&self),
+                )
+            }
+        }
+
+        impl TryFrom<$crate::Envelope> for $enum_name {
+            type Error = $crate::EnvelopeError;
+
+            fn try_from(envelope: $crate::Envelope) -> Result<Self, Self::Error> {
+                <Self as $crate::extension::known_values::KnownValueEnum>::from_envelope(&envelope)
+            }
+        }
+    };
+}
@@ -0,0 +1,120 @@
+use std::ops::RangeInclusive;
+
+use super::known_value::KnownValue;
+
+/// The registry range that a known value falls in.
+///
+/// The known values namespace is partitioned so that values assigned by the
+/// registry can never collide with values chosen by applications:
+///
+/// | Range                         | Kind           |
+/// |-------------------------------|----------------|
+/// | `0..=999`                     | Core           |
+/// | `1000..=0xFFFF_FFFF`          | Extension      |
+/// | `0x1_0000_0000..=u64::MAX`    | Private use    |
+///
+/// Core and extension values are reserved for assignment by the registry and
+/// may only be registered in a [`KnownValuesStore`](super::KnownValuesStore)
+/// using
+/// [`register_standard`](super::KnownValuesStore::register_standard).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KnownValueRange {
+    /// Values assigned by the registry for the envelope specification itself.
+    Core,
+    /// Values assigned by the registry for extensions and other specifications.
+    Extension,
+    /// Values available for private use without registration.
+    PrivateUse,
+}
+
+impl KnownValueRange {
+    /// The raw values in the core range.
+    pub const CORE: RangeInclusive<u64> = 0..=999;
+
+    /// The raw values in the extension range.
+    pub const EXTENSION: RangeInclusive<u64> = 1000..=0xFFFF_FFFF;
+
+    /// The raw values in the private use range.
+    pub const PRIVATE_USE: RangeInclusive<u64> = 0x1_0000_0000..=u64::MAX;
+
+    /// Returns the range that the given raw value falls in.
+    pub fn of(raw_value: u64) -> Self {
+        if Self::CORE.contains(&raw_value) {
+            Self::Core
+        } else if Self::EXTENSION.contains(&raw_value) {
+            Self::Extension
+        } else {
+            Self::PrivateUse
+        }
+    }
+
+    /// The raw values in this range.
+    pub fn raw_values(&self) -> RangeInclusive<u64> {
+        match self {
+            Self::Core => Self::CORE,
+            Self::Extension => Self::EXTENSION,
+            Self::PrivateUse => Self::PRIVATE_USE,
+        }
+    }
+
+    /// Returns `true` if values in this range are reserved for assignment by
+    /// the registry.
+    pub fn is_reserved(&self) -> bool {
+        !matches!(self, Self::PrivateUse)
+    }
+}
+
+impl KnownValue {
+    /// The registry range this known value falls in.
+    pub fn range(&self) -> KnownValueRange {
+        KnownValueRange::of(self.value())
+    }
+}
+
+impl std::fmt::Display for KnownValueRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Core => "core",
+            Self::Extension => "extension",
+            Self::PrivateUse => "private use",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extension::known_values::{self, KnownValuesStore};
+
+    #[test]
+    fn test_ranges() {
+        assert_eq!(known_values::IS_A.range(), KnownValueRange::Core);
+        assert_eq!(KnownValueRange::of(999), KnownValueRange::Core);
+        assert_eq!(KnownValueRange::of(1000), KnownValueRange::Extension);
+        assert_eq!(KnownValueRange::of(0xFFFF_FFFF), KnownValueRange::Extension);
+        assert_eq!(KnownValueRange::of(0x1_0000_0000), KnownValueRange::PrivateUse);
+        assert!(KnownValueRange::of(u64::MAX).raw_values().contains(&u64::MAX));
+    }
+
+    #[test]
+    fn test_register() {
+        let mut store = KnownValuesStore::new([known_values::IS_A]);
+
+        // Reserved values are only accepted when registered as standard.
+        let custom = KnownValue::new_with_name(1234u64, "custom".into());
+        assert!(store.register(custom.clone()).is_err());
+        store.register_standard(custom).unwrap();
+
+        let private = KnownValue::new_with_name(0x1_0000_0001u64, "private".into());
+        store.register(private.clone()).unwrap();
+        // Re-registering the identical value is harmless.
+        store.register(private).unwrap();
+        assert_eq!(store.known_value_named("private").unwrap().value(), 0x1_0000_0001);
+
+        // Collisions with existing values or names are rejected.
+        assert!(store.register(KnownValue::new_with_name(0x1_0000_0001u64, "other".into())).is_err());
+        assert!(store.register(KnownValue::new_with_name(0x1_0000_0002u64, "private".into())).is_err());
+        assert!(store.register_standard(KnownValue::new_with_name(1u64, "isNotA".into())).is_err());
+    }
+}
@@ -0,0 +1,254 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{bail, Result};
+use bc_components::{Digest, DigestProvider};
+
+use crate::{base::envelope::EnvelopeCase, Envelope, EnvelopeError};
+
+use super::KnownValue;
+
+/// The predicate of the assertion in a translation's audit envelope whose
+/// object is the digest of the envelope before it was translated.
+pub const TRANSLATED_FROM: &str = "translatedFrom";
+
+/// The predicate of the assertions in a translation's audit envelope
+/// recording each assertion whose predicate was translated.
+pub const REWROTE: &str = "rewrote";
+
+const REWROTE_AS: &str = "as";
+const REWROTE_FROM: &str = "from";
+const REWROTE_TO: &str = "to";
+const OBSCURED: &str = "obscured";
+
+/// A table of known values used as predicates by one party, and the known
+/// values a peer with a different registry uses for the same predicates.
+///
+/// The table is negotiated by the parties and applied with
+/// [`Envelope::translate_known_values`]. Its [`inverse`](Self::inverse)
+/// translates the peer's envelopes back.
+#[derive(Debug, Clone, Default)]
+pub struct KnownValueTranslation {
+    mappings: HashMap<u64, KnownValue>,
+}
+
+impl KnownValueTranslation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Translates the known value with the raw value `from` to `to`.
+    pub fn with_mapping(mut self, from: u64, to: KnownValue) -> Self {
+        self.mappings.insert(from, to);
+        self
+    }
+
+    /// The known value that `from` translates to, if any.
+    pub fn translation(&self, from: u64) -> Option<&KnownValue> {
+        self.mappings.get(&from)
+    }
+
+    /// The table translating in the opposite direction.
+    ///
+    /// The original values have no names in the inverse table, since the
+    /// table records only their raw values.
+    pub fn inverse(&self) -> Self {
+        Self {
+            mappings: self
+                .mappings
+                .iter()
+                .map(|(from, to)| (to.value(), KnownValue::new(*from)))
+                .collect(),
+        }
+    }
+}
+
+/// An assertion whose predicate was translated by
+/// [`Envelope::translate_known_values`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TranslatedPredicate {
+    /// The digest of the assertion before it was translated.
+    pub original: Digest,
+    /// The digest of the assertion after it was translated, which differs
+    /// from `original` unless the known value was unchanged.
+    pub translated: Digest,
+    /// The raw value of the known value before it was translated.
+    pub from: u64,
+    /// The known value it was translated to.
+    pub to: KnownValue,
+}
+
+/// The outcome of [`Envelope::translate_known_values`].
+///
+/// Converting the report to an envelope gives an audit record of the
+/// translation that the sender can keep, or send along with the translated
+/// envelope:
+///
+/// ```text
+/// Digest(translated) [
+///     "translatedFrom": Digest(original)
+///     "rewrote": Digest(original assertion) [
+///         "as": Digest(translated assertion)
+///         "from": 1000
+///         "to": 2000
+///     ]
+///     "obscured": Digest(elided element)
+/// ]
+/// ```
+#[derive(Debug, Clone)]
+pub struct TranslationReport {
+    original: Digest,
+    translated: Digest,
+    predicates: Vec<TranslatedPredicate>,
+    obscured_elements: Vec<Digest>,
+}
+
+impl TranslationReport {
+    /// The digest of the envelope before it was translated.
+    pub fn original(&self) -> &Digest {
+        &self.original
+    }
+
+    /// The digest of the envelope after it was translated.
+    pub fn translated(&self) -> &Digest {
+        &self.translated
+    }
+
+    /// The assertions whose predicates were translated, in the order they
+    /// were found.
+    pub fn predicates(&self) -> &[TranslatedPredicate] {
+        &self.predicates
+    }
+
+    /// The digests of the elided, encrypted, or compressed elements that
+    /// could not be inspected, and so may still contain untranslated
+    /// predicates.
+    pub fn obscured_elements(&self) -> &[Digest] {
+        &self.obscured_elements
+    }
+
+    /// `true` if the translated envelope has the same digest as the
+    /// original, so that signatures on it still verify.
+    pub fn is_digest_preserving(&self) -> bool {
+        self.original == self.translated
+    }
+}
+
+impl From<&TranslationReport> for Envelope {
+    fn from(report: &TranslationReport) -> Self {
+        let envelope = Envelope::new(report.translated.clone())
+            .add_assertion(TRANSLATED_FROM, report.original.clone());
+        let envelope = report.predicates.iter().fold(envelope, |envelope, predicate| {
+            let rewrite = Envelope::new(predicate.original.clone())
+                .add_assertion(REWROTE_AS, predicate.translated.clone())
+                .add_assertion(REWROTE_FROM, predicate.from)
+                .add_assertion(REWROTE_TO, predicate.to.value());
+            envelope.add_assertion(REWROTE, rewrite)
+        });
+        report
+            .obscured_elements
+            .iter()
+            .fold(envelope, |envelope, digest| envelope.add_assertion(OBSCURED, digest.clone()))
+    }
+}
+
+/// Support for exchanging envelopes with peers using other known value
+/// registries.
+impl Envelope {
+    /// Returns the envelope with each known value predicate that
+    /// `translation` maps replaced by its translation, along with a report of
+    /// the predicates translated.
+    ///
+    /// Only known values in the predicate position of assertions, including
+    /// assertions nested in subjects, objects, and wrapped envelopes, are
+    /// translated. Translating a known value to one with the same raw value
+    /// changes only its name, so digests and signatures are preserved.
+    /// Otherwise the digests of the translated assertions and all of their
+    /// ancestors change, and any signatures on them will no longer verify.
+    /// Elided, encrypted, and compressed elements cannot be inspected and are
+    /// left unchanged, keeping their digests.
+    ///
+    /// Returns an error if translating makes two assertions on the same
+    /// element identical.
+    pub fn translate_known_values(&self, translation: &KnownValueTranslation) -> Result<(Self, TranslationReport)> {
+        let mut predicates = Vec::new();
+        let mut obscured_elements = Vec::new();
+        let translated = self.translate_element(translation, &mut predicates, &mut obscured_elements)?;
+        let report = TranslationReport {
+            original: self.digest().into_owned(),
+            translated: translated.digest().into_owned(),
+            predicates,
+            obscured_elements,
+        };
+        Ok((translated, report))
+    }
+
+    fn translate_element(
+        &self,
+        translation: &KnownValueTranslation,
+        predicates: &mut Vec<TranslatedPredicate>,
+        obscured_elements: &mut Vec<Digest>,
+    ) -> Result<Self> {
+        let translated = match self.case() {
+            EnvelopeCase::Node { subject, assertions, .. } => {
+                let subject = subject.translate_element(translation, predicates, obscured_elements)?;
+                let assertions = assertions
+                    .iter()
+                    .map(|assertion| assertion.translate_element(translation, predicates, obscured_elements))
+                    .collect::<Result<Vec<_>>>()?;
+                let mut digests = HashSet::new();
+                if !assertions.iter().all(|assertion| digests.insert(assertion.digest().into_owned())) {
+                    bail!(EnvelopeError::TranslationCollision);
+                }
+                Self::new_with_unchecked_assertions(subject, assertions)
+            }
+            EnvelopeCase::Wrapped { envelope, .. } => {
+                Self::new_wrapped(envelope.translate_element(translation, predicates, obscured_elements)?)
+            }
+            EnvelopeCase::Assertion(assertion) => {
+                let count = predicates.len();
+                let predicate = assertion.predicate();
+                let rewrite = predicate
+                    .subject()
+                    .as_known_value()
+                    .and_then(|from| Some((from.value(), translation.translation(from.value())?.clone())));
+                let translated_predicate = match &rewrite {
+                    Some((_, to)) => match predicate.case() {
+                        EnvelopeCase::Node { .. } => {
+                            let predicate = predicate.translate_element(translation, predicates, obscured_elements)?;
+                            predicate.replace_subject(Self::new_with_known_value(to.clone()))
+                        }
+                        _ => Self::new_with_known_value(to.clone()),
+                    },
+                    None => predicate.translate_element(translation, predicates, obscured_elements)?,
+                };
+                let object = assertion.object().translate_element(translation, predicates, obscured_elements)?;
+                let translated = Self::new_assertion(translated_predicate, object);
+                if let Some((from, to)) = rewrite {
+                    predicates.insert(count, TranslatedPredicate {
+                        original: self.digest().into_owned(),
+                        translated: translated.digest().into_owned(),
+                        from,
+                        to,
+                    });
+                }
+                translated
+            }
+            EnvelopeCase::Elided(_) => {
+                obscured_elements.push(self.digest().into_owned());
+                self.clone()
+            }
+            #[cfg(feature = "encrypt")]
+            EnvelopeCase::Encrypted(_) => {
+                obscured_elements.push(self.digest().into_owned());
+                self.clone()
+            }
+            #[cfg(feature = "compress")]
+            EnvelopeCase::Compressed(_) => {
+                obscured_elements.push(self.digest().into_owned());
+                self.clone()
+            }
+            _ => self.clone(),
+        };
+        Ok(translated)
+    }
+}
@@ -1,5 +1,9 @@
 use std::collections::HashMap;
 
+use anyhow::{bail, Result};
+
+use crate::EnvelopeError;
+
 use super::known_value::KnownValue;
 
 /// A type that maps between known values and their assigned names.
@@ -37,6 +41,41 @@ impl KnownValuesStore {
         );
     }
 
+    /// Registers an application-defined known value.
+    ///
+    /// Fails if the value lies in a range reserved for the registry (see
+    /// [`KnownValueRange`](super::KnownValueRange)), or if its value or name is
+    /// already registered to a different known value.
+    pub fn register(&mut self, known_value: KnownValue) -> Result<()> {
+        let range = known_value.range();
+        if range.is_reserved() {
+            bail!(EnvelopeError::ReservedKnownValue(known_value.value(), range));
+        }
+        self.register_standard(known_value)
+    }
+
+    /// Registers a known value that has been assigned by the registry.
+    ///
+    /// Unlike [`register`](Self::register), this accepts values in reserved
+    /// ranges. Fails if the value or name is already registered to a different
+    /// known value.
+    pub fn register_standard(&mut self, known_value: KnownValue) -> Result<()> {
+        if let Some(existing) = self.known_values_by_raw_value.get(&known_value.value()) {
+            if existing.assigned_name() != known_value.assigned_name() {
+                bail!(EnvelopeError::DuplicateKnownValue(known_value.value()));
+            }
+        }
+        if let Some(name) = known_value.assigned_name() {
+            if let Some(existing) = self.known_values_by_assigned_name.get(name) {
+                if existing.value() != known_value.value() {
+                    bail!(EnvelopeError::DuplicateKnownValue(known_value.value()));
+                }
+            }
+        }
+        self.insert(known_value);
+        Ok(())
+    }
+
     pub fn assigned_name(&self, known_value: &KnownValue) -> Option<&str> {
         self.known_values_by_raw_value
             .get(&known_value.value())
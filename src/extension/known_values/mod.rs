@@ -1,6 +1,15 @@
 pub mod known_value;
 pub use known_value::KnownValue;
 
+pub mod known_value_enum;
+pub use known_value_enum::KnownValueEnum;
+
+pub mod known_value_translation;
+pub use known_value_translation::{KnownValueTranslation, TranslatedPredicate, TranslationReport};
+
+pub mod known_value_range;
+pub use known_value_range::KnownValueRange;
+
 pub mod known_values_registry;
 pub use known_values_registry as registry;
 pub use registry::*;
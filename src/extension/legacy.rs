@@ -0,0 +1,219 @@
+use std::collections::{HashMap, HashSet};
+
+use bc_components::{tags::TAG_ARID, Digest, DigestProvider};
+use dcbor::prelude::*;
+
+use crate::{base::envelope::EnvelopeCase, Envelope};
+use crate::extension::KnownValue;
+
+/// A rewrite of an envelope produced by an early implementation into the
+/// current canonical form.
+///
+/// Early implementations used some CBOR tags and known values that have since
+/// been renumbered, most notably the CID type that is now ARID. Which legacy
+/// numbers were used depends on the implementation, and the release of it,
+/// that produced the envelope, and was never recorded in a registry, so this
+/// crate does not ship tables of them. Instead, the migration is configured
+/// with the rewrites to apply, which should be taken from the implementation
+/// that produced the envelopes being migrated.
+#[derive(Debug, Clone, Default)]
+pub struct LegacyMigration {
+    tag_rewrites: HashMap<TagValue, TagValue>,
+    known_value_rewrites: HashMap<u64, KnownValue>,
+}
+
+impl LegacyMigration {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rewrites the CBOR tag `from`, wherever it appears in a leaf, to `to`.
+    pub fn with_tag_rewrite(mut self, from: TagValue, to: TagValue) -> Self {
+        self.tag_rewrites.insert(from, to);
+        self
+    }
+
+    /// Rewrites CIDs, tagged with `legacy_cid_tag`, to ARIDs.
+    ///
+    /// A CID and an ARID have the same content, so only the tag changes.
+    pub fn with_cid_tag(self, legacy_cid_tag: TagValue) -> Self {
+        self.with_tag_rewrite(legacy_cid_tag, TAG_ARID)
+    }
+
+    /// Rewrites the known value with the raw value `from` to `to`.
+    pub fn with_known_value_rewrite(mut self, from: u64, to: KnownValue) -> Self {
+        self.known_value_rewrites.insert(from, to);
+        self
+    }
+}
+
+/// A single rewrite applied by a [`LegacyMigration`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum LegacyRewrite {
+    /// A CBOR tag in the leaf with digest `element` was renumbered.
+    Tag { element: Digest, from: TagValue, to: TagValue },
+
+    /// The known value with digest `element` was renumbered.
+    KnownValue { element: Digest, from: u64, to: KnownValue },
+}
+
+/// The rewrites applied when migrating an envelope.
+///
+/// Returned by [`Envelope::migrate_legacy`].
+#[derive(Debug, Clone, Default)]
+pub struct MigrationReport {
+    rewrites: Vec<LegacyRewrite>,
+    obscured_elements: Vec<Digest>,
+    merged_assertions: Vec<Digest>,
+}
+
+impl MigrationReport {
+    /// The rewrites applied, in the order they were made.
+    pub fn rewrites(&self) -> &[LegacyRewrite] {
+        &self.rewrites
+    }
+
+    /// The digests of the elided, encrypted, or compressed elements that could
+    /// not be inspected, and so may still contain legacy content.
+    pub fn obscured_elements(&self) -> &[Digest] {
+        &self.obscured_elements
+    }
+
+    /// The digests, after migration, of the assertions that the rewrites made
+    /// identical to another assertion on the same subject, and which were
+    /// therefore merged into one.
+    pub fn merged_assertions(&self) -> &[Digest] {
+        &self.merged_assertions
+    }
+
+    /// `true` if the migration changed the envelope.
+    pub fn is_migrated(&self) -> bool {
+        !self.rewrites.is_empty()
+    }
+}
+
+/// Support for migrating envelopes produced by early implementations.
+impl Envelope {
+    /// Returns `true` if the envelope contains legacy content that `migration`
+    /// would rewrite.
+    pub fn is_legacy(&self, migration: &LegacyMigration) -> bool {
+        self.migrate_legacy(migration).1.is_migrated()
+    }
+
+    /// Returns the envelope rewritten into the current canonical form, along
+    /// with a report of the rewrites applied.
+    ///
+    /// Rewriting changes the digests of the rewritten elements and all of
+    /// their ancestors, so any signatures on the envelope will no longer
+    /// verify and the envelope must be re-signed. Elided, encrypted, and
+    /// compressed elements cannot be inspected and are left unchanged.
+    /// Assertions that the rewrites make identical are merged, and reported
+    /// in [`MigrationReport::merged_assertions`].
+    pub fn migrate_legacy(&self, migration: &LegacyMigration) -> (Self, MigrationReport) {
+        let mut report = MigrationReport::default();
+        let migrated = self.migrate_legacy_reporting(migration, &mut report);
+        (migrated, report)
+    }
+
+    fn migrate_legacy_reporting(&self, migration: &LegacyMigration, report: &mut MigrationReport) -> Self {
+        match self.case() {
+            EnvelopeCase::Node { subject, assertions, .. } => {
+                let subject = subject.migrate_legacy_reporting(migration, report);
+                let mut digests = HashSet::new();
+                let mut distinct = Vec::with_capacity(assertions.len());
+                for assertion in assertions {
+                    let assertion = assertion.migrate_legacy_reporting(migration, report);
+                    if digests.insert(assertion.digest().into_owned()) {
+                        distinct.push(assertion);
+                    } else {
+                        report.merged_assertions.push(assertion.digest().into_owned());
+                    }
+                }
+                Self::new_with_unchecked_assertions(subject, distinct)
+            }
+            EnvelopeCase::Leaf { cbor, .. } => {
+                let count = report.rewrites.len();
+                let element = self.digest().into_owned();
+                let cbor = migrate_cbor(cbor, migration, &element, report);
+                if report.rewrites.len() == count {
+                    self.clone()
+                } else {
+                    Self::new_leaf(cbor)
+                }
+            }
+            EnvelopeCase::Wrapped { envelope, .. } => {
+                Self::new_wrapped(envelope.migrate_legacy_reporting(migration, report))
+            }
+            EnvelopeCase::Assertion(assertion) => {
+                Self::new_assertion(
+                    assertion.predicate().migrate_legacy_reporting(migration, report),
+                    assertion.object().migrate_legacy_reporting(migration, report),
+                )
+            }
+            EnvelopeCase::KnownValue { value, .. } => {
+                match migration.known_value_rewrites.get(&value.value()) {
+                    Some(to) => {
+                        report.rewrites.push(LegacyRewrite::KnownValue {
+                            element: self.digest().into_owned(),
+                            from: value.value(),
+                            to: to.clone(),
+                        });
+                        Self::new_with_known_value(to.clone())
+                    }
+                    None => self.clone(),
+                }
+            }
+            EnvelopeCase::Elided(_) => {
+                report.obscured_elements.push(self.digest().into_owned());
+                self.clone()
+            }
+            #[cfg(feature = "encrypt")]
+            EnvelopeCase::Encrypted(_) => {
+                report.obscured_elements.push(self.digest().into_owned());
+                self.clone()
+            }
+            #[cfg(feature = "compress")]
+            EnvelopeCase::Compressed(_) => {
+                report.obscured_elements.push(self.digest().into_owned());
+                self.clone()
+            }
+        }
+    }
+}
+
+fn migrate_cbor(cbor: &CBOR, migration: &LegacyMigration, element: &Digest, report: &mut MigrationReport) -> CBOR {
+    match cbor.as_case() {
+        CBORCase::Tagged(tag, content) => {
+            let content = migrate_cbor(content, migration, element, report);
+            match migration.tag_rewrites.get(&tag.value()) {
+                Some(&to) => {
+                    report.rewrites.push(LegacyRewrite::Tag {
+                        element: element.clone(),
+                        from: tag.value(),
+                        to,
+                    });
+                    CBOR::to_tagged_value(to, content)
+                }
+                None => CBOR::to_tagged_value(tag.clone(), content),
+            }
+        }
+        CBORCase::Array(items) => {
+            items
+                .iter()
+                .map(|item| migrate_cbor(item, migration, element, report))
+                .collect::<Vec<CBOR>>()
+                .into()
+        }
+        CBORCase::Map(map) => {
+            let mut migrated = Map::new();
+            for (key, value) in map.iter() {
+                migrated.insert(
+                    migrate_cbor(key, migration, element, report),
+                    migrate_cbor(value, migration, element, report),
+                );
+            }
+            migrated.into()
+        }
+        _ => cbor.clone(),
+    }
+}
@@ -0,0 +1,212 @@
+use std::{fs::File, ops::Range, path::Path, sync::{Arc, OnceLock}};
+
+use anyhow::{bail, Error, Result};
+use bc_components::tags;
+use dcbor::prelude::*;
+use memmap2::Mmap;
+
+use crate::{base::envelope::RefCounted, Envelope, EnvelopeError};
+
+/// A read-only envelope, or an element of one, backed by a memory-mapped
+/// file.
+///
+/// Opening a `MappedEnvelope` only maps the file, and subtrees are
+/// materialized on demand. [`MappedEnvelope::subject`] and
+/// [`MappedEnvelope::assertions`] locate the elements of a node by reading
+/// the CBOR headers in the mapping, without decoding the elements, and each
+/// element is only decoded, along with its descendants, when it is accessed
+/// with [`MappedEnvelope::envelope`]. Clones share both the mapping and the
+/// decoded element, so a server can open a large stored envelope once at
+/// startup, decode just the assertions each request needs, and hand out cheap
+/// clones.
+///
+/// The file must not be modified while it is mapped.
+#[derive(Debug, Clone)]
+pub struct MappedEnvelope {
+    map: Arc<Mmap>,
+    range: Range<usize>,
+    is_root: bool,
+    envelope: RefCounted<OnceLock<Envelope>>,
+}
+
+impl MappedEnvelope {
+    /// Maps the file at `path`, which must contain a tagged CBOR envelope.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: The mapping is read-only, and callers are required not to
+        // modify the file while it is mapped.
+        let map = unsafe { Mmap::map(&file)? };
+        let range = 0..map.len();
+        Ok(Self {
+            map: Arc::new(map),
+            range,
+            is_root: true,
+            envelope: RefCounted::new(OnceLock::new()),
+        })
+    }
+
+    /// The raw CBOR data of the element, read directly from the mapping.
+    ///
+    /// This is the tagged envelope for the opened envelope, and the untagged
+    /// element for its subtrees, as they are encoded within it.
+    pub fn cbor_data(&self) -> &[u8] {
+        &self.map[self.range.clone()]
+    }
+
+    /// Returns the element, decoding it and its descendants from the mapping
+    /// on first access.
+    ///
+    /// Returns an error if the mapped data is not a valid envelope. Failed
+    /// decodes are not cached, so a subsequent call will try again.
+    pub fn envelope(&self) -> Result<&Envelope> {
+        if let Some(envelope) = self.envelope.get() {
+            return Ok(envelope);
+        }
+        let envelope = if self.is_root {
+            Envelope::from_tagged_cbor_data(self.cbor_data())?
+        } else {
+            Envelope::from_untagged_cbor(CBOR::try_from_data(self.cbor_data())?)?
+        };
+        Ok(self.envelope.get_or_init(|| envelope))
+    }
+
+    /// Returns `true` if the element has already been decoded.
+    pub fn is_decoded(&self) -> bool {
+        self.envelope.get().is_some()
+    }
+
+    /// Returns the subject of the element without decoding it.
+    ///
+    /// As with [`Envelope::subject`], an element that is not a node is its
+    /// own subject. Returns an error if the mapped data is malformed.
+    pub fn subject(&self) -> Result<Self> {
+        let content = self.content_range()?;
+        let (major_type, count, header_len) = header(&self.map, content.start)?;
+        if major_type != MAJOR_TYPE_ARRAY {
+            return Ok(self.clone());
+        }
+        if count == 0 {
+            bail!(CBORError::Underrun);
+        }
+        let start = content.start + header_len;
+        Ok(self.element(start..item_end(&self.map, start)?))
+    }
+
+    /// Returns the assertions of the element without decoding them.
+    ///
+    /// The assertions are in the same order as those returned by
+    /// [`Envelope::assertions`]. An element that is not a node has none.
+    /// Returns an error if the mapped data is malformed.
+    pub fn assertions(&self) -> Result<Vec<Self>> {
+        let content = self.content_range()?;
+        let (major_type, count, header_len) = header(&self.map, content.start)?;
+        if major_type != MAJOR_TYPE_ARRAY {
+            return Ok(Vec::new());
+        }
+        let mut start = item_end(&self.map, content.start + header_len)?;
+        let mut assertions = Vec::new();
+        for _ in 1..count {
+            let end = item_end(&self.map, start)?;
+            assertions.push(self.element(start..end));
+            start = end;
+        }
+        Ok(assertions)
+    }
+
+    /// The range of the untagged element within the mapping.
+    fn content_range(&self) -> Result<Range<usize>> {
+        if !self.is_root {
+            return Ok(self.range.clone());
+        }
+        let (major_type, tag, header_len) = header(&self.map, self.range.start)?;
+        if major_type != MAJOR_TYPE_TAGGED || tag != tags::TAG_ENVELOPE {
+            bail!(EnvelopeError::InvalidFormat);
+        }
+        Ok(self.range.start + header_len..self.range.end)
+    }
+
+    fn element(&self, range: Range<usize>) -> Self {
+        Self {
+            map: self.map.clone(),
+            range,
+            is_root: false,
+            envelope: RefCounted::new(OnceLock::new()),
+        }
+    }
+}
+
+const MAJOR_TYPE_BYTE_STRING: u8 = 2;
+const MAJOR_TYPE_TEXT: u8 = 3;
+const MAJOR_TYPE_ARRAY: u8 = 4;
+const MAJOR_TYPE_MAP: u8 = 5;
+const MAJOR_TYPE_TAGGED: u8 = 6;
+
+/// Returns the major type and argument of the CBOR header at `position`, and
+/// the length of the header.
+fn header(data: &[u8], position: usize) -> Result<(u8, u64, usize)> {
+    let Some(&initial) = data.get(position) else {
+        bail!(CBORError::Underrun);
+    };
+    let (major_type, additional) = (initial >> 5, initial & 0x1f);
+    let argument_len = match additional {
+        0..=23 => return Ok((major_type, additional as u64, 1)),
+        24 => 1,
+        25 => 2,
+        26 => 4,
+        27 => 8,
+        _ => bail!(CBORError::UnsupportedHeaderValue(additional)),
+    };
+    let Some(bytes) = data.get(position + 1..position + 1 + argument_len) else {
+        bail!(CBORError::Underrun);
+    };
+    let argument = bytes.iter().fold(0, |argument, &byte| argument << 8 | byte as u64);
+    Ok((major_type, argument, 1 + argument_len))
+}
+
+/// Returns the position just past the CBOR item that starts at `position`,
+/// without decoding it.
+fn item_end(data: &[u8], mut position: usize) -> Result<usize> {
+    // Each item read adds the items it contains, so hostile counts cannot
+    // make this recurse, and each item consumes at least one byte.
+    let mut pending: u64 = 1;
+    while pending > 0 {
+        pending -= 1;
+        let (major_type, argument, header_len) = header(data, position)?;
+        position += header_len;
+        let contained = match major_type {
+            MAJOR_TYPE_BYTE_STRING | MAJOR_TYPE_TEXT => {
+                position = usize::try_from(argument)
+                    .ok()
+                    .and_then(|len| position.checked_add(len))
+                    .filter(|&end| end <= data.len())
+                    .ok_or(CBORError::Underrun)?;
+                0
+            }
+            MAJOR_TYPE_ARRAY => argument,
+            MAJOR_TYPE_MAP => argument.checked_mul(2).ok_or(CBORError::Underrun)?,
+            MAJOR_TYPE_TAGGED => 1,
+            _ => 0,
+        };
+        pending = pending.checked_add(contained).ok_or(CBORError::Underrun)?;
+    }
+    Ok(position)
+}
+
+impl TryFrom<MappedEnvelope> for Envelope {
+    type Error = Error;
+
+    fn try_from(mapped: MappedEnvelope) -> Result<Self> {
+        mapped.envelope().cloned()
+    }
+}
+
+/// Support for memory-mapped envelopes.
+impl Envelope {
+    /// Maps the envelope stored in the file at `path` for read-only access.
+    ///
+    /// Only the elements that are accessed are decoded; see
+    /// [`MappedEnvelope`].
+    pub fn open_mmap(path: impl AsRef<Path>) -> Result<MappedEnvelope> {
+        MappedEnvelope::open(path)
+    }
+}
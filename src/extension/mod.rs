@@ -1,20 +1,58 @@
+///
+/// Anonymization Extension
+///
+#[cfg(feature = "anonymize")]
+pub mod anonymize;
+#[cfg(feature = "anonymize")]
+pub use anonymize::{AnonymizeAction, AnonymizePolicy};
+
 ///
 /// Attachments Extension
 ///
 #[cfg(feature = "attachment")]
 pub mod attachment;
+#[cfg(feature = "attachment")]
+pub use attachment::{AttachmentInfo, AttachmentPolicy};
+
+///
+/// Capabilities Extension
+///
+#[cfg(all(feature = "expression", feature = "signature"))]
+pub mod capability;
+#[cfg(all(feature = "expression", feature = "signature"))]
+pub use capability::Capability;
+
+///
+/// Claims Extension
+///
+#[cfg(feature = "claims")]
+pub mod claims;
+#[cfg(feature = "claims")]
+pub use claims::{ClaimsSet, CLAIM_AUDIENCE};
 
 ///
 /// Compression Extension
 ///
 #[cfg(feature = "compress")]
 pub mod compress;
+#[cfg(feature = "compress")]
+pub use compress::CompressionReport;
 
 ///
 /// Symmetric Encryption Extension
 ///
 #[cfg(feature = "encrypt")]
 pub mod encrypt;
+#[cfg(feature = "encrypt")]
+pub use encrypt::{DecryptionFailure, DecryptionReport, SecretEnvelopeContent};
+#[cfg(feature = "encrypt")]
+pub mod encrypted_ur;
+#[cfg(feature = "encrypt")]
+pub use encrypted_ur::URSecret;
+#[cfg(feature = "encrypt")]
+pub mod key_provider;
+#[cfg(feature = "encrypt")]
+pub use key_provider::{KeyProvider, CONTENT_KEY_ID};
 
 ///
 /// Expressions Extension
@@ -25,13 +63,28 @@ pub mod expressions;
 pub use expressions::{
     Expression,
     ExpressionBehavior,
+    ExpressionUR,
+    IdempotencyCache,
     IntoExpression,
+    NestedExpression,
     Request,
     RequestBehavior,
     Response,
     ResponseBehavior,
 };
 
+///
+/// C ABI Extension
+///
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
+///
+/// Test Vector Fixtures Extension
+///
+#[cfg(feature = "fixtures")]
+pub mod fixtures;
+
 ///
 /// Known Values Extension
 ///
@@ -40,17 +93,51 @@ pub mod known_values;
 #[cfg(feature = "known_value")]
 pub use known_values::*;
 
+///
+/// Legacy Migration Extension
+///
+#[cfg(feature = "legacy")]
+pub mod legacy;
+#[cfg(feature = "legacy")]
+pub use legacy::{LegacyMigration, LegacyRewrite, MigrationReport};
+
+///
+/// Memory-Mapped Envelopes Extension
+///
+#[cfg(feature = "mmap")]
+pub mod mmap;
+#[cfg(feature = "mmap")]
+pub use mmap::MappedEnvelope;
+
 ///
 /// Inclusion Proof Extension
 ///
 #[cfg(feature = "proof")]
 pub mod proof;
 
+///
+/// Provenance Extension
+///
+#[cfg(feature = "provenance")]
+pub mod provenance;
+#[cfg(feature = "provenance")]
+pub use provenance::{EditJournal, JOURNAL_ENTRY, PREVIOUS_ENTRY};
+
+///
+/// RDF Export Extension
+///
+#[cfg(feature = "rdf")]
+pub mod rdf;
+
 ///
 /// Public Key Encryption Extension
 ///
 #[cfg(feature = "recipient")]
 pub mod recipient;
+#[cfg(feature = "recipient")]
+pub mod recipient_group;
+#[cfg(feature = "recipient")]
+pub use recipient_group::RecipientGroup;
 
 ///
 /// Public Key Signing Extension
@@ -58,7 +145,7 @@ pub mod recipient;
 #[cfg(feature = "signature")]
 pub mod signature;
 #[cfg(feature = "signature")]
-pub use signature::SignatureMetadata;
+pub use signature::{present, verify_corpus, verify_presentation, CorpusPolicy, CorpusResult, CorpusResults, RotationHistory, SignatureCoverage, SignatureMetadata, SignatureReport, SignerResolver, SigningWitness, TrustLink, TrustPath, TrustStore, VerifiedIdentity, witness_message, Witness, WitnessSet};
 
 ///
 /// Salt Extension
@@ -77,6 +164,20 @@ pub mod ssh;
 ///
 #[cfg(feature = "sskr")]
 pub mod sskr;
+#[cfg(feature = "sskr")]
+pub use sskr::{SSKRJoinStatus, SSKRJoiner};
+
+///
+/// Signed, Encrypted, and Sealed Envelope Wrappers
+///
+#[cfg(any(feature = "signature", feature = "encrypt"))]
+pub mod type_state;
+#[cfg(feature = "signature")]
+pub use type_state::SignedEnvelope;
+#[cfg(feature = "encrypt")]
+pub use type_state::EncryptedEnvelope;
+#[cfg(all(feature = "signature", feature = "recipient"))]
+pub use type_state::SealedEnvelope;
 
 ///
 /// Types Extension
@@ -1,8 +1,14 @@
-use std::{collections::{HashSet, hash_map::RandomState}, iter};
+use std::{cell::Cell, collections::{HashSet, hash_map::RandomState}, iter};
 
+use anyhow::{bail, Result};
 use bc_components::{DigestProvider, Digest};
+use dcbor::prelude::*;
 
-use crate::{Envelope, base::envelope::EnvelopeCase};
+use crate::{Envelope, EnvelopeError, EdgeType, base::envelope::EnvelopeCase};
+
+/// The predicate of the assertion with which a node commits to its other
+/// assertions. See [`Envelope::add_assertion_commitment`].
+pub const ASSERTION_COMMITMENT: &str = "assertionCommitment";
 
 /// Support for inclusions proofs.
 impl Envelope {
@@ -53,9 +59,136 @@ impl Envelope {
         let set = HashSet::from_iter(iter::once(target.digest().into_owned()));
         self.confirm_contains_set(&set, proof)
     }
+
+    /// Returns an opening of the element of this envelope with the given
+    /// digest.
+    ///
+    /// An opening lets the holder of an elided version of this envelope
+    /// confirm the value of one elided element without revealing any of its
+    /// neighbors. It is simply the original element, including any salt it was
+    /// given to decorrelate it: since the element's digest commits to its
+    /// entire content, nothing more is needed.
+    ///
+    /// # Parameters
+    /// - `digest`: The digest of the element to open.
+    /// # Returns
+    /// The opening, or `None` if this envelope has no element with the digest.
+    pub fn opening_for(&self, digest: &Digest) -> Option<Envelope> {
+        self.find_by_digest(digest).map(|(element, _)| element)
+    }
+
+    /// Confirms whether `opening` is an opening of an element of this
+    /// envelope, which may be elided here.
+    ///
+    /// If it is, the opening is the element's original value, so the verifier
+    /// can compare it with the value claimed for the element.
+    ///
+    /// An opening with any obscured element, such as an assertion whose
+    /// object is elided, is rejected, since it has the same digest as the
+    /// element but withholds part of its value.
+    ///
+    /// # Parameters
+    /// - `opening`: The opening to verify.
+    /// # Returns
+    /// `true` if no element of the opening is obscured and this envelope
+    /// contains an element, elided or not, with the opening's digest, `false`
+    /// otherwise.
+    pub fn verify_opening(&self, opening: &Envelope) -> bool {
+        let has_obscured_element = Cell::new(false);
+        let visitor = |element: Envelope, _: usize, _: EdgeType, _: Option<&()>| -> _ {
+            if element.is_obscured() {
+                has_obscured_element.set(true);
+            }
+            None
+        };
+        opening.walk(false, &visitor);
+        !has_obscured_element.get() && self.deep_digests_ref().contains(&opening.digest())
+    }
+
+    /// Returns a version of this envelope that commits to the exact count and
+    /// order of its assertions.
+    ///
+    /// The commitment is an `"assertionCommitment"` assertion whose object is
+    /// an array of the digests of the envelope's other assertions, in order.
+    /// Elided assertions keep their digests, so a verifier that requires the
+    /// commitment can tell from any elided version, without knowing the
+    /// envelope's original digest, whether assertions were withheld entirely.
+    ///
+    /// Any existing commitment is replaced. Assertions added afterwards are
+    /// not covered, so the commitment should be added last, before signing.
+    /// The commitment must be left revealed when eliding.
+    pub fn add_assertion_commitment(&self) -> Self {
+        let envelope = self
+            .assertions_with_predicate(ASSERTION_COMMITMENT)
+            .into_iter()
+            .fold(self.clone(), |envelope, commitment| envelope.remove_assertion(commitment));
+        let digests: Vec<CBOR> = envelope
+            .assertions()
+            .iter()
+            .map(|assertion| assertion.digest().into_owned().into())
+            .collect();
+        envelope.add_assertion(ASSERTION_COMMITMENT, CBOR::from(digests))
+    }
+
+    /// Returns the digests of the assertions committed to by this envelope's
+    /// assertion commitment that are not present, even elided.
+    ///
+    /// # Returns
+    /// The withheld assertions' digests, which are empty if none were
+    /// withheld, or an error if the envelope does not have exactly one
+    /// revealed commitment.
+    pub fn withheld_assertions(&self) -> Result<Vec<Digest>> {
+        let (commitment, committed) = self.assertion_commitment()?;
+        let present: HashSet<Digest> = self
+            .assertions()
+            .iter()
+            .filter(|assertion| assertion.digest() != commitment.digest())
+            .map(|assertion| assertion.digest().into_owned())
+            .collect();
+        Ok(committed.into_iter().filter(|digest| !present.contains(digest)).collect())
+    }
+
+    /// Verifies that this envelope's assertions, revealed or elided, are
+    /// exactly those committed to by its assertion commitment.
+    ///
+    /// # Returns
+    /// The envelope, or an error if it does not have exactly one revealed
+    /// commitment, or if any assertion was withheld or added.
+    pub fn verify_assertion_commitment(&self) -> Result<Self> {
+        let (commitment, committed) = self.assertion_commitment()?;
+        let present: Vec<Digest> = self
+            .assertions()
+            .iter()
+            .filter(|assertion| assertion.digest() != commitment.digest())
+            .map(|assertion| assertion.digest().into_owned())
+            .collect();
+        if present != committed {
+            bail!(EnvelopeError::AssertionCommitmentMismatch);
+        }
+        Ok(self.clone())
+    }
 }
 
 impl Envelope {
+    /// Returns the commitment assertion and the digests it commits to.
+    fn assertion_commitment(&self) -> Result<(Envelope, Vec<Digest>)> {
+        let commitment = match self.assertions_with_predicate(ASSERTION_COMMITMENT).as_slice() {
+            [] => bail!(EnvelopeError::MissingAssertionCommitment),
+            [commitment] => commitment.clone(),
+            _ => bail!(EnvelopeError::AmbiguousPredicate),
+        };
+        let Some(object) = commitment.subject().as_object() else {
+            bail!(EnvelopeError::InvalidFormat);
+        };
+        let committed = object
+            .try_leaf()?
+            .try_into_array()?
+            .into_iter()
+            .map(Digest::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok((commitment, committed))
+    }
+
     fn reveal_set_of_set(&self, target: &HashSet<Digest>) -> HashSet<Digest> {
         let mut result = HashSet::new();
         self.reveal_sets(target, &HashSet::new(), &mut result);
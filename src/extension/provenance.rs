@@ -0,0 +1,188 @@
+use anyhow::{bail, Result};
+use bc_components::{Digest, DigestProvider};
+use dcbor::Date;
+
+use crate::{Envelope, EnvelopeEncodable, EnvelopeError};
+use crate::extension::known_values;
+
+/// The predicate of the assertions on a journal envelope whose objects are
+/// its entries.
+pub const JOURNAL_ENTRY: &str = "journalEntry";
+
+/// The predicate of the assertion on a journal entry whose object is the
+/// digest of the entry before it.
+pub const PREVIOUS_ENTRY: &str = "previousEntry";
+
+/// A journal of edits made to an envelope.
+///
+/// Each journaled editing call (for example [`Envelope::add_assertion_journaled`])
+/// records an entry describing the operation, the digests of the envelope
+/// before and after the edit, the digest of the affected assertion, and the
+/// date of the edit. Each entry after the first also records the digest of
+/// the entry before it, so the entries form a hash chain. The journal can then
+/// be rendered as an envelope using [`EditJournal::journal_envelope`], signed,
+/// and shipped alongside the edited document as a tamper-evident change log,
+/// whose entries a reader recovers in order with
+/// [`EditJournal::verified_entries`].
+#[derive(Debug, Clone)]
+pub struct EditJournal {
+    editor: Envelope,
+    entries: Vec<Envelope>,
+    test_date: Option<Date>,
+}
+
+impl EditJournal {
+    /// Creates a new, empty journal for edits made by `editor`.
+    pub fn new(editor: impl EnvelopeEncodable) -> Self {
+        Self::new_opt(editor, None)
+    }
+
+    #[doc(hidden)]
+    /// Creates a new, empty journal that stamps every entry with the given date.
+    ///
+    /// Only used for testing.
+    pub fn new_opt(editor: impl EnvelopeEncodable, test_date: Option<Date>) -> Self {
+        Self {
+            editor: editor.into_envelope(),
+            entries: Vec::new(),
+            test_date,
+        }
+    }
+
+    /// The editor responsible for the journaled edits.
+    pub fn editor(&self) -> &Envelope {
+        &self.editor
+    }
+
+    /// The recorded entries, in the order the edits were made.
+    pub fn entries(&self) -> &[Envelope] {
+        &self.entries
+    }
+
+    /// The number of recorded entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// `true` if no edits have been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the journal as an envelope.
+    ///
+    /// The subject is the editor, and each entry is attached as a
+    /// `"journalEntry"` assertion. Entries carry a `"sequence"` number and the
+    /// digest of the previous entry, so their order can be recovered despite
+    /// assertions being unordered, and an entry that is dropped or reordered
+    /// is detected.
+    pub fn journal_envelope(&self) -> Envelope {
+        self.entries
+            .iter()
+            .fold(self.editor.clone(), |e, entry| e.add_assertion(JOURNAL_ENTRY, entry.clone()))
+    }
+
+    /// Returns the entries of a journal envelope made by
+    /// [`EditJournal::journal_envelope`], in the order the edits were made.
+    ///
+    /// Returns `EnvelopeError::InvalidJournal` unless the entries form a
+    /// single chain from the first, in which each entry names the digest of
+    /// the one before it, has the next sequence number, and starts from the
+    /// version of the document the previous edit produced. Entries dropped
+    /// from the end of the chain cannot be detected this way, so the journal
+    /// envelope should be signed.
+    pub fn verified_entries(journal: &Envelope) -> Result<Vec<Envelope>> {
+        let mut remaining = journal.objects_for_predicate(JOURNAL_ENTRY);
+        let mut entries: Vec<Envelope> = Vec::with_capacity(remaining.len());
+        while !remaining.is_empty() {
+            let previous = entries.last().map(|entry| entry.digest().into_owned());
+            let position = remaining
+                .iter()
+                .position(|entry| {
+                    entry.extract_optional_object_for_predicate::<Digest>(PREVIOUS_ENTRY).ok() == Some(previous.clone())
+                })
+                .ok_or(EnvelopeError::InvalidJournal)?;
+            let entry = remaining.swap_remove(position);
+            if entry.extract_object_for_predicate::<usize>("sequence")? != entries.len() {
+                bail!(EnvelopeError::InvalidJournal);
+            }
+            if let Some(previous) = entries.last() {
+                let after: Digest = previous.extract_object_for_predicate("after")?;
+                if entry.extract_object_for_predicate::<Digest>("before")? != after {
+                    bail!(EnvelopeError::InvalidJournal);
+                }
+            }
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+
+    fn record(&mut self, operation: &str, before: &Envelope, after: &Envelope, assertion: &Envelope) {
+        let date = self.test_date.clone().unwrap_or_else(Date::now);
+        let previous = self.entries.last().map(|entry| entry.digest().into_owned());
+        let entry = Envelope::new(operation)
+            .add_assertion("sequence", self.entries.len())
+            .add_optional_assertion(PREVIOUS_ENTRY, previous)
+            .add_assertion(known_values::DATE, date)
+            .add_assertion("before", before.digest().into_owned())
+            .add_assertion("after", after.digest().into_owned())
+            .add_assertion("assertion", assertion.digest().into_owned());
+        self.entries.push(entry);
+    }
+}
+
+/// Support for editing envelopes while recording an edit journal.
+impl Envelope {
+    /// Returns the result of adding the given assertion to the envelope,
+    /// recording the edit in `journal`.
+    pub fn add_assertion_journaled(
+        &self,
+        predicate: impl EnvelopeEncodable,
+        object: impl EnvelopeEncodable,
+        journal: &mut EditJournal,
+    ) -> Self {
+        self.add_assertion_envelope_journaled(Self::new_assertion(predicate, object), journal)
+            .unwrap()
+    }
+
+    /// Returns the result of adding the given assertion envelope to the
+    /// envelope, recording the edit in `journal`.
+    ///
+    /// The assertion envelope must be a valid assertion envelope, or an
+    /// obscured variant (elided, encrypted, compressed) of one.
+    pub fn add_assertion_envelope_journaled(
+        &self,
+        assertion_envelope: impl EnvelopeEncodable,
+        journal: &mut EditJournal,
+    ) -> Result<Self> {
+        let assertion = assertion_envelope.into_envelope();
+        let result = self.add_assertion_envelope(assertion.clone())?;
+        journal.record("add", self, &result, &assertion);
+        Ok(result)
+    }
+
+    /// Returns a new envelope with the given assertion removed, recording the
+    /// edit in `journal`.
+    ///
+    /// If the envelope has no such assertion, it is returned unchanged and
+    /// nothing is recorded.
+    pub fn remove_assertion_journaled(&self, target: Self, journal: &mut EditJournal) -> Self {
+        let result = self.remove_assertion(target.clone());
+        if result.digest() != self.digest() {
+            journal.record("remove", self, &result, &target);
+        }
+        result
+    }
+
+    /// Returns a new envelope with the given assertion replaced by the provided
+    /// one, recording both the removal and the addition in `journal`.
+    pub fn replace_assertion_journaled(
+        &self,
+        assertion: Self,
+        new_assertion: Self,
+        journal: &mut EditJournal,
+    ) -> Result<Self> {
+        self.remove_assertion_journaled(assertion, journal)
+            .add_assertion_envelope_journaled(new_assertion, journal)
+    }
+}
@@ -0,0 +1,233 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use bc_components::{Digest, DigestProvider, URI};
+use dcbor::{prelude::*, Date, Simple};
+
+use crate::{base::envelope::EnvelopeCase, extension::{KnownValue, KNOWN_VALUES}, Envelope};
+
+/// The namespace of the structural terms and datatypes used in exported RDF.
+pub const RDF_ENVELOPE_NAMESPACE: &str = "https://blockchaincommons.com/ns/envelope#";
+
+/// The namespace to which known values are mapped in exported RDF.
+pub const RDF_KNOWN_VALUE_NAMESPACE: &str = "https://blockchaincommons.com/ns/known-value#";
+
+/// The namespace to which string predicates are mapped in exported RDF.
+pub const RDF_STRING_PREDICATE_NAMESPACE: &str = "https://blockchaincommons.com/ns/string-predicate#";
+
+/// Support for exporting envelopes as RDF.
+impl Envelope {
+    /// Returns the envelope as an RDF graph in Turtle notation.
+    ///
+    /// Each envelope that has assertions, or that appears as the object of an
+    /// assertion and is not a simple value, is represented by a blank node
+    /// labelled with its digest. Each assertion becomes a triple whose subject
+    /// is that blank node.
+    ///
+    /// * A leaf subject is given as the `rdf:value` of its blank node, and a
+    ///   wrapped subject as its `bc:wraps`.
+    /// * Known values become IRIs in the `kv:` namespace, named as in the
+    ///   registry of standard known values, or numbered if they are not in
+    ///   it. Text predicates become IRIs in the `str:` namespace. Predicates
+    ///   that are URIs are used directly.
+    /// * Leaf objects become literals, using XSD datatypes where possible. Other
+    ///   CBOR is given as a `bc:cbor` literal of its hex encoding.
+    /// * Obscured elements are given the type `bc:Elided`, `bc:Encrypted`, or
+    ///   `bc:Compressed`.
+    ///
+    /// Assertions whose predicates cannot be represented as an IRI, such as
+    /// numbers or obscured predicates, are written as comments.
+    pub fn to_turtle(&self) -> String {
+        let mut exporter = TurtleExporter::default();
+        let root = exporter.node_term(self);
+        let mut output = String::new();
+        writeln!(output, "@prefix rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#> .").unwrap();
+        writeln!(output, "@prefix xsd: <http://www.w3.org/2001/XMLSchema#> .").unwrap();
+        writeln!(output, "@prefix bc: <{}> .", RDF_ENVELOPE_NAMESPACE).unwrap();
+        writeln!(output, "@prefix kv: <{}> .", RDF_KNOWN_VALUE_NAMESPACE).unwrap();
+        writeln!(output, "@prefix str: <{}> .", RDF_STRING_PREDICATE_NAMESPACE).unwrap();
+        writeln!(output).unwrap();
+        writeln!(output, "# root: {}", root).unwrap();
+        for line in exporter.lines {
+            writeln!(output, "{}", line).unwrap();
+        }
+        output
+    }
+}
+
+#[derive(Default)]
+struct TurtleExporter {
+    visited: HashSet<Digest>,
+    lines: Vec<String>,
+}
+
+impl TurtleExporter {
+    /// Returns the blank node for `envelope`, emitting its triples the first
+    /// time it is seen.
+    fn node_term(&mut self, envelope: &Envelope) -> String {
+        let term = blank_node(envelope);
+        if !self.visited.insert(envelope.digest().into_owned()) {
+            return term;
+        }
+        let subject = envelope.subject();
+        match subject.case() {
+            EnvelopeCase::Leaf { cbor, .. } => {
+                let value = leaf_term(cbor);
+                self.triple(&term, "rdf:value", &value);
+            }
+            EnvelopeCase::KnownValue { value, .. } => {
+                self.triple(&term, "rdf:value", &known_value_term(value));
+            }
+            EnvelopeCase::Wrapped { envelope: wrapped, .. } => {
+                let wrapped = self.node_term(wrapped);
+                self.triple(&term, "bc:wraps", &wrapped);
+            }
+            EnvelopeCase::Assertion(_) | EnvelopeCase::Node { .. } => {}
+            _ => {
+                self.triple(&term, "a", obscured_type(&subject));
+            }
+        }
+        for assertion in envelope.assertions() {
+            let (Ok(predicate), Ok(object)) = (assertion.try_predicate(), assertion.try_object()) else {
+                let obscured = self.node_term(&assertion);
+                self.triple(&term, "bc:hasObscuredAssertion", &obscured);
+                continue;
+            };
+            let Some(predicate_term) = predicate_term(&predicate) else {
+                self.lines.push(format!("# {} has an assertion {} whose predicate is not an IRI", term, blank_node(&assertion)));
+                continue;
+            };
+            let object_term = self.object_term(&object);
+            self.triple(&term, &predicate_term, &object_term);
+        }
+        term
+    }
+
+    fn object_term(&mut self, object: &Envelope) -> String {
+        if !object.has_assertions() {
+            match object.case() {
+                EnvelopeCase::Leaf { cbor, .. } => return leaf_term(cbor),
+                EnvelopeCase::KnownValue { value, .. } => {
+                    return known_value_term(value)
+                }
+                _ => {}
+            }
+        }
+        self.node_term(object)
+    }
+
+    fn triple(&mut self, subject: &str, predicate: &str, object: &str) {
+        self.lines.push(format!("{} {} {} .", subject, predicate, object));
+    }
+}
+
+fn blank_node(envelope: &Envelope) -> String {
+    format!("_:{}", hex::encode(envelope.digest().data()))
+}
+
+fn obscured_type(envelope: &Envelope) -> &'static str {
+    match envelope.case() {
+        #[cfg(feature = "encrypt")]
+        EnvelopeCase::Encrypted(_) => "bc:Encrypted",
+        #[cfg(feature = "compress")]
+        EnvelopeCase::Compressed(_) => "bc:Compressed",
+        _ => "bc:Elided",
+    }
+}
+
+fn predicate_term(predicate: &Envelope) -> Option<String> {
+    if predicate.has_assertions() {
+        return None;
+    }
+    match predicate.case() {
+        EnvelopeCase::KnownValue { value, .. } => Some(known_value_term(value)),
+        EnvelopeCase::Leaf { cbor, .. } => {
+            if let Ok(uri) = URI::try_from(cbor.clone()) {
+                Some(format!("<{}>", uri))
+            } else if let CBORCase::Text(text) = cbor.as_case() {
+                Some(format!("str:{}", local_name(text)))
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Names known values from the registry of standard known values rather than
+/// by the names they carry, which are lost when an envelope is decoded, so
+/// that equal envelopes export the same RDF.
+fn known_value_term(value: &KnownValue) -> String {
+    let binding = KNOWN_VALUES.get();
+    match binding.as_ref().and_then(|known_values| known_values.assigned_name(value)) {
+        Some(name) => format!("kv:{}", local_name(name)),
+        None => format!("kv:{}", value.value()),
+    }
+}
+
+fn leaf_term(cbor: &CBOR) -> String {
+    match cbor.as_case() {
+        CBORCase::Text(text) => string_literal(text),
+        CBORCase::Unsigned(n) => format!("\"{}\"^^xsd:integer", n),
+        CBORCase::Negative(n) => format!("\"{}\"^^xsd:integer", -1 - (*n as i128)),
+        CBORCase::ByteString(data) => format!("\"{}\"^^xsd:hexBinary", hex::encode(data)),
+        CBORCase::Simple(Simple::True) => "\"true\"^^xsd:boolean".to_string(),
+        CBORCase::Simple(Simple::False) => "\"false\"^^xsd:boolean".to_string(),
+        CBORCase::Simple(Simple::Float(f)) => format!("\"{}\"^^xsd:double", double_lexical(*f)),
+        _ => {
+            if let Ok(date) = Date::try_from(cbor.clone()) {
+                format!("\"{}\"^^xsd:dateTime", date.datetime().to_rfc3339())
+            } else if let Ok(uri) = URI::try_from(cbor.clone()) {
+                format!("<{}>", uri)
+            } else {
+                format!("\"{}\"^^bc:cbor", hex::encode(cbor.to_cbor_data()))
+            }
+        }
+    }
+}
+
+/// Rust spells the special values `NaN`, `inf`, and `-inf`, but the
+/// `xsd:double` lexical space only admits `NaN`, `INF`, and `-INF`.
+fn double_lexical(f: f64) -> String {
+    if f.is_nan() {
+        "NaN".to_string()
+    } else if f.is_infinite() {
+        if f.is_sign_positive() { "INF" } else { "-INF" }.to_string()
+    } else {
+        format!("{:e}", f)
+    }
+}
+
+fn string_literal(text: &str) -> String {
+    let mut result = String::with_capacity(text.len() + 2);
+    result.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            _ => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+/// Percent-encodes every character of `name` that may not appear unescaped in
+/// a Turtle local name.
+fn local_name(name: &str) -> String {
+    let mut result = String::with_capacity(name.len());
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            result.push(c);
+        } else {
+            let mut buf = [0u8; 4];
+            for byte in c.encode_utf8(&mut buf).bytes() {
+                write!(result, "%{:02X}", byte).unwrap();
+            }
+        }
+    }
+    result
+}
@@ -4,6 +4,12 @@ use crate::extension::known_values;
 
 #[cfg(feature = "encrypt")]
 use bc_components::Decrypter;
+#[cfg(feature = "encrypt")]
+use zeroize::Zeroizing;
+#[cfg(feature = "encrypt")]
+use crate::SecretEnvelopeContent;
+#[cfg(feature = "encrypt")]
+use crate::extension::{KeyProvider, CONTENT_KEY_ID};
 
 use anyhow::{bail, Result};
 use bc_components::{SealedMessage, SymmetricKey, Nonce, Encrypter};
@@ -76,11 +82,39 @@ impl Envelope {
     ) -> Result<Self>
     {
         let content_key = SymmetricKey::new();
-        let mut e = self.encrypt_subject(&content_key)?;
+        let e = self.encrypt_subject(&content_key)?;
+        Ok(e.add_recipients_opt(recipients, &content_key, test_nonce))
+    }
+
+    /// Returns a new envelope with its subject encrypted with the next key
+    /// from `provider` and a `hasRecipient` assertion added for each of the
+    /// `recipients`, as well as the `contentKeyID` assertion added by
+    /// [`Envelope::encrypt_subject_with_provider`].
+    ///
+    /// Recipients may decrypt the subject either with
+    /// [`Envelope::decrypt_subject_to_recipient`] or, if they share the
+    /// provider's state, with [`Envelope::decrypt_subject_with_provider`].
+    #[cfg(feature = "encrypt")]
+    pub fn encrypt_subject_to_recipients_with_provider(
+        &self,
+        recipients: &[&dyn Encrypter],
+        provider: &mut dyn KeyProvider,
+    ) -> Result<Self>
+    {
+        let (key_id, content_key) = provider.next_content_key()?;
+        let e = self
+            .encrypt_subject(&content_key)?
+            .add_assertion(CONTENT_KEY_ID, ByteString::from(key_id));
+        Ok(e.add_recipients_opt(recipients, &content_key, None))
+    }
+
+    #[cfg(feature = "encrypt")]
+    fn add_recipients_opt(&self, recipients: &[&dyn Encrypter], content_key: &SymmetricKey, test_nonce: Option<&Nonce>) -> Self {
+        let mut e = self.clone();
         for recipient in recipients {
-            e = e.add_recipient_opt(*recipient, &content_key, test_nonce);
+            e = e.add_recipient_opt(*recipient, content_key, test_nonce);
         }
-        Ok(e)
+        e
     }
 
     /// Returns a new envelope with its subject encrypted and a `hasRecipient`
@@ -104,11 +138,11 @@ impl Envelope {
     }
 
     #[cfg(feature = "encrypt")]
-    fn first_plaintext_in_sealed_messages(sealed_messages: &[SealedMessage], private_key: &dyn Decrypter) -> Result<Vec<u8>> {
+    fn first_plaintext_in_sealed_messages(sealed_messages: &[SealedMessage], private_key: &dyn Decrypter) -> Result<Zeroizing<Vec<u8>>> {
         for sealed_message in sealed_messages {
             let a = sealed_message.decrypt(private_key).ok();
             if let Some(plaintext) = a {
-                return Ok(plaintext);
+                return Ok(Zeroizing::new(plaintext));
             }
         }
         bail!(EnvelopeError::UnknownRecipient)
@@ -127,10 +161,22 @@ impl Envelope {
     pub fn decrypt_subject_to_recipient(&self, recipient: &dyn Decrypter) -> Result<Self> {
         let sealed_messages = self.clone().recipients()?;
         let content_key_data = Self::first_plaintext_in_sealed_messages(&sealed_messages, recipient)?;
-        let content_key = SymmetricKey::from_tagged_cbor_data(content_key_data)?;
+        let content_key = SymmetricKey::from_tagged_cbor_data(content_key_data.as_slice())?;
         self.decrypt_subject(&content_key)
     }
 
+    /// Returns the decrypted content of the envelope's subject using the
+    /// recipient's `Decrypter`, which is zeroized when it is dropped.
+    ///
+    /// See [`Envelope::decrypt_subject_to_secret`].
+    #[cfg(feature = "encrypt")]
+    pub fn decrypt_subject_to_recipient_secret(&self, recipient: &dyn Decrypter) -> Result<SecretEnvelopeContent> {
+        let sealed_messages = self.clone().recipients()?;
+        let content_key_data = Self::first_plaintext_in_sealed_messages(&sealed_messages, recipient)?;
+        let content_key = SymmetricKey::from_tagged_cbor_data(content_key_data.as_slice())?;
+        self.decrypt_subject_to_secret(&content_key)
+    }
+
     /// Convenience constructor for a `hasRecipient: SealedMessage` assertion.
     ///
     /// The `SealedMessage` contains the `contentKey` encrypted to the recipient's `PublicKeyBase`.
@@ -0,0 +1,184 @@
+use anyhow::{bail, Result};
+use bc_components::{Encrypter, PublicKeyBase};
+use bc_components::Decrypter;
+
+use crate::{extension::known_values, Envelope, EnvelopeError};
+
+/// The predicate of the assertion describing the group an envelope was
+/// encrypted to.
+pub const RECIPIENT_GROUP: &str = "recipientGroup";
+
+/// The predicate of the assertions listing the roles of a recipient group.
+pub const ROLE: &str = "role";
+
+/// A named set of public keys, each of which belongs to a member with a role.
+///
+/// Encrypting an envelope to a group with
+/// [`Envelope::encrypt_subject_to_group`] rather than to a list of keys lets
+/// applications manage access to documents by role. When membership changes,
+/// update the group and re-encrypt existing envelopes to it with
+/// [`Envelope::reencrypt_to_group`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecipientGroup {
+    name: String,
+    members: Vec<(PublicKeyBase, String)>,
+}
+
+impl RecipientGroup {
+    /// Creates an empty group with the given name.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into(), members: Vec::new() }
+    }
+
+    /// The name of the group.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the group with the given member added.
+    ///
+    /// See [`RecipientGroup::add_member`].
+    pub fn with_member(mut self, key: PublicKeyBase, role: impl Into<String>) -> Self {
+        self.add_member(key, role);
+        self
+    }
+
+    /// Adds a member with the given key and role, or if the key already
+    /// belongs to a member, changes that member's role.
+    pub fn add_member(&mut self, key: PublicKeyBase, role: impl Into<String>) {
+        let role = role.into();
+        match self.members.iter_mut().find(|(member, _)| *member == key) {
+            Some((_, existing)) => *existing = role,
+            None => self.members.push((key, role)),
+        }
+    }
+
+    /// Removes the member with the given key, returning whether there was one.
+    pub fn remove_member(&mut self, key: &PublicKeyBase) -> bool {
+        let count = self.members.len();
+        self.members.retain(|(member, _)| member != key);
+        self.members.len() != count
+    }
+
+    /// Replaces the key of a member with a new key, keeping their role.
+    ///
+    /// Returns an error if `old_key` does not belong to a member.
+    pub fn rotate_member_key(&mut self, old_key: &PublicKeyBase, new_key: PublicKeyBase) -> Result<()> {
+        let Some((key, _)) = self.members.iter_mut().find(|(member, _)| member == old_key) else {
+            bail!(EnvelopeError::UnknownRecipient);
+        };
+        *key = new_key;
+        Ok(())
+    }
+
+    /// The role of the member with the given key, if any.
+    pub fn role_of(&self, key: &PublicKeyBase) -> Option<&str> {
+        self.members
+            .iter()
+            .find(|(member, _)| member == key)
+            .map(|(_, role)| role.as_str())
+    }
+
+    /// The keys of all members, in the order they were added.
+    pub fn keys(&self) -> Vec<&PublicKeyBase> {
+        self.members.iter().map(|(key, _)| key).collect()
+    }
+
+    /// The keys of the members with the given role.
+    pub fn keys_with_role(&self, role: &str) -> Vec<&PublicKeyBase> {
+        self.members
+            .iter()
+            .filter(|(_, member_role)| member_role == role)
+            .map(|(key, _)| key)
+            .collect()
+    }
+
+    /// The distinct roles of the members, in sorted order.
+    pub fn roles(&self) -> Vec<&str> {
+        let mut roles: Vec<&str> = self.members.iter().map(|(_, role)| role.as_str()).collect();
+        roles.sort();
+        roles.dedup();
+        roles
+    }
+
+    /// Returns a group with the same name containing only the members with
+    /// one of the given roles.
+    pub fn restricted_to_roles(&self, roles: &[&str]) -> Self {
+        Self {
+            name: self.name.clone(),
+            members: self.members
+                .iter()
+                .filter(|(_, role)| roles.contains(&role.as_str()))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// The number of members.
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    /// `true` if the group has no members.
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+}
+
+/// Support for encrypting envelopes to recipient groups.
+impl Envelope {
+    /// Returns a new envelope with its subject encrypted, a `hasRecipient`
+    /// assertion added for each member of `group`, and a `recipientGroup`
+    /// assertion whose object is the group's name with a `role` assertion for
+    /// each of its roles.
+    ///
+    /// The group metadata is not encrypted, so it is visible to anyone holding
+    /// the envelope.
+    ///
+    /// Returns an error if the group has no members, or if the subject is
+    /// already encrypted.
+    pub fn encrypt_subject_to_group(&self, group: &RecipientGroup) -> Result<Self> {
+        if group.is_empty() {
+            bail!(EnvelopeError::EmptyRecipientGroup);
+        }
+        let recipients: Vec<&dyn Encrypter> = group
+            .keys()
+            .into_iter()
+            .map(|key| key as &dyn Encrypter)
+            .collect();
+        let metadata = group
+            .roles()
+            .into_iter()
+            .fold(Envelope::new(group.name()), |metadata, role| metadata.add_assertion(ROLE, role));
+        Ok(self
+            .encrypt_subject_to_recipients(&recipients)?
+            .add_assertion(RECIPIENT_GROUP, metadata))
+    }
+
+    /// Returns the name of the group the envelope was encrypted to with
+    /// [`Envelope::encrypt_subject_to_group`].
+    ///
+    /// Returns an error if there is not exactly one `recipientGroup`
+    /// assertion.
+    pub fn recipient_group_name(&self) -> Result<String> {
+        self.object_for_predicate(RECIPIENT_GROUP)?.extract_subject()
+    }
+
+    /// Returns a new envelope encrypted to `group` in place of the recipients
+    /// this envelope was encrypted to.
+    ///
+    /// `recipient` must be able to decrypt the envelope. The subject is
+    /// encrypted with a new content key, so members removed from the group
+    /// cannot decrypt the result even if they kept the previous one.
+    pub fn reencrypt_to_group(&self, recipient: &dyn Decrypter, group: &RecipientGroup) -> Result<Self> {
+        let mut decrypted = self.decrypt_subject_to_recipient(recipient)?;
+        let obsolete = decrypted
+            .assertions_with_predicate(known_values::HAS_RECIPIENT)
+            .into_iter()
+            .chain(decrypted.assertions_with_predicate(RECIPIENT_GROUP));
+        for assertion in obsolete.collect::<Vec<_>>() {
+            decrypted = decrypted.remove_assertion(assertion);
+        }
+        decrypted.encrypt_subject_to_group(group)
+    }
+}
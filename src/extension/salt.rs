@@ -1,14 +1,18 @@
-use std::ops::RangeInclusive;
+use std::{collections::HashSet, ops::RangeInclusive};
 
-use crate::Envelope;
+use crate::{Assertion, Envelope, base::envelope::EnvelopeCase};
 #[cfg(feature = "known_value")]
 use crate::extension::known_values;
 
 use anyhow::Result;
-use bc_components::Salt;
+use bc_components::{DigestProvider, Salt};
 use bc_rand::{RandomNumberGenerator, SecureRandomNumberGenerator};
 use dcbor::prelude::*;
 
+/// The number of bytes of salt in each decoy added by
+/// [`Envelope::pad_assertions`].
+const DECOY_SALT_LEN: usize = 16;
+
 /// Support for decorrelation of envelopes using salt.
 impl Envelope {
     /// Add a number of bytes of salt generally proportionate to the size of the object being salted.
@@ -63,4 +67,96 @@ impl Envelope {
         let salt = Salt::new_for_size_using(self.tagged_cbor().to_cbor_data().len(), rng);
         self.add_salt_instance(salt)
     }
+
+    /// Returns every `'salt'` assertion in the envelope, paired with the node
+    /// it is an assertion on, in depth-first order.
+    ///
+    /// Salt assertions that are obscured cannot be recognized, so they are
+    /// not included.
+    pub fn salts(&self) -> Vec<(Envelope, Envelope)> {
+        let mut salts = Vec::new();
+        self.collect_salts(&mut salts);
+        salts
+    }
+
+    fn collect_salts(&self, salts: &mut Vec<(Envelope, Envelope)>) {
+        match self.case() {
+            EnvelopeCase::Node { subject, assertions, .. } => {
+                subject.collect_salts(salts);
+                for assertion in assertions {
+                    if assertion.is_salt_assertion() {
+                        salts.push((assertion.clone(), self.clone()));
+                    } else {
+                        assertion.collect_salts(salts);
+                    }
+                }
+            }
+            EnvelopeCase::Wrapped { envelope, .. } => envelope.collect_salts(salts),
+            EnvelopeCase::Assertion(assertion) => {
+                assertion.predicate().collect_salts(salts);
+                assertion.object().collect_salts(salts);
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns the envelope with every `'salt'` assertion removed.
+    ///
+    /// This produces the semantic core of the envelope, so that envelopes
+    /// that differ only in their salt, and which therefore have different
+    /// digests, become equivalent. The digests of the result differ from
+    /// those of this envelope wherever salt was removed, so signatures on
+    /// salted elements no longer verify. Obscured salt assertions are kept,
+    /// and assertions that differed only in their salt are merged.
+    pub fn strip_salts(&self) -> Self {
+        match self.case() {
+            EnvelopeCase::Node { subject, assertions, .. } => {
+                let subject = subject.strip_salts();
+                let mut seen = HashSet::new();
+                let assertions: Vec<Envelope> = assertions
+                    .iter()
+                    .filter(|assertion| !assertion.is_salt_assertion())
+                    .map(|assertion| assertion.strip_salts())
+                    .filter(|assertion| seen.insert(assertion.digest().into_owned()))
+                    .collect();
+                if assertions.is_empty() {
+                    subject
+                } else {
+                    Envelope::new_with_unchecked_assertions(subject, assertions)
+                }
+            }
+            EnvelopeCase::Wrapped { envelope, .. } => envelope.strip_salts().wrap_envelope(),
+            EnvelopeCase::Assertion(assertion) => {
+                Envelope::new_with_assertion(Assertion::new(assertion.predicate().strip_salts(), assertion.object().strip_salts()))
+            }
+            _ => self.clone(),
+        }
+    }
+
+    /// Returns the envelope with decoy `'salt'` assertions added until it has
+    /// at least `count` assertions.
+    ///
+    /// Each decoy has its own random salt, so once elided it cannot be told
+    /// apart from the envelope's other elided assertions. Padding every node
+    /// of a class of documents to the same count before signing, and eliding
+    /// the decoys along with the withheld assertions, hides how many real
+    /// assertions were withheld. See [`Envelope::leakage_report`].
+    pub fn pad_assertions(&self, count: usize) -> Self {
+        let mut rng = SecureRandomNumberGenerator;
+        self.pad_assertions_using(count, &mut rng)
+    }
+
+    #[doc(hidden)]
+    /// Returns the envelope with decoy `'salt'` assertions added until it has
+    /// at least `count` assertions.
+    pub fn pad_assertions_using(&self, count: usize, rng: &mut impl RandomNumberGenerator) -> Self {
+        let padding = count.saturating_sub(self.assertions().len());
+        (0..padding).fold(self.clone(), |envelope, _| {
+            envelope.add_salt_instance(Salt::new_with_len_using(DECOY_SALT_LEN, rng).unwrap())
+        })
+    }
+
+    fn is_salt_assertion(&self) -> bool {
+        self.as_predicate().is_some_and(|predicate| predicate.as_known_value() == Some(&known_values::SALT))
+    }
 }
@@ -0,0 +1,182 @@
+use std::{
+    num::NonZeroUsize,
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::{mpsc, Arc, Mutex, PoisonError},
+    thread,
+};
+
+use anyhow::Result;
+use bc_components::{Digest, DigestProvider, Verifier};
+
+use crate::{Envelope, EnvelopeError, IngestLimits, TimePolicy};
+
+/// How [`verify_corpus`] checks each envelope.
+#[derive(Debug, Clone)]
+pub struct CorpusPolicy {
+    workers: usize,
+    limits: IngestLimits,
+    verify_signatures: bool,
+    time_policy: Option<TimePolicy>,
+}
+
+impl Default for CorpusPolicy {
+    fn default() -> Self {
+        Self {
+            workers: thread::available_parallelism().map_or(1, NonZeroUsize::get),
+            limits: IngestLimits::default(),
+            verify_signatures: true,
+            time_policy: None,
+        }
+    }
+}
+
+impl CorpusPolicy {
+    /// Creates a policy that checks the structure and signature of each
+    /// envelope using one worker per available CPU.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the policy with the given number of worker threads.
+    pub fn with_workers(mut self, workers: usize) -> Self {
+        self.workers = workers.max(1);
+        self
+    }
+
+    /// Returns the policy with the limits each envelope is ingested within.
+    pub fn with_ingest_limits(mut self, limits: IngestLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Returns the policy checking only the structure of each envelope if
+    /// `verify_signatures` is `false`.
+    pub fn with_verify_signatures(mut self, verify_signatures: bool) -> Self {
+        self.verify_signatures = verify_signatures;
+        self
+    }
+
+    /// Returns the policy checking the metadata of each signature against
+    /// `time_policy`, as [`Envelope::verify_signature_from_with_policy`] does.
+    pub fn with_time_policy(mut self, time_policy: TimePolicy) -> Self {
+        self.time_policy = Some(time_policy);
+        self
+    }
+
+    pub fn workers(&self) -> usize {
+        self.workers
+    }
+
+    fn verify(&self, data: &[u8], verifier: &dyn Verifier) -> (Option<Digest>, Result<()>) {
+        let envelope = match Envelope::ingest_cbor_data(data, &self.limits) {
+            Ok(envelope) => envelope,
+            Err(error) => return (None, Err(error.into())),
+        };
+        let digest = Some(envelope.digest().into_owned());
+        if !self.verify_signatures {
+            return (digest, Ok(()));
+        }
+        let result = match &self.time_policy {
+            Some(time_policy) => envelope.verify_signature_from_with_policy(verifier, time_policy),
+            None => envelope.verify_signature_from(verifier),
+        };
+        (digest, result.map(|_| ()))
+    }
+}
+
+/// The outcome of verifying one envelope of a corpus.
+#[derive(Debug)]
+pub struct CorpusResult {
+    /// The position of the envelope in the corpus.
+    pub index: usize,
+    /// The digest of the envelope, or `None` if it could not be decoded.
+    pub digest: Option<Digest>,
+    /// `Ok` if the envelope passed every check, or the first error found.
+    pub result: Result<()>,
+}
+
+impl CorpusResult {
+    pub fn is_ok(&self) -> bool {
+        self.result.is_ok()
+    }
+}
+
+/// The results of [`verify_corpus`], in the order they complete.
+///
+/// Dropping the iterator before it is exhausted stops the workers once they
+/// finish the envelopes they are verifying.
+#[derive(Debug)]
+pub struct CorpusResults {
+    receiver: mpsc::Receiver<CorpusResult>,
+}
+
+impl Iterator for CorpusResults {
+    type Item = CorpusResult;
+
+    fn next(&mut self) -> Option<CorpusResult> {
+        self.receiver.recv().ok()
+    }
+}
+
+/// Verifies a corpus of envelopes using a pool of worker threads, returning
+/// each envelope's result as soon as it completes.
+///
+/// Each item is the tagged CBOR encoding of an envelope. It is ingested
+/// within the policy's [`IngestLimits`], and unless the policy checks only
+/// structure, its subject must be signed by `verifier`. Items are decoded by
+/// the workers, so the corpus may be read lazily, and no more than a few
+/// items per worker are held in memory at once.
+///
+/// Results arrive in the order they complete, which is not in general the
+/// order of the corpus; each carries the index of its item. A failure is
+/// reported for its item and does not stop the others, even if verifying the
+/// item panics, which is reported as `EnvelopeError::VerificationPanicked`.
+pub fn verify_corpus<I, V>(envelopes: I, verifier: V, policy: CorpusPolicy) -> CorpusResults
+where
+    I: IntoIterator<Item = Vec<u8>>,
+    I::IntoIter: Send + 'static,
+    V: Verifier + Send + Sync + 'static,
+{
+    let workers = policy.workers;
+    let verifier = Arc::new(verifier);
+    let policy = Arc::new(policy);
+    let (work_sender, work_receiver) = mpsc::sync_channel::<(usize, Vec<u8>)>(workers * 2);
+    let work_receiver = Arc::new(Mutex::new(work_receiver));
+    let (result_sender, result_receiver) = mpsc::channel();
+
+    for _ in 0..workers {
+        let work_receiver = Arc::clone(&work_receiver);
+        let result_sender = result_sender.clone();
+        let verifier = Arc::clone(&verifier);
+        let policy = Arc::clone(&policy);
+        thread::spawn(move || loop {
+            let next = work_receiver.lock().unwrap_or_else(PoisonError::into_inner).recv();
+            let Ok((index, data)) = next else {
+                break;
+            };
+            let (digest, result) = catch_unwind(AssertUnwindSafe(|| policy.verify(&data, verifier.as_ref())))
+                .unwrap_or_else(|panic| {
+                    let message = panic
+                        .downcast_ref::<&str>()
+                        .map(|message| message.to_string())
+                        .or_else(|| panic.downcast_ref::<String>().cloned())
+                        .unwrap_or_default();
+                    (None, Err(EnvelopeError::VerificationPanicked(message).into()))
+                });
+            if result_sender.send(CorpusResult { index, digest, result }).is_err() {
+                break;
+            }
+        });
+    }
+
+    let envelopes = envelopes.into_iter();
+    thread::spawn(move || {
+        for item in envelopes.enumerate() {
+            if work_sender.send(item).is_err() {
+                break;
+            }
+        }
+    });
+
+    CorpusResults { receiver: result_receiver }
+}
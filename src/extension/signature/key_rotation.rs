@@ -0,0 +1,182 @@
+use anyhow::{bail, Result};
+use bc_components::{PublicKeyBase, Signer};
+use dcbor::Date;
+
+use crate::{extension::known_values, Envelope, EnvelopeError};
+
+/// The predicate of the assertions on a document holding its key rotation
+/// attestations.
+///
+/// Each object is an envelope whose subject is the date the rotation takes
+/// effect, with a `"rotatedTo"` assertion for each new key, wrapped and
+/// signed by a key valid before the rotation:
+///
+/// ```text
+/// {
+///     Date [
+///         "rotatedTo": PublicKeyBase
+///     ]
+/// } [
+///     'signed': Signature
+/// ]
+/// ```
+pub const KEY_ROTATION: &str = "keyRotation";
+
+/// The predicate of the assertions in a key rotation attestation whose
+/// objects are the new keys.
+pub const ROTATED_TO: &str = "rotatedTo";
+
+#[derive(Debug, Clone, PartialEq)]
+struct KeyEpoch {
+    keys: Vec<PublicKeyBase>,
+    from: Option<Date>,
+}
+
+/// The keys that have controlled a document over time, built from its
+/// initial keys and the rotations attested by [`Envelope::attest_rotation`].
+///
+/// Each rotation must be signed by a key valid before it, so the history is
+/// a chain of trust back to the initial keys.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RotationHistory {
+    epochs: Vec<KeyEpoch>,
+}
+
+impl RotationHistory {
+    /// Creates a history in which `initial_keys` have always been valid.
+    pub fn new(initial_keys: Vec<PublicKeyBase>) -> Self {
+        Self { epochs: vec![KeyEpoch { keys: initial_keys, from: None }] }
+    }
+
+    /// Returns the history of a document with the given initial keys,
+    /// applying each of its `"keyRotation"` attestations in date order.
+    pub fn from_document(initial_keys: Vec<PublicKeyBase>, document: &Envelope) -> Result<Self> {
+        let mut rotations = document
+            .objects_for_predicate(KEY_ROTATION)
+            .into_iter()
+            .map(|rotation| Ok((rotation.unwrap_envelope()?.extract_subject::<Date>()?, rotation)))
+            .collect::<Result<Vec<_>>>()?;
+        rotations.sort_by(|(a, _), (b, _)| a.cmp(b));
+        rotations
+            .into_iter()
+            .try_fold(Self::new(initial_keys), |history, (_, rotation)| history.with_rotation(&rotation))
+    }
+
+    /// Returns the history extended by a rotation attestation.
+    ///
+    /// Returns an error if the attestation is not signed by one of the
+    /// current keys, or if it takes effect before the current keys did.
+    pub fn with_rotation(mut self, rotation: &Envelope) -> Result<Self> {
+        let current = self.epochs.last().unwrap();
+        let signer = current
+            .keys
+            .iter()
+            .find(|key| rotation.has_signature_from(*key).unwrap_or(false));
+        if signer.is_none() {
+            bail!(EnvelopeError::InvalidKeyRotation);
+        }
+        let body = rotation.unwrap_envelope()?;
+        let from: Date = body.extract_subject()?;
+        if current.from.as_ref().is_some_and(|current_from| from < *current_from) {
+            bail!(EnvelopeError::InvalidKeyRotation);
+        }
+        let keys: Vec<PublicKeyBase> = body.extract_objects_for_predicate(ROTATED_TO)?;
+        if keys.is_empty() {
+            bail!(EnvelopeError::InvalidKeyRotation);
+        }
+        self.epochs.push(KeyEpoch { keys, from: Some(from) });
+        Ok(self)
+    }
+
+    /// The keys valid now.
+    pub fn current_keys(&self) -> &[PublicKeyBase] {
+        &self.epochs.last().unwrap().keys
+    }
+
+    /// The keys that were valid at `date`.
+    pub fn keys_at(&self, date: &Date) -> &[PublicKeyBase] {
+        let epoch = self
+            .epochs
+            .iter()
+            .rev()
+            .find(|epoch| epoch.from.as_ref().is_none_or(|from| from <= date))
+            .unwrap();
+        &epoch.keys
+    }
+
+    /// The number of rotations in the history.
+    pub fn rotation_count(&self) -> usize {
+        self.epochs.len() - 1
+    }
+}
+
+/// Support for documents whose controller keys rotate.
+impl Envelope {
+    /// Returns the document with a `"keyRotation"` assertion, signed by
+    /// `old_signer`, attesting that `new_public_keys` replace the current
+    /// keys from now on.
+    pub fn attest_rotation(&self, old_signer: &dyn Signer, new_public_keys: &[PublicKeyBase]) -> Self {
+        self.attest_rotation_at(old_signer, new_public_keys, Date::now())
+    }
+
+    /// Returns the document with a `"keyRotation"` assertion, signed by
+    /// `old_signer`, attesting that `new_public_keys` replace the current
+    /// keys from `date`.
+    pub fn attest_rotation_at(&self, old_signer: &dyn Signer, new_public_keys: &[PublicKeyBase], date: Date) -> Self {
+        let rotation = new_public_keys
+            .iter()
+            .fold(Envelope::new(date), |rotation, key| rotation.add_assertion(ROTATED_TO, key.clone()))
+            .sign(old_signer);
+        self.add_assertion(KEY_ROTATION, rotation)
+    }
+
+    /// Checks that the envelope's subject has a signature made by a key that
+    /// was valid when it signed, according to `history`.
+    ///
+    /// The signing time is taken from the `'date'` in the signature's
+    /// metadata, as added with [`SignatureMetadata`](crate::SignatureMetadata).
+    /// A signature without a date is only accepted from a current key.
+    ///
+    /// The date is chosen by the signer, so anyone holding a retired key,
+    /// including someone who compromised it, can backdate a signature to when
+    /// the key was valid and have it accepted. Use
+    /// [`Envelope::verify_with_rotation_history_opt`] to reject signatures
+    /// from retired keys when that matters.
+    ///
+    /// - Returns: This envelope.
+    ///
+    /// - Throws: Throws `EnvelopeError::UnverifiedSignature` if no signature
+    ///   was made by a key valid at its signing time.
+    pub fn verify_with_rotation_history(&self, history: &RotationHistory) -> Result<Self> {
+        self.verify_with_rotation_history_opt(history, true)
+    }
+
+    /// Checks that the envelope's subject has a signature made by a key that
+    /// was valid when it signed, according to `history`, optionally rejecting
+    /// signatures from retired keys whatever date they claim.
+    ///
+    /// If `accept_retired_keys` is `true`, this is the same as
+    /// [`Envelope::verify_with_rotation_history`]. If it is `false`, only
+    /// signatures from the current keys are accepted.
+    ///
+    /// - Returns: This envelope.
+    ///
+    /// - Throws: Throws `EnvelopeError::UnverifiedSignature` if no signature
+    ///   was made by an accepted key.
+    pub fn verify_with_rotation_history_opt(&self, history: &RotationHistory, accept_retired_keys: bool) -> Result<Self> {
+        let epochs = if accept_retired_keys { &history.epochs[..] } else { &history.epochs[history.epochs.len() - 1..] };
+        for key in epochs.iter().rev().flat_map(|epoch| &epoch.keys) {
+            let Some(metadata) = self.has_signature_from_returning_metadata(key)? else {
+                continue;
+            };
+            let valid_keys = match metadata.extract_optional_object_for_predicate::<Date>(known_values::DATE)? {
+                Some(date) => history.keys_at(&date),
+                None => history.current_keys(),
+            };
+            if valid_keys.contains(key) {
+                return Ok(self.clone());
+            }
+        }
+        bail!(EnvelopeError::UnverifiedSignature)
+    }
+}
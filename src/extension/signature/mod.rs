@@ -1,3 +1,17 @@
+pub mod corpus;
+pub use corpus::{verify_corpus, CorpusPolicy, CorpusResult, CorpusResults};
+pub mod key_rotation;
+pub use key_rotation::RotationHistory;
+pub mod presentation;
+pub use presentation::{present, verify_presentation};
 pub mod signature_impl;
 pub mod signature_metadata;
 pub use signature_metadata::SignatureMetadata;
+pub mod signature_report;
+pub use signature_report::{SignatureCoverage, SignatureReport};
+pub mod signer_resolver;
+pub use signer_resolver::{SignerResolver, TrustLink, TrustPath};
+pub mod trust_store;
+pub use trust_store::{TrustStore, VerifiedIdentity};
+pub mod witness;
+pub use witness::{witness_message, SigningWitness, Witness, WitnessSet};
@@ -0,0 +1,77 @@
+use anyhow::{bail, Result};
+use bc_components::{Nonce, PublicKeyBase, Signer, Verifier, ARID};
+use dcbor::Date;
+
+use crate::{extension::known_values, Envelope, EnvelopeError, TimePolicy};
+
+/// The predicate of the assertion naming the verifier a presentation is
+/// intended for.
+pub const AUDIENCE: &str = "audience";
+
+/// The predicate of the assertion carrying the nonce a verifier issued for a
+/// presentation.
+pub const NONCE: &str = "nonce";
+
+/// Presents `credential` to `audience` in response to `nonce`, signed by its
+/// holder.
+///
+/// The credential, which may have been redacted, is wrapped with the audience,
+/// the nonce, and the current date, and the result is signed by `holder`:
+///
+/// ```text
+/// {
+///     {
+///         credential
+///     } [
+///         "audience": ARID(verifier)
+///         "nonce": Nonce
+///         'date': Date
+///     ]
+/// } [
+///     'signed': Signature
+/// ]
+/// ```
+///
+/// A verifier checks it with [`verify_presentation`]. Binding the
+/// presentation to the verifier and its nonce keeps it from being replayed
+/// to other verifiers, or to the same verifier later.
+pub fn present(credential: &Envelope, audience: &ARID, nonce: &Nonce, holder: &dyn Signer) -> Envelope {
+    credential
+        .wrap_envelope()
+        .add_assertion(AUDIENCE, audience.clone())
+        .add_assertion(NONCE, nonce.clone())
+        .add_assertion(known_values::DATE, Date::now())
+        .sign(holder)
+}
+
+/// Verifies a presentation made by [`present`], returning the content of the
+/// presented credential.
+///
+/// The credential must be signed by `issuer`, and must have a revealed
+/// `'holder'` assertion whose object is the public key of its holder, which
+/// must have signed the presentation. The presentation must be for
+/// `audience`, carry `nonce`, and be dated within the limits of `policy`,
+/// which should set a maximum age for the check of freshness to be
+/// meaningful.
+pub fn verify_presentation(
+    presentation: &Envelope,
+    issuer: &dyn Verifier,
+    audience: &ARID,
+    nonce: &Nonce,
+    policy: &TimePolicy,
+) -> Result<Envelope> {
+    let binding = presentation.subject().unwrap_envelope()?;
+    let content = binding.subject().unwrap_envelope()?.verify(issuer)?;
+    let Some(holder) = content.extract_optional_object_for_predicate::<PublicKeyBase>(known_values::HOLDER)? else {
+        bail!(EnvelopeError::NoHolderBinding);
+    };
+    presentation.verify_signature_from(&holder)?;
+    if binding.extract_object_for_predicate::<ARID>(AUDIENCE)? != *audience {
+        bail!(EnvelopeError::WrongAudience);
+    }
+    if binding.extract_object_for_predicate::<Nonce>(NONCE)? != *nonce {
+        bail!(EnvelopeError::WrongNonce);
+    }
+    policy.check_date(&binding.extract_object_for_predicate::<Date>(known_values::DATE)?)?;
+    Ok(content)
+}
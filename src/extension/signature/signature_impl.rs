@@ -1,7 +1,14 @@
+use std::collections::HashSet;
+
 use anyhow::{ bail, Result };
-use bc_components::{ DigestProvider, Signature, Signer, SigningOptions, Verifier };
+use bc_components::{ Digest, DigestProvider, Signature, Signer, SigningOptions, Verifier };
+#[cfg(feature = "encrypt")]
+use bc_components::SymmetricKey;
+#[cfg(feature = "recipient")]
+use bc_components::Decrypter;
 
-use crate::{ Envelope, EnvelopeEncodable, EnvelopeError };
+use crate::{ Envelope, EnvelopeEncodable, EnvelopeError, TimePolicy };
+use crate::base::error_context::ErrorContextExt;
 #[cfg(feature = "known_value")]
 use crate::extension::known_values;
 
@@ -34,38 +41,12 @@ impl Envelope {
         options: Option<SigningOptions>,
         metadata: Option<SignatureMetadata>
     ) -> Self {
-        let digest = *self.subject().digest().data();
-        let mut signature = Envelope::new(
-            private_key.sign_with_options(&digest as &dyn AsRef<[u8]>, options.clone()).unwrap()
-        );
-
-        if let Some(metadata) = metadata {
-            if metadata.has_assertions() {
-                let mut signature_with_metadata = signature;
-
-                metadata
-                    .assertions()
-                    .iter()
-                    .for_each(|assertion| {
-                        signature_with_metadata = signature_with_metadata
-                            .add_assertion_envelope(assertion.to_envelope())
-                            .unwrap();
-                    });
-
-                signature_with_metadata = signature_with_metadata.wrap_envelope();
-
-                let outer_signature = Envelope::new(
-                    private_key
-                        .sign_with_options(&signature_with_metadata.digest().as_ref(), options)
-                        .unwrap()
-                );
-                signature = signature_with_metadata.add_assertion(
-                    known_values::SIGNED,
-                    outer_signature
-                );
-            }
-        }
-
+        let signature = Self::signature_object(
+            &self.subject().digest(),
+            private_key,
+            options,
+            metadata
+        ).unwrap();
         self.add_assertion(known_values::SIGNED, signature)
     }
 
@@ -185,20 +166,40 @@ impl Envelope {
     /// - Throws: Throws `EnvelopeError.unverifiedSignature` if the signature is not valid.
     ///     valid.
     pub fn verify_signature_from(&self, public_key: &dyn Verifier) -> Result<Self> {
-        if !self.has_some_signature_from_key(public_key)? {
-            bail!(EnvelopeError::UnverifiedSignature);
+        if !self.has_some_signature_from_key(public_key).error_context(self)? {
+            return Err(self.add_error_context(EnvelopeError::UnverifiedSignature.into()));
         }
         Ok(self.clone())
     }
 
     pub fn verify_signature_from_returning_metadata(&self, public_key: &dyn Verifier) -> Result<Envelope> {
-        let metadata = self.has_some_signature_from_key_returning_metadata(public_key)?;
+        let metadata = self.has_some_signature_from_key_returning_metadata(public_key).error_context(self)?;
         if metadata.is_none() {
-            bail!(EnvelopeError::UnverifiedSignature);
+            return Err(self.add_error_context(EnvelopeError::UnverifiedSignature.into()));
         }
         Ok(metadata.unwrap())
     }
 
+    /// Checks whether the envelope's subject has a valid signature from the
+    /// given public key, and whether the dates in the signature's metadata are
+    /// acceptable to the given policy.
+    ///
+    /// - Parameters:
+    ///   - public_key: The potential signer's `Verifier`.
+    ///   - policy: The `TimePolicy` the metadata's `'date'`, `'validFrom'`,
+    ///     and `'validUntil'` assertions are checked against.
+    ///
+    /// - Returns: This envelope.
+    ///
+    /// - Throws: Throws `EnvelopeError.unverifiedSignature` if the signature is not valid,
+    ///     or an error from `Envelope::check_validity` if the metadata is not.
+    pub fn verify_signature_from_with_policy(&self, public_key: &dyn Verifier, policy: &TimePolicy) -> Result<Self> {
+        self.verify_signature_from_returning_metadata(public_key)?
+            .check_validity(policy)
+            .error_context(self)?;
+        Ok(self.clone())
+    }
+
     /// Checks whether the envelope's subject has a set of signatures.
     pub fn has_signatures_from(&self, public_keys: &[&dyn Verifier]) -> Result<bool> {
         self.has_signatures_from_threshold(public_keys, None)
@@ -264,10 +265,98 @@ impl Envelope {
     pub fn verify_signatures_from(&self, public_keys: &[&dyn Verifier]) -> Result<Self> {
         self.verify_signatures_from_threshold(public_keys, None)
     }
+
+    /// Returns the signers whose signatures on the envelope's subject meet a
+    /// target weight.
+    ///
+    /// Each signer has a weight, such as 2 for an officer and 1 for a clerk,
+    /// and the signatures must have a total weight of at least `target`.
+    /// Signers are considered in order, and the first whose weights meet the
+    /// target are returned. Each signature counts toward the target once, so
+    /// a key listed more than once adds its weight only once.
+    ///
+    /// - Parameters:
+    ///   - weighted_keys: An array of potential signers' `Verifier`s with
+    ///     their weights.
+    ///   - target: The minimum total weight of valid signatures.
+    ///
+    /// - Returns: The indexes into `weighted_keys` of the signers that met
+    ///   the target, or `None` if the target is not met.
+    ///
+    /// - Throws: Throws an exception if any `'signed'` assertion doesn't contain a
+    ///     valid `Signature` as its object.
+    pub fn has_signatures_from_weighted(
+        &self,
+        weighted_keys: &[(&dyn Verifier, u32)],
+        target: u32
+    ) -> Result<Option<Vec<usize>>> {
+        let mut signers = Vec::new();
+        let mut counted_signatures = HashSet::new();
+        let mut weight: u32 = 0;
+        for (index, (key, key_weight)) in weighted_keys.iter().enumerate() {
+            if weight >= target {
+                break;
+            }
+            let Some(signature) = self.has_some_signature_from_key_returning_metadata(*key)? else {
+                continue;
+            };
+            if counted_signatures.insert(signature.digest().into_owned()) {
+                signers.push(index);
+                weight = weight.saturating_add(*key_weight);
+            }
+        }
+        Ok((weight >= target).then_some(signers))
+    }
+
+    /// Checks whether the envelope's subject has signatures meeting a target
+    /// weight, returning the signers that met it.
+    ///
+    /// See [`Envelope::has_signatures_from_weighted`].
+    ///
+    /// - Throws: Throws an exception if the target weight is not met.
+    pub fn verify_signatures_from_weighted(
+        &self,
+        weighted_keys: &[(&dyn Verifier, u32)],
+        target: u32
+    ) -> Result<Vec<usize>> {
+        match self.has_signatures_from_weighted(weighted_keys, target)? {
+            Some(signers) => Ok(signers),
+            None => bail!(EnvelopeError::UnverifiedSignature),
+        }
+    }
 }
 
 #[doc(hidden)]
 impl Envelope {
+    /// Returns the object of a `'signed'` assertion for the given digest: a
+    /// `Signature`, or if there is metadata, the `Signature` with the metadata
+    /// assertions, wrapped and then signed by the same key.
+    pub(crate) fn signature_object(
+        digest: &Digest,
+        private_key: &dyn Signer,
+        options: Option<SigningOptions>,
+        metadata: Option<SignatureMetadata>
+    ) -> Result<Envelope> {
+        let signature = Envelope::new(
+            private_key.sign_with_options(digest.data() as &dyn AsRef<[u8]>, options.clone())?
+        );
+
+        let Some(metadata) = metadata.filter(|metadata| metadata.has_assertions()) else {
+            return Ok(signature);
+        };
+
+        let mut signature_with_metadata = signature;
+        for assertion in metadata.assertions() {
+            signature_with_metadata = signature_with_metadata.add_assertion_envelope(assertion.to_envelope())?;
+        }
+        let signature_with_metadata = signature_with_metadata.wrap_envelope();
+
+        let outer_signature = Envelope::new(
+            private_key.sign_with_options(&signature_with_metadata.digest().as_ref(), options)?
+        );
+        Ok(signature_with_metadata.add_assertion(known_values::SIGNED, outer_signature))
+    }
+
     fn is_signature_from_key(&self, signature: &Signature, key: &dyn Verifier) -> bool {
         key.verify(signature, self.subject().digest().as_ref())
     }
@@ -341,4 +430,25 @@ impl Envelope {
         let metadata = self.verify_signature_from_returning_metadata(verifier)?;
         Ok((self.unwrap_envelope()?, metadata))
     }
+
+    /// Verifies the signature on an envelope that was encrypted and then
+    /// signed, and only if it is valid decrypts it.
+    ///
+    /// This is the inverse of `envelope.encrypt(key).sign(signer)`. Because
+    /// the signature covers the ciphertext, an envelope that was not signed by
+    /// `verifier` is rejected without being decrypted.
+    #[cfg(feature = "encrypt")]
+    pub fn verify_then_decrypt(&self, verifier: &dyn Verifier, key: &SymmetricKey) -> Result<Envelope> {
+        self.verify(verifier)?.decrypt(key)
+    }
+
+    /// Verifies the signature on an envelope that was encrypted to a recipient
+    /// and then signed, and only if it is valid decrypts it.
+    ///
+    /// This is the inverse of
+    /// `envelope.encrypt_to_recipient(recipient).sign(signer)`.
+    #[cfg(feature = "recipient")]
+    pub fn verify_then_decrypt_to_recipient(&self, verifier: &dyn Verifier, recipient: &dyn Decrypter) -> Result<Envelope> {
+        self.verify(verifier)?.decrypt_to_recipient(recipient)
+    }
 }
@@ -0,0 +1,133 @@
+use bc_components::{Signature, Verifier};
+
+use crate::Envelope;
+use crate::extension::known_values;
+
+/// What a signature covers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureCoverage {
+    /// The signature covers only the subject of the signed envelope. The
+    /// envelope's other assertions may be added or removed without
+    /// invalidating it.
+    Subject,
+
+    /// The subject of the signed envelope is a wrapped envelope, so the
+    /// signature covers the wrapped envelope in its entirety, including all of
+    /// its assertions.
+    WrappedEnvelope,
+}
+
+/// A description of a single `'signed'` assertion on an envelope.
+///
+/// Returned by [`Envelope::signature_report`].
+#[derive(Debug, Clone)]
+pub struct SignatureReport {
+    signature: Option<Signature>,
+    metadata: Option<Envelope>,
+    coverage: SignatureCoverage,
+    verifier_index: Option<usize>,
+}
+
+impl SignatureReport {
+    /// The signature, or `None` if the `'signed'` assertion does not contain a
+    /// well-formed signature.
+    pub fn signature(&self) -> Option<&Signature> {
+        self.signature.as_ref()
+    }
+
+    /// The name of the signing algorithm, or `None` if the signature is not
+    /// well-formed.
+    pub fn algorithm(&self) -> Option<&'static str> {
+        self.signature.as_ref().map(|signature| match signature {
+            Signature::Schnorr(_) => "Schnorr",
+            Signature::ECDSA(_) => "ECDSA",
+            Signature::Ed25519(_) => "Ed25519",
+            Signature::SSH(_) => "SSH",
+        })
+    }
+
+    /// The signature metadata, if the signer attached any.
+    ///
+    /// The subject of the metadata envelope is the signature, and each item of
+    /// metadata is an assertion on it.
+    pub fn metadata(&self) -> Option<&Envelope> {
+        self.metadata.as_ref()
+    }
+
+    /// What the signature covers.
+    pub fn coverage(&self) -> SignatureCoverage {
+        self.coverage
+    }
+
+    /// The index of the first verifier that validated the signature, or `None`
+    /// if none did.
+    pub fn verifier_index(&self) -> Option<usize> {
+        self.verifier_index
+    }
+
+    /// `true` if one of the verifiers validated the signature, and its
+    /// metadata if present.
+    pub fn is_valid(&self) -> bool {
+        self.verifier_index.is_some()
+    }
+}
+
+/// Support for auditing the signatures on an envelope.
+impl Envelope {
+    /// Returns a report for each `'signed'` assertion on the envelope.
+    ///
+    /// Each signature is checked against each of `verifiers` in turn, and the
+    /// report records the first one that validates it. Unlike the
+    /// `verify_signature*` family of methods, malformed or unverifiable
+    /// signatures are reported rather than treated as errors.
+    pub fn signature_report(&self, verifiers: &[&dyn Verifier]) -> Vec<SignatureReport> {
+        let coverage = if self.subject().is_wrapped() {
+            SignatureCoverage::WrappedEnvelope
+        } else {
+            SignatureCoverage::Subject
+        };
+        self.objects_for_predicate(known_values::SIGNED)
+            .iter()
+            .map(|signature_object| self.signature_report_for(signature_object, coverage, verifiers))
+            .collect()
+    }
+
+    fn signature_report_for(
+        &self,
+        signature_object: &Envelope,
+        coverage: SignatureCoverage,
+        verifiers: &[&dyn Verifier],
+    ) -> SignatureReport {
+        let signature_object_subject = signature_object.subject();
+        let (signature, metadata, outer_signature) = if signature_object_subject.is_wrapped() {
+            let metadata = signature_object_subject.unwrap_envelope().ok();
+            let signature = metadata
+                .as_ref()
+                .and_then(|metadata| metadata.extract_subject::<Signature>().ok());
+            let outer_signature = signature_object
+                .object_for_predicate(known_values::SIGNED)
+                .and_then(|outer| outer.extract_subject::<Signature>())
+                .ok();
+            (signature, metadata, outer_signature)
+        } else {
+            (signature_object.extract_subject::<Signature>().ok(), None, None)
+        };
+
+        let verifier_index = signature.as_ref().and_then(|signature| {
+            verifiers.iter().position(|verifier| {
+                self.is_verified_signature(signature, *verifier)
+                    && (metadata.is_none()
+                        || outer_signature.as_ref().is_some_and(|outer_signature| {
+                            signature_object_subject.is_verified_signature(outer_signature, *verifier)
+                        }))
+            })
+        });
+
+        SignatureReport {
+            signature,
+            metadata,
+            coverage,
+            verifier_index,
+        }
+    }
+}
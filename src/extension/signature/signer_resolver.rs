@@ -0,0 +1,170 @@
+use std::future::Future;
+
+use anyhow::{bail, Result};
+use bc_components::PublicKeyBase;
+
+use crate::{extension::known_values, Envelope, EnvelopeError};
+
+/// The predicate of the assertion naming the signer of an envelope.
+///
+/// The object is a reference, such as an ARID or URI, that a
+/// [`SignerResolver`] can resolve to the signer's document.
+pub const VERIFIED_BY: &str = "verifiedBy";
+
+/// The predicate of the assertions on the wrapped content of a signer's
+/// document whose objects are the signer's public keys.
+pub const PUBLIC_KEYS: &str = "publicKeys";
+
+/// The default maximum number of documents fetched while verifying a
+/// signature with [`Envelope::verify_signature_resolving`].
+pub const DEFAULT_MAX_RESOLUTION_DEPTH: usize = 8;
+
+/// Fetches signer documents on behalf of
+/// [`Envelope::verify_signature_resolving`].
+///
+/// Implementations typically look up the reference in a local store or fetch
+/// it over the network. Any async runtime may be used, since the returned
+/// future is only awaited.
+pub trait SignerResolver {
+    /// Returns the document that `reference` refers to.
+    fn resolve(&self, reference: &Envelope) -> impl Future<Output = Result<Envelope>>;
+}
+
+/// One step of a [`TrustPath`]: a signed envelope, the document of its
+/// signer, and the key that validated the signature.
+#[derive(Debug, Clone)]
+pub struct TrustLink {
+    signed: Envelope,
+    reference: Envelope,
+    signer: Envelope,
+    key: PublicKeyBase,
+}
+
+impl TrustLink {
+    /// The envelope whose signature was validated.
+    pub fn signed(&self) -> &Envelope {
+        &self.signed
+    }
+
+    /// The object of the signed envelope's `verifiedBy` assertion.
+    pub fn reference(&self) -> &Envelope {
+        &self.reference
+    }
+
+    /// The signer's document, after following any `'dereferenceVia'`
+    /// assertions.
+    ///
+    /// The document's subject is its wrapped content, from which the key was
+    /// taken.
+    pub fn signer(&self) -> &Envelope {
+        &self.signer
+    }
+
+    /// The key from the signer's document that validated the signature.
+    pub fn key(&self) -> &PublicKeyBase {
+        &self.key
+    }
+}
+
+/// The chain of signers that vouch for an envelope.
+///
+/// Returned by [`Envelope::verify_signature_resolving`]. The first link is
+/// the signer of the verified envelope, and each subsequent link is the
+/// signer of the previous link's document.
+#[derive(Debug, Clone)]
+pub struct TrustPath {
+    links: Vec<TrustLink>,
+}
+
+impl TrustPath {
+    /// The links of the path, starting with the signer of the verified
+    /// envelope.
+    pub fn links(&self) -> &[TrustLink] {
+        &self.links
+    }
+
+    /// The document at the end of the path, which is not signed by anyone
+    /// else.
+    ///
+    /// The path only shows that the envelope is vouched for by this document;
+    /// whether the document is trusted is for the caller to decide.
+    pub fn anchor(&self) -> &Envelope {
+        self.links.last().expect("a trust path has at least one link").signer()
+    }
+
+    /// The number of links in the path.
+    pub fn len(&self) -> usize {
+        self.links.len()
+    }
+
+    /// Always `false`, since a trust path has at least one link.
+    pub fn is_empty(&self) -> bool {
+        self.links.is_empty()
+    }
+}
+
+/// Support for verifying signatures from signers that must be looked up.
+impl Envelope {
+    /// Verifies the envelope's signature, fetching the signer's document with
+    /// `resolver`, and returns the path of signers that vouch for it.
+    ///
+    /// The object of the envelope's `verifiedBy` assertion is resolved to the
+    /// signer's document. While the document has a `'dereferenceVia'`
+    /// assertion, its object is resolved in turn.
+    ///
+    /// A signer's document must be wrapped, as [`Envelope::sign`] does, and
+    /// its `publicKeys` and `verifiedBy` assertions are read only from the
+    /// wrapped content. Assertions added outside the wrapper are not covered
+    /// by the document's signature, so they are ignored. One of the
+    /// document's `publicKeys` must have signed the envelope's subject. If
+    /// the document's content has a `verifiedBy` assertion, the document's
+    /// signature is verified in the same way, and so on until a document
+    /// without one is reached. That document is the anchor of the path, and
+    /// since nobody vouches for it, the caller must decide whether to trust
+    /// it.
+    ///
+    /// Returns an error if the envelope has no `verifiedBy` assertion, if a
+    /// signer's document is not wrapped, if a signature in the chain cannot
+    /// be verified, if `resolver` fails, or if more than `max_depth`
+    /// documents would be fetched.
+    pub async fn verify_signature_resolving(
+        &self,
+        resolver: &impl SignerResolver,
+        max_depth: usize,
+    ) -> Result<TrustPath> {
+        let mut links = Vec::new();
+        let mut signed = self.clone();
+        let mut reference = signed.object_for_predicate(VERIFIED_BY)?;
+        let mut fetched = 0;
+        loop {
+            let mut signer = reference.clone();
+            loop {
+                if fetched == max_depth {
+                    bail!(EnvelopeError::ResolutionDepthExceeded);
+                }
+                fetched += 1;
+                signer = resolver.resolve(&signer).await?;
+                match signer.object_for_predicate(known_values::DEREFERENCE_VIA) {
+                    Ok(via) => signer = via,
+                    Err(_) => break,
+                }
+            }
+            let content = signer.unwrap_envelope()?;
+            let key = content
+                .objects_for_predicate(PUBLIC_KEYS)
+                .into_iter()
+                .filter_map(|key| key.extract_subject::<PublicKeyBase>().ok())
+                .find(|key| signed.has_signature_from(key).unwrap_or(false));
+            let Some(key) = key else {
+                bail!(EnvelopeError::UnverifiedSignature);
+            };
+            let next = content.object_for_predicate(VERIFIED_BY).ok();
+            links.push(TrustLink { signed, reference, signer: signer.clone(), key });
+            let Some(next) = next else {
+                return Ok(TrustPath { links });
+            };
+            signed = signer;
+            reference = next;
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Error, Result};
+use bc_components::PublicKeyBase;
+
+use crate::{Envelope, EnvelopeEncodable, EnvelopeError};
+
+use super::signer_resolver::PUBLIC_KEYS;
+
+/// The subject of an exported [`TrustStore`].
+pub const TRUST_STORE: &str = "TrustStore";
+
+/// The predicate of the assertions in an exported [`TrustStore`], each of
+/// whose objects is a trusted key with an [`IDENTITY`] assertion.
+pub const TRUSTS: &str = "trusts";
+
+/// The predicate of the assertion binding a trusted key to its identity in an
+/// exported [`TrustStore`].
+pub const IDENTITY: &str = "identity";
+
+/// A set of trusted public keys, each bound to an identity envelope that
+/// describes its holder, such as a name and role.
+///
+/// Verifying a signature against a trust store with
+/// [`Envelope::verify_signature_trusted`] reports who signed, not only that a
+/// trusted key did. A trust store is exported by converting it to an
+/// envelope, and imported by converting the envelope back:
+///
+/// ```text
+/// "TrustStore" [
+///     "trusts": PublicKeyBase [
+///         "identity": "Alice" [
+///             "role": "Administrator"
+///         ]
+///     ]
+/// ]
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TrustStore {
+    identities: HashMap<PublicKeyBase, Envelope>,
+}
+
+impl TrustStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the store with `key` bound to `identity`.
+    pub fn with_identity(mut self, key: PublicKeyBase, identity: impl EnvelopeEncodable) -> Self {
+        self.insert(key, identity);
+        self
+    }
+
+    /// Returns the store with each of a signer's `"publicKeys"` bound to the
+    /// signer's document, as used by
+    /// [`Envelope::verify_signature_resolving`].
+    ///
+    /// Returns an error if the document has no public keys.
+    pub fn with_document(mut self, document: &Envelope) -> Result<Self> {
+        let keys: Vec<PublicKeyBase> = document.extract_objects_for_predicate(PUBLIC_KEYS)?;
+        if keys.is_empty() {
+            bail!(EnvelopeError::NonexistentPredicate);
+        }
+        for key in keys {
+            self.insert(key, document.clone());
+        }
+        Ok(self)
+    }
+
+    /// Binds `key` to `identity`, returning the identity it was bound to
+    /// before, if any.
+    pub fn insert(&mut self, key: PublicKeyBase, identity: impl EnvelopeEncodable) -> Option<Envelope> {
+        self.identities.insert(key, identity.into_envelope())
+    }
+
+    /// Removes `key` from the store, returning the identity it was bound to.
+    pub fn remove(&mut self, key: &PublicKeyBase) -> Option<Envelope> {
+        self.identities.remove(key)
+    }
+
+    /// The identity bound to `key`, if it is trusted.
+    pub fn identity(&self, key: &PublicKeyBase) -> Option<&Envelope> {
+        self.identities.get(key)
+    }
+
+    /// Whether `key` is trusted.
+    pub fn contains(&self, key: &PublicKeyBase) -> bool {
+        self.identities.contains_key(key)
+    }
+
+    /// The trusted keys, in no particular order.
+    pub fn keys(&self) -> impl Iterator<Item = &PublicKeyBase> {
+        self.identities.keys()
+    }
+
+    pub fn len(&self) -> usize {
+        self.identities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.identities.is_empty()
+    }
+}
+
+impl From<TrustStore> for Envelope {
+    fn from(store: TrustStore) -> Self {
+        store.identities.into_iter().fold(Envelope::new(TRUST_STORE), |envelope, (key, identity)| {
+            envelope.add_assertion(TRUSTS, Envelope::new(key).add_assertion(IDENTITY, identity))
+        })
+    }
+}
+
+impl TryFrom<Envelope> for TrustStore {
+    type Error = Error;
+
+    fn try_from(envelope: Envelope) -> Result<Self> {
+        if envelope.extract_subject::<String>().ok().as_deref() != Some(TRUST_STORE) {
+            bail!(EnvelopeError::InvalidFormat);
+        }
+        envelope.objects_for_predicate(TRUSTS).into_iter().try_fold(Self::new(), |store, entry| {
+            let key: PublicKeyBase = entry.extract_subject()?;
+            let identity = entry.object_for_predicate(IDENTITY)?;
+            Ok(store.with_identity(key, identity))
+        })
+    }
+}
+
+/// A trusted key that signed an envelope, and the identity it is bound to in
+/// a [`TrustStore`].
+///
+/// Returned by [`Envelope::verify_signature_trusted`] and
+/// [`Envelope::trusted_signers`].
+#[derive(Debug, Clone)]
+pub struct VerifiedIdentity {
+    key: PublicKeyBase,
+    identity: Envelope,
+}
+
+impl VerifiedIdentity {
+    /// The key that made the signature.
+    pub fn key(&self) -> &PublicKeyBase {
+        &self.key
+    }
+
+    /// The identity the key is bound to.
+    pub fn identity(&self) -> &Envelope {
+        &self.identity
+    }
+}
+
+/// Support for verifying signatures from the keys in a [`TrustStore`].
+impl Envelope {
+    /// Checks that the envelope's subject was signed by a key in `store`,
+    /// and returns that key with the identity it is bound to.
+    ///
+    /// If several trusted keys signed the subject, any one of them may be
+    /// returned. Use [`Envelope::trusted_signers`] to find them all.
+    ///
+    /// - Throws: Throws `EnvelopeError::UnverifiedSignature` if no key in
+    ///   `store` signed the envelope's subject.
+    pub fn verify_signature_trusted(&self, store: &TrustStore) -> Result<VerifiedIdentity> {
+        match self.trusted_signers(store).into_iter().next() {
+            Some(signer) => Ok(signer),
+            None => bail!(EnvelopeError::UnverifiedSignature),
+        }
+    }
+
+    /// Returns each key in `store` that signed the envelope's subject, with
+    /// the identity it is bound to, in no particular order.
+    pub fn trusted_signers(&self, store: &TrustStore) -> Vec<VerifiedIdentity> {
+        store
+            .identities
+            .iter()
+            .filter(|(key, _)| self.has_signature_from(*key).unwrap_or(false))
+            .map(|(key, identity)| VerifiedIdentity { key: key.clone(), identity: identity.clone() })
+            .collect()
+    }
+}
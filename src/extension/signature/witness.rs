@@ -0,0 +1,189 @@
+use anyhow::{bail, Result};
+use bc_components::{Digest, DigestProvider, Signer, SigningOptions, Verifier};
+
+use crate::{extension::known_values, Envelope, EnvelopeError};
+
+use super::SignatureMetadata;
+
+/// The predicate of the assertion holding an envelope's bundle of witness
+/// signatures.
+///
+/// The object is an envelope whose subject is the digest of the witnessed
+/// subject, with a `'signed'` assertion for each witness.
+pub const WITNESSED_BY: &str = "witnessedBy";
+
+/// The context string that witnesses sign along with the digest of the
+/// witnessed subject.
+///
+/// Prefixing the digest keeps a witness signature from also being a valid
+/// `'signed'` assertion on the subject itself.
+pub const WITNESS_CONTEXT: &str = "bc-envelope witness";
+
+/// Returns the digest a witness signs for the witnessed subject's `digest`:
+/// the digest of [`WITNESS_CONTEXT`] followed by `digest`.
+pub fn witness_message(digest: &Digest) -> Digest {
+    Digest::from_image_parts(&[WITNESS_CONTEXT.as_bytes(), digest.data()])
+}
+
+/// A party that attests to having seen an envelope by signing the digest of
+/// its subject.
+///
+/// Witnesses only ever see the digest, so an envelope can be notarized
+/// without revealing its content. Implementations typically forward the
+/// digest to a remote signing service.
+pub trait Witness {
+    /// Returns the object of a `'signed'` assertion for
+    /// [`witness_message`]`(digest)`: a `Signature`, or a signature with
+    /// metadata as made by [`Envelope::add_signature_opt`].
+    ///
+    /// Implementations must not sign `digest` itself, or the signature could
+    /// be presented as a signature on the witnessed subject.
+    fn witness(&self, digest: &Digest) -> Result<Envelope>;
+}
+
+/// A [`Witness`] that signs with a local `Signer`, optionally adding
+/// metadata such as the date the digest was witnessed.
+pub struct SigningWitness<'a> {
+    signer: &'a dyn Signer,
+    options: Option<SigningOptions>,
+    metadata: Option<SignatureMetadata>,
+}
+
+impl<'a> SigningWitness<'a> {
+    pub fn new(signer: &'a dyn Signer) -> Self {
+        Self { signer, options: None, metadata: None }
+    }
+
+    pub fn with_options(mut self, options: SigningOptions) -> Self {
+        self.options = Some(options);
+        self
+    }
+
+    pub fn with_metadata(mut self, metadata: SignatureMetadata) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+}
+
+impl Witness for SigningWitness<'_> {
+    fn witness(&self, digest: &Digest) -> Result<Envelope> {
+        Envelope::signature_object(&witness_message(digest), self.signer, self.options.clone(), self.metadata.clone())
+    }
+}
+
+/// A set of witnesses that co-sign envelopes as a light-weight notarization.
+#[derive(Default)]
+pub struct WitnessSet<'a> {
+    witnesses: Vec<&'a dyn Witness>,
+}
+
+impl<'a> WitnessSet<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_witness(mut self, witness: &'a dyn Witness) -> Self {
+        self.witnesses.push(witness);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.witnesses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.witnesses.is_empty()
+    }
+
+    /// Sends the digest of the envelope's subject to each witness, returning
+    /// their signatures in the order the witnesses were added.
+    pub fn collect(&self, envelope: &Envelope) -> Vec<Result<Envelope>> {
+        let digest = envelope.subject().digest().into_owned();
+        self.witnesses.iter().map(|witness| witness.witness(&digest)).collect()
+    }
+
+    /// Collects signatures from the witnesses and adds them to the envelope
+    /// with [`Envelope::add_witness_signatures`].
+    ///
+    /// Witnesses that fail are skipped, unless fewer than `min_count`
+    /// signatures are collected, in which case the first failure, or
+    /// [`EnvelopeError::UnverifiedSignature`], is returned.
+    pub fn notarize(&self, envelope: &Envelope, min_count: usize) -> Result<Envelope> {
+        let mut signatures = Vec::new();
+        let mut first_error = None;
+        for result in self.collect(envelope) {
+            match result {
+                Ok(signature) => signatures.push(signature),
+                Err(error) => {
+                    first_error.get_or_insert(error);
+                }
+            }
+        }
+        if signatures.len() < min_count {
+            return Err(first_error.unwrap_or_else(|| EnvelopeError::UnverifiedSignature.into()));
+        }
+        envelope.add_witness_signatures(&signatures)
+    }
+}
+
+/// Support for witness co-signatures.
+impl Envelope {
+    /// Adds witness signatures of the envelope's subject, as collected by
+    /// [`WitnessSet::collect`], to its `"witnessedBy"` bundle.
+    ///
+    /// The bundle is created if the envelope does not have one yet. Its
+    /// subject is the digest of the witnessed subject, so it can be detached
+    /// and kept as a record of the notarization.
+    ///
+    /// - Throws: Throws an exception if the envelope already has a bundle
+    ///   for a different subject, or more than one bundle.
+    pub fn add_witness_signatures(&self, signatures: &[Envelope]) -> Result<Self> {
+        let (envelope, bundle) = match self.witness_bundle()? {
+            Some(bundle) => (self.remove_assertion(self.assertion_with_predicate(WITNESSED_BY)?), bundle),
+            None => (self.clone(), Envelope::new(self.subject().digest().into_owned())),
+        };
+        let bundle = signatures
+            .iter()
+            .fold(bundle, |bundle, signature| bundle.add_assertion(known_values::SIGNED, signature.clone()));
+        Ok(envelope.add_assertion(WITNESSED_BY, bundle))
+    }
+
+    /// Returns the envelope's `"witnessedBy"` bundle, if any.
+    ///
+    /// - Throws: Throws an exception if the bundle is not for the envelope's
+    ///   subject, or there is more than one bundle.
+    pub fn witness_bundle(&self) -> Result<Option<Envelope>> {
+        let bundle = match self.optional_object_for_predicate(WITNESSED_BY)? {
+            Some(bundle) => bundle,
+            None => return Ok(None),
+        };
+        if bundle.extract_subject::<Digest>()? != *self.subject().digest() {
+            bail!(EnvelopeError::InvalidDigest);
+        }
+        Ok(Some(bundle))
+    }
+
+    /// Checks that at least `min_count` of the given witnesses have signed
+    /// the envelope's subject in its `"witnessedBy"` bundle.
+    ///
+    /// - Parameters:
+    ///   - witnesses: The potential witnesses' `Verifier`s.
+    ///   - min_count: The minimum number of witnesses that must have signed.
+    ///
+    /// - Returns: The envelope.
+    ///
+    /// - Throws: Throws an exception if there is no valid bundle, or too few
+    ///   of the witnesses signed.
+    pub fn verify_witnesses(&self, witnesses: &[&dyn Verifier], min_count: usize) -> Result<Self> {
+        let Some(bundle) = self.witness_bundle()? else {
+            bail!(EnvelopeError::UnverifiedSignature);
+        };
+        // The witnesses signed the witness message for the envelope's
+        // subject, so check their signatures against a subject with that
+        // digest.
+        let message = Envelope::new_elided(witness_message(&self.subject().digest()));
+        let witnessed = message.add_assertion_envelopes(&bundle.assertions())?;
+        witnessed.verify_signatures_from_threshold(witnesses, Some(min_count))?;
+        Ok(self.clone())
+    }
+}
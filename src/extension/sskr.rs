@@ -2,7 +2,7 @@ use std::collections::HashMap;
 
 use anyhow::{bail, Result};
 pub use bc_components::{SSKRShare, SSKRSpec, SSKRGroupSpec, SSKRSecret, SSKRError};
-use bc_components::{sskr_generate_using, sskr_combine, SymmetricKey};
+use bc_components::{sskr_generate_using, sskr_combine, DigestProvider, SymmetricKey};
 use bc_rand::RandomNumberGenerator;
 
 use crate::{Envelope, EnvelopeError};
@@ -126,3 +126,160 @@ impl Envelope {
         bail!(EnvelopeError::InvalidShares)
     }
 }
+
+/// The status of an [`SSKRJoiner`] after receiving a share.
+#[derive(Debug, Clone)]
+pub enum SSKRJoinStatus {
+    /// More shares are needed to reach the threshold.
+    NeedsMore {
+        /// The number of groups with enough shares to meet their thresholds.
+        groups_complete: usize,
+        /// The number of complete groups needed to join the split.
+        groups_needed: usize,
+    },
+
+    /// The envelope has been joined.
+    Complete(Envelope),
+}
+
+impl SSKRJoinStatus {
+    pub fn is_complete(&self) -> bool {
+        matches!(self, Self::Complete(_))
+    }
+}
+
+/// Joins an envelope split by SSKR from shares received one at a time.
+///
+/// Unlike [`Envelope::sskr_join`], which needs every share up front, a joiner
+/// checks each share envelope as it arrives: the share must belong to the
+/// same split and encrypt the same subject as the shares before it, and must
+/// not conflict with a share already received. Receiving the same share again
+/// is harmless. Once enough groups have met their thresholds, the shares are
+/// combined and the envelope is decrypted.
+///
+/// A share envelope that is rejected, including one whose shares pass these
+/// checks but cannot be combined with the others, leaves the joiner as it was
+/// before, so later shares can still complete the join.
+#[derive(Debug, Clone, Default)]
+pub struct SSKRJoiner {
+    first: Option<Envelope>,
+    identifier: u16,
+    group_threshold: usize,
+    group_count: usize,
+    share_len: usize,
+    groups: HashMap<usize, Vec<SSKRShare>>,
+    result: Option<Envelope>,
+}
+
+impl SSKRJoiner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the shares in a share envelope, returning whether the envelope
+    /// has now been joined.
+    ///
+    /// - Throws: Throws `EnvelopeError::InvalidShares` if the envelope has no
+    ///   `sskrShare` assertion or the shares cannot be combined,
+    ///   `EnvelopeError::MismatchedShare` if it belongs to a different split
+    ///   or its shares differ in length from those already received, or
+    ///   `EnvelopeError::ConflictingShare` if a share has the same group and
+    ///   member index as a different share already received.
+    pub fn add_share(&mut self, envelope: &Envelope) -> Result<SSKRJoinStatus> {
+        if self.result.is_some() {
+            return Ok(self.status());
+        }
+        let shares = envelope
+            .assertions_with_predicate(known_values::SSKR_SHARE)
+            .iter()
+            .map(|assertion| assertion.as_object().unwrap().extract_subject::<SSKRShare>())
+            .collect::<Result<Vec<_>>>()?;
+        if shares.is_empty() {
+            bail!(EnvelopeError::InvalidShares);
+        }
+        let previous = self.clone();
+        let status = self.add_shares(envelope, shares);
+        if status.is_err() {
+            *self = previous;
+        }
+        status
+    }
+
+    fn add_shares(&mut self, envelope: &Envelope, shares: Vec<SSKRShare>) -> Result<SSKRJoinStatus> {
+        for share in shares {
+            self.check_share(envelope, &share)?;
+            if self.first.is_none() {
+                self.first = Some(envelope.clone());
+                self.identifier = share.identifier();
+                self.group_threshold = share.group_threshold();
+                self.group_count = share.group_count();
+                self.share_len = share.data().len();
+            }
+            let group = self.groups.entry(share.group_index()).or_default();
+            if !group.iter().any(|existing| existing.member_index() == share.member_index()) {
+                group.push(share);
+            }
+        }
+        if self.groups_complete() >= self.group_threshold {
+            let shares: Vec<SSKRShare> = self.groups.values().flatten().cloned().collect();
+            let secret = sskr_combine(&shares).map_err(|_| EnvelopeError::InvalidShares)?;
+            let content_key = SymmetricKey::from_data_ref(&secret)?;
+            let Ok(envelope) = self.first.as_ref().unwrap().decrypt_subject(&content_key) else {
+                bail!(EnvelopeError::InvalidShares);
+            };
+            self.result = Some(envelope.subject());
+        }
+        Ok(self.status())
+    }
+
+    fn check_share(&self, envelope: &Envelope, share: &SSKRShare) -> Result<()> {
+        let Some(first) = &self.first else {
+            if share.group_index() >= share.group_count() {
+                bail!(EnvelopeError::InvalidShares);
+            }
+            return Ok(());
+        };
+        if share.identifier() != self.identifier
+            || share.group_threshold() != self.group_threshold
+            || share.group_count() != self.group_count
+            || share.group_index() >= self.group_count
+            || share.data().len() != self.share_len
+            || envelope.subject().digest() != first.subject().digest()
+        {
+            bail!(EnvelopeError::MismatchedShare);
+        }
+        if let Some(group) = self.groups.get(&share.group_index()) {
+            if group.iter().any(|existing| existing.member_threshold() != share.member_threshold()) {
+                bail!(EnvelopeError::MismatchedShare);
+            }
+            if group.iter().any(|existing| existing.member_index() == share.member_index() && existing.data() != share.data()) {
+                bail!(EnvelopeError::ConflictingShare);
+            }
+        }
+        Ok(())
+    }
+
+    fn groups_complete(&self) -> usize {
+        self.groups
+            .values()
+            .filter(|shares| shares.first().is_some_and(|share| shares.len() >= share.member_threshold()))
+            .count()
+    }
+
+    /// Whether the envelope has been joined, or how many more groups are
+    /// needed.
+    pub fn status(&self) -> SSKRJoinStatus {
+        match &self.result {
+            Some(envelope) => SSKRJoinStatus::Complete(envelope.clone()),
+            None => SSKRJoinStatus::NeedsMore {
+                groups_complete: self.groups_complete(),
+                groups_needed: self.group_threshold.max(1),
+            },
+        }
+    }
+
+    /// The joined envelope, or `None` if more shares are needed.
+    pub fn envelope(&self) -> Option<&Envelope> {
+        self.result.as_ref()
+    }
+}
@@ -0,0 +1,180 @@
+//! Wrappers that record at compile time that an envelope has been signed,
+//! encrypted, or sealed.
+//!
+//! Each wrapper is a transparent newtype around [`Envelope`] that can only be
+//! made by performing its operation, or by checking a received envelope. An
+//! API can take one to require that its callers have done the work, such as a
+//! send function that only accepts a [`SealedEnvelope`]. Each wrapper
+//! dereferences to [`Envelope`], and converting it back is free.
+//!
+//! A received [`SignedEnvelope`] is only made by verifying its signature. A
+//! received [`SealedEnvelope`] can only be checked for its form, since its
+//! signature is encrypted, so it proves nothing about who signed it.
+
+use std::ops::Deref;
+
+use anyhow::{bail, Result};
+#[cfg(feature = "encrypt")]
+use anyhow::Error;
+#[cfg(feature = "signature")]
+use bc_components::{Signer, Verifier};
+#[cfg(feature = "encrypt")]
+use bc_components::SymmetricKey;
+#[cfg(feature = "recipient")]
+use bc_components::Encrypter;
+
+use crate::{Envelope, EnvelopeError};
+
+macro_rules! envelope_newtype {
+    ($name:ident) => {
+        impl $name {
+            /// The wrapped envelope.
+            pub fn envelope(&self) -> &Envelope {
+                &self.0
+            }
+        }
+
+        impl Deref for $name {
+            type Target = Envelope;
+
+            fn deref(&self) -> &Envelope {
+                &self.0
+            }
+        }
+
+        impl AsRef<Envelope> for $name {
+            fn as_ref(&self) -> &Envelope {
+                &self.0
+            }
+        }
+
+        impl From<$name> for Envelope {
+            fn from(wrapper: $name) -> Self {
+                wrapper.0
+            }
+        }
+    };
+}
+
+/// An envelope whose wrapped subject has been signed.
+///
+/// Made by [`SignedEnvelope::sign`], or from a received envelope by
+/// [`SignedEnvelope::verify`], which checks its signature.
+#[cfg(feature = "signature")]
+#[derive(Debug, Clone)]
+#[repr(transparent)]
+pub struct SignedEnvelope(Envelope);
+
+#[cfg(feature = "signature")]
+envelope_newtype!(SignedEnvelope);
+
+#[cfg(feature = "signature")]
+impl SignedEnvelope {
+    /// Wraps `envelope` and signs it, as [`Envelope::sign`] does.
+    pub fn sign(envelope: &Envelope, signer: &dyn Signer) -> Self {
+        Self(envelope.sign(signer))
+    }
+
+    /// Checks that `envelope` has a wrapped subject with a valid signature
+    /// from `verifier`.
+    pub fn verify(envelope: Envelope, verifier: &dyn Verifier) -> Result<Self> {
+        if !envelope.subject().is_wrapped() {
+            bail!(EnvelopeError::NotSigned);
+        }
+        Ok(Self(envelope.verify_signature_from(verifier)?))
+    }
+}
+
+/// An envelope whose wrapped subject has been encrypted.
+///
+/// Made by [`EncryptedEnvelope::encrypt`] or
+/// [`EncryptedEnvelope::encrypt_to_recipient`], or by checking an envelope
+/// with [`TryFrom`], which requires an encrypted subject.
+#[cfg(feature = "encrypt")]
+#[derive(Debug, Clone)]
+#[repr(transparent)]
+pub struct EncryptedEnvelope(Envelope);
+
+#[cfg(feature = "encrypt")]
+envelope_newtype!(EncryptedEnvelope);
+
+#[cfg(feature = "encrypt")]
+impl EncryptedEnvelope {
+    /// Wraps `envelope` and encrypts it with `key`, as [`Envelope::encrypt`]
+    /// does.
+    pub fn encrypt(envelope: &Envelope, key: &SymmetricKey) -> Self {
+        Self(envelope.encrypt(key))
+    }
+
+    /// Wraps `envelope` and encrypts it to `recipient`, as
+    /// [`Envelope::encrypt_to_recipient`] does.
+    #[cfg(feature = "recipient")]
+    pub fn encrypt_to_recipient(envelope: &Envelope, recipient: &dyn Encrypter) -> Self {
+        Self(envelope.encrypt_to_recipient(recipient))
+    }
+}
+
+#[cfg(feature = "encrypt")]
+impl TryFrom<Envelope> for EncryptedEnvelope {
+    type Error = Error;
+
+    fn try_from(envelope: Envelope) -> Result<Self> {
+        if !envelope.subject().is_encrypted() {
+            bail!(EnvelopeError::NotEncrypted);
+        }
+        Ok(Self(envelope))
+    }
+}
+
+/// An envelope that has been signed by its sender and then encrypted to its
+/// recipient.
+///
+/// Made by [`SealedEnvelope::seal`] or [`SignedEnvelope::seal_to`], or from a
+/// received envelope by [`SealedEnvelope::from_envelope_unchecked`].
+///
+/// Since the signature is encrypted, a received `SealedEnvelope` proves
+/// nothing about signing: the subject may not be signed at all, or may be
+/// signed by anyone. Only the recipient can check the signature, with
+/// [`Envelope::unseal`].
+#[cfg(all(feature = "signature", feature = "recipient"))]
+#[derive(Debug, Clone)]
+#[repr(transparent)]
+pub struct SealedEnvelope(Envelope);
+
+#[cfg(all(feature = "signature", feature = "recipient"))]
+envelope_newtype!(SealedEnvelope);
+
+#[cfg(all(feature = "signature", feature = "recipient"))]
+impl SealedEnvelope {
+    /// Signs `envelope` and encrypts it to `recipient`, as [`Envelope::seal`]
+    /// does.
+    pub fn seal(envelope: &Envelope, sender: &dyn Signer, recipient: &dyn Encrypter) -> Self {
+        Self(envelope.seal(sender, recipient))
+    }
+}
+
+#[cfg(all(feature = "signature", feature = "recipient"))]
+impl SignedEnvelope {
+    /// Encrypts this signed envelope to `recipient`.
+    pub fn seal_to(&self, recipient: &dyn Encrypter) -> SealedEnvelope {
+        SealedEnvelope(self.0.encrypt_to_recipient(recipient))
+    }
+}
+
+#[cfg(all(feature = "signature", feature = "recipient"))]
+impl SealedEnvelope {
+    /// Checks that `envelope` has an encrypted subject with at least one
+    /// `'hasRecipient'` assertion.
+    ///
+    /// This only checks the form of the envelope. It does not, and cannot,
+    /// check that the encrypted subject was signed.
+    pub fn from_envelope_unchecked(envelope: Envelope) -> Result<Self> {
+        if !envelope.subject().is_encrypted() {
+            bail!(EnvelopeError::NotEncrypted);
+        }
+        if envelope.assertions_with_predicate(crate::known_values::HAS_RECIPIENT).is_empty() {
+            bail!(EnvelopeError::NotSealed);
+        }
+        Ok(Self(envelope))
+    }
+}
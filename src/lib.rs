@@ -36,6 +36,21 @@
 //! * [`Envelope::new`] Creates an envelope with a `subject`.
 //! * [`Envelope::new_assertion`] Creates an assertion envelope with a
 //!   `predicate` and `object`.
+//! * [`envelope!`] Creates an envelope with assertions from a literal
+//!   description.
+//! * [`Envelope::new_streaming_bytes`] Creates an elided stand-in for a large
+//!   byte string subject by streaming it from a reader.
+//! * [`Envelope::with_assertions_unchecked_sorted`] Creates an envelope from a
+//!   subject and assertions that are already in canonical order.
+//!
+//! # Creating Envelopes from Tables
+//!
+//! * [`TableMapping::envelopes`] Creates an envelope for each row of a table,
+//!   mapping columns to predicates with a [`ColumnType`] for each.
+//! * [`TableMapping::envelopes_from_csv`] Creates an envelope for each row of
+//!   CSV text.
+//! * [`TableMapping::collection`] Creates a single envelope with an assertion
+//!   for each row of a table.
 //!
 //! # Adding Assertions
 //!
@@ -58,11 +73,111 @@
 //! * [`Envelope::add_optional_assertion_envelope_salted`] Optionally adds an
 //!   assertion envelope to an envelope.
 //!
+//! ### Adding Assertions to Obscured Subjects
+//!
+//! * [`Envelope::add_assertion_to_obscured`] Adds an assertion to an envelope
+//!   whose subject is elided, encrypted, or compressed, preserving its digest.
+//! * [`Envelope::lint`] Reports nodes with assertions on an elided subject,
+//!   which are usually a mistake.
+//!
+//! ### Validating Assertions
+//!
+//! * [`AssertionValidator`] A function that checks an assertion as it is
+//!   added, rejecting malformed documents at construction.
+//! * [`Envelope::try_add_assertion`] Adds an assertion after checking it with
+//!   the given validators.
+//! * [`Envelope::add_assertion_envelope_validated`] Adds an assertion
+//!   envelope after checking it with the given validators.
+//! * [`validate_assertion`] Checks an assertion with the given validators.
+//!
+//! # Transforming Envelopes
+//!
+//! * [`Envelope::map_leaves`] Replaces each leaf with the result of a
+//!   function.
+//! * [`Envelope::map_objects_for_predicate`] Replaces the objects of the
+//!   assertions with a given predicate.
+//! * [`Envelope::map_subtree`] Replaces the element with a given digest.
+//!
 //! # Removing and Replacing Assertions
 //!
 //! * [`Envelope::remove_assertion`] Removes an assertion from an envelope.
 //! * [`Envelope::replace_assertion`] Replaces an assertion in an envelope.
 //! * [`Envelope::replace_subject`] Replaces the subject of an envelope.
+//! * [`Envelope::replace_subject_checked`] Replaces the subject of an
+//!   envelope only if the new subject has the same digest.
+//! * [`Envelope::replace_subject_keeping_assertions`] Replaces the subject of
+//!   an envelope, keeping only the selected assertions.
+//!
+//! # Journaling Edits
+//!
+//! * [`Envelope::add_assertion_journaled`] Adds an assertion, recording the
+//!   edit in an [`EditJournal`].
+//! * [`Envelope::remove_assertion_journaled`] Removes an assertion, recording
+//!   the edit in an [`EditJournal`].
+//! * [`Envelope::replace_assertion_journaled`] Replaces an assertion,
+//!   recording the edit in an [`EditJournal`].
+//! * [`EditJournal::verified_entries`] Returns a journal envelope's entries
+//!   in order, checking that they form an unbroken hash chain.
+//!
+//! # Anonymizing Envelopes
+//!
+//! * [`Envelope::anonymize`] Replaces values selected by an
+//!   [`AnonymizePolicy`], returning a mapping for re-identification.
+//! * [`Envelope::reidentify`] Restores the values removed by
+//!   [`Envelope::anonymize`].
+//!
+//! # JWT/CWT Claims
+//!
+//! * [`Envelope::from_claims`] Creates an envelope from a [`ClaimsSet`].
+//! * [`Envelope::to_claims`] Recovers the [`ClaimsSet`] from an envelope.
+//!
+//! # Attachments
+//!
+//! * [`Envelope::add_attachment`] Adds an `'attachment'` assertion with the
+//!   given payload, `vendor`, and optional `conformsTo`.
+//! * [`Envelope::add_attachment_with_id`] Adds an attachment with an `id` that
+//!   distinguishes it from others with the same `vendor` and `conformsTo`.
+//! * [`Envelope::add_attachment_to_assertion`] Adds an attachment to one of
+//!   the envelope's assertions rather than to its subject.
+//! * [`Envelope::attachment_with_id`] Returns the attachment with the given
+//!   `id`.
+//! * [`Envelope::attachment_infos`] Returns an [`AttachmentInfo`] describing
+//!   each attachment on the envelope's subject and its assertions.
+//! * [`Envelope::validate_attachments`] Checks the envelope's attachments
+//!   against an [`AttachmentPolicy`] limiting their number, size, and
+//!   vendors.
+//!
+//! # Migrating Legacy Envelopes
+//!
+//! * [`Envelope::migrate_legacy`] Rewrites an envelope produced by an early
+//!   implementation into the current canonical form, as configured by a
+//!   [`LegacyMigration`].
+//! * [`Envelope::is_legacy`] Tests whether an envelope contains legacy content.
+//!
+//! # Translating Known Values
+//!
+//! * [`Envelope::translate_known_values`] Rewrites known value predicates for
+//!   a peer using a different registry, as configured by a
+//!   [`KnownValueTranslation`], and returns a [`TranslationReport`] that
+//!   converts to an auditable mapping envelope.
+//!
+//! # Exporting to RDF
+//!
+//! * [`Envelope::to_turtle`] Returns the envelope as an RDF graph in Turtle
+//!   notation.
+//!
+//! # Test Vectors
+//!
+//! * [`extension::fixtures`] Canonical example envelopes with digests that are
+//!   stable across releases.
+//! * [`extension::fixtures::ScenarioBuilder`] Builds deterministic families of
+//!   issuer documents, credentials, and presentations with varying
+//!   redactions for integration tests.
+//!
+//! # Memory-Mapped Envelopes
+//!
+//! * [`Envelope::open_mmap`] Maps a stored envelope for read-only access,
+//!   decoding only the subtrees that are accessed.
 //!
 //! # Queries
 //!
@@ -77,6 +192,8 @@
 //! ### Getting assertions on an envelope
 //!
 //! * [`Envelope::assertions`] Returns the assertions of an envelope.
+//! * [`Envelope::iter_assertions`] Returns an iterator over the assertions of
+//!   an envelope.
 //! * [`Envelope::has_assertions`] Returns whether an envelope has assertions.
 //! * [`Envelope::assertion`] If the envelope’s subject is an assertion return
 //!   it, else return `None`.
@@ -119,12 +236,20 @@
 //!   given predicate.
 //! * [`Envelope::assertions_with_predicate`] Returns all assertions with the
 //!   given predicate.
+//! * [`Envelope::iter_assertions_with_predicate`] Returns an iterator over all
+//!   assertions with the given predicate.
 //! * [`Envelope::object_for_predicate`] Returns the object of the assertion
 //!   with the given predicate.
 //! * [`Envelope::objects_for_predicate`] Returns the objects of all assertions
 //!   with the matching predicate.
+//! * [`Envelope::iter_objects_for_predicate`] Returns an iterator over the
+//!   objects of all assertions with the matching predicate.
 //! * [`Envelope::elements_count`] Returns the number of elements in the
 //!   envelope.
+//! * [`Envelope::predicate_inventory`] Returns the distinct predicates used
+//!   anywhere in the envelope, with their counts.
+//! * [`Envelope::heap_size_estimate`] Returns an estimate of the heap memory
+//!   retained by the envelope, counting shared subtrees once.
 //!
 //! ### Extracting parts of envelopes as specific types
 //!
@@ -134,6 +259,27 @@
 //!   assertion with the given predicate, decoded as the given type.
 //! * [`Envelope::extract_objects_for_predicate`] Returns the objects of all
 //!   assertions with the matching predicate, decoded as the given type.
+//! * [`Envelope::extract_adapted_subject`] Returns the envelope’s subject,
+//!   decoded by the [`LeafTagAdapter`] registered for its tag.
+//! * [`known_value_enum!`] Declares an enum whose variants are represented by
+//!   known values, which can be used as objects and extracted again with
+//!   `TryFrom<Envelope>`.
+//!
+//! ### Dates
+//!
+//! * [`Envelope::add_date_assertion`] Adds an assertion whose object is a
+//!   date.
+//! * [`Envelope::dates_for_predicate`] Returns the dates that are the objects
+//!   of the assertions with the given predicate, earliest first.
+//! * [`Envelope::assertions_with_date_in_range`] Returns the assertions with
+//!   the given predicate whose dates fall in a range.
+//! * [`envelopes_with_date_in_range`] Returns the envelopes in a collection
+//!   with an assertion whose date falls in a range.
+//!
+//! ### Validating tagged leaves
+//!
+//! * [`Envelope::validate_leaf_tags`] Checks every tagged leaf that has a
+//!   registered [`LeafTagAdapter`] validator.
 //!
 //! ### Other queries
 //!
@@ -146,20 +292,68 @@
 //!
 //! * [`Envelope::wrap_envelope`] Wraps an envelope in a new envelope.
 //! * [`Envelope::unwrap_envelope`] Unwraps an envelope.
+//! * [`Envelope::unwrap_n`] Unwraps a given number of layers of wrapping.
+//! * [`Envelope::unwrap_all`] Unwraps every layer of wrapping, returning the
+//!   number of layers removed.
+//! * [`Envelope::unwrap_until`] Unwraps layers of wrapping until reaching an
+//!   envelope that matches an [`EnvelopeMatcher`].
+//! * [`Envelope::unwrap_all_to_depth`] and [`Envelope::unwrap_until_depth`]
+//!   Do the same, removing at most a given number of layers.
 //!
 //! # Formatting Envelopes
 //!
+//! ### Format contexts
+//!
+//! * [`FormatContext::with_tags`] Builds a format context fluently, together
+//!   with `with_known_values`, `with_functions`, and `with_parameters`,
+//!   without touching the global context. Clones share their registries
+//!   until one of them is changed.
+//! * [`FormatContext::with_conflict_resolver`] Decides which name to keep
+//!   when names registered from several sources conflict.
+//!
 //! ### Envelope notation
 //!
 //! * [`Envelope::format`] Formats an envelope in envelope notation.
 //! * [`Envelope::format_opt`] Formats an envelope in envelope notation, with
 //!   optional annotations.
+//! * [`Envelope::format_versioned`] Formats an envelope in envelope notation,
+//!   pinned to a [`FormatVersion`] whose output never changes.
+//! * [`FormatContext::set_sort_by_predicate_name`] Lists assertions by
+//!   predicate name rather than in canonical order, for display only.
+//!
+//! ### Localized names
+//!
+//! * [`set_localized_names`] Sets translated display names for known values
+//!   and functions in the global format context. The canonical names are
+//!   unaffected.
+//!
+//! * [`FormatContext::set_known_value_display`] Shows a known value as the
+//!   given text, such as `type` in place of `'isA'`. Digests are unaffected.
+//!
+//! ### URI prefixes
+//!
+//! * [`Envelope::uri_predicate`] Returns a URI predicate, such as
+//!   `https://schema.org/name`, for Linked Data-style vocabularies.
+//! * [`FormatContext::set_uri_prefix`] Abbreviates URIs in a namespace, such
+//!   as showing `https://schema.org/name` as `schema:name`.
 //!
 //! ### Tree notation
 //!
 //! * [`Envelope::tree_format`] Formats an envelope in envelope tree notation.
 //! * [`Envelope::tree_format_with_target`] Formats an envelope in envelope tree
 //!   notation, highlighting a target set of elements.
+//! * [`Envelope::tree_format_versioned`] Formats an envelope in envelope tree
+//!   notation, pinned to a [`FormatVersion`].
+//! * [`Envelope::tree_format_paged`] Formats one page of an envelope's tree
+//!   notation, so that huge envelopes can be shown incrementally.
+//! * [`FormatContext::with_max_depth`] Limits tree notation to a number of
+//!   levels below the root.
+//! * [`FormatContext::set_digest_namer`] Shows a memorable name next to each
+//!   digest in tree notation, such as `8cc96cdb (lucid-otter)`, using a
+//!   [`DigestNamer`] such as [`Petnames`].
+//! * [`FormatContext::with_digest_display_format`] Shows digests in tree
+//!   notation and error messages as a [`DigestDisplayFormat`], such as
+//!   bytewords or emoji.
 //!
 //! ### CBOR diagnostic notation
 //!
@@ -173,6 +367,84 @@
 //! * [`Envelope::hex_opt`] Formats an envelope in CBOR hexadecimal notation,
 //!   with optional annotations.
 //!
+//! # Checking Encoding
+//!
+//! * [`Envelope::verify_round_trip`] Checks that an envelope survives a round
+//!   trip through its CBOR encoding.
+//! * [`set_round_trip_checking`] In debug builds, checks the encoding of every
+//!   envelope as it is constructed.
+//! * [`Envelope::freeze`] Returns a [`FrozenEnvelope`] that caches the
+//!   envelope's CBOR encoding, UR string, and digests for repeated
+//!   serialization.
+//! * [`Envelope::check_data_spec_conformance`] Reports the MUST and SHOULD
+//!   requirements of the specification that encoded envelope data does not
+//!   meet, keyed to draft section numbers.
+//! * [`Envelope::check_spec_conformance`] Does the same for an envelope's
+//!   own encoding.
+//!
+//! # Ingesting Untrusted Data
+//!
+//! * [`Envelope::ingest_cbor_data`] Decodes an envelope from untrusted CBOR
+//!   data within [`IngestLimits`], reporting failures as an [`IngestError`].
+//! * [`Envelope::ingest_ur_string`] Decodes an envelope from an untrusted UR
+//!   string within [`IngestLimits`].
+//! * [`IngestLimits::with_lenient`] Decodes elements this build does not
+//!   support, such as those from later versions of the format, instead of
+//!   rejecting the envelope.
+//!
+//! Decoding fails with [`EnvelopeError::TooDeeplyNested`] for envelopes nested
+//! more than [`IngestLimits::max_depth`] levels deep, or
+//! [`MAX_DECODE_DEPTH`](base::cbor::MAX_DECODE_DEPTH) levels when not
+//! ingesting, and the walks behind [`Envelope::walk`] and
+//! [`Envelope::walk_events`] use no recursion, so hostile data cannot exhaust
+//! the stack.
+//!
+//! # Scanning Multipart URs
+//!
+//! * [`MultipartEnvelopeDecoder`] Assembles an envelope from the parts of a
+//!   multipart `ur:envelope`, such as the frames of an animated QR code,
+//!   reporting its [`MultipartProgress`] as each part arrives. Its state
+//!   converts to and from CBOR, so a long scan can be resumed later.
+//!
+//! # Reading and Writing CBOR Sequences
+//!
+//! * [`EnvelopeSeqWriter`] Appends envelopes to a CBOR sequence, such as a
+//!   `.cborseq` file.
+//! * [`write_indexed_envelope_seq`] Writes envelopes as a CBOR sequence with an
+//!   index of their offsets.
+//! * [`EnvelopeSeqReader`] Reads the envelopes of a CBOR sequence one after
+//!   another, or by offset.
+//!
+//! # Checking Dates
+//!
+//! * [`TimePolicy`] Describes how dates are checked against the current time,
+//!   with a tolerance for clock skew and a replaceable [`Clock`].
+//! * [`Envelope::check_validity`] Checks an envelope's `'validFrom'`,
+//!   `'validUntil'`, and `'date'` assertions.
+//! * [`Envelope::verify_signature_from_with_policy`] Verifies a signature and
+//!   checks the dates in its metadata.
+//!
+//! # Describing Errors
+//!
+//! * [`set_error_context_length`] Attaches an [`ErrorContext`] identifying
+//!   the offending element to errors from unwrapping, signature verification,
+//!   and typed extraction.
+//!
+//! # Checking Build Features
+//!
+//! * [`capabilities`] Returns the names of the features this crate was
+//!   compiled with, so applications can adapt at runtime.
+//! * [`has_capability`] Returns whether the crate was compiled with a
+//!   feature.
+//! * [`require_capability`] Returns an error naming a feature the crate was
+//!   compiled without.
+//!
+//! # Binding from C
+//!
+//! * [`extension::ffi`] With the `ffi` feature, a C ABI for creating,
+//!   signing, verifying, eliding, and serializing envelopes through opaque
+//!   handles, with stable status codes.
+//!
 //! # Working with the Digest Tree
 //!
 //! ### Semantic equivalence
@@ -184,8 +456,30 @@
 //! * [`Envelope::deep_digests`] Returns the set of all digests in the envelope.
 //! * [`Envelope::shallow_digests`] Returns the set of all digests in the
 //!   envelope, down to its second level.
+//! * [`Envelope::find_by_digest`] Returns the element with the given digest
+//!   and the [`EnvelopePath`] to it.
+//! * [`Envelope::path_to`] Returns the path to the element with the given
+//!   digest as a string such as `subject/assertion[2]/object`.
+//! * [`Envelope::paths_matching`] Returns the path to each element that an
+//!   [`EnvelopeMatcher`] matches.
+//! * [`search_store`] Lazily searches every envelope in an [`EnvelopeStore`]
+//!   for elements that an [`EnvelopeMatcher`] matches.
+//! * [`Forest`] Holds many root envelopes that share their common subtrees,
+//!   searching and encoding each shared subtree once.
+//! * [`Envelope::unelide_from_store`] Restores each elided element that an
+//!   [`EnvelopeStore`] has, including elided elements within restored ones.
+//! * [`Envelope::walk_unelide`] Restores each elided element that is one of
+//!   a set of envelopes.
+//! * [`Pattern`] Matches elements by their number of assertions, their
+//!   digests, or whether they are obscured.
+//! * [`QueryCache`] Caches the results of queries by envelope structure and
+//!   query text.
 //! * [`Envelope::is_equivalent_to`] Tests two envelopes for semantic
 //!   equivalence.
+//! * [`Envelope::equivalence_failure_hint`] Explains where two envelopes that
+//!   are not equivalent diverge.
+//! * [`assert_equivalent!`] Asserts that two envelopes are equivalent,
+//!   explaining where they diverge on failure.
 //!
 //! ### Structural identicality
 //!
@@ -194,6 +488,12 @@
 //!   semantically equivalent.
 //! * [`Envelope::is_identical_to`] Tests two envelopes for structural equality.
 //!
+//! ### Similar shapes
+//!
+//! * [`Envelope::structural_fingerprint`] Returns a locality-sensitive
+//!   fingerprint of the shape of an envelope that ignores the values of its
+//!   leaves, for clustering similar kinds of document.
+//!
 //! # Signing and Verifying Signatures
 //!
 //! ### Signing
@@ -233,6 +533,19 @@
 //!   has a set of signatures.
 //! * [`Envelope::verify_signatures_from_threshold`] Checks whether the
 //!   envelope's subject has some threshold of signatures.
+//! * [`Envelope::verify_signatures_from_weighted`] Checks whether the
+//!   envelope's subject has signatures from weighted signers meeting a target
+//!   weight, and returns which signers met it.
+//! * [`Envelope::signature_report`] Describes each of the envelope's
+//!   signatures, including which verifier matched and what it covers.
+//! * [`Envelope::verify_signature_resolving`] Verifies the envelope's
+//!   signature and its signer's, fetching each signer's document with a
+//!   [`SignerResolver`], and returns the [`TrustPath`].
+//! * [`Envelope::verify_then_decrypt`] Decrypts an envelope that was encrypted
+//!   and then signed, only if its signature is valid.
+//! * [`Envelope::verify_then_decrypt_to_recipient`] Decrypts an envelope that
+//!   was encrypted to a recipient and then signed, only if its signature is
+//!   valid.
 //!
 //! ### Helpers
 //!
@@ -241,11 +554,67 @@
 //! * [`Envelope::make_signed_assertion`] Convenience constructor for a
 //!   `signed: Signature` assertion envelope.
 //!
+//! ### Witnesses
+//!
+//! * [`WitnessSet`] Collects signatures of an envelope's subject digest from
+//!   several [`Witness`]es, such as [`SigningWitness`]es.
+//! * [`Envelope::add_witness_signatures`] Adds collected witness signatures to
+//!   the envelope's `"witnessedBy"` bundle.
+//! * [`Envelope::verify_witnesses`] Checks that a minimum number of the given
+//!   witnesses signed the envelope's subject.
+//! * [`witness_message`] Returns the domain-separated digest that witnesses
+//!   sign in place of the subject's digest.
+//!
+//! Aggregated signatures, such as MuSig2, in which several signers produce a
+//! single `'signed': Signature`, are not supported, because `bc-components`
+//! provides no aggregation primitives. Multi-party signing is done instead
+//! with one signature per signer, checked with
+//! [`Envelope::verify_signatures_from_threshold`] or
+//! [`Envelope::verify_witnesses`].
+//!
+//! ### Trust Stores
+//!
+//! * [`TrustStore`] Binds trusted public keys to identity envelopes, such as
+//!   names and roles, and is exported and imported as an envelope.
+//! * [`Envelope::verify_signature_trusted`] Checks that the envelope's subject
+//!   was signed by a trusted key, and returns the signer's
+//!   [`VerifiedIdentity`].
+//! * [`Envelope::trusted_signers`] Returns the identities of every trusted key
+//!   that signed the envelope's subject.
+//!
+//! ### Key Rotation
+//!
+//! * [`Envelope::attest_rotation`] Adds a `"keyRotation"` assertion, signed
+//!   by an outgoing key, naming the keys that replace it.
+//! * [`RotationHistory`] The chain of keys that have controlled a document,
+//!   built from its initial keys and its attested rotations.
+//! * [`Envelope::verify_with_rotation_history`] Checks that the envelope's
+//!   subject was signed by a key valid at the signing time it claims.
+//! * [`Envelope::verify_with_rotation_history_opt`] Optionally rejects
+//!   signatures from retired keys, which could otherwise be backdated.
+//!
+//! ### Presenting Credentials
+//!
+//! * [`present`] Wraps a credential, which may be redacted, with the
+//!   verifier's ARID and nonce and the current date, signed by its holder.
+//! * [`verify_presentation`] Checks the issuer's and holder's signatures, the
+//!   audience, the nonce, and the freshness of a presentation.
+//!
+//! ### Verifying Corpora
+//!
+//! * [`verify_corpus`] Checks the structure and signatures of many encoded
+//!   envelopes on a pool of worker threads, streaming a [`CorpusResult`] for
+//!   each as it completes.
+//! * [`CorpusPolicy`] Sets the number of workers, the [`IngestLimits`], and
+//!   whether signatures and their dates are checked.
+//!
 //! # Splitting Envelopes with SSKR
 //!
 //! * [`Envelope::sskr_split`] Splits the envelope into a set of SSKR shares.
 //! * [`Envelope::sskr_join`] Creates a new envelope resulting from the joining
 //!   a set of envelopes split by SSKR.
+//! * [`SSKRJoiner`] Joins an envelope from shares received one at a time,
+//!   checking each share as it arrives.
 //!
 //! # Encryption
 //!
@@ -253,6 +622,25 @@
 //!   encrypted.
 //! * [`Envelope::decrypt_subject`] Returns a new envelope with its subject
 //!   decrypted.
+//! * [`Envelope::decrypt_subject_to_secret`] Returns the decrypted content of
+//!   the envelope's subject as a [`SecretEnvelopeContent`], which is zeroized
+//!   when it is dropped.
+//! * [`Envelope::walk_decrypt`] Decrypts every encrypted element that one of
+//!   a set of keys can decrypt.
+//! * [`Envelope::walk_decrypt_with_report`] Also returns a
+//!   [`DecryptionReport`] of the elements that remain encrypted, and why.
+//! * [`Envelope::new_with_encrypted`] Creates an encrypted envelope from a
+//!   message encrypted elsewhere, such as by a hardware security module.
+//! * [`Envelope::to_encrypted_ur`] Returns a `ur:crypto-envelope` string
+//!   holding the envelope encrypted with a key or password.
+//! * [`Envelope::from_encrypted_ur`] Decrypts the envelope in a
+//!   `ur:crypto-envelope` string.
+//! * [`KeyProvider`] Supplies per-message content keys, such as from a
+//!   ratchet or an MLS group.
+//! * [`Envelope::encrypt_subject_with_provider`] Encrypts the subject with
+//!   the next key from a [`KeyProvider`], recording the key's identifier.
+//! * [`Envelope::decrypt_subject_with_provider`] Decrypts the subject with
+//!   the key a [`KeyProvider`] supplies for its identifier.
 //!
 //! # Public Key Encryption
 //!
@@ -265,8 +653,33 @@
 //! * [`Envelope::encrypt_subject_to_recipient`] Returns a new envelope with its
 //!   subject encrypted and a `hasRecipient` assertion added for the
 //!   `recipient`.
+//! * [`Envelope::encrypt_subject_to_recipients_with_provider`] Encrypts the
+//!   subject with the next key from a [`KeyProvider`] and seals that key to
+//!   each recipient.
 //! * [`Envelope::decrypt_to_recipient`] Returns a new envelope with its subject
 //!   decrypted using the recipient's `PrivateKeyBase`.
+//! * [`Envelope::seal_onion`] Signs an envelope and encrypts it in layers for
+//!   relaying along a route of recipients.
+//! * [`Envelope::open_onion_layer`] Removes one layer of an onion-sealed
+//!   envelope, revealing the next hop's address and the envelope to forward
+//!   to it.
+//!
+//! ### Recipient Groups
+//!
+//! * [`RecipientGroup`] A named set of public keys, each with a role.
+//! * [`Envelope::encrypt_subject_to_group`] Encrypts the subject to every
+//!   member of a group, and records the group's name and roles.
+//! * [`Envelope::reencrypt_to_group`] Re-encrypts an envelope to a group
+//!   after its membership or keys have changed.
+//!
+//! ### Requiring Signed, Encrypted, or Sealed Envelopes
+//!
+//! * [`SignedEnvelope`] An envelope known to have been signed, so that APIs
+//!   can require signing at compile time. A received envelope must be
+//!   verified to become one.
+//! * [`EncryptedEnvelope`] An envelope known to have been encrypted.
+//! * [`SealedEnvelope`] An envelope that was sealed to a recipient. Only the
+//!   recipient can check its signature.
 //!
 //! # Compression
 //!
@@ -277,6 +690,15 @@
 //!   compressed.
 //! * [`Envelope::uncompress_subject`] Returns this envelope with its subject
 //!   uncompressed.
+//! * [`Envelope::verify_compressed_integrity`] Checks that every compressed
+//!   element decompresses to content matching its digest.
+//! * [`Envelope::new_with_compressed`] Creates a compressed envelope from data
+//!   compressed elsewhere.
+//! * [`Envelope::optimize_for_compression`] Returns the smallest encoding of
+//!   the envelope with the same digest, compressing it as a whole rather than
+//!   in separately compressed parts where that is smaller.
+//! * [`Envelope::optimize_for_compression_with_report`] Does the same, with a
+//!   [`CompressionReport`] of the sizes measured.
 //!
 //! # Eliding, Encrypting, or Compressing Parts of an Envelope
 //!
@@ -314,9 +736,41 @@
 //!     * [`Envelope::elide_array_with_action`]
 //!     * [`Envelope::elide_target_with_action`]
 //!
+//! * [`Envelope::elide_with_action_map`] Returns a version with each given
+//!   element obscured using its own action, so that a single pass can elide,
+//!   encrypt, and compress different elements.
+//!
+//! * [`Envelope::reveal_token`] Returns a compact [`RevealToken`] describing
+//!   the elements to leave revealed, which the holder of the envelope can
+//!   apply with [`Envelope::apply_reveal_token`].
+//!
+//! * [`Envelope::elide_with_placeholders`] Returns a version with the leaves
+//!   chosen by a [`PlaceholderPolicy`] replaced by placeholders disclosing
+//!   their kind and length, which a verifier can read with
+//!   [`Envelope::placeholder_shape`] and check against a value revealed
+//!   later with [`Envelope::verify_placeholder`]. The shapes are
+//!   unauthenticated claims by whoever elided the envelope.
+//!
+//! * [`Envelope::leakage_report`] Returns a [`LeakageReport`] of the
+//!   structure an obscured envelope still reveals, such as the number of
+//!   assertions withheld from each node, its depth, and the sizes of its
+//!   encrypted and compressed elements.
+//!
+//! * [`Envelope::pad_assertions`] Adds decoy salt assertions so that eliding
+//!   them along with real assertions hides how many were withheld.
+//!
 //! * [`Envelope::unelide`] Returns the unelided variant of this envelope, given
 //!   the envelope that was elided.
 //!
+//! * [`Envelope::opening_for`] Returns an opening of a single element, which
+//!   the holder of the elided envelope can check with
+//!   [`Envelope::verify_opening`] without seeing the element's neighbors.
+//!
+//! * [`Envelope::add_assertion_commitment`] Commits to the exact count and
+//!   order of the envelope's assertions, so that a verifier of an elided
+//!   version can detect with [`Envelope::verify_assertion_commitment`] that
+//!   assertions were withheld entirely rather than elided.
+//!
 //! # Decorrelating Envelopes using Salt
 //!
 //! * [`Envelope::add_salt`] Add a number of bytes of salt generally
@@ -324,11 +778,20 @@
 //! * [`Envelope::add_salt_with_len`] Add a specified number of bytes of salt.
 //! * [`Envelope::add_salt_in_range`] Add a number of bytes of salt chosen
 //!   randomly from the given range.
+//! * [`Envelope::salts`] Returns every `'salt'` assertion with the node it is
+//!   on.
+//! * [`Envelope::strip_salts`] Removes every `'salt'` assertion, so that
+//!   envelopes differing only in their salt become equivalent.
 //!
 //! # Walking an Envelope's Hierarchy
 //!
 //! * [`Envelope::walk`] Walk the envelope, calling the visitor function for
 //!   each element.
+//! * [`Envelope::visit`] Dispatch on the envelope's case, calling the
+//!   corresponding method of an [`EnvelopeVisitor`].
+//! * [`Envelope::walk_events`] Iterate over the envelope's elements as
+//!   [`WalkEvent`]s, which can be written as JSON Lines with
+//!   [`WalkEvent::to_json`].
 //!
 //! # Envelope Expressions
 //!
@@ -354,6 +817,61 @@
 //!   subject and a `error: value` assertion.
 //! * [`Envelope::new_error_response`] Creates an envelope with an `unknown`
 //!   subject and a `error: value` assertion.
+//! * [`ExpressionUR::ur_string`] Encodes a request or response as a
+//!   `ur:request` or `ur:response`, which [`ExpressionUR::from_ur_string`]
+//!   decodes, rejecting URs of any other type.
+//!
+//! ### Analyzing Expressions
+//!
+//! * [`Expression::nested_expressions`] Returns the expressions nested in an
+//!   expression's parameters as [`NestedExpression`]s, each after the
+//!   expressions it depends on.
+//!
+//! ### Capabilities
+//!
+//! * [`Capability::issue`] Signs a [`Capability`] granting the authority to
+//!   invoke functions on a target, with caveats such as an expiry date or a
+//!   maximum number of uses.
+//! * [`Capability::delegate`] Delegates a capability to a new holder,
+//!   possibly with further restrictions.
+//! * [`Envelope::verify_capability_chain`] Verifies a capability and its
+//!   delegations, returning the authority they grant.
+//! * [`Capability::check_expression`] Checks that a capability allows an
+//!   expression.
+//!
+//! ### Retrying Requests
+//!
+//! * [`RequestBehavior::with_idempotency_key`] Adds an `"idempotencyKey"`
+//!   assertion so that the request is performed at most once when retried.
+//! * [`IdempotencyCache::respond`] Performs a request, or returns the cached
+//!   response to an earlier request with the same idempotency key, or a
+//!   processing response while that request is still being performed.
+//! * [`Response::new_processing`] Creates a response saying that a request
+//!   has not yet completed, optionally with [`Response::with_retry_after`]
+//!   and [`Response::with_progress`].
+//! * [`ResponseBehavior::retry_after`] Returns how long the server asked the
+//!   client to wait before asking again.
+//! * [`PollPolicy::poll`] Sends a request until it is no longer processing,
+//!   waiting as long as the server asks between attempts.
+//!
+//! ### Tracing Requests
+//!
+//! * [`TraceContext::new_root`] Starts a distributed trace, and
+//!   [`TraceContext::child`] continues it in a further request.
+//! * [`RequestBehavior::with_trace_context`] and
+//!   [`ResponseBehavior::with_trace_context`] Add `traceID`, `spanID`, and
+//!   `parentSpanID` assertions to a request or response.
+//! * [`TraceContext::to_traceparent`] and [`TraceContext::from_traceparent`]
+//!   Propagate a trace context as a W3C `traceparent` header.
+//! * [`CallTrace::from_envelopes`] Assembles the tree of requests in a trace
+//!   from the envelopes exchanged, and [`CallTrace::to_json_lines`] exports it
+//!   for OpenTelemetry-style tooling.
+//!
+//! ### Testing Other Implementations
+//!
+//! * [`extension::expressions::conformance`] A scripted peer that exchanges
+//!   canned requests and responses with another implementation over stdin
+//!   and stdout or TCP, reporting where it differs.
 //!
 //! ### Decoding Parameters and Results
 //!
@@ -370,12 +888,53 @@
 //! * [`Envelope::is_result_ok`] Returns whether the `result` predicate has the
 //!   `KnownValue` `.ok`.
 //! * [`Envelope::error`] Returns the error value, decoded as the given type.
+//! * [`ResponseBehavior::error_details`] Returns the error value, decoded as a
+//!   structured [`ErrorResponse`].
+//! * [`Response::from_result`] Creates a response from a Rust `Result`, whose
+//!   error converts to an [`ErrorResponse`].
+//! * [`ResponseBehavior::extract_result_as`] Returns the result decoded as a
+//!   structured value, or the error mapped to the caller's error type.
 
 pub use anyhow::Result;
 
 pub mod base;
-pub use base::{Assertion, Envelope, EnvelopeEncodable, EnvelopeError};
+pub use base::{Assertion, Envelope, EnvelopeEncodable, EnvelopeError, EdgeType, EnvelopeVisitor, WalkEvent};
 pub use base::{register_tags, register_tags_in, FormatContext, GLOBAL_FORMAT_CONTEXT};
+pub use base::{ConflictResolution, ConflictResolver, RegistrationConflict, RegistrationKind};
+pub use base::{
+    register_leaf_tag_adapter,
+    register_leaf_tag_adapter_in,
+    LeafTagAdapter,
+    LeafTagAdaptersStore,
+};
+pub use base::{set_localized_names, set_localized_names_in, LocalizedNames};
+pub use base::{
+    validate_assertion,
+    AssertionValidator,
+};
+pub use base::{is_round_trip_checking, set_round_trip_checking};
+pub use base::{error_context_length, set_error_context_length, ErrorContext};
+pub use base::EnvelopePath;
+pub use base::RevealToken;
+pub use base::{LeafKind, LeafShape, PlaceholderDetail, PlaceholderPolicy};
+pub use base::{LeakageReport, NodeLeakage};
+pub use base::StructuralFingerprint;
+pub use base::{MultipartEnvelopeDecoder, MultipartProgress};
+pub use base::FrozenEnvelope;
+pub use base::Forest;
+pub use base::FormatVersion;
+pub use base::{DigestDisplayFormat, DigestNamer, Petnames};
+pub use base::{Clock, FixedClock, SystemClock, TimePolicy};
+pub use base::envelopes_with_date_in_range;
+pub use base::{IngestError, IngestLimits};
+pub use base::{write_indexed_envelope_seq, EnvelopeSeqIter, EnvelopeSeqReader, EnvelopeSeqWriter};
+pub use base::EnvelopeLint;
+pub use base::{SpecFinding, SpecRequirement};
+pub use base::{search_store, EnvelopeMatcher, EnvelopeStore};
+pub use base::{DigestPattern, NodePattern, ObscuredPattern, Pattern};
+pub use base::QueryCache;
+pub use base::{capabilities, has_capability, require_capability};
+pub use base::{ColumnType, TableMapping};
 pub use base::elide::{self, ObscureAction};
 
 pub mod extension;
@@ -383,23 +942,75 @@ pub mod prelude;
 
 mod string_utils;
 
+#[cfg(all(feature = "signature", feature = "recipient"))]
 use bc_components::{EncapsulationPrivateKey, Encrypter};
+#[cfg(all(feature = "signature", feature = "recipient"))]
+use bc_components::Decrypter;
+#[cfg(all(feature = "signature", feature = "recipient"))]
+use anyhow::bail;
 #[cfg(feature = "signature")]
 pub use bc_components::{Signer, Verifier};
 
 #[cfg(feature = "signature")]
-pub use extension::SignatureMetadata;
+pub use extension::{present, verify_corpus, verify_presentation, CorpusPolicy, CorpusResult, CorpusResults, RotationHistory, SignatureCoverage, SignatureMetadata, SignatureReport, SignerResolver, SigningWitness, TrustLink, TrustPath, TrustStore, VerifiedIdentity, witness_message, Witness, WitnessSet};
+
+#[cfg(feature = "signature")]
+pub use extension::SignedEnvelope;
+
+#[cfg(feature = "encrypt")]
+pub use extension::EncryptedEnvelope;
+
+#[cfg(all(feature = "signature", feature = "recipient"))]
+pub use extension::SealedEnvelope;
 
 #[cfg(feature = "recipient")]
 pub use bc_components::{PrivateKeyBase, PublicKeyBase};
 
+#[cfg(feature = "recipient")]
+pub use extension::RecipientGroup;
+
+#[cfg(feature = "sskr")]
+pub use extension::{SSKRJoinStatus, SSKRJoiner};
+
+#[cfg(feature = "compress")]
+pub use extension::CompressionReport;
+
+#[cfg(feature = "encrypt")]
+pub use extension::{DecryptionFailure, DecryptionReport, KeyProvider, SecretEnvelopeContent, URSecret};
+
+#[cfg(feature = "provenance")]
+pub use extension::{EditJournal, JOURNAL_ENTRY, PREVIOUS_ENTRY};
+
+#[cfg(feature = "anonymize")]
+pub use extension::{AnonymizeAction, AnonymizePolicy};
+
+#[cfg(feature = "legacy")]
+pub use extension::{LegacyMigration, LegacyRewrite, MigrationReport};
+
+#[cfg(feature = "mmap")]
+pub use extension::MappedEnvelope;
+
+#[cfg(feature = "claims")]
+pub use extension::{ClaimsSet, CLAIM_AUDIENCE};
+
+#[cfg(all(feature = "expression", feature = "signature"))]
+pub use extension::Capability;
+
+#[cfg(feature = "attachment")]
+pub use extension::{AttachmentInfo, AttachmentPolicy};
+
 #[cfg(feature = "known_value")]
 pub use extension::known_values::{
     self,
     known_value,
     KnownValue,
+    KnownValueEnum,
     KNOWN_VALUES,
     KnownValuesStore,
+    KnownValueRange,
+    KnownValueTranslation,
+    TranslatedPredicate,
+    TranslationReport,
 };
 
 #[cfg(feature = "expression")]
@@ -410,15 +1021,30 @@ pub use extension::expressions::{
     Parameter,
     Expression,
     ExpressionBehavior,
+    ExpressionUR,
+    IdempotencyCache,
     IntoExpression,
+    NestedExpression,
+    PollPolicy,
     Request,
     RequestBehavior,
     Response,
     ResponseBehavior,
+    ErrorResponse,
     Event,
     EventBehavior,
+    CallTrace,
+    SpanStatus,
+    TraceContext,
+    TraceSpan,
 };
 
+/// The predicate of the assertion in each intermediate layer of an envelope
+/// sealed with [`Envelope::seal_onion`] whose object is the address of the
+/// next hop.
+#[cfg(all(feature = "signature", feature = "recipient"))]
+pub const NEXT_HOP: &str = "nextHop";
+
 #[cfg(all(feature = "signature", feature = "recipient"))]
 impl Envelope {
     pub fn seal(&self, sender: &dyn Signer, recipient: &dyn Encrypter) -> Envelope {
@@ -432,4 +1058,49 @@ impl Envelope {
             .decrypt_to_recipient(recipient)?
             .verify(sender)
     }
+
+    /// Seals the envelope for the last hop in `route`, then encrypts the
+    /// result to each earlier hop in turn, so that it can be relayed along
+    /// the route.
+    ///
+    /// Each hop is given as its public key and its address, such as a URL
+    /// or an `ARID`. Each intermediary's layer names the address of the next
+    /// hop in a `"nextHop"` assertion, so the intermediary learns only where
+    /// to forward the envelope. The sender delivers the onion to the first
+    /// hop, whose address is not included.
+    ///
+    /// Each intermediary removes its own layer with
+    /// [`Envelope::open_onion_layer`], which reveals only the next hop and
+    /// the envelope to forward to it. The final recipient uses
+    /// [`Envelope::unseal`]. Only the innermost layer is signed, so the outer
+    /// layers reveal nothing about the sender.
+    ///
+    /// Returns an error if `route` is empty.
+    pub fn seal_onion(&self, sender: &dyn Signer, route: &[(&dyn Encrypter, Envelope)]) -> Result<Envelope> {
+        let Some(((recipient, _), _)) = route.split_last() else {
+            bail!(EnvelopeError::EmptyOnionRoute);
+        };
+        Ok(route
+            .windows(2)
+            .rev()
+            .fold(self.seal(sender, *recipient), |envelope, hops| {
+                let ((hop, _), (_, next_hop)) = (&hops[0], &hops[1]);
+                envelope
+                    .wrap_envelope()
+                    .add_assertion(NEXT_HOP, next_hop.clone())
+                    .encrypt_to_recipient(*hop)
+            }))
+    }
+
+    /// Removes the outermost layer of an envelope sealed with
+    /// [`Envelope::seal_onion`], returning the address of the next hop and
+    /// the envelope to forward to it.
+    ///
+    /// Returns an error if the layer is not for `hop`, or is the final layer,
+    /// which has no next hop.
+    pub fn open_onion_layer(&self, hop: &dyn Decrypter) -> Result<(Envelope, Envelope)> {
+        let layer = self.decrypt_to_recipient(hop)?;
+        let next_hop = layer.object_for_predicate(NEXT_HOP)?;
+        Ok((next_hop, layer.unwrap_envelope()?))
+    }
 }
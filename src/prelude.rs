@@ -1,21 +1,130 @@
 pub use crate::{
     Envelope,
     EnvelopeEncodable,
+    EnvelopeVisitor,
+    WalkEvent,
+    EdgeType,
     FormatContext,
+    ConflictResolution,
+    RegistrationConflict,
+    RegistrationKind,
     with_format_context,
+    envelope,
+    assert_equivalent,
     register_tags,
     register_tags_in,
+    register_leaf_tag_adapter,
+    register_leaf_tag_adapter_in,
+    LeafTagAdapter,
+    LocalizedNames,
+    RevealToken,
+    EnvelopePath,
+    PlaceholderPolicy,
+    PlaceholderDetail,
+    LeafShape,
+    LeafKind,
+    LeakageReport,
+    NodeLeakage,
+    StructuralFingerprint,
+    MultipartEnvelopeDecoder,
+    MultipartProgress,
+    FrozenEnvelope,
+    Forest,
+    FormatVersion,
+    DigestNamer,
+    DigestDisplayFormat,
+    Petnames,
+    TimePolicy,
+    Clock,
+    FixedClock,
+    SystemClock,
+    envelopes_with_date_in_range,
+    IngestError,
+    IngestLimits,
+    EnvelopeSeqReader,
+    EnvelopeSeqWriter,
+    write_indexed_envelope_seq,
+    EnvelopeLint,
+    SpecFinding,
+    SpecRequirement,
+    EnvelopeStore,
+    EnvelopeMatcher,
+    Pattern,
+    QueryCache,
+    ColumnType,
+    TableMapping,
+    search_store,
+    capabilities,
+    has_capability,
+    require_capability,
+    set_localized_names,
+    set_localized_names_in,
+    set_round_trip_checking,
+    is_round_trip_checking,
+    validate_assertion,
+    AssertionValidator,
+    set_error_context_length,
+    error_context_length,
+    ErrorContext,
 };
 
 #[cfg(feature = "known_value")]
 pub use crate::{
+    known_value_enum,
     known_values,
     KnownValue,
+    KnownValueEnum,
     KnownValuesStore,
+    KnownValueRange,
+    KnownValueTranslation,
+    TranslatedPredicate,
+    TranslationReport,
 };
 
 #[cfg(feature = "signature")]
-pub use crate::SignatureMetadata;
+pub use crate::{present, verify_corpus, verify_presentation, CorpusPolicy, CorpusResult, CorpusResults, RotationHistory, SignatureCoverage, SignatureMetadata, SignatureReport, SignerResolver, SigningWitness, TrustLink, TrustPath, TrustStore, VerifiedIdentity, witness_message, Witness, WitnessSet};
+
+#[cfg(feature = "signature")]
+pub use crate::SignedEnvelope;
+
+#[cfg(feature = "encrypt")]
+pub use crate::EncryptedEnvelope;
+
+#[cfg(all(feature = "signature", feature = "recipient"))]
+pub use crate::{SealedEnvelope, NEXT_HOP};
+
+#[cfg(feature = "provenance")]
+pub use crate::{EditJournal, JOURNAL_ENTRY, PREVIOUS_ENTRY};
+
+#[cfg(feature = "anonymize")]
+pub use crate::{AnonymizeAction, AnonymizePolicy};
+
+#[cfg(feature = "legacy")]
+pub use crate::{LegacyMigration, MigrationReport};
+
+#[cfg(feature = "mmap")]
+pub use crate::MappedEnvelope;
+
+#[cfg(feature = "claims")]
+pub use crate::{ClaimsSet, CLAIM_AUDIENCE};
+
+#[cfg(all(feature = "expression", feature = "signature"))]
+pub use crate::Capability;
+
+#[cfg(feature = "attachment")]
+pub use crate::{AttachmentInfo, AttachmentPolicy};
+
+#[cfg(feature = "recipient")]
+pub use crate::RecipientGroup;
+
+#[cfg(feature = "sskr")]
+pub use crate::{SSKRJoinStatus, SSKRJoiner};
+
+#[cfg(feature = "compress")]
+pub use crate::CompressionReport;
+
+#[cfg(feature = "encrypt")]
+pub use crate::{DecryptionFailure, DecryptionReport, KeyProvider, SecretEnvelopeContent, URSecret};
 
 #[cfg(feature = "expression")]
 pub use crate::{
@@ -25,13 +134,22 @@ pub use crate::{
     parameters,
     Expression,
     ExpressionBehavior,
+    ExpressionUR,
+    IdempotencyCache,
     IntoExpression,
+    NestedExpression,
+    PollPolicy,
     Request,
     RequestBehavior,
     Response,
     ResponseBehavior,
+    ErrorResponse,
     Event,
     EventBehavior,
+    CallTrace,
+    SpanStatus,
+    TraceContext,
+    TraceSpan,
 };
 
 pub use crate::elide::{
@@ -0,0 +1,78 @@
+#![cfg(feature = "anonymize")]
+
+use bc_envelope::prelude::*;
+use bc_rand::make_fake_random_number_generator;
+
+mod common;
+use crate::common::check_encoding::*;
+
+fn employee() -> Envelope {
+    Envelope::new("Employee")
+        .add_assertion("name", "Alice Smith")
+        .add_assertion("contact", "alice@example.com")
+        .add_assertion("department", "Engineering")
+        .add_assertion("manager", Envelope::new("Bob").add_assertion("email", "bob@example.com"))
+}
+
+#[test]
+fn test_anonymize() {
+    let e = employee();
+    let policy = AnonymizePolicy::new()
+        .anonymize_predicate("name", AnonymizeAction::SaltedHash)
+        .anonymize_matching(|s| s.contains('@'), AnonymizeAction::Elide);
+
+    let mut rng = make_fake_random_number_generator();
+    let (anonymized, mapping) = e.anonymize_using(&policy, &mut rng);
+    anonymized.check_encoding().unwrap();
+
+    // Unselected values are untouched, selected values are replaced.
+    assert_eq!(anonymized.extract_object_for_predicate::<String>("department").unwrap(), "Engineering");
+    assert!(anonymized.object_for_predicate("contact").unwrap().is_elided());
+    let name = anonymized.object_for_predicate("name").unwrap();
+    assert!(name.extract_subject::<Digest>().is_ok());
+    let manager = anonymized.object_for_predicate("manager").unwrap();
+    assert!(manager.object_for_predicate("email").unwrap().is_elided());
+
+    // Elision preserves the digest tree, salted hashes do not.
+    assert_ne!(anonymized.digest(), e.digest());
+    assert_eq!(mapping.assertions().len(), 3);
+
+    let reidentified = anonymized.reidentify(&mapping).unwrap();
+    assert_equivalent!(reidentified, e);
+    assert!(reidentified.is_identical_to(&e));
+
+    // The mapping only applies to the envelope it was produced for.
+    assert!(e.reidentify(&mapping).is_err());
+
+    // Elided entries of the mapping are skipped, and salted ones are used.
+    let contact = mapping.assertions().into_iter().find(|entry| {
+        entry.as_object().unwrap().extract_subject::<String>().unwrap() == "alice@example.com"
+    }).unwrap();
+    let partial = mapping.elide_removing_target(&contact);
+    let reidentified = anonymized.reidentify(&partial).unwrap();
+    assert!(reidentified.object_for_predicate("contact").unwrap().is_elided());
+    assert_eq!(reidentified.extract_object_for_predicate::<String>("name").unwrap(), "Alice Smith");
+    #[cfg(feature = "salt")]
+    {
+        let salted = mapping.assertions().into_iter().fold(Envelope::new(anonymized.digest().into_owned()), |salted, entry| {
+            salted.add_assertion_envelope(entry.add_salt()).unwrap()
+        });
+        assert!(anonymized.reidentify(&salted).unwrap().is_identical_to(&e));
+    }
+    let malformed = Envelope::new(anonymized.digest().into_owned())
+        .add_assertion("not a digest", "value");
+    assert!(anonymized.reidentify(&malformed).is_err());
+}
+
+#[test]
+fn test_anonymize_salted_hashes_are_uncorrelated() {
+    let e = Envelope::new("Meeting")
+        .add_assertion("attendee", "alice@example.com")
+        .add_assertion("organizer", "alice@example.com");
+    let policy = AnonymizePolicy::new()
+        .anonymize_matching(|s| s.contains('@'), AnonymizeAction::SaltedHash);
+    let (anonymized, _) = e.anonymize(&policy);
+    let attendee = anonymized.object_for_predicate("attendee").unwrap();
+    let organizer = anonymized.object_for_predicate("organizer").unwrap();
+    assert_ne!(attendee.digest(), organizer.digest());
+}
@@ -0,0 +1,47 @@
+use std::sync::Arc;
+
+use anyhow::bail;
+use bc_envelope::prelude::*;
+use dcbor::Date;
+
+#[test]
+fn test_assertion_validators() {
+    let date: AssertionValidator = Arc::new(|assertion| {
+        if assertion.as_predicate().and_then(|p| p.extract_subject::<String>().ok()).as_deref() == Some("date") {
+            assertion.as_object().unwrap().extract_subject::<Date>()?;
+        }
+        Ok(())
+    });
+    let vocabulary: AssertionValidator = Arc::new(|assertion| {
+        let predicate: String = assertion.as_predicate().unwrap().extract_subject()?;
+        if !["date", "name", "knows"].contains(&predicate.as_str()) {
+            bail!("unapproved predicate: {}", predicate);
+        }
+        Ok(())
+    });
+    let validators = [date, vocabulary];
+
+    let alice = Envelope::new("Alice")
+        .try_add_assertion("name", "Alice Adams", &validators)
+        .unwrap()
+        .try_add_assertion("date", Date::from_string("2024-07-01").unwrap(), &validators)
+        .unwrap();
+    assert_eq!(alice.assertions().len(), 2);
+
+    // A malformed date and an unapproved predicate are both rejected.
+    assert!(alice.try_add_assertion("date", "July 1st", &validators).is_err());
+    let error = alice.try_add_assertion("likes", "Bob", &validators).unwrap_err();
+    assert_eq!(error.to_string(), "unapproved predicate: likes");
+    assert!(alice
+        .add_assertion_envelope_validated(Envelope::new_assertion("likes", "Bob"), &validators)
+        .is_err());
+
+    // Obscured assertions cannot be checked, so they are accepted.
+    let elided = Envelope::new_assertion("likes", "Bob").elide();
+    assert!(alice.add_assertion_envelope_validated(elided, &validators).is_ok());
+
+    // Validators apply only where they are given, so other assertions, such
+    // as those added by other components, are unaffected.
+    assert_eq!(alice.add_assertion("likes", "Bob").assertions().len(), 3);
+    assert!(alice.try_add_assertion("likes", "Bob", &validators[..1]).is_ok());
+}
@@ -67,3 +67,86 @@ fn test_attachment() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_attachment_ids_and_infos() -> anyhow::Result<()> {
+    use bc_components::DigestProvider;
+
+    let envelope = Envelope::new("Alice")
+        .add_assertion("knows", "Bob")
+        .add_attachment_with_id("Data A", "com.example", Some("https://example.com/v1"), "a")
+        .add_attachment_with_id("Data B", "com.example", Some("https://example.com/v1"), "b");
+    assert_eq!(envelope.attachments()?.len(), 2);
+    assert!(envelope.attachment_with_vendor_and_conforms_to(Some("com.example"), None).is_err());
+    let b = envelope.attachment_with_id("b")?;
+    assert_eq!(b.attachment_payload()?.extract_subject::<String>()?, "Data B");
+    assert_eq!(b.attachment_id()?.as_deref(), Some("b"));
+    assert!(envelope.attachment_with_id("c").is_err());
+
+    let knows = envelope.assertion_with_predicate("knows")?;
+    let envelope = envelope.add_attachment_to_assertion(
+        &knows.digest(),
+        Envelope::new_attachment("Source", "com.example", None),
+    )?;
+    let knows_with_attachment = envelope.assertion_with_predicate("knows")?;
+    assert_eq!(knows_with_attachment.subject().digest(), knows.digest());
+    assert_eq!(knows_with_attachment.attachments()?.len(), 1);
+    assert!(envelope.add_attachment_to_assertion(&knows.digest(), Envelope::new_attachment("Source", "com.example", None)).is_err());
+
+    let infos = envelope.attachment_infos()?;
+    assert_eq!(infos.len(), 3);
+    let mut ids: Vec<_> = infos.iter().filter_map(|info| info.id()).collect();
+    ids.sort();
+    assert_eq!(ids, ["a", "b"]);
+    let info = infos.iter().find(|info| info.id().is_none()).unwrap();
+    assert_eq!(info.target(), knows.digest().as_ref());
+    assert_eq!(info.vendor(), "com.example");
+    assert_eq!(info.conforms_to(), None);
+    assert_eq!(info.payload().extract_subject::<String>()?, "Source");
+    for info in infos.iter().filter(|info| info.id().is_some()) {
+        assert_eq!(info.target(), envelope.subject().digest().as_ref());
+        assert_eq!(info.conforms_to(), Some("https://example.com/v1"));
+        info.attachment().validate_attachment()?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_attachment_policy() -> anyhow::Result<()> {
+    let envelope = Envelope::new("Alice")
+        .add_assertion("knows", "Bob")
+        .add_attachment("Small", "com.example", None)
+        .add_attachment("A much larger attachment payload", "org.other", None);
+
+    envelope.validate_attachments(&AttachmentPolicy::default())?;
+    envelope.validate_attachments(&AttachmentPolicy {
+        max_count: Some(2),
+        max_payload_bytes: Some(64),
+        allowed_vendors: Some(vec!["com.example".to_string(), "org.other".to_string()]),
+    })?;
+
+    let too_many = AttachmentPolicy { max_count: Some(1), ..Default::default() };
+    assert!(envelope.validate_attachments(&too_many).is_err());
+
+    let too_large = AttachmentPolicy { max_payload_bytes: Some(16), ..Default::default() };
+    assert!(envelope.validate_attachments(&too_large).is_err());
+
+    let vendors = AttachmentPolicy { allowed_vendors: Some(vec!["com.example".to_string()]), ..Default::default() };
+    assert!(envelope.validate_attachments(&vendors).is_err());
+
+    // Attachments on assertions count too.
+    let knows = envelope.assertion_with_predicate("knows")?;
+    let envelope = envelope.add_attachment_to_assertion(
+        &knows.digest(),
+        Envelope::new_attachment("Source", "com.example", None),
+    )?;
+    let policy = AttachmentPolicy { max_count: Some(2), ..Default::default() };
+    assert!(envelope.validate_attachments(&policy).is_err());
+
+    // The count is enforced before the attachments are decoded.
+    let malformed = envelope.add_assertion(known_values::ATTACHMENT, "not an attachment");
+    let error = malformed.validate_attachments(&policy).unwrap_err();
+    assert_eq!(error.to_string(), "the envelope has 4 attachments, more than the policy allows");
+    assert!(malformed.validate_attachments(&AttachmentPolicy::default()).is_err());
+    Ok(())
+}
@@ -0,0 +1,109 @@
+#![cfg(all(feature = "expression", feature = "signature"))]
+
+use bc_components::ARID;
+use bc_envelope::prelude::*;
+use dcbor::Date;
+
+mod common;
+use crate::common::test_data::*;
+
+#[test]
+fn test_capability_chain() -> anyhow::Result<()> {
+    let service = carol_private_key();
+    let target = ARID::new();
+    let expiration = Date::from_string("2030-01-01T00:00:00Z")?;
+
+    // The service grants Alice the use of `read` and `write`.
+    let root = Capability::new(target.clone())
+        .with_function("read")
+        .with_function("write")
+        .with_expiration(expiration.clone())
+        .with_holder(alice_public_key())
+        .issue(&service);
+    let capability = root.verify_capability_chain(&carol_public_key())?;
+    assert_eq!(capability.target(), &target);
+    assert_eq!(capability.holder(), Some(&alice_public_key()));
+    assert!(root.verify_capability_chain(&alice_public_key()).is_err());
+
+    let policy = TimePolicy::new().with_clock(FixedClock::new(Date::from_string("2025-01-01T00:00:00Z")?));
+    let read = Expression::new("read").with_parameter("path", "/notes");
+    let delete = Expression::new("delete").with_parameter("path", "/notes");
+    capability.check_expression(&read, &policy)?;
+    assert!(capability.check_expression(&delete, &policy).is_err());
+    let late = TimePolicy::new().with_clock(FixedClock::new(Date::from_string("2031-01-01T00:00:00Z")?));
+    assert!(capability.check_expression(&read, &late).is_err());
+
+    // Alice delegates read-only access with a use limit to Bob.
+    let delegated = Capability::new(target.clone())
+        .with_function("read")
+        .with_parameter("path")
+        .with_max_uses(3)
+        .with_holder(bob_public_key())
+        .delegate(&root, &alice_private_key())?;
+    let capability = delegated.verify_capability_chain(&carol_public_key())?;
+    assert_eq!(capability.functions(), [Function::from("read")]);
+    assert_eq!(capability.expiration(), Some(&expiration));
+    assert_eq!(capability.max_uses(), Some(3));
+    assert_eq!(capability.holder(), Some(&bob_public_key()));
+    capability.check_expression(&read, &policy)?;
+    let write = Expression::new("write").with_parameter("path", "/notes");
+    assert!(capability.check_expression(&write, &policy).is_err());
+    let read_other = Expression::new("read").with_parameter("mode", "raw");
+    assert!(capability.check_expression(&read_other, &policy).is_err());
+
+    // Only the holder can delegate.
+    let forged = Capability::new(target.clone())
+        .with_holder(bob_public_key())
+        .delegate(&root, &bob_private_key())?;
+    assert!(forged.verify_capability_chain(&carol_public_key()).is_err());
+
+    // A delegation cannot allow more than its parent.
+    let escalated = Capability::new(target.clone())
+        .with_function("delete")
+        .delegate(&root, &alice_private_key())?;
+    assert!(escalated.verify_capability_chain(&carol_public_key()).is_err());
+
+    // Nor can it be for another target.
+    assert!(Capability::new(ARID::new()).delegate(&root, &alice_private_key()).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_capability_elided_restrictions() -> anyhow::Result<()> {
+    let target = ARID::new();
+    let root = Capability::new(target.clone())
+        .with_function("read")
+        .with_parameter("path")
+        .with_expiration(Date::from_string("2030-01-01T00:00:00Z")?)
+        .with_max_uses(3)
+        .with_holder(alice_public_key())
+        .issue(&carol_private_key());
+    let delegated = Capability::new(target)
+        .with_function("read")
+        .with_parameter("path")
+        .with_expiration(Date::from_string("2029-01-01T00:00:00Z")?)
+        .with_max_uses(2)
+        .with_holder(bob_public_key())
+        .delegate(&root, &alice_private_key())?;
+    delegated.verify_capability_chain(&carol_public_key())?;
+
+    // Eliding any restriction of any link keeps the signatures valid, but is
+    // rejected rather than widening the authority granted.
+    let predicates: [Envelope; 5] = [
+        "allowFunction".to_envelope(),
+        "allowParameter".to_envelope(),
+        known_values::VALID_UNTIL.to_envelope(),
+        "maxUses".to_envelope(),
+        "holder".to_envelope(),
+    ];
+    for layer in [&delegated, &root] {
+        let body = layer.unwrap_envelope()?;
+        for predicate in &predicates {
+            let assertion = body.assertion_with_predicate(predicate.clone())?;
+            let elided = delegated.elide_removing_target(&assertion);
+            assert!(elided.verify_capability_chain(&carol_public_key()).is_err());
+        }
+    }
+    Ok(())
+}
+
@@ -0,0 +1,69 @@
+use bc_envelope::prelude::*;
+
+mod common;
+use crate::common::test_data::*;
+
+fn envelopes() -> Vec<Envelope> {
+    vec![
+        hello_envelope(),
+        Envelope::new("Alice").add_assertion("knows", "Bob"),
+        hello_envelope().wrap_envelope(),
+    ]
+}
+
+#[test]
+fn test_envelope_seq() {
+    let mut writer = EnvelopeSeqWriter::new(Vec::new());
+    let offsets: Vec<u64> = envelopes().iter().map(|envelope| writer.append(envelope).unwrap()).collect();
+    assert_eq!(offsets[0], 0);
+    let data = writer.into_inner();
+
+    let reader = EnvelopeSeqReader::new(&data, &IngestLimits::default()).unwrap();
+    assert!(reader.index().is_none());
+    let read: Vec<(u64, Envelope)> = reader.iter().collect::<Result<_, _>>().unwrap();
+    assert_eq!(read.len(), 3);
+    for ((offset, envelope), (expected_offset, expected)) in read.iter().zip(offsets.iter().zip(envelopes())) {
+        assert_eq!(offset, expected_offset);
+        assert_equivalent!(envelope.clone(), expected);
+    }
+
+    // Random access by offset.
+    assert_equivalent!(reader.get(offsets[2]).unwrap(), envelopes()[2].clone());
+    assert!(reader.get(offsets[2] + 1).is_err());
+    assert!(reader.get(data.len() as u64 + 1).is_err());
+
+    // Appending to an existing sequence continues its offsets.
+    let mut writer = EnvelopeSeqWriter::new_at(data.clone(), data.len() as u64);
+    let offset = writer.append(&Envelope::new("Carol")).unwrap();
+    let data = writer.into_inner();
+    let reader = EnvelopeSeqReader::new(&data, &IngestLimits::default()).unwrap();
+    assert_eq!(reader.get(offset).unwrap().extract_subject::<String>().unwrap(), "Carol");
+    assert_eq!(reader.iter().count(), 4);
+
+    // A truncated sequence yields the envelopes before the damage, then an
+    // error.
+    let truncated = &data[..data.len() - 1];
+    let reader = EnvelopeSeqReader::new(truncated, &IngestLimits::default()).unwrap();
+    let results: Vec<_> = reader.iter().collect();
+    assert_eq!(results.len(), 4);
+    assert!(results[3].is_err());
+}
+
+#[test]
+fn test_indexed_envelope_seq() {
+    let mut data = Vec::new();
+    let offsets = write_indexed_envelope_seq(&mut data, &envelopes()).unwrap();
+
+    let reader = EnvelopeSeqReader::new(&data, &IngestLimits::default()).unwrap();
+    assert_eq!(reader.index(), Some(offsets.as_slice()));
+    for (offset, expected) in offsets.iter().zip(envelopes()) {
+        assert_equivalent!(reader.get(*offset).unwrap(), expected);
+    }
+    let read: Vec<u64> = (&reader).into_iter().map(|result| result.unwrap().0).collect();
+    assert_eq!(read, offsets);
+
+    // The offsets are the same as those of a plain sequence.
+    let mut writer = EnvelopeSeqWriter::new(Vec::new());
+    let plain_offsets: Vec<u64> = envelopes().iter().map(|envelope| writer.append(envelope).unwrap()).collect();
+    assert_eq!(plain_offsets, offsets);
+}
@@ -0,0 +1,83 @@
+#![cfg(feature = "claims")]
+
+use bc_envelope::prelude::*;
+use dcbor::Date;
+
+mod common;
+use crate::common::check_encoding::*;
+
+#[test]
+fn test_claims_round_trip() {
+    let claims = ClaimsSet::new()
+        .with_issuer("https://issuer.example.com")
+        .with_subject("alice")
+        .with_audience("https://api.example.com")
+        .with_audience("https://other.example.com")
+        .with_expiration(Date::from_timestamp(1_800_000_000.0))
+        .with_not_before(Date::from_timestamp(1_700_000_000.0))
+        .with_issued_at(Date::from_timestamp(1_700_000_000.0))
+        .with_id("token-1")
+        .with_private_claim("role", "admin")
+        .with_private_claim("level", 3);
+
+    let envelope = Envelope::from_claims(&claims).check_encoding().unwrap();
+    assert_eq!(envelope.extract_subject::<String>().unwrap(), "alice");
+    assert_eq!(
+        envelope.extract_object_for_predicate::<String>(known_values::ISSUER).unwrap(),
+        "https://issuer.example.com"
+    );
+    assert_eq!(envelope.assertions_with_predicate(CLAIM_AUDIENCE).len(), 2);
+    assert_eq!(envelope.to_claims().unwrap(), claims);
+
+    // Individual claims can be elided, after which the remaining claims can
+    // still be recovered.
+    let role = envelope.assertion_with_predicate("role").unwrap();
+    let elided = envelope.elide_removing_target(&role);
+    assert_equivalent!(elided, envelope);
+    let recovered = elided.to_claims().unwrap();
+    assert_eq!(recovered.private_claims().get("role"), None);
+    assert_eq!(recovered.issuer(), Some("https://issuer.example.com"));
+    assert_eq!(recovered.audience().len(), 2);
+
+    // So can the claims whose values alone are elided.
+    let level = envelope.object_for_predicate("level").unwrap();
+    let recovered = envelope.elide_removing_target(&level).to_claims().unwrap();
+    assert_eq!(recovered.private_claims().get("level"), None);
+    assert!(recovered.private_claims().contains_key("role"));
+}
+
+#[test]
+fn test_claims_private_aud() {
+    // A private claim named `aud` is kept apart from the audience.
+    let claims = ClaimsSet::new()
+        .with_audience("https://api.example.com")
+        .with_private_claim("aud", "private");
+    let recovered = Envelope::from_claims(&claims).to_claims().unwrap();
+    assert_eq!(recovered, claims);
+    assert_eq!(recovered.audience().len(), 1);
+    assert_eq!(recovered.private_claims().get("aud"), Some(&CBOR::from("private")));
+}
+
+#[test]
+fn test_claims_without_subject() {
+    let claims = ClaimsSet::new().with_issuer("issuer");
+    let envelope = Envelope::from_claims(&claims);
+    assert!(envelope.subject().is_null());
+    let recovered = envelope.to_claims().unwrap();
+    assert_eq!(recovered.subject(), None);
+    assert_eq!(recovered.issuer(), Some("issuer"));
+
+    let envelope = envelope.add_assertion(known_values::NOTE, "unmapped");
+    assert!(envelope.to_claims().is_err());
+}
+
+#[test]
+fn test_claims_check_validity() {
+    let claims = ClaimsSet::new()
+        .with_not_before(Date::from_string("2024-01-01").unwrap())
+        .with_expiration(Date::from_string("2025-01-01").unwrap());
+    let policy = |now: &str| TimePolicy::new().with_clock(FixedClock::new(Date::from_string(now).unwrap()));
+    assert!(claims.check_validity(&policy("2024-06-01")).is_ok());
+    assert!(claims.check_validity(&policy("2023-06-01")).is_err());
+    assert!(claims.check_validity(&policy("2025-06-01")).is_err());
+}
@@ -1,5 +1,5 @@
 #![cfg(feature = "compress")]
-use bc_components::DigestProvider;
+use bc_components::{Compressed, DigestProvider};
 
 use dcbor::prelude::*;
 use bc_envelope::prelude::*;
@@ -29,6 +29,21 @@ fn test_compress() {
     assert_eq!(uncompressed.structural_digest(), original.structural_digest());
 }
 
+#[test]
+fn test_new_with_compressed() {
+    // The envelope is compressed elsewhere, keeping its digest.
+    let original = Envelope::new(SOURCE);
+    let compressed = Compressed::from_uncompressed_data(original.tagged_cbor_data(), Some(original.digest().into_owned()));
+    let envelope = Envelope::new_with_compressed(compressed).unwrap()
+        .check_encoding().unwrap();
+    assert_eq!(envelope.digest(), original.digest());
+    assert_equivalent!(envelope.uncompress().unwrap(), original);
+
+    // Compressed data without a digest cannot be an envelope.
+    let compressed = Compressed::from_uncompressed_data(original.tagged_cbor_data(), None);
+    assert!(Envelope::new_with_compressed(compressed).is_err());
+}
+
 #[cfg(feature = "signature")]
 #[test]
 fn test_compress_subject() {
@@ -73,3 +88,56 @@ fn test_compress_subject() {
     assert_eq!(uncompressed.digest(), original.digest());
     assert_eq!(uncompressed.structural_digest(), original.structural_digest());
 }
+
+#[test]
+fn test_verify_compressed_integrity() {
+    use bc_components::Compressed;
+
+    let inner = Envelope::new(SOURCE).compress().unwrap();
+    let envelope = Envelope::new("Alice")
+        .add_assertion("note", inner)
+        .wrap_envelope()
+        .compress()
+        .unwrap();
+    envelope.verify_compressed_integrity().unwrap();
+
+    // A compressed blob whose declared digest doesn't match its content.
+    let forged = Compressed::from_uncompressed_data(
+        Envelope::new("Mallory").tagged_cbor().to_cbor_data(),
+        Some(Envelope::new(SOURCE).digest().into_owned()),
+    );
+    let forged = Envelope::try_from(forged).unwrap();
+    assert!(forged.verify_compressed_integrity().is_err());
+    let envelope = Envelope::new("Alice").add_assertion("note", forged);
+    assert!(envelope.verify_compressed_integrity().is_err());
+}
+
+#[test]
+fn test_optimize_for_compression() {
+    // Similar notes compressed one by one can't share their repeated text.
+    let envelope = (0..8).fold(Envelope::new("Alice"), |envelope, i| {
+        let note = Envelope::new(format!("{} ({})", SOURCE, i)).compress().unwrap();
+        envelope.add_assertion("note", note)
+    });
+    let (optimized, report) = envelope.optimize_for_compression_with_report().unwrap();
+    let optimized = optimized.check_encoding().unwrap();
+    assert_eq!(optimized.digest(), envelope.digest());
+    assert!(optimized.is_compressed());
+    assert_eq!(report.original_size, envelope.tagged_cbor_data().len());
+    assert_eq!(report.optimized_size, optimized.tagged_cbor_data().len());
+    assert!(report.optimized_size < report.original_size);
+    assert!(report.ratio() < 0.5);
+    let uncompressed = optimized.uncompress().unwrap();
+    assert_eq!(uncompressed.tagged_cbor_data().len(), report.uncompressed_size);
+    assert!(!uncompressed.assertions().iter().any(|assertion| assertion.as_object().unwrap().is_compressed()));
+
+    // A small envelope is smallest uncompressed.
+    let small = Envelope::new("Hello.").compress().unwrap();
+    let optimized = small.optimize_for_compression().unwrap();
+    assert_eq!(optimized.digest(), small.digest());
+    assert!(!optimized.is_compressed());
+
+    // An envelope that can't be made smaller is returned unchanged.
+    let elided = envelope.elide();
+    assert_eq!(elided.optimize_for_compression().unwrap().tagged_cbor_data(), elided.tagged_cbor_data());
+}
@@ -123,3 +123,54 @@ fn test_3() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_freeze() {
+    bc_components::register_tags();
+
+    let e = Envelope::new("Alice").add_assertion("knows", "Bob");
+    let frozen = e.freeze();
+    assert_eq!(frozen.tagged_cbor_data(), e.tagged_cbor_data());
+    assert_eq!(frozen.ur_string(), e.ur_string());
+    assert_eq!(frozen.deep_digests(), &e.deep_digests());
+
+    // Read APIs are available through `Deref`.
+    assert_eq!(frozen.extract_object_for_predicate::<String>("knows").unwrap(), "Bob");
+    assert_eq!(Envelope::from_ur_string(frozen.ur_string()).unwrap().digest(), e.digest());
+
+    let thawed: Envelope = frozen.clone().into();
+    assert!(thawed.is_identical_to(frozen.envelope()));
+}
+
+#[test]
+fn test_spec_conformance() {
+    let envelope = Envelope::new("Alice")
+        .add_assertion("knows", "Bob")
+        .add_assertion("knows", "Carol");
+    assert!(envelope.check_spec_conformance().is_empty());
+    assert!(Envelope::check_data_spec_conformance(envelope.tagged_cbor_data()).is_empty());
+
+    // Reverse the assertions so they are no longer sorted by digest.
+    let CBORCase::Array(elements) = envelope.untagged_cbor().into_case() else {
+        panic!("expected a node");
+    };
+    let reversed = vec![elements[0].clone(), elements[2].clone(), elements[1].clone()];
+    let unsorted = CBOR::to_tagged_value(200, CBOR::from(reversed));
+    let findings = Envelope::check_data_spec_conformance(unsorted.to_cbor_data());
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].section, "3.4");
+    assert_eq!(findings[0].requirement, SpecRequirement::Must);
+    assert_eq!(findings[0].to_string(), "§3.4 MUST: node assertions are not sorted by digest");
+
+    let legacy_leaf = CBOR::to_tagged_value(200, CBOR::to_tagged_value(24, "Hello."));
+    let findings = Envelope::check_data_spec_conformance(legacy_leaf.to_cbor_data());
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].requirement, SpecRequirement::Should);
+
+    let untagged = CBOR::from(Digest::from_image("Hello.".as_bytes()).data().to_vec());
+    let findings = Envelope::check_data_spec_conformance(untagged.to_cbor_data());
+    assert_eq!(findings[0].section, "3.1");
+
+    let findings = Envelope::check_data_spec_conformance([0xf9, 0x3c, 0x00, 0x00]);
+    assert_eq!(findings[0].section, "3");
+}
@@ -281,3 +281,26 @@ fn test_assertion_on_bare_assertion() {
     "#}.trim();
     assert_eq!(envelope.format(), expected_format);
 }
+
+#[test]
+fn test_unwrap_layers() {
+    let inner = Envelope::new("Hello.").add_assertion("note", "inner");
+    let middle = inner.wrap_envelope().add_assertion("note", "middle");
+    let outer = middle.wrap_envelope().wrap_envelope();
+
+    assert_equivalent!(outer.unwrap_n(0).unwrap(), outer);
+    assert_equivalent!(outer.unwrap_n(2).unwrap(), middle);
+    assert_equivalent!(outer.unwrap_n(3).unwrap(), inner);
+    assert!(outer.unwrap_n(4).is_err());
+
+    let (innermost, count) = outer.unwrap_all();
+    assert_equivalent!(innermost, inner);
+    assert_eq!(count, 3);
+    assert_eq!(inner.unwrap_all().1, 0);
+
+    let has_note = |envelope: &Envelope| envelope.assertion_with_predicate("note").is_ok();
+    let (found, count) = outer.unwrap_until(&has_note).unwrap();
+    assert_equivalent!(found, middle);
+    assert_eq!(count, 2);
+    assert!(outer.unwrap_until(&|envelope: &Envelope| envelope.is_null()).is_none());
+}
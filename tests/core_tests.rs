@@ -324,3 +324,405 @@ fn test_unknown_leaf() {
     let expected = "555({1: h'6fc4981e8da778332bf93342f3f77d3a'})";
     assert_eq!(e.format(), expected);
 }
+
+#[test]
+fn test_replace_subject_checked() {
+    let e = Envelope::new("Alice")
+        .add_assertion("knows", "Bob")
+        .add_assertion("knows", "Carol");
+
+    // An elided subject has the same digest, so the envelope is unchanged semantically.
+    let elided = e.replace_subject_checked(e.subject().elide(), false).unwrap();
+    assert!(elided.is_equivalent_to(&e));
+    assert!(elided.subject().is_elided());
+
+    // A different subject is refused unless forced.
+    assert!(e.replace_subject_checked(Envelope::new("Bob"), false).is_err());
+    let forced = e.replace_subject_checked(Envelope::new("Bob"), true).unwrap();
+    assert_eq!(forced.extract_subject::<String>().unwrap(), "Bob");
+    assert_eq!(forced.assertions().len(), 2);
+
+    let carol = Envelope::new_assertion("knows", "Carol");
+    let kept = e.replace_subject_keeping_assertions(Envelope::new("Bob"), |a| a.digest() == carol.digest());
+    assert_eq!(kept.assertions().len(), 1);
+    assert!(kept.assertions()[0].is_equivalent_to(&carol));
+}
+
+#[test]
+fn test_visit() {
+    #[derive(Default)]
+    struct CaseCounter {
+        nodes: usize,
+        leaves: usize,
+        wrapped: usize,
+        assertions: usize,
+        elided: usize,
+    }
+
+    impl EnvelopeVisitor for CaseCounter {
+        fn visit_node(&mut self, _: &Envelope, subject: &Envelope, assertions: &[Envelope]) {
+            self.nodes += 1;
+            subject.visit(self);
+            for assertion in assertions {
+                assertion.visit(self);
+            }
+        }
+
+        fn visit_leaf(&mut self, _: &Envelope, _: &CBOR) {
+            self.leaves += 1;
+        }
+
+        fn visit_wrapped(&mut self, _: &Envelope, wrapped: &Envelope) {
+            self.wrapped += 1;
+            wrapped.visit(self);
+        }
+
+        fn visit_assertion(&mut self, _: &Envelope, assertion: &bc_envelope::Assertion) {
+            self.assertions += 1;
+            assertion.predicate().visit(self);
+            assertion.object().visit(self);
+        }
+
+        fn visit_elided(&mut self, _: &Envelope, _: &Digest) {
+            self.elided += 1;
+        }
+    }
+
+    let e = Envelope::new("Alice")
+        .add_assertion("knows", "Bob")
+        .add_assertion("knows", Envelope::new("Carol").elide())
+        .wrap_envelope();
+
+    let mut counter = CaseCounter::default();
+    e.visit(&mut counter);
+    assert_eq!(counter.wrapped, 1);
+    assert_eq!(counter.nodes, 1);
+    assert_eq!(counter.assertions, 2);
+    assert_eq!(counter.leaves, 4);
+    assert_eq!(counter.elided, 1);
+}
+
+#[test]
+fn test_envelope_macro() {
+    let bob = Envelope::new("Bob").add_assertion("age", 42);
+    let e = envelope!("Alice" {
+        "knows": (bob.clone()),
+        "knows": "Carol" {
+            "knows": "Dan" { "age": 7 },
+        },
+        ("is".to_string() + "A"): "Person"
+    });
+    let expected = Envelope::new("Alice")
+        .add_assertion("knows", bob.clone())
+        .add_assertion("knows", Envelope::new("Carol")
+            .add_assertion("knows", Envelope::new("Dan").add_assertion("age", 7))
+        )
+        .add_assertion("isA", "Person");
+    assert!(e.is_identical_to(&expected));
+
+    assert!(envelope!("Alice").is_identical_to(&Envelope::new("Alice")));
+    assert!(envelope!(42 {}).is_identical_to(&Envelope::new(42)));
+}
+
+#[test]
+fn test_assertions_with_predicate_indexed() {
+    let e = (0..1000).fold(Envelope::new("Telemetry"), |e, i| {
+        e.add_assertion(if i % 10 == 0 { "alert" } else { "reading" }, i)
+    });
+    assert_eq!(e.assertions_with_predicate("reading").len(), 900);
+    assert_eq!(e.assertions_with_predicate("alert").len(), 100);
+    assert!(e.assertions_with_predicate("missing").is_empty());
+    for assertion in e.assertions_with_predicate("alert") {
+        assert_eq!(assertion.extract_object::<i32>().unwrap() % 10, 0);
+    }
+
+    // Salted assertions are found by the predicate of their subject.
+    #[cfg(feature = "salt")]
+    {
+        let salted = e.add_assertion_salted("alert", 1000, true);
+        assert_eq!(salted.assertions_with_predicate("alert").len(), 101);
+    }
+
+    // Obscured assertions are never matched.
+    let alert = Envelope::new_assertion("alert", 0);
+    let elided = e.elide_removing_target(&alert);
+    assert_eq!(elided.assertions_with_predicate("alert").len(), 99);
+    assert!(Envelope::new("Leaf").assertions_with_predicate("alert").is_empty());
+}
+
+#[test]
+fn test_equivalence_failure_hint() {
+    let e1 = Envelope::new("Alice")
+        .add_assertion("knows", Envelope::new("Bob").add_assertion("age", 30))
+        .wrap_envelope();
+    let e2 = Envelope::new("Alice")
+        .add_assertion("knows", Envelope::new("Bob").add_assertion("age", 31))
+        .wrap_envelope();
+
+    // Elided and unelided forms are equivalent.
+    assert!(e1.equivalence_failure_hint(&e1.elide()).is_none());
+    assert_equivalent!(e1, e1.elide());
+
+    let hint = e1.equivalence_failure_hint(&e2).unwrap();
+    assert!(hint.starts_with(r#"at envelope.wrapped.assertion("knows").object.assertion("age").object: left is leaf"#), "{}", hint);
+    assert!(hint.contains("`30`") && hint.contains("`31`"), "{}", hint);
+
+    let e3 = e1.unwrap_envelope().unwrap().add_assertion("knows", "Carol").wrap_envelope();
+    let hint = e1.equivalence_failure_hint(&e3).unwrap();
+    assert!(hint.starts_with("at envelope.wrapped: assertion only in right: assertion"), "{}", hint);
+
+    let result = std::panic::catch_unwind(|| assert_equivalent!(e1, e2));
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_with_assertions_unchecked_sorted() {
+    let e = Envelope::new("Alice")
+        .add_assertion("knows", "Bob")
+        .add_assertion("knows", "Carol")
+        .add_assertion("age", 30);
+
+    let mut assertions = e.assertions();
+    assertions.sort_by(|a, b| a.digest().cmp(&b.digest()));
+    let rebuilt = Envelope::with_assertions_unchecked_sorted(e.subject(), assertions);
+    assert_equivalent!(rebuilt, e);
+    assert_eq!(rebuilt.assertions(), e.assertions());
+
+    let subject = Envelope::new("Alice");
+    assert_equivalent!(Envelope::with_assertions_unchecked_sorted(subject.clone(), []), subject);
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "not sorted")]
+fn test_with_assertions_unchecked_sorted_unsorted() {
+    let e = Envelope::new("Alice")
+        .add_assertion("knows", "Bob")
+        .add_assertion("knows", "Carol");
+    let mut assertions = e.assertions();
+    assertions.reverse();
+    Envelope::with_assertions_unchecked_sorted(e.subject(), assertions);
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "duplicated")]
+fn test_with_assertions_unchecked_sorted_duplicated() {
+    let e = Envelope::new("Alice").add_assertion("knows", "Bob");
+    let assertion = e.assertions()[0].clone();
+    Envelope::with_assertions_unchecked_sorted(e.subject(), [assertion.clone(), assertion]);
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "may not be a node")]
+fn test_with_assertions_unchecked_sorted_node_subject() {
+    let e = Envelope::new("Alice").add_assertion("knows", "Bob");
+    let assertion = Envelope::new_assertion("age", 30);
+    Envelope::with_assertions_unchecked_sorted(e, [assertion]);
+}
+
+#[test]
+fn test_heap_size_estimate() {
+    let bob = Envelope::new("Bob").add_assertion("note", "x".repeat(1000));
+    let bob_size = bob.heap_size_estimate();
+    assert!(bob_size > 1000);
+
+    // A subtree referenced twice is counted once.
+    let e = Envelope::new("Alice")
+        .add_assertion("knows", bob.clone())
+        .add_assertion("likes", bob.clone());
+    let e_size = e.heap_size_estimate();
+    assert!(e_size > bob_size);
+    assert!(e_size < 2 * bob_size);
+
+    // A copy of the same content that is not shared is counted separately.
+    let bob_copy = Envelope::new("Bob").add_assertion("note", "x".repeat(1000));
+    let e2 = Envelope::new("Alice")
+        .add_assertion("knows", bob)
+        .add_assertion("likes", bob_copy);
+    assert!(e2.heap_size_estimate() > 2 * bob_size);
+}
+
+#[test]
+fn test_find_by_digest() {
+    let carol = Envelope::new("Carol");
+    let bob = Envelope::new("Bob").add_assertion("knows", carol.clone());
+    let knows_bob = Envelope::new_assertion("knows", bob.clone());
+    let inner = Envelope::new("Alice").add_assertion_envelope(&knows_bob).unwrap();
+    let e = inner.wrap_envelope().add_assertion("note", "Hello");
+
+    let (found, path) = e.find_by_digest(&e.digest()).unwrap();
+    assert_equivalent!(found, e);
+    assert!(path.is_empty());
+
+    let (found, path) = e.find_by_digest(&carol.digest()).unwrap();
+    assert_equivalent!(found, carol);
+    let path_digests: Vec<_> = path.iter().map(|e| e.digest().into_owned()).collect();
+    let bob_knows_carol = bob.assertion_with_predicate("knows").unwrap();
+    assert_eq!(path_digests, vec![
+        e.digest().into_owned(),
+        e.subject().digest().into_owned(),
+        inner.digest().into_owned(),
+        knows_bob.digest().into_owned(),
+        bob.digest().into_owned(),
+        bob_knows_carol.digest().into_owned(),
+    ]);
+
+    assert!(e.find_by_digest(&Envelope::new("Dave").digest()).is_none());
+}
+
+#[test]
+fn test_path_to() {
+    let carol = Envelope::new("Carol");
+    let bob = Envelope::new("Bob").add_assertion("knows", carol.clone());
+    let inner = Envelope::new("Alice").add_assertion("knows", bob);
+    let e = inner.wrap_envelope().add_assertion("note", "Hello");
+
+    assert_eq!(e.path_to(&e.digest()).unwrap(), "");
+    assert_eq!(e.path_to(&carol.digest()).unwrap(), "subject/wrapped/assertion[0]/object/assertion[0]/object");
+    assert_eq!(e.path_to(&Envelope::new("note").digest()).unwrap(), "assertion[0]/predicate");
+    assert!(e.path_to(&Envelope::new("Dave").digest()).is_none());
+
+    // Assertions are numbered in the order of their digests.
+    let e = e.add_assertion("note", "Goodbye");
+    let goodbye = e.assertions_with_predicate("note").into_iter().position(|a| a.as_object().unwrap().digest() == Envelope::new("Goodbye").digest()).unwrap();
+    assert_eq!(e.path_to(&Envelope::new("Goodbye").digest()).unwrap(), format!("assertion[{}]/object", goodbye));
+    assert_eq!(EdgeType::Wrapped.path_component(0).unwrap(), "wrapped");
+    assert!(EdgeType::None.path_component(0).is_none());
+}
+
+#[test]
+fn test_assertion_iterators() {
+    let e = Envelope::new("Alice")
+        .add_assertion("knows", "Bob")
+        .add_assertion("knows", "Carol")
+        .add_assertion("age", 30);
+
+    assert_eq!(e.iter_assertions().cloned().collect::<Vec<_>>(), e.assertions());
+    assert_eq!(Envelope::new("Alice").iter_assertions().count(), 0);
+
+    let knows = e.iter_assertions_with_predicate("knows");
+    assert_eq!(knows.len(), 2);
+    assert_eq!(knows.cloned().collect::<Vec<_>>(), e.assertions_with_predicate("knows"));
+
+    let mut names: Vec<String> = e.iter_objects_for_predicate("knows")
+        .map(|object| object.extract_subject().unwrap())
+        .collect();
+    names.sort();
+    assert_eq!(names, vec!["Bob", "Carol"]);
+    assert_eq!(e.iter_objects_for_predicate("likes").count(), 0);
+}
+
+#[test]
+fn test_predicate_inventory() {
+    let address = Envelope::new("123 Main St.")
+        .add_assertion("city", "Springfield");
+    let e = Envelope::new("Alice")
+        .add_assertion("knows", "Bob")
+        .add_assertion("knows", Envelope::new("Carol").add_assertion("knows", "Dave"))
+        .add_assertion("address", address)
+        .wrap_envelope()
+        .add_assertion("note", "Wrapped");
+
+    let inventory = e.predicate_inventory();
+    assert_eq!(inventory.len(), 4);
+    let count = |predicate: &str| {
+        let (envelope, count) = &inventory[&Envelope::new(predicate).digest()];
+        assert_equivalent!(envelope, Envelope::new(predicate));
+        *count
+    };
+    assert_eq!(count("knows"), 3);
+    assert_eq!(count("address"), 1);
+    assert_eq!(count("city"), 1);
+    assert_eq!(count("note"), 1);
+
+    assert!(Envelope::new("Alice").predicate_inventory().is_empty());
+}
+
+#[test]
+fn test_walk_events() {
+    let e = Envelope::new("Alice").add_assertion("knows", "Bob");
+    let events: Vec<WalkEvent> = e.walk_events().collect();
+    let summary: Vec<(&str, &str, usize)> = events
+        .iter()
+        .map(|event| match event {
+            WalkEvent::Enter { case, level, .. } => ("enter", *case, *level),
+            WalkEvent::Leaf { case, level, .. } => ("leaf", *case, *level),
+            WalkEvent::Exit { case, level, .. } => ("exit", *case, *level),
+        })
+        .collect();
+    assert_eq!(summary, vec![
+        ("enter", "node", 0),
+        ("leaf", "leaf", 1),
+        ("enter", "assertion", 1),
+        ("leaf", "leaf", 2),
+        ("leaf", "leaf", 2),
+        ("exit", "assertion", 1),
+        ("exit", "node", 0),
+    ]);
+
+    // Every element is visited once, in the same order as `walk`.
+    let walked = std::cell::RefCell::new(Vec::new());
+    e.walk(false, &|element: Envelope, _, _, _: Option<()>| {
+        walked.borrow_mut().push(element.digest().into_owned());
+        None
+    });
+    let entered: Vec<Digest> = events
+        .iter()
+        .filter_map(|event| match event {
+            WalkEvent::Enter { digest, .. } | WalkEvent::Leaf { digest, .. } => Some(digest.clone()),
+            WalkEvent::Exit { .. } => None,
+        })
+        .collect();
+    assert_eq!(entered, walked.into_inner());
+
+    let subject = Envelope::new("Alice").digest().into_owned();
+    assert_eq!(
+        events[1].to_json(),
+        format!(r#"{{"event":"leaf","digest":"{}","case":"leaf","level":1,"edge":"subject"}}"#, subject.hex())
+    );
+    assert_eq!(
+        events[6].to_json(),
+        format!(r#"{{"event":"exit","digest":"{}","case":"node","level":0}}"#, e.digest().hex())
+    );
+}
+
+#[test]
+fn test_capabilities() {
+    let capabilities = bc_envelope::capabilities();
+    let mut sorted = capabilities.clone();
+    sorted.sort();
+    assert_eq!(capabilities, sorted);
+    assert_eq!(capabilities.contains(&"signature"), cfg!(feature = "signature"));
+    assert_eq!(capabilities.contains(&"compress"), cfg!(feature = "compress"));
+    assert_eq!(bc_envelope::has_capability("encrypt"), cfg!(feature = "encrypt"));
+    assert!(!bc_envelope::has_capability("teleport"));
+    let error = bc_envelope::require_capability("teleport").unwrap_err();
+    assert_eq!(error.to_string(), "this build of bc-envelope does not have the `teleport` feature");
+    assert_eq!(bc_envelope::require_capability("sskr").is_ok(), cfg!(feature = "sskr"));
+}
+
+#[test]
+fn test_structural_fingerprint() {
+    let person = |name: &str, age: u32| Envelope::new(name)
+        .add_assertion("age", age)
+        .add_assertion("email", format!("{}@example.com", name));
+    let alice = person("Alice", 30);
+    let bob = person("Bob", 45);
+    assert_ne!(alice.digest(), bob.digest());
+    assert_eq!(alice.structural_fingerprint(), bob.structural_fingerprint());
+
+    // The values of predicates are leaves too.
+    let renamed = Envelope::new("Carol").add_assertion("years", 52).add_assertion("mail", "c@example.com");
+    assert_eq!(alice.structural_fingerprint(), renamed.structural_fingerprint());
+
+    // A small change in shape is closer than an unrelated shape.
+    let extended = alice.add_assertion("phone", "555-1234");
+    let unrelated = Envelope::new("Alice").wrap_envelope().wrap_envelope();
+    let fingerprint = alice.structural_fingerprint();
+    assert_ne!(fingerprint, extended.structural_fingerprint());
+    assert!(fingerprint.distance(&extended.structural_fingerprint()) < fingerprint.distance(&unrelated.structural_fingerprint()));
+    assert!(fingerprint.similarity(&extended.structural_fingerprint()) > 0.5);
+    assert_eq!(fingerprint.to_string().len(), 16);
+}
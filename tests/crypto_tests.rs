@@ -1,6 +1,6 @@
 #![cfg(feature = "encrypt")]
 
-use bc_components::SymmetricKey;
+use bc_components::{Encrypter, SymmetricKey};
 use bc_ur::prelude::*;
 use indoc::indoc;
 
@@ -358,3 +358,149 @@ fn test_hidden_signature_multi_recipient() {
     // Alice didn't encrypt it to herself, so she can't read it.
     assert!(received_envelope.decrypt_subject_to_recipient(&alice_private_key()).is_err());
 }
+
+#[cfg(feature = "signature")]
+#[test]
+fn test_verify_then_decrypt() {
+    let key = SymmetricKey::new();
+    let envelope = hello_envelope()
+        .encrypt(&key)
+        .sign(&alice_private_key())
+        .check_encoding().unwrap();
+
+    let decrypted = envelope.verify_then_decrypt(&alice_public_key(), &key).unwrap();
+    assert_equivalent!(decrypted, hello_envelope());
+
+    // The wrong signer is rejected before decryption is attempted.
+    assert!(envelope.verify_then_decrypt(&bob_public_key(), &key).is_err());
+
+    // An unsigned ciphertext is rejected.
+    assert!(hello_envelope().encrypt(&key).verify_then_decrypt(&alice_public_key(), &key).is_err());
+}
+
+#[cfg(all(feature = "signature", feature = "recipient"))]
+#[test]
+fn test_verify_then_decrypt_to_recipient() {
+    let envelope = hello_envelope()
+        .encrypt_to_recipient(&bob_public_key())
+        .sign(&alice_private_key())
+        .check_encoding().unwrap();
+
+    let decrypted = envelope.verify_then_decrypt_to_recipient(&alice_public_key(), &bob_private_key()).unwrap();
+    assert_equivalent!(decrypted, hello_envelope());
+
+    assert!(envelope.verify_then_decrypt_to_recipient(&carol_public_key(), &bob_private_key()).is_err());
+    assert!(envelope.verify_then_decrypt_to_recipient(&alice_public_key(), &carol_private_key()).is_err());
+}
+
+#[cfg(all(feature = "signature", feature = "recipient"))]
+#[test]
+fn test_seal_onion() -> anyhow::Result<()> {
+    let envelope = hello_envelope();
+    let (bob, carol) = (bob_public_key(), carol_public_key());
+    let route: [(&dyn Encrypter, Envelope); 2] = [
+        (&bob, Envelope::new("https://bob.example.com")),
+        (&carol, Envelope::new("https://carol.example.com")),
+    ];
+    let onion = envelope.seal_onion(&alice_private_key(), &route)?;
+
+    // Carol can't open the outer layer, which is for Bob.
+    assert!(onion.open_onion_layer(&carol_private_key()).is_err());
+
+    // Bob's layer reveals only where to forward the envelope sealed for
+    // Carol.
+    let (next_hop, forwarded) = onion.open_onion_layer(&bob_private_key())?;
+    assert_eq!(next_hop.extract_subject::<String>()?, "https://carol.example.com");
+    assert!(forwarded.open_onion_layer(&bob_private_key()).is_err());
+
+    // The final layer has no next hop.
+    assert!(forwarded.open_onion_layer(&carol_private_key()).is_err());
+    let received = forwarded
+        .decrypt_to_recipient(&carol_private_key())?
+        .verify(&alice_public_key())?;
+    assert_equivalent!(received, envelope);
+
+    // A single-hop route is the same as sealing.
+    let sealed = envelope.seal_onion(&alice_private_key(), &route[1..])?;
+    let received = sealed
+        .decrypt_to_recipient(&carol_private_key())?
+        .verify(&alice_public_key())?;
+    assert_equivalent!(received, envelope);
+
+    // An empty route is an error.
+    assert!(envelope.seal_onion(&alice_private_key(), &[]).is_err());
+    Ok(())
+}
+
+#[cfg(feature = "recipient")]
+#[test]
+fn test_recipient_group() -> anyhow::Result<()> {
+    let mut group = RecipientGroup::new("Finance")
+        .with_member(alice_public_key(), "editor")
+        .with_member(bob_public_key(), "viewer");
+    assert_eq!(group.roles(), vec!["editor", "viewer"]);
+    assert_eq!(group.role_of(&bob_public_key()), Some("viewer"));
+    assert!(hello_envelope().encrypt_subject_to_group(&RecipientGroup::new("Empty")).is_err());
+
+    let envelope = hello_envelope().encrypt_subject_to_group(&group)?;
+    assert_eq!(envelope.recipient_group_name()?, "Finance");
+    assert_eq!(envelope.recipients()?.len(), 2);
+    assert_equivalent!(envelope.decrypt_subject_to_recipient(&bob_private_key())?.subject(), hello_envelope());
+    assert!(envelope.decrypt_subject_to_recipient(&carol_private_key()).is_err());
+
+    // Encrypting to only the editors excludes Bob.
+    let editors = hello_envelope().encrypt_subject_to_group(&group.restricted_to_roles(&["editor"]))?;
+    assert_eq!(editors.recipients()?.len(), 1);
+    assert!(editors.decrypt_subject_to_recipient(&bob_private_key()).is_err());
+
+    // After Bob's key is rotated to Carol's, re-encrypting excludes the old key.
+    group.rotate_member_key(&bob_public_key(), carol_public_key())?;
+    assert_eq!(group.role_of(&carol_public_key()), Some("viewer"));
+    let rotated = envelope.reencrypt_to_group(&alice_private_key(), &group)?;
+    assert_eq!(rotated.recipients()?.len(), 2);
+    assert_eq!(rotated.assertions_with_predicate("recipientGroup").len(), 1);
+    assert!(rotated.decrypt_subject_to_recipient(&bob_private_key()).is_err());
+    assert_equivalent!(rotated.decrypt_subject_to_recipient(&carol_private_key())?.subject(), hello_envelope());
+
+    assert!(group.remove_member(&carol_public_key()));
+    assert!(!group.remove_member(&carol_public_key()));
+    assert!(group.rotate_member_key(&carol_public_key(), bob_public_key()).is_err());
+    Ok(())
+}
+
+#[cfg(all(feature = "signature", feature = "recipient"))]
+#[test]
+fn test_type_state_wrappers() -> anyhow::Result<()> {
+    fn send(sealed: SealedEnvelope) -> Envelope {
+        sealed.into()
+    }
+
+    let envelope = hello_envelope();
+    let signed = SignedEnvelope::sign(&envelope, &alice_private_key());
+    assert!(signed.has_signature_from(&alice_public_key())?);
+    let sent = send(signed.seal_to(&bob_public_key()));
+    let received = sent.unseal(&alice_public_key(), &bob_private_key())?;
+    assert_equivalent!(received, envelope.clone());
+
+    let sealed = SealedEnvelope::seal(&envelope, &alice_private_key(), &bob_public_key());
+    assert_equivalent!(sealed.unseal(&alice_public_key(), &bob_private_key())?, envelope.clone());
+
+    let key = SymmetricKey::new();
+    let encrypted = EncryptedEnvelope::encrypt(&envelope, &key);
+    assert_equivalent!(encrypted.decrypt(&key)?, envelope.clone());
+
+    // Received signed envelopes are verified.
+    assert!(SignedEnvelope::verify(signed.envelope().clone(), &alice_public_key()).is_ok());
+    assert!(SignedEnvelope::verify(signed.envelope().clone(), &bob_public_key()).is_err());
+    assert!(SignedEnvelope::verify(envelope.clone(), &alice_public_key()).is_err());
+    let forged = envelope.wrap_envelope().add_assertion(known_values::SIGNED, "not a signature");
+    assert!(SignedEnvelope::verify(forged, &alice_public_key()).is_err());
+
+    // Other received envelopes are checked for the form each operation
+    // produces.
+    assert!(EncryptedEnvelope::try_from(Envelope::from(encrypted.clone())).is_ok());
+    assert!(EncryptedEnvelope::try_from(envelope.clone()).is_err());
+    assert!(SealedEnvelope::from_envelope_unchecked(sent).is_ok());
+    assert!(SealedEnvelope::from_envelope_unchecked(Envelope::from(encrypted)).is_err());
+    Ok(())
+}
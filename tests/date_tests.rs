@@ -0,0 +1,49 @@
+use bc_envelope::prelude::*;
+use dcbor::Date;
+
+mod common;
+
+fn date(s: &str) -> Date {
+    Date::from_string(s).unwrap()
+}
+
+fn log() -> Vec<Envelope> {
+    vec![
+        Envelope::new("deploy").add_date_assertion("at", date("2024-03-01T09:00:00Z")),
+        Envelope::new("rollback").add_date_assertion("at", date("2024-03-15T17:30:00Z")),
+        Envelope::new("deploy").add_date_assertion("at", date("2024-04-02T08:00:00Z")),
+        Envelope::new("note").add_assertion("at", "sometime"),
+    ]
+}
+
+#[test]
+fn test_dates_for_predicate() {
+    let event = Envelope::new("meeting")
+        .add_date_assertion("at", date("2024-03-15T17:30:00Z"))
+        .add_date_assertion("at", date("2024-03-01T09:00:00Z"));
+    assert_eq!(event.dates_for_predicate("at").unwrap(), vec![date("2024-03-01T09:00:00Z"), date("2024-03-15T17:30:00Z")]);
+    assert!(event.dates_for_predicate("until").unwrap().is_empty());
+    assert!(event.add_assertion("at", "tomorrow").dates_for_predicate("at").is_err());
+}
+
+#[test]
+fn test_date_ranges() {
+    let event = Envelope::new("meeting")
+        .add_date_assertion("at", date("2024-03-15T17:30:00Z"))
+        .add_assertion("at", "tomorrow");
+    let march = date("2024-03-01T00:00:00Z")..date("2024-04-01T00:00:00Z");
+    assert_eq!(event.assertions_with_date_in_range("at", &march).len(), 1);
+    assert!(event.has_date_in_range("at", &march));
+    assert!(!event.has_date_in_range("at", &(date("2024-04-01T00:00:00Z")..)));
+
+    // Dates in other time zones are compared as instants.
+    let evening = date("2024-03-15T19:30:00+02:00");
+    assert!(event.has_date_in_range("at", &(evening.clone()..=evening)));
+
+    let log = log();
+    let in_march: Vec<String> = envelopes_with_date_in_range(&log, "at", march)
+        .map(|e| e.extract_subject().unwrap())
+        .collect();
+    assert_eq!(in_march, vec!["deploy", "rollback"]);
+    assert_eq!(envelopes_with_date_in_range(&log, "at", ..).count(), 3);
+}
@@ -476,3 +476,194 @@ fn test_targeted_remove() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_deep_digests_cached() {
+    let e1 = double_assertion_envelope()
+        .wrap_envelope()
+        .add_assertion("note", "A wrapped document");
+
+    // The cached set matches a full, uncached walk of the tree.
+    let walked = e1.digests(usize::MAX - 1);
+    assert_eq!(e1.deep_digests(), walked);
+    assert_eq!(e1.deep_digests_ref(), &walked);
+    assert_eq!(e1.digests(usize::MAX), walked);
+
+    // The set is cached on the queried envelope, and shared by its clones.
+    let clone = e1.clone();
+    assert!(std::ptr::eq(e1.deep_digests_ref(), clone.deep_digests_ref()));
+
+    // A derived envelope computes and caches its own set.
+    let e2 = e1.add_assertion("knows", "Dave");
+    let e3 = e2.elide_removing_target(&Envelope::new_assertion("knows", "Dave"));
+    assert_eq!(e2.deep_digests(), e2.digests(usize::MAX - 1));
+    assert!(e2.deep_digests().is_superset(&e1.subject().digests(usize::MAX)));
+    assert!(!std::ptr::eq(e2.deep_digests_ref(), e1.deep_digests_ref()));
+    assert_eq!(e3.deep_digests(), e3.digests(usize::MAX - 1));
+}
+
+#[test]
+fn test_reveal_token() -> anyhow::Result<()> {
+    let envelope = Envelope::new("Alice")
+        .add_assertion("knows", "Bob")
+        .add_assertion("knows", "Carol")
+        .add_assertion("age", 30);
+
+    // Reveal the subject and the "age" assertion.
+    let age = envelope.assertion_with_predicate("age")?;
+    let mut target = HashSet::new();
+    target.insert(envelope.digest().into_owned());
+    target.insert(envelope.subject().digest().into_owned());
+    target.extend(age.deep_digests());
+
+    let token = envelope.reveal_token(&target);
+    let ur_string = token.ur_string();
+    assert!(ur_string.starts_with("ur:reveal-token/"));
+    let token = RevealToken::from_ur_string(ur_string)?;
+    assert_eq!(token.root(), envelope.digest().as_ref());
+
+    let projection = envelope.apply_reveal_token(&token)?.check_encoding()?;
+    assert_equivalent!(projection, envelope.elide_revealing_set(&target));
+    assert_equivalent!(projection, envelope);
+    assert_eq!(projection.extract_object_for_predicate::<i32>("age")?, 30);
+    assert!(projection.assertions_with_predicate("knows").is_empty());
+
+    // The token only applies to the envelope it was created for.
+    let other = envelope.add_assertion("knows", "Dave");
+    assert!(other.apply_reveal_token(&token).is_err());
+
+    // Nor to a copy of it with other elements obscured, which has the same
+    // root digest but different digests within.
+    let elided = envelope.elide_removing_target(&age);
+    assert_eq!(elided.digest(), envelope.digest());
+    assert!(elided.apply_reveal_token(&token).is_err());
+    let token = elided.reveal_token(&target);
+    assert!(envelope.apply_reveal_token(&token).is_err());
+    elided.apply_reveal_token(&token)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_placeholders() -> anyhow::Result<()> {
+    let email = Envelope::new("alice@example.com");
+    let envelope = Envelope::new("Alice")
+        .add_assertion("email", "alice@example.com")
+        .add_assertion("ssn", "123-45-6789")
+        .add_assertion("age", 30);
+    let age = Envelope::new(30);
+    let ssn = Envelope::new("123-45-6789");
+    let policy = PlaceholderPolicy::new()
+        .with_target(&email, PlaceholderDetail::Length)
+        .with_target(&ssn, PlaceholderDetail::LengthBound(16))
+        .with_target(&age, PlaceholderDetail::Kind);
+    let redacted = envelope.elide_with_placeholders(&policy).check_encoding()?;
+
+    let email_placeholder = redacted.object_for_predicate("email")?;
+    assert!(email_placeholder.is_placeholder());
+    let shape = email_placeholder.placeholder_shape()?.unwrap();
+    assert_eq!(shape.kind(), LeafKind::String);
+    assert_eq!(shape.length(), Some(17));
+    assert_eq!(shape.description(), "string of length 17");
+    assert_eq!(email_placeholder.subject().unwrap_envelope()?.digest(), email.digest());
+
+    let ssn_shape = redacted.object_for_predicate("ssn")?.placeholder_shape()?.unwrap();
+    assert_eq!(ssn_shape.to_string(), "string of length at most 16");
+    let age_shape = redacted.object_for_predicate("age")?.placeholder_shape()?.unwrap();
+    assert_eq!(age_shape.to_string(), "integer");
+
+    // The withheld values can be checked once revealed.
+    email_placeholder.verify_placeholder(&email)?;
+    redacted.object_for_predicate("ssn")?.verify_placeholder(&ssn)?;
+    assert!(email_placeholder.verify_placeholder(&ssn).is_err());
+
+    // A placeholder that misrepresents the value's length is caught.
+    let forged = email
+        .elide()
+        .wrap_envelope()
+        .add_assertion("placeholderKind", "string")
+        .add_assertion("placeholderLength", 5);
+    assert!(forged.verify_placeholder(&email).is_err());
+
+    // Untargeted elements are unchanged, and ordinary elided elements are
+    // not placeholders.
+    assert_eq!(redacted.subject().digest(), envelope.subject().digest());
+    assert!(!email.elide().is_placeholder());
+    assert_eq!(email.elide().placeholder_shape()?, None);
+
+    // A targeted subject keeps its assertions, which are not mixed with the
+    // placeholder's, even when they use the same predicates.
+    let envelope = envelope.add_assertion("placeholderLength", 3);
+    let subject_redacted = envelope.elide_with_placeholders(
+        &PlaceholderPolicy::new().with_target(&Envelope::new("Alice"), PlaceholderDetail::Length),
+    );
+    assert!(!subject_redacted.is_placeholder());
+    let subject_placeholder = subject_redacted.subject().unwrap_envelope()?;
+    assert_eq!(subject_placeholder.placeholder_shape()?.unwrap().description(), "string of length 5");
+    assert_eq!(subject_placeholder.assertions().len(), 2);
+    assert_eq!(subject_redacted.extract_object_for_predicate::<i32>("age")?, 30);
+    assert_eq!(subject_redacted.extract_object_for_predicate::<i32>("placeholderLength")?, 3);
+    subject_placeholder.verify_placeholder(&Envelope::new("Alice"))?;
+
+    Ok(())
+}
+
+#[test]
+fn test_leakage_report() -> anyhow::Result<()> {
+    let envelope = Envelope::new("Alice")
+        .add_assertion("knows", "Bob")
+        .add_assertion("knows", "Carol")
+        .add_assertion("age", 30);
+    let report = envelope.leakage_report();
+    assert_eq!(report.encoded_size(), envelope.tagged_cbor_data().len());
+    assert_eq!(report.obscured_elements(), 0);
+    assert_eq!(report.obscured_assertions(), 0);
+    assert_eq!(report.visible_elements(), envelope.elements_count());
+    assert_eq!(report.max_depth(), 2);
+
+    // Eliding two assertions still reveals that they were there.
+    let age = envelope.assertion_with_predicate("age")?;
+    let mut target = HashSet::new();
+    target.insert(envelope.digest().into_owned());
+    target.insert(envelope.subject().digest().into_owned());
+    target.extend(age.deep_digests());
+    let redacted = envelope.elide_revealing_set(&target);
+    let report = redacted.leakage_report();
+    assert_eq!(report.obscured_elements(), 2);
+    assert_eq!(report.obscured_assertions(), 2);
+    let leaky: Vec<_> = report.nodes_with_obscured_assertions().collect();
+    assert_eq!(leaky.len(), 1);
+    assert_eq!(leaky[0].assertion_count, 3);
+    assert_eq!(&leaky[0].digest, envelope.digest().as_ref());
+    assert!(report.obscured_sizes().is_empty());
+
+    Ok(())
+}
+
+#[cfg(feature = "salt")]
+#[test]
+fn test_pad_assertions() -> anyhow::Result<()> {
+    let alice = Envelope::new("Alice").add_assertion("knows", "Bob").pad_assertions(8);
+    let carol = Envelope::new("Carol")
+        .add_assertion("knows", "Bob")
+        .add_assertion("knows", "Dave")
+        .add_assertion("age", 40)
+        .pad_assertions(8);
+    assert_eq!(alice.assertions().len(), 8);
+    assert_eq!(carol.assertions().len(), 8);
+    assert_eq!(carol.pad_assertions(4).assertions().len(), 8);
+    assert_eq!(alice.salts().len(), 7);
+    assert!(alice.strip_salts().is_equivalent_to(&Envelope::new("Alice").add_assertion("knows", "Bob")));
+
+    // Revealing only the subject, the two redactions leak the same count.
+    let reveal_subject = |envelope: &Envelope| {
+        let target: HashSet<_> = [envelope.digest().into_owned(), envelope.subject().digest().into_owned()].into();
+        envelope.elide_revealing_set(&target).leakage_report()
+    };
+    let (alice_report, carol_report) = (reveal_subject(&alice), reveal_subject(&carol));
+    assert_eq!(alice_report.obscured_assertions(), 8);
+    assert_eq!(carol_report.obscured_assertions(), 8);
+    assert_eq!(alice_report.nodes(), &[NodeLeakage { digest: alice.digest().into_owned(), depth: 0, assertion_count: 8, obscured_assertion_count: 8 }]);
+
+    Ok(())
+}
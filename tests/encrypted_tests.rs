@@ -73,3 +73,157 @@ fn test_encrypted() {
     encrypted_test(single_assertion_envelope()).unwrap();
     encrypted_test(double_assertion_envelope()).unwrap();
 }
+
+#[test]
+fn test_decrypt_subject_to_secret() {
+    let e1 = single_assertion_envelope();
+    let e2 = e1.encrypt_subject(&symmetric_key()).unwrap();
+
+    let secret = e2.decrypt_subject_to_secret(&symmetric_key()).unwrap();
+    assert_eq!(secret.digest(), &*e1.subject().digest());
+    assert_eq!(secret.cbor_data(), e1.subject().tagged_cbor_data());
+    assert!(secret.to_envelope().unwrap().is_identical_to(&e1.subject()));
+    assert!(!format!("{:?}", secret).contains("cbor_data"));
+
+    assert!(e2.decrypt_subject_to_secret(&SymmetricKey::new()).is_err());
+    assert!(e1.decrypt_subject_to_secret(&symmetric_key()).is_err());
+}
+
+#[test]
+fn test_new_with_encrypted() {
+    // The subject is encrypted elsewhere, with its digest as the additional
+    // authenticated data.
+    let e1 = single_assertion_envelope();
+    let subject = e1.subject();
+    let message = symmetric_key().encrypt_with_digest(subject.tagged_cbor_data(), subject.digest(), None::<Nonce>);
+    let encrypted_subject = Envelope::new_with_encrypted(message).unwrap()
+        .check_encoding().unwrap();
+    assert!(encrypted_subject.is_encrypted());
+    assert_eq!(encrypted_subject.digest(), subject.digest());
+
+    let e2 = e1.replace_subject(encrypted_subject);
+    assert_eq!(e2.digest(), e1.digest());
+    assert_equivalent!(e2.decrypt_subject(&symmetric_key()).unwrap(), e1);
+
+    // A message without a digest cannot be an envelope.
+    let message = symmetric_key().encrypt(subject.tagged_cbor_data(), None::<Vec<u8>>, None::<Nonce>);
+    assert!(Envelope::new_with_encrypted(message).is_err());
+}
+
+#[test]
+fn test_encrypted_ur() {
+    let e1 = double_assertion_envelope();
+
+    // With a key.
+    let ur = e1.to_encrypted_ur(&symmetric_key()).unwrap();
+    assert!(ur.starts_with("ur:crypto-envelope/"));
+    assert_equivalent!(Envelope::from_encrypted_ur(&ur, &symmetric_key()).unwrap(), e1);
+    assert!(Envelope::from_encrypted_ur(&ur, &SymmetricKey::new()).is_err());
+
+    // With a password, using few iterations to keep the test fast.
+    let mut rng = bc_rand::make_fake_random_number_generator();
+    let ur = e1.to_encrypted_ur_opt("correct horse", 1000, &mut rng).unwrap();
+    assert!(ur.starts_with("ur:crypto-envelope/"));
+    assert_equivalent!(Envelope::from_encrypted_ur(&ur, "correct horse").unwrap(), e1);
+    assert!(Envelope::from_encrypted_ur(&ur, "battery staple").is_err());
+
+    // A key cannot open a password-protected UR.
+    assert!(Envelope::from_encrypted_ur(&ur, &symmetric_key()).is_err());
+
+    // An envelope UR is not an encrypted UR.
+    let plain = UR::new("envelope", e1.untagged_cbor()).unwrap().string();
+    assert!(Envelope::from_encrypted_ur(&plain, &symmetric_key()).is_err());
+}
+
+/// A stand-in for a ratchet, which issues a fresh key for each message and
+/// forgets each key once it has been used.
+#[derive(Clone, Default)]
+struct OneTimeKeys {
+    next_id: u32,
+    keys: std::collections::HashMap<Vec<u8>, SymmetricKey>,
+}
+
+impl KeyProvider for OneTimeKeys {
+    fn next_content_key(&mut self) -> anyhow::Result<(Vec<u8>, SymmetricKey)> {
+        let key_id = self.next_id.to_be_bytes().to_vec();
+        self.next_id += 1;
+        let key = SymmetricKey::new();
+        self.keys.insert(key_id.clone(), key.clone());
+        Ok((key_id, key))
+    }
+
+    fn content_key(&mut self, key_id: &[u8]) -> anyhow::Result<SymmetricKey> {
+        self.keys.remove(key_id).ok_or_else(|| anyhow::anyhow!("unknown key"))
+    }
+}
+
+#[test]
+fn test_key_provider() {
+    let mut sender = OneTimeKeys::default();
+    let first = basic_envelope().encrypt_subject_with_provider(&mut sender).unwrap();
+    let second = double_assertion_envelope().encrypt_subject_with_provider(&mut sender).unwrap();
+    assert!(first.subject().is_encrypted());
+    let key_id = |e: &Envelope| e.object_for_predicate(bc_envelope::extension::CONTENT_KEY_ID).unwrap().digest().into_owned();
+    assert_ne!(key_id(&first), key_id(&second));
+
+    let mut receiver = sender.clone();
+    assert_equivalent!(second.decrypt_subject_with_provider(&mut receiver).unwrap(), double_assertion_envelope());
+    assert_equivalent!(first.decrypt_subject_with_provider(&mut receiver).unwrap(), basic_envelope());
+
+    // Each key can only be used once.
+    assert!(first.decrypt_subject_with_provider(&mut receiver).is_err());
+}
+
+#[cfg(feature = "recipient")]
+#[test]
+fn test_key_provider_recipients() {
+    use crate::common::test_data::*;
+
+    let mut sender = OneTimeKeys::default();
+    let envelope = basic_envelope()
+        .encrypt_subject_to_recipients_with_provider(&[&bob_public_key()], &mut sender)
+        .unwrap();
+    assert_eq!(envelope.recipients().unwrap().len(), 1);
+
+    let decrypted = envelope.decrypt_subject_to_recipient(&bob_private_key()).unwrap();
+    assert_equivalent!(decrypted.subject(), basic_envelope());
+    let decrypted = envelope.decrypt_subject_with_provider(&mut sender.clone()).unwrap();
+    assert_eq!(decrypted.recipients().unwrap().len(), 1);
+}
+
+#[test]
+fn test_walk_decrypt() {
+    let key1 = SymmetricKey::new();
+    let key2 = SymmetricKey::new();
+    let inner = Envelope::new("Bob").encrypt_subject(&key2).unwrap();
+    let original = Envelope::new("Alice").add_assertion("knows", Envelope::new("Bob"));
+    let encrypted = Envelope::new("Alice")
+        .add_assertion("knows", inner.clone())
+        .encrypt_subject(&key1)
+        .unwrap()
+        .wrap_envelope()
+        .encrypt_subject(&key1)
+        .unwrap();
+
+    // Only the elements encrypted with the first key are decrypted.
+    let (partial, report) = encrypted.walk_decrypt_with_report(std::slice::from_ref(&key1));
+    assert_equivalent!(partial, encrypted);
+    assert_eq!(report.decrypted.len(), 2);
+    assert_eq!(report.remaining, vec![(inner.digest().into_owned(), DecryptionFailure::NoMatchingKey)]);
+    assert!(!report.is_complete());
+    let partial = partial.unwrap_envelope().unwrap();
+    assert!(!partial.subject().is_encrypted());
+    assert!(partial.object_for_predicate("knows").unwrap().is_encrypted());
+
+    // With both keys everything is decrypted, including elements inside
+    // decrypted elements.
+    let (complete, report) = encrypted.walk_decrypt_with_report(&[key2, key1]);
+    assert!(report.is_complete());
+    assert_eq!(report.decrypted.len(), 3);
+    assert_eq!(complete.unwrap_envelope().unwrap().structural_digest(), original.structural_digest());
+
+    // Envelopes without encrypted elements are returned unchanged.
+    let (unchanged, report) = original.walk_decrypt_with_report(&[]);
+    assert_eq!(report, DecryptionReport::default());
+    assert_eq!(unchanged.structural_digest(), original.structural_digest());
+}
@@ -0,0 +1,45 @@
+// Error context is a global setting, so these tests are kept in their own
+// test binary.
+
+use bc_components::DigestProvider;
+use bc_envelope::prelude::*;
+use bc_envelope::EnvelopeError;
+
+#[test]
+fn test_error_context() {
+    let envelope = Envelope::new("Alice").add_assertion("knows", "Bob");
+
+    set_error_context_length(None);
+    let error = envelope.extract_object_for_predicate::<u64>("knows").unwrap_err();
+    assert!(error.downcast_ref::<ErrorContext>().is_none());
+
+    set_error_context_length(Some(80));
+    assert_eq!(error_context_length(), Some(80));
+
+    let error = envelope.extract_object_for_predicate::<u64>("knows").unwrap_err();
+    let context = error.downcast_ref::<ErrorContext>().unwrap();
+    let assertion = envelope.assertion_with_predicate("knows").unwrap();
+    let object = assertion.try_object().unwrap();
+    assert_eq!(context.path(), [
+        envelope.digest().into_owned(),
+        assertion.digest().into_owned(),
+        object.digest().into_owned(),
+    ]);
+    assert_eq!(context.element(), r#""Bob""#);
+    assert!(format!("{:#}", error).starts_with(&format!("at {}/{}/{}: \"Bob\": ", envelope.short_id(), assertion.short_id(), object.short_id())));
+
+    let error = envelope.unwrap_envelope().unwrap_err();
+    let context = error.downcast_ref::<ErrorContext>().unwrap();
+    assert_eq!(context.path(), [envelope.digest().into_owned(), envelope.subject().digest().into_owned()]);
+    assert_eq!(context.element(), r#""Alice""#);
+    assert!(matches!(error.downcast_ref::<EnvelopeError>(), Some(EnvelopeError::NotWrapped)));
+
+    set_error_context_length(Some(10));
+    let error = envelope.extract_subject::<u64>().unwrap_err();
+    let context = error.downcast_ref::<ErrorContext>().unwrap();
+    assert_eq!(context.element(), r#""Alice""#);
+    let error = envelope.wrap_envelope().extract_subject::<u64>().unwrap_err();
+    let context = error.downcast_ref::<ErrorContext>().unwrap();
+    assert_eq!(context.element(), r#"{ "Alice"…"#);
+    set_error_context_length(None);
+}
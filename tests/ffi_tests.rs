@@ -0,0 +1,90 @@
+#![cfg(feature = "ffi")]
+
+use std::{ffi::CStr, ptr};
+
+use bc_envelope::extension::ffi::*;
+use bc_components::PrivateKeyBase;
+use bc_envelope::prelude::*;
+use bc_ur::UR;
+
+unsafe fn last_error() -> String {
+    CStr::from_ptr(bc_envelope_last_error()).to_str().unwrap().to_string()
+}
+
+#[test]
+fn test_ffi() {
+    unsafe {
+        let mut alice = ptr::null_mut();
+        assert_eq!(bc_envelope_new_string(c"Alice".as_ptr(), &mut alice), BcEnvelopeStatus::Ok);
+        let mut with_assertion = ptr::null_mut();
+        assert_eq!(
+            bc_envelope_add_string_assertion(alice, c"knows".as_ptr(), c"Bob".as_ptr(), &mut with_assertion),
+            BcEnvelopeStatus::Ok
+        );
+
+        // Sign, serialize, decode, and verify.
+        let private_key = PrivateKeyBase::from_data(b"0123456789abcdef").tagged_cbor().to_cbor_data();
+        let mut signed = ptr::null_mut();
+        assert_eq!(bc_envelope_sign(with_assertion, private_key.as_ptr(), private_key.len(), &mut signed), BcEnvelopeStatus::Ok);
+        let mut cbor = BcBuffer { data: ptr::null_mut(), len: 0 };
+        assert_eq!(bc_envelope_to_cbor(signed, &mut cbor), BcEnvelopeStatus::Ok);
+        let mut decoded = ptr::null_mut();
+        assert_eq!(bc_envelope_from_cbor(cbor.data, cbor.len, &mut decoded), BcEnvelopeStatus::Ok);
+        bc_buffer_free(cbor);
+
+        let mut public_key = BcBuffer { data: ptr::null_mut(), len: 0 };
+        assert_eq!(bc_envelope_public_key(private_key.as_ptr(), private_key.len(), &mut public_key), BcEnvelopeStatus::Ok);
+        let mut verified = ptr::null_mut();
+        assert_eq!(bc_envelope_verify(decoded, public_key.data, public_key.len, &mut verified), BcEnvelopeStatus::Ok);
+        bc_buffer_free(public_key);
+
+        let mut digest = [0u8; 32];
+        let mut verified_digest = [0u8; 32];
+        assert_eq!(bc_envelope_digest(with_assertion, digest.as_mut_ptr()), BcEnvelopeStatus::Ok);
+        assert_eq!(bc_envelope_digest(verified, verified_digest.as_mut_ptr()), BcEnvelopeStatus::Ok);
+        assert_eq!(digest, verified_digest);
+
+        // Verifying with the wrong key fails with a status and a message.
+        let other_key = PrivateKeyBase::from_data(b"fedcba9876543210");
+        let other_key = UR::new("crypto-prvkeys", other_key.untagged_cbor()).unwrap().string();
+        let mut other_public_key = BcBuffer { data: ptr::null_mut(), len: 0 };
+        assert_eq!(bc_envelope_public_key(other_key.as_ptr(), other_key.len(), &mut other_public_key), BcEnvelopeStatus::Ok);
+        let mut unverified = ptr::null_mut();
+        assert_eq!(
+            bc_envelope_verify(decoded, other_public_key.data, other_public_key.len, &mut unverified),
+            BcEnvelopeStatus::UnverifiedSignature
+        );
+        assert!(unverified.is_null());
+        assert_eq!(last_error(), "could not verify a signature");
+        bc_buffer_free(other_public_key);
+
+        // Eliding keeps the digest.
+        let bob = Envelope::new("Bob").digest().into_owned();
+        let mut elided = ptr::null_mut();
+        assert_eq!(bc_envelope_elide_removing(with_assertion, bob.data().as_ptr(), &mut elided), BcEnvelopeStatus::Ok);
+        let mut elided_digest = [0u8; 32];
+        bc_envelope_digest(elided, elided_digest.as_mut_ptr());
+        assert_eq!(elided_digest, digest);
+
+        // Invalid arguments are reported rather than crashing.
+        let mut out = ptr::null_mut();
+        assert_eq!(bc_envelope_new_string(ptr::null(), &mut out), BcEnvelopeStatus::NullArgument);
+        assert_eq!(bc_envelope_new_string(c"\xff".as_ptr(), &mut out), BcEnvelopeStatus::InvalidUtf8);
+        assert_eq!(bc_envelope_from_cbor(b"\x01".as_ptr(), 1, &mut out), BcEnvelopeStatus::InvalidData);
+        assert_eq!(bc_envelope_verify(alice, b"\x01".as_ptr(), 1, &mut out), BcEnvelopeStatus::InvalidKey);
+        // Private keys must be tagged CBOR or a UR, not raw key material.
+        let raw_key = b"0123456789abcdef";
+        assert_eq!(bc_envelope_sign(alice, raw_key.as_ptr(), raw_key.len(), &mut out), BcEnvelopeStatus::InvalidKey);
+        assert_eq!(bc_envelope_sign(alice, b"\x01".as_ptr(), 1, &mut out), BcEnvelopeStatus::InvalidKey);
+        let empty_key = PrivateKeyBase::from_data([]).tagged_cbor().to_cbor_data();
+        assert_eq!(bc_envelope_sign(alice, empty_key.as_ptr(), empty_key.len(), &mut out), BcEnvelopeStatus::InvalidKey);
+        let mut buffer = BcBuffer { data: ptr::null_mut(), len: 0 };
+        assert_eq!(bc_envelope_public_key(raw_key.as_ptr(), raw_key.len(), &mut buffer), BcEnvelopeStatus::InvalidKey);
+        assert!(out.is_null());
+
+        for envelope in [alice, with_assertion, signed, decoded, verified, elided] {
+            bc_envelope_free(envelope);
+        }
+        bc_envelope_free(ptr::null_mut());
+    }
+}
@@ -0,0 +1,108 @@
+#![cfg(feature = "fixtures")]
+
+use bc_components::{DigestProvider, Nonce, PublicKeyBaseProvider, ARID};
+use bc_envelope::prelude::*;
+use bc_envelope::extension::fixtures;
+
+mod common;
+use crate::common::check_encoding::*;
+
+fn digest_hex(envelope: &Envelope) -> String {
+    hex::encode(envelope.check_encoding().unwrap().digest().data())
+}
+
+#[test]
+fn test_fixture_digests() {
+    assert_eq!(fixtures::TEST_VECTORS_VERSION, "1.0");
+    assert_eq!(digest_hex(&fixtures::alice_credential()), "0b721f78903458215365cbb9f383404fd15e43993fb508a2e98800256abadf7c");
+    assert_eq!(digest_hex(&fixtures::seed_backup()), "0d4f4bfd3a06be62c513d2b8fb32c0a70ced936a8698eb4aa255c31c0dcc1137");
+    assert_eq!(digest_hex(&fixtures::request()), "09afba3accad98bbc5c8e76810da1b13f25730845a8c87f3728e7bfabd4eddf9");
+    assert_eq!(digest_hex(&fixtures::response()), "ab8296434a181cb72de227569a3516785c4ec3e6a1186e06f0c3173946be6b33");
+}
+
+#[test]
+fn test_fixtures_are_deterministic() {
+    assert_equivalent!(fixtures::alice_credential(), fixtures::alice_credential());
+    assert_equivalent!(fixtures::seed_backup(), fixtures::seed_backup());
+    assert_equivalent!(fixtures::request(), fixtures::request());
+    assert_equivalent!(fixtures::response(), fixtures::response());
+}
+
+#[test]
+fn test_alice_credential_signature() {
+    let credential = fixtures::alice_credential();
+    let alice_public_key = fixtures::alice_private_key().public_key_base();
+    credential.verify_signature_from(&alice_public_key).unwrap();
+}
+
+#[test]
+fn test_scenario_builder() -> anyhow::Result<()> {
+    let builder = fixtures::ScenarioBuilder::new("degrees")
+        .issuer("Example University")
+        .issuer("Example College")
+        .credential("Example University", "Alice", "Degree", [
+            ("degree", "BSc".to_cbor()),
+            ("year", 2020.to_cbor()),
+            ("gpa", 3.9.to_cbor()),
+        ])
+        .credential("Example College", "Bob", "Diploma", [("diploma", "Nursing".to_cbor())])
+        .presentation(0, &["degree", "year"])
+        .presentation_per_claim(1);
+    let scenario = builder.build()?;
+    assert_eq!(scenario.issuers().len(), 2);
+    assert_eq!(scenario.credentials_of("Alice").count(), 1);
+    assert_eq!(scenario.presentations().len(), 3);
+
+    // Credentials are signed by their issuers, and presentations elide the
+    // claims they don't reveal without breaking the signature.
+    let university = scenario.issuer("Example University").unwrap();
+    let degree = &scenario.credentials()[0];
+    degree.envelope().verify_signature_from(&university.public_key())?;
+    assert!(degree.envelope().verify_signature_from(&scenario.issuer("Example College").unwrap().public_key()).is_err());
+    let presentation = &scenario.presentations()[0];
+    let content = presentation.envelope().verify_signature_from(&university.public_key())?.subject().unwrap_envelope()?;
+    assert_eq!(content.extract_object_for_predicate::<String>("degree")?, "BSc");
+    assert!(content.assertion_with_predicate("gpa").is_err());
+    assert_equivalent!(presentation.envelope().clone(), degree.envelope().clone());
+    assert_eq!(scenario.presentations()[1].revealed(), &[] as &[String]);
+    assert_eq!(scenario.presentations()[2].revealed(), ["diploma"]);
+
+    // Scenarios are deterministic.
+    let again = builder.build()?;
+    assert_eq!(again.credentials()[0].envelope().digest(), degree.envelope().digest());
+    assert_eq!(again.issuers()[1].document().digest(), scenario.issuers()[1].document().digest());
+    assert_equivalent!(again.credentials()[1].envelope().clone(), scenario.credentials()[1].envelope().clone());
+
+    assert!(fixtures::ScenarioBuilder::new("x").credential("Nobody", "Alice", "Degree", []).build().is_err());
+    assert!(builder.clone().presentation(0, &["name"]).build().is_err());
+    assert!(builder.presentation(5, &[]).build().is_err());
+    Ok(())
+}
+
+#[test]
+fn test_scenario_presentation() -> anyhow::Result<()> {
+    let scenario = fixtures::ScenarioBuilder::new("degrees")
+        .issuer("Example University")
+        .credential("Example University", "Alice", "Degree", [("degree", "BSc".to_cbor()), ("year", 2020.to_cbor())])
+        .credential("Example University", "Bob", "Degree", [("degree", "MSc".to_cbor())])
+        .presentation(0, &["degree"])
+        .build()?;
+    let university = scenario.issuer("Example University").unwrap().public_key();
+    let alice = &scenario.credentials()[0];
+    let bob = &scenario.credentials()[1];
+    assert_ne!(alice.holder_public_key(), bob.holder_public_key());
+
+    // The holder presents a redacted credential, which the verifier checks.
+    let verifier = ARID::new();
+    let nonce = Nonce::new();
+    let redacted = scenario.presentations()[0].envelope();
+    let presentation = present(redacted, &verifier, &nonce, alice.holder_private_key());
+    let content = verify_presentation(&presentation, &university, &verifier, &nonce, &TimePolicy::new())?;
+    assert_eq!(content.extract_object_for_predicate::<String>("degree")?, "BSc");
+    assert!(content.assertion_with_predicate("year").is_err());
+
+    // Someone else can't present it.
+    let stolen = present(redacted, &verifier, &nonce, bob.holder_private_key());
+    assert!(verify_presentation(&stolen, &university, &verifier, &nonce, &TimePolicy::new()).is_err());
+    Ok(())
+}
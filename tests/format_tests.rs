@@ -830,3 +830,299 @@ fn test_redacted_credential() {
     "#}.trim());
     assert_eq!(warranty.elements_count(), warranty.tree_format(false).split('\n').count());
 }
+
+#[test]
+fn test_leaf_tag_adapter() {
+    #[derive(Debug, PartialEq)]
+    struct Descriptor(String);
+
+    let adapter = LeafTagAdapter::new(Tag::new(90_000, "descriptor"))
+        .with_summarizer(|cbor| Ok(format!("descriptor({})", String::try_from(cbor)?)))
+        .with_validator(|cbor| {
+            let text = String::try_from(cbor.clone())?;
+            if !text.starts_with("wpkh(") {
+                anyhow::bail!("unsupported descriptor");
+            }
+            Ok(())
+        })
+        .with_extractor(|cbor| Ok(Descriptor(String::try_from(cbor)?)));
+
+    let mut context = FormatContext::default();
+    register_leaf_tag_adapter_in(&mut context, adapter);
+
+    let valid = CBOR::to_tagged_value(90_000, "wpkh(xpub)");
+    let invalid = CBOR::to_tagged_value(90_000, "sh(xpub)");
+    let e = Envelope::new("Wallet")
+        .add_assertion("descriptor", valid);
+    assert!(e.validate_leaf_tags_opt(Some(&context)).is_ok());
+    assert_eq!(e.format_opt(Some(&context)), indoc! {r#"
+    "Wallet" [
+        "descriptor": descriptor(wpkh(xpub))
+    ]
+    "#}.trim());
+
+    let descriptor = e.object_for_predicate("descriptor").unwrap();
+    assert_eq!(
+        descriptor.extract_adapted_subject_opt::<Descriptor>(Some(&context)).unwrap(),
+        Descriptor("wpkh(xpub)".to_string())
+    );
+    assert!(descriptor.extract_adapted_subject_opt::<String>(Some(&context)).is_err());
+    assert!(descriptor.extract_adapted_subject_opt::<Descriptor>(Some(&FormatContext::default())).is_err());
+
+    let e = e.add_assertion("descriptor", invalid);
+    assert!(e.validate_leaf_tags_opt(Some(&context)).is_err());
+}
+
+#[cfg(feature = "known_value")]
+#[test]
+fn test_localized_names() {
+    let names = LocalizedNames::new()
+        .with_known_value_name(known_values::IS_A_RAW, "estUn")
+        .with_known_value_name(known_values::NOTE_RAW, "remarque");
+    let mut context = FormatContext::default();
+    set_localized_names_in(&mut context, names);
+
+    let e = Envelope::new("Alice")
+        .add_assertion(known_values::IS_A, "Person")
+        .add_assertion(known_values::NOTE, "Hello");
+    assert_eq!(e.format_opt(Some(&context)), indoc! {r#"
+    "Alice" [
+        'estUn': "Person"
+        'remarque': "Hello"
+    ]
+    "#}.trim());
+
+    // Canonical names are unaffected.
+    assert_eq!(known_values::IS_A.name(), "isA");
+    let unlocalized = FormatContext::default().set_flat(true);
+    assert_eq!(e.format_opt(Some(&unlocalized)), r#""Alice" [ 'isA': "Person", 'note': "Hello" ]"#);
+}
+
+#[cfg(feature = "known_value")]
+#[test]
+fn test_known_value_display() {
+    let context = FormatContext::default()
+        .with_known_value_display(known_values::IS_A_RAW, "type")
+        .set_flat(true);
+    let e = Envelope::new("Alice")
+        .add_assertion(known_values::IS_A, "Person")
+        .add_assertion(known_values::NOTE, "Hello");
+    assert_eq!(context.known_value_display(known_values::IS_A_RAW), Some("type"));
+    assert_eq!(e.format_opt(Some(&context)), r#""Alice" [ type: "Person", 'note': "Hello" ]"#);
+
+    // The display text takes precedence over a localized name.
+    let mut context = context;
+    set_localized_names_in(&mut context, LocalizedNames::new().with_known_value_name(known_values::IS_A_RAW, "estUn"));
+    assert_eq!(e.format_opt(Some(&context)), r#""Alice" [ type: "Person", 'note': "Hello" ]"#);
+
+    // Other format contexts are unaffected.
+    let unchanged = FormatContext::default().set_flat(true);
+    assert_eq!(e.format_opt(Some(&unchanged)), r#""Alice" [ 'isA': "Person", 'note': "Hello" ]"#);
+}
+
+#[test]
+fn test_uri_predicates() {
+    let context = FormatContext::default()
+        .with_uri_prefix("schema", "https://schema.org/")
+        .with_uri_prefix("person", "https://schema.org/Person/")
+        .set_flat(true);
+    let e = Envelope::new("Alice")
+        .add_assertion(Envelope::uri_predicate("https://schema.org/name").unwrap(), "Alice Smith")
+        .add_assertion(Envelope::uri_predicate("https://schema.org/Person/email").unwrap(), "alice@example.com")
+        .add_assertion(Envelope::uri_predicate("https://example.com/id").unwrap(), 42);
+    assert_eq!(
+        e.format_opt(Some(&context)),
+        r#""Alice" [ 32("https://example.com/id"): 42, person:email: "alice@example.com", schema:name: "Alice Smith" ]"#
+    );
+    assert_eq!(context.abbreviate_uri("https://example.com/id"), None);
+    assert_eq!(e.extract_object_for_predicate::<String>(Envelope::uri_predicate("https://schema.org/name").unwrap()).unwrap(), "Alice Smith");
+    assert!(Envelope::uri_predicate("not a uri").is_err());
+}
+
+#[test]
+fn test_format_version() {
+    let envelope = Envelope::new("Alice").add_assertion("knows", "Bob");
+    let context = FormatContext::default();
+    assert_eq!(context.format_version(), FormatVersion::LATEST);
+    assert_eq!(FormatVersion::default(), FormatVersion::LATEST);
+    assert_eq!(FormatVersion::V1.to_string(), "v1");
+
+    // Golden output pinned to version 1 must never change.
+    let v1 = context.set_format_version(FormatVersion::V1);
+    assert_eq!(envelope.format_opt(Some(&v1)), indoc! {r#"
+    "Alice" [
+        "knows": "Bob"
+    ]
+    "#}.trim());
+    assert_eq!(envelope.tree_format_opt(false, Some(&v1)), indoc! {r#"
+    8955db5e NODE
+        13941b48 subj "Alice"
+        78d666eb ASSERTION
+            db7dd21c pred "knows"
+            13b74194 obj "Bob"
+    "#}.trim());
+}
+
+#[test]
+fn test_digest_namer() {
+    let envelope = Envelope::new("Alice").add_assertion("knows", "Bob");
+    let context = FormatContext::default().with_digest_namer(Petnames);
+    let tree = envelope.tree_format_opt(false, Some(&context));
+    let first_line = tree.lines().next().unwrap();
+    let name = Petnames.name(&envelope.digest()).unwrap();
+    assert_eq!(first_line, format!("8955db5e ({}) NODE", name));
+    assert_eq!(tree.lines().count(), 5);
+    assert!(tree.lines().all(|line| line.contains('(') && line.contains('-')));
+
+    // Hiding nodes hides their names too.
+    assert!(!envelope.tree_format_opt(true, Some(&context)).contains(&name));
+}
+
+#[cfg(feature = "known_value")]
+#[test]
+fn test_fluent_format_context() {
+    use std::sync::{Arc, Mutex};
+
+    let envelope = Envelope::new("Alice").add_assertion(KnownValue::new(1000), "Bob");
+    let likes = || KnownValue::new_with_name(1000u64, "likes".to_string());
+    let loves = || KnownValue::new_with_name(1000u64, "loves".to_string());
+
+    let context = FormatContext::default()
+        .with_tags([dcbor::Tag::new(99999, "widget")])
+        .with_known_values([likes()]);
+    assert_eq!(context.tags().assigned_name_for_tag(&dcbor::Tag::from(99999u64)), Some("widget".to_string()));
+    assert_eq!(envelope.format_opt(Some(&context)), indoc! {r#"
+    "Alice" [
+        'likes': "Bob"
+    ]
+    "#}.trim());
+
+    // Clones share their registries.
+    let clone = context.clone();
+    assert!(std::ptr::eq(clone.known_values(), context.known_values()));
+
+    // By default, later registrations replace earlier ones.
+    let replaced = context.clone().with_known_values([loves()]);
+    assert!(envelope.format_opt(Some(&replaced)).contains("'loves'"));
+    assert!(envelope.format_opt(Some(&context)).contains("'likes'"));
+
+    // A resolver can keep the existing names, and sees each conflict.
+    let conflicts = Arc::new(Mutex::new(Vec::new()));
+    let recorded = conflicts.clone();
+    let kept = context.clone()
+        .with_conflict_resolver(move |conflict| {
+            recorded.lock().unwrap().push(conflict.clone());
+            ConflictResolution::KeepExisting
+        })
+        .with_known_values([loves(), likes()]);
+    assert!(envelope.format_opt(Some(&kept)).contains("'likes'"));
+    let conflicts = conflicts.lock().unwrap();
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].kind, RegistrationKind::KnownValue);
+    assert_eq!(conflicts[0].value, 1000);
+    assert_eq!(conflicts[0].existing_name, "likes");
+    assert_eq!(conflicts[0].new_name, "loves");
+}
+
+#[test]
+fn test_fluent_format_context_replaces_tags() {
+    use dcbor::TagsStoreTrait;
+
+    let widget = || dcbor::Tag::new(99999, "widget");
+    let gadget = || dcbor::Tag::new(99999, "gadget");
+    let cbor = CBOR::to_tagged_value(99999, 1);
+    let context = FormatContext::default().with_tags([widget()]);
+
+    // By default, a tag is renamed rather than rejected.
+    let replaced = context.clone().with_tags([gadget()]);
+    assert_eq!(replaced.assigned_name_for_tag(&dcbor::Tag::from(99999u64)), Some("gadget".to_string()));
+    assert_eq!(replaced.tag_for_name("gadget").map(|tag| tag.value()), Some(99999));
+    assert!(replaced.tag_for_name("widget").is_none());
+    assert_eq!(cbor.diagnostic_opt(true, false, false, Some(&replaced)), "99999(1)   / gadget /");
+    assert_eq!(context.assigned_name_for_tag(&dcbor::Tag::from(99999u64)), Some("widget".to_string()));
+
+    // Renaming it back restores the original name.
+    let restored = replaced.with_tags([widget()]);
+    assert_eq!(restored.name_for_value(99999), "widget");
+
+    // A resolver may keep the existing name.
+    let kept = context
+        .with_conflict_resolver(|_| ConflictResolution::KeepExisting)
+        .with_tags([gadget()]);
+    assert_eq!(kept.name_for_value(99999), "widget");
+}
+
+#[cfg(feature = "known_value")]
+#[test]
+fn test_sort_by_predicate_name() {
+    let envelope = Envelope::new("Alice")
+        .add_assertion("zip", "12345")
+        .add_assertion(KnownValue::new_with_name(1000u64, "name".to_string()), "Alice Smith")
+        .add_assertion("Age", 30)
+        .add_assertion("city", "Springfield");
+
+    let context = FormatContext::default();
+    assert_eq!(envelope.format_opt(Some(&context)), indoc! {r#"
+    "Alice" [
+        "Age": 30
+        "city": "Springfield"
+        "zip": "12345"
+        'name': "Alice Smith"
+    ]
+    "#}.trim());
+
+    let sorted = context.set_sort_by_predicate_name(true);
+    assert_eq!(envelope.format_opt(Some(&sorted)), indoc! {r#"
+    "Alice" [
+        "Age": 30
+        "city": "Springfield"
+        'name': "Alice Smith"
+        "zip": "12345"
+    ]
+    "#}.trim());
+}
+
+#[test]
+fn test_tree_format_paged() {
+    let envelope = Envelope::new("Alice")
+        .add_assertion("knows", "Bob")
+        .add_assertion("knows", "Carol")
+        .add_assertion("note", Envelope::new("Hi").add_assertion("lang", "en"));
+    let context = FormatContext::default();
+    let full = envelope.tree_format_opt(false, Some(&context));
+    let lines: Vec<&str> = full.split('\n').collect();
+    assert_eq!(envelope.tree_format_page_count(false, Some(&context), 5), lines.len().div_ceil(5));
+
+    let pages: Vec<String> = (0..envelope.tree_format_page_count(false, Some(&context), 5))
+        .map(|index| envelope.tree_format_paged(false, Some(&context), 5, index))
+        .collect();
+    assert_eq!(pages.join("\n"), full);
+    assert_eq!(pages[0].split('\n').count(), 5);
+    assert_eq!(envelope.tree_format_paged(false, Some(&context), 5, pages.len()), "");
+
+    // Limiting the depth hides the elements below the limit.
+    let limited = context.with_max_depth(Some(1));
+    let tree = envelope.tree_format_opt(false, Some(&limited));
+    assert_eq!(tree.split('\n').count(), 5);
+    assert!(tree.split('\n').skip(1).all(|line| line.starts_with("    ") && !line.starts_with("        ")));
+    assert_eq!(tree.matches('…').count(), 3);
+    assert!(!tree.contains("\"lang\""));
+}
+
+#[test]
+fn test_digest_display_format() {
+    let envelope = Envelope::new("Hello.");
+    let digest = envelope.digest();
+    assert_eq!(DigestDisplayFormat::Short.format(&digest), "8cc96cdb");
+    assert_eq!(DigestDisplayFormat::Full.format(&digest), "8cc96cdb771176e835114a0f8936690b41cfed0df22d014eedd64edaea945d59");
+    assert_eq!(DigestDisplayFormat::Bytewords.format(&digest), "luck solo jazz ugly");
+    assert_eq!(DigestDisplayFormat::BytewordsMinimal.format(&digest), "lksojzuy");
+    assert_eq!(DigestDisplayFormat::Emoji.format(&digest), "🟩 👖 🌺 🐹");
+
+    let context = FormatContext::default().with_digest_display_format(DigestDisplayFormat::Emoji);
+    assert_eq!(envelope.tree_format_opt(false, Some(&context)), "🟩 👖 🌺 🐹 \"Hello.\"");
+
+    let mut context = FormatContext::default();
+    context.set_digest_display_format(DigestDisplayFormat::Emoji);
+    assert_eq!(envelope.tree_format_opt(false, Some(&context)), "🟩 👖 🌺 🐹 \"Hello.\"");
+}
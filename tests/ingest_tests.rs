@@ -0,0 +1,297 @@
+#[cfg(feature = "encrypt")]
+use bc_components::SymmetricKey;
+use dcbor::prelude::*;
+use bc_envelope::prelude::*;
+
+mod common;
+use crate::common::test_data::*;
+
+/// A small deterministic pseudorandom number generator, so that failures are
+/// reproducible.
+struct Xorshift(u64);
+
+impl Xorshift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+fn sample_envelopes() -> Vec<Envelope> {
+    let mut envelopes = vec![
+        hello_envelope(),
+        Envelope::new("Alice")
+            .add_assertion("knows", "Bob")
+            .add_assertion("note", Envelope::new(42).wrap_envelope()),
+    ];
+    #[cfg(feature = "encrypt")]
+    envelopes.push(envelopes[1].encrypt_subject(&SymmetricKey::new()).unwrap());
+    #[cfg(feature = "compress")]
+    envelopes.push(envelopes[1].compress().unwrap());
+    envelopes.push(envelopes[1].elide_removing_target(&envelopes[1].subject()));
+    envelopes
+}
+
+#[test]
+fn test_ingest_valid() {
+    let limits = IngestLimits::default();
+    for envelope in sample_envelopes() {
+        let ingested = Envelope::ingest_cbor_data(&envelope.tagged_cbor_data(), &limits).unwrap();
+        assert_eq!(ingested.digest(), envelope.digest());
+    }
+    // A UR string for "Hello." encoded by another implementation.
+    let ur_string = "ur:envelope/tpsoiyfdihjzjzjldmksbaoede";
+    let ingested = Envelope::ingest_ur_string(ur_string, &limits).unwrap();
+    assert_eq!(ingested.extract_subject::<String>().unwrap(), "Hello.");
+    let ingested = Envelope::ingest_ur_string(&ur_string.to_uppercase(), &limits).unwrap();
+    assert_eq!(ingested.extract_subject::<String>().unwrap(), "Hello.");
+}
+
+/// Returns an envelope whose `"future"` assertion has an object with a tag that
+/// is not part of the format.
+fn future_envelope_data() -> Vec<u8> {
+    let mut assertion = Map::new();
+    assertion.insert(Envelope::new("future").untagged_cbor(), CBOR::to_tagged_value(99999, "extension"));
+    let node = CBOR::from(vec![Envelope::new("Alice").untagged_cbor(), assertion.into()]);
+    CBOR::to_tagged_value(bc_components::tags::TAG_ENVELOPE, node).to_cbor_data()
+}
+
+#[test]
+fn test_ingest_lenient() {
+    let data = future_envelope_data();
+    assert!(Envelope::ingest_cbor_data(&data, &IngestLimits::default()).is_err());
+
+    let limits = IngestLimits::default().with_lenient(true);
+    assert!(limits.is_lenient());
+    let envelope = Envelope::ingest_cbor_data(&data, &limits).unwrap();
+    assert_eq!(envelope.extract_subject::<String>().unwrap(), "Alice");
+    let object = envelope.object_for_predicate("future").unwrap();
+    assert!(object.is_leaf());
+    assert_eq!(object.as_leaf().unwrap(), CBOR::to_tagged_value(99999, "extension"));
+
+    // Lenient decoding of supported envelopes is unchanged.
+    for envelope in sample_envelopes() {
+        let ingested = Envelope::ingest_cbor_data(&envelope.tagged_cbor_data(), &limits).unwrap();
+        assert_eq!(ingested.digest(), envelope.digest());
+    }
+}
+
+/// Builds without compression can still process compressed envelopes, which
+/// are decoded as elided.
+#[cfg(not(feature = "compress"))]
+#[test]
+fn test_ingest_lenient_unsupported_feature() {
+    let envelope = Envelope::new("Alice").add_assertion("knows", "Bob");
+    let compressed = bc_components::Compressed::from_uncompressed_data(
+        envelope.subject().tagged_cbor_data(),
+        Some(envelope.subject().digest().into_owned()),
+    );
+    let node = CBOR::from(vec![compressed.tagged_cbor(), envelope.assertions()[0].untagged_cbor()]);
+    let data = CBOR::to_tagged_value(bc_components::tags::TAG_ENVELOPE, node).to_cbor_data();
+    assert!(Envelope::ingest_cbor_data(&data, &IngestLimits::default()).is_err());
+
+    let ingested = Envelope::ingest_cbor_data(&data, &IngestLimits::default().with_lenient(true)).unwrap();
+    assert!(ingested.subject().is_elided());
+    assert_eq!(ingested.digest(), envelope.digest());
+}
+
+#[test]
+fn test_ingest_errors() {
+    let limits = IngestLimits::default();
+    let data = hello_envelope().tagged_cbor_data();
+
+    assert!(matches!(
+        Envelope::ingest_cbor_data(&data[..data.len() - 1], &limits),
+        Err(IngestError::MalformedCBOR { offset: Some(_), .. })
+    ));
+    assert_eq!(
+        Envelope::ingest_cbor_data(&CBOR::from("Hello.").to_cbor_data(), &limits),
+        Err(IngestError::TagMismatch { expected: 200, found: None })
+    );
+    assert_eq!(
+        Envelope::ingest_cbor_data(&CBOR::to_tagged_value(201, "Hello.").to_cbor_data(), &limits),
+        Err(IngestError::TagMismatch { expected: 200, found: Some(201) })
+    );
+    assert!(matches!(
+        Envelope::ingest_cbor_data(&CBOR::to_tagged_value(200, CBOR::from(-1)).to_cbor_data(), &limits),
+        Err(IngestError::InvalidEnvelope(_))
+    ));
+    assert_eq!(
+        Envelope::ingest_cbor_data(&data, &IngestLimits::new().with_max_bytes(4)),
+        Err(IngestError::LimitExceeded { limit: "size", max: 4, offset: None })
+    );
+
+    // Deeply nested wrapping is rejected before it can exhaust the stack.
+    let mut deep = [0xd8, 0xc8].repeat(100_000);
+    deep.extend([0x01]);
+    assert_eq!(
+        Envelope::ingest_cbor_data(&deep, &limits),
+        Err(IngestError::LimitExceeded { limit: "depth", max: 256, offset: Some(512) })
+    );
+
+    assert_eq!(
+        Envelope::ingest_ur_string("ur:seed/oyadgdhkwzdtfthptokigtvwnnjsqzcxknsktdhpyljeda", &limits),
+        Err(IngestError::URTypeMismatch { expected: "envelope".to_string(), found: "seed".to_string() })
+    );
+    assert!(matches!(Envelope::ingest_ur_string("envelope/tpsoiyfdihjzjzjldmksbaoede", &limits), Err(IngestError::InvalidUR(_))));
+    assert!(matches!(Envelope::ingest_ur_string("ur:envelope/tpsoiyfdihjzjzjldmksbaoedf", &limits), Err(IngestError::InvalidUR(_))));
+    assert!(matches!(Envelope::ingest_ur_string("ur:envelope/1-2/lpadaobkcy", &limits), Err(IngestError::InvalidUR(_))));
+    assert!(matches!(Envelope::ingest_ur_string("ur:envelope/tpsoiyfdihjzjzjldmksbaoedé", &limits), Err(IngestError::InvalidUR(_))));
+}
+
+/// Ingests mutations of valid envelopes, checking only that ingestion returns
+/// rather than panicking.
+#[test]
+fn test_ingest_fuzz() {
+    let limits = IngestLimits::new().with_max_depth(64);
+    let mut rng = Xorshift(0x9e3779b97f4a7c15);
+    let encodings: Vec<Vec<u8>> = sample_envelopes().iter().map(|e| e.tagged_cbor_data()).collect();
+    for _ in 0..20_000 {
+        let mut data = encodings[rng.below(encodings.len())].clone();
+        match rng.below(4) {
+            0 => {
+                let index = rng.below(data.len());
+                data[index] = rng.next() as u8;
+            },
+            1 => data.truncate(rng.below(data.len())),
+            2 => {
+                let index = rng.below(data.len() + 1);
+                data.insert(index, rng.next() as u8);
+            },
+            _ => {
+                let len = rng.below(64);
+                data = (0..len).map(|_| rng.next() as u8).collect();
+            },
+        }
+        let _ = Envelope::ingest_cbor_data(&data, &limits);
+        let ur_string = format!("ur:envelope/{}", bc_ur::bytewords::encode(&data, bc_ur::bytewords::Style::Minimal));
+        let _ = Envelope::ingest_ur_string(&ur_string, &limits);
+    }
+}
+
+/// Decodes hostile CBOR nested far deeper than any real envelope, checking
+/// that decoding fails rather than exhausting the stack.
+#[test]
+fn test_decode_depth_fuzz() {
+    let mut rng = Xorshift(0x2545f4914f6cdd1d);
+    for _ in 0..20 {
+        let mut cbor = CBOR::to_tagged_value(24, 1);
+        for _ in 0..2_000 {
+            cbor = match rng.below(3) {
+                0 => CBOR::to_tagged_value(200, cbor),
+                1 => CBOR::from(vec![cbor, CBOR::to_tagged_value(24, 2)]),
+                _ => {
+                    let mut map = Map::new();
+                    map.insert(CBOR::to_tagged_value(24, 3), cbor);
+                    CBOR::from(map)
+                },
+            };
+        }
+        let error = Envelope::from_untagged_cbor(cbor).unwrap_err();
+        assert!(matches!(error.downcast_ref(), Some(bc_envelope::EnvelopeError::TooDeeplyNested(_))));
+    }
+
+    // Nesting within the limit decodes.
+    let envelope = (0..100).fold(Envelope::new("Hello."), |envelope, _| envelope.wrap_envelope());
+    let decoded = Envelope::from_tagged_cbor(envelope.tagged_cbor()).unwrap();
+    assert_eq!(decoded.digest(), envelope.digest());
+    assert_eq!(decoded.unwrap_all_to_depth(usize::MAX), (Envelope::new("Hello."), 100));
+    assert_eq!(decoded.unwrap_all().1, 64);
+    let hello = Pattern::digest(Envelope::new("Hello.").digest().into_owned());
+    assert!(decoded.unwrap_until_depth(&hello, 99).is_none());
+    assert_eq!(decoded.unwrap_until_depth(&hello, 100).unwrap().1, 100);
+}
+
+/// Walks and drops an envelope nested far deeper than the stack of the
+/// thread could hold if walking or dropping were recursive.
+#[test]
+fn test_walk_stack_safety() {
+    const DEPTH: usize = 5_000;
+    std::thread::Builder::new()
+        .stack_size(64 * 1024)
+        .spawn(|| {
+            let mut envelope = Envelope::new("Hello.").add_assertion("knows", "Bob");
+            for _ in 0..DEPTH {
+                envelope = envelope.wrap_envelope();
+            }
+            let visits = std::cell::Cell::new(0);
+            let max_level = std::cell::Cell::new(0);
+            let visitor = |_: Envelope, level: usize, _: bc_envelope::base::walk::EdgeType, _: Option<()>| -> Option<()> {
+                visits.set(visits.get() + 1);
+                max_level.set(max_level.get().max(level));
+                None
+            };
+            envelope.walk(false, &visitor);
+            assert_eq!(visits.get(), DEPTH + 5);
+            assert_eq!(max_level.get(), DEPTH + 2);
+            visits.set(0);
+            envelope.walk(true, &visitor);
+            assert_eq!(visits.get(), DEPTH + 4);
+            assert_eq!(envelope.walk_events().count(), 2 * DEPTH + 7);
+            assert_eq!(envelope.unwrap_all_to_depth(usize::MAX).1, DEPTH);
+
+            // Dropping the envelope does not recurse either.
+            drop(envelope);
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+}
+
+#[test]
+fn test_multipart_progress() -> anyhow::Result<()> {
+    use bc_ur::{MultipartEncoder, UR};
+
+    let envelope = Envelope::new("A fairly long message split across many animated QR frames.")
+        .add_assertion("note", "The scan may be interrupted and resumed.");
+    let ur = UR::new("envelope", envelope.untagged_cbor())?;
+    let mut encoder = MultipartEncoder::new(&ur, 10)?;
+    let parts_count = encoder.parts_count();
+    assert!(parts_count > 4);
+    let parts: Vec<String> = (0..parts_count * 3).map(|_| encoder.next_part().unwrap()).collect();
+
+    // Receive half of the fragments, some of them twice, then save the state.
+    let mut decoder = MultipartEnvelopeDecoder::new();
+    assert_eq!(decoder.progress().fraction(), 0.0);
+    for part in &parts[..parts_count / 2] {
+        decoder.receive(part)?;
+        decoder.receive(part)?;
+    }
+    let progress = decoder.progress();
+    assert_eq!(progress.parts_received, parts_count / 2);
+    assert_eq!(progress.parts_needed, Some(parts_count));
+    assert!(!progress.is_complete);
+    assert!(progress.percent_complete() > 0.0 && progress.percent_complete() < 100.0);
+    let state: CBOR = (&decoder).into();
+
+    // Resume from the saved state, skipping a fragment so that the fountain
+    // parts are needed to finish.
+    let mut decoder = MultipartEnvelopeDecoder::try_from(CBOR::try_from_data(state.to_cbor_data())?)?;
+    assert_eq!(decoder.progress(), progress);
+    for part in &parts[parts_count / 2 + 1..] {
+        if decoder.receive(part)?.is_complete {
+            break;
+        }
+    }
+    assert!(decoder.is_complete());
+    assert_eq!(decoder.progress().fraction(), 1.0);
+    assert_eq!(decoder.envelope().unwrap().digest(), envelope.digest());
+
+    // Single-part URs complete at once, and parts of other types or other
+    // envelopes are rejected.
+    let mut decoder = MultipartEnvelopeDecoder::new();
+    assert!(decoder.receive(&ur.string())?.is_complete);
+    let mut decoder = MultipartEnvelopeDecoder::new();
+    assert!(decoder.receive(&UR::new("seed", envelope.untagged_cbor())?.string()).is_err());
+    decoder.receive(&parts[0])?;
+    let other = UR::new("envelope", Envelope::new("Other").untagged_cbor())?;
+    assert!(decoder.receive(&MultipartEncoder::new(&other, 2)?.next_part()?).is_err());
+
+    Ok(())
+}
@@ -0,0 +1,75 @@
+#![cfg(feature = "legacy")]
+
+use bc_components::ARID;
+use bc_envelope::prelude::*;
+
+mod common;
+use crate::common::check_encoding::*;
+
+// An illustrative legacy numbering, standing in for that of the
+// implementation that produced the envelopes being migrated. `verifiedBy` is
+// now `'signed'`.
+const LEGACY_CID_TAG: TagValue = 58;
+const LEGACY_VERIFIED_BY: u64 = 99;
+
+#[test]
+fn test_migrate_legacy() {
+    let arid_data = [0x11u8; 32];
+    let legacy_cid = CBOR::to_tagged_value(LEGACY_CID_TAG, CBOR::to_byte_string(arid_data));
+    let legacy = Envelope::new(legacy_cid)
+        .add_assertion(KnownValue::new(LEGACY_VERIFIED_BY), "Bob")
+        .add_assertion("note", "Hello")
+        .check_encoding().unwrap();
+
+    let migration = LegacyMigration::new()
+        .with_cid_tag(LEGACY_CID_TAG)
+        .with_known_value_rewrite(LEGACY_VERIFIED_BY, known_values::SIGNED);
+    assert!(legacy.is_legacy(&migration));
+
+    let (migrated, report) = legacy.migrate_legacy(&migration);
+    migrated.check_encoding().unwrap();
+    assert!(report.is_migrated());
+    assert_eq!(report.rewrites().len(), 2);
+    assert!(report.obscured_elements().is_empty());
+    assert!(report.merged_assertions().is_empty());
+
+    assert_eq!(migrated.extract_subject::<ARID>().unwrap(), ARID::from_data(arid_data));
+    assert_eq!(migrated.extract_object_for_predicate::<String>(known_values::SIGNED).unwrap(), "Bob");
+    assert_eq!(migrated.extract_object_for_predicate::<String>("note").unwrap(), "Hello");
+    assert!(!migrated.is_legacy(&migration));
+
+    // Migrating a current envelope changes nothing.
+    let (unchanged, report) = migrated.migrate_legacy(&migration);
+    assert!(!report.is_migrated());
+    assert_equivalent!(unchanged, migrated);
+}
+
+#[test]
+fn test_migrate_legacy_obscured() {
+    let legacy = Envelope::new("Alice")
+        .add_assertion(KnownValue::new(LEGACY_VERIFIED_BY), "Bob");
+    let elided = legacy.elide_removing_target(&legacy.subject());
+
+    let migration = LegacyMigration::new()
+        .with_known_value_rewrite(LEGACY_VERIFIED_BY, known_values::SIGNED);
+    let (_, report) = elided.migrate_legacy(&migration);
+    assert_eq!(report.rewrites().len(), 1);
+    assert_eq!(report.obscured_elements(), &[legacy.subject().digest().into_owned()]);
+}
+
+#[test]
+fn test_migrate_legacy_merges_collisions() {
+    // The same assertion, made with both the legacy and the current known
+    // value, becomes a single assertion.
+    let legacy = Envelope::new("Alice")
+        .add_assertion(KnownValue::new(LEGACY_VERIFIED_BY), "Bob")
+        .add_assertion(known_values::SIGNED, "Bob")
+        .add_assertion("note", "Hello");
+    let migration = LegacyMigration::new()
+        .with_known_value_rewrite(LEGACY_VERIFIED_BY, known_values::SIGNED);
+    let (migrated, report) = legacy.migrate_legacy(&migration);
+    migrated.check_encoding().unwrap();
+    assert_eq!(migrated.assertions().len(), 2);
+    let signed = Envelope::new_assertion(known_values::SIGNED, "Bob");
+    assert_eq!(report.merged_assertions(), &[signed.digest().into_owned()]);
+}
@@ -0,0 +1,66 @@
+#![cfg(feature = "mmap")]
+
+use bc_envelope::prelude::*;
+
+#[test]
+fn test_open_mmap() {
+    let e = Envelope::new("Registry")
+        .add_assertion("credential", "A")
+        .add_assertion("credential", "B");
+    let path = std::env::temp_dir().join(format!("bc-envelope-mmap-{}.envelope", std::process::id()));
+    std::fs::write(&path, e.tagged_cbor().to_cbor_data()).unwrap();
+
+    let mapped = Envelope::open_mmap(&path).unwrap();
+    let clone = mapped.clone();
+    assert!(!mapped.is_decoded());
+    assert_eq!(mapped.cbor_data(), e.tagged_cbor().to_cbor_data());
+
+    assert!(mapped.envelope().unwrap().is_identical_to(&e));
+    // Clones share the decoded envelope.
+    assert!(clone.is_decoded());
+    let decoded: Envelope = clone.try_into().unwrap();
+    assert!(decoded.is_identical_to(&e));
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_open_mmap_subtrees() {
+    let e = Envelope::new("Registry")
+        .add_assertion("credential", Envelope::new("A").add_assertion("issuer", "Example"))
+        .add_assertion("credential", "B");
+    let path = std::env::temp_dir().join(format!("bc-envelope-mmap-subtrees-{}.envelope", std::process::id()));
+    std::fs::write(&path, e.tagged_cbor().to_cbor_data()).unwrap();
+
+    // Subtrees are located and decoded without decoding the whole envelope.
+    let mapped = Envelope::open_mmap(&path).unwrap();
+    let subject = mapped.subject().unwrap();
+    assert_eq!(subject.envelope().unwrap().extract_subject::<String>().unwrap(), "Registry");
+    let assertions = mapped.assertions().unwrap();
+    assert_eq!(assertions.len(), 2);
+    for (mapped_assertion, assertion) in assertions.iter().zip(e.assertions()) {
+        assert!(!mapped_assertion.is_decoded());
+        assert_eq!(mapped_assertion.envelope().unwrap().digest(), assertion.digest());
+    }
+    assert!(!mapped.is_decoded());
+
+    // An element that is not a node is its own subject, with no assertions.
+    let leaf = subject.subject().unwrap();
+    assert_eq!(leaf.cbor_data(), subject.cbor_data());
+    assert!(leaf.assertions().unwrap().is_empty());
+    assert_eq!(mapped.envelope().unwrap().digest(), e.digest());
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_open_mmap_invalid() {
+    let path = std::env::temp_dir().join(format!("bc-envelope-mmap-invalid-{}.envelope", std::process::id()));
+    std::fs::write(&path, [0x01, 0x02, 0x03]).unwrap();
+    let mapped = Envelope::open_mmap(&path).unwrap();
+    assert!(mapped.envelope().is_err());
+    assert!(!mapped.is_decoded());
+    assert!(mapped.subject().is_err());
+    assert!(mapped.assertions().is_err());
+    std::fs::remove_file(&path).unwrap();
+}
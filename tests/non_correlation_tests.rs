@@ -119,3 +119,41 @@ fn test_add_salt() {
     "#}.trim();
     assert_eq!(e1_elided.format(), redacted_expected_format);
 }
+
+#[test]
+fn test_strip_salts() {
+    let e1 = Envelope::new("Alice")
+        .add_assertion("knows", "Bob")
+        .add_assertion_salted("age", 30, true)
+        .wrap_envelope()
+        .add_salt();
+    let e2 = Envelope::new("Alice")
+        .add_assertion("knows", "Bob")
+        .add_assertion_salted("age", 30, true)
+        .wrap_envelope()
+        .add_salt();
+    assert!(!e1.is_equivalent_to(&e2));
+
+    let salts = e1.salts();
+    assert_eq!(salts.len(), 2);
+    assert!(salts.iter().all(|(assertion, parent)| parent.assertions().contains(assertion)));
+
+    let core = Envelope::new("Alice")
+        .add_assertion("knows", "Bob")
+        .add_assertion("age", 30)
+        .wrap_envelope();
+    assert_equivalent!(e1.strip_salts().check_encoding().unwrap(), core);
+    assert_equivalent!(e2.strip_salts(), core);
+    assert!(e1.strip_salts().salts().is_empty());
+
+    // An envelope without salt is unchanged.
+    assert_equivalent!(core.strip_salts(), core);
+
+    // Assertions that differed only in their salt are merged.
+    let twice = Envelope::new("Alice")
+        .add_assertion_salted("knows", "Bob", true)
+        .add_assertion_salted("knows", "Bob", true);
+    assert_eq!(twice.assertions().len(), 2);
+    let stripped = twice.strip_salts().check_encoding().unwrap();
+    assert!(stripped.is_identical_to(&Envelope::new("Alice").add_assertion("knows", "Bob")));
+}
@@ -117,3 +117,59 @@ fn test_obscuring() {
         assert!(compressed_compressed.is_compressed());
     }
 }
+
+#[cfg(all(feature = "compress", feature = "encrypt"))]
+#[test]
+fn test_elide_with_action_map() {
+    use std::collections::HashMap;
+
+    let key = SymmetricKey::new();
+    let envelope = Envelope::new("Alice")
+        .add_assertion("knows", "Bob")
+        .add_assertion("likes", "Carol")
+        .add_assertion("note", Envelope::new(PLAINTEXT_HELLO));
+
+    let knows = envelope.assertion_with_predicate("knows").unwrap();
+    let likes = envelope.assertion_with_predicate("likes").unwrap();
+    let note = envelope.object_for_predicate("note").unwrap();
+
+    let mut actions = HashMap::new();
+    actions.insert(knows.digest().into_owned(), ObscureAction::Elide);
+    actions.insert(likes.digest().into_owned(), ObscureAction::Encrypt(key.clone()));
+    actions.insert(note.digest().into_owned(), ObscureAction::Compress);
+
+    let obscured = envelope.elide_with_action_map(&actions);
+    assert_equivalent!(obscured, envelope);
+
+    assert!(obscured.assertion_with_predicate("knows").is_err());
+    assert!(obscured.assertions().iter().any(|a| a.digest() == knows.digest() && a.is_elided()));
+    assert!(obscured.assertions().iter().any(|a| a.digest() == likes.digest() && a.is_encrypted()));
+    assert!(obscured.object_for_predicate("note").unwrap().is_compressed());
+
+    // An empty map leaves the envelope untouched.
+    assert!(envelope.elide_with_action_map(&HashMap::new()).is_identical_to(&envelope));
+}
+
+#[test]
+fn test_add_assertion_to_obscured() {
+    let envelope = Envelope::new("Alice");
+    assert!(envelope.lint().is_empty());
+    assert!(envelope.add_assertion_to_obscured("knows", "Bob").is_err());
+
+    // Adding an assertion to an elided subject gives the same digest as
+    // adding it before eliding.
+    let elided = envelope.elide();
+    let with_assertion = elided.add_assertion_to_obscured("knows", "Bob").unwrap();
+    assert_equivalent!(with_assertion, envelope.add_assertion("knows", "Bob"));
+    assert_eq!(
+        with_assertion.lint(),
+        vec![EnvelopeLint::AssertionsOnElidedSubject(with_assertion.digest().into_owned())]
+    );
+
+    // Lints are found anywhere in the envelope.
+    let nested = Envelope::new("Carol").add_assertion("friend", with_assertion.clone());
+    assert_eq!(nested.lint().len(), 1);
+
+    // A node whose assertions are all elided is fine.
+    assert!(with_assertion.elide_removing_target(&with_assertion.assertions()[0]).lint().is_empty());
+}
@@ -166,3 +166,77 @@ fn test_verifiable_credential() {
     let first_name_assertion = Envelope::new_assertion("firstName", "John");
     assert!(!credential_root.confirm_contains_target(&first_name_assertion, &address_proof));
 }
+
+#[test]
+fn test_opening() {
+    let credential = Envelope::new("Alice")
+        .add_assertion_salted("birthYear", 1990, true)
+        .add_assertion_salted("nationality", "Swiss", true)
+        .add_assertion_salted("employer", "Example Corp", true);
+
+    // The holder discloses only the subject, and the verifier receives an
+    // envelope with every assertion elided.
+    let disclosed = credential.elide_revealing_set(&HashSet::from([
+        credential.digest().into_owned(),
+        credential.subject().digest().into_owned(),
+    ]));
+    let nationality = credential.assertion_with_predicate("nationality").unwrap();
+    assert!(disclosed.assertion_with_predicate("nationality").is_err());
+
+    // Later, the holder opens the nationality assertion alone.
+    let opening = credential.opening_for(&nationality.digest()).unwrap();
+    assert!(disclosed.verify_opening(&opening));
+    assert_eq!(opening.subject().as_object().unwrap().extract_subject::<String>().unwrap(), "Swiss");
+
+    // A forged opening, even one with the claimed value, does not verify,
+    // because it lacks the original salt.
+    let forged = Envelope::new_assertion("nationality", "Swiss").add_salt();
+    assert!(!disclosed.verify_opening(&forged));
+
+    // An elided opening has the right digest but proves nothing.
+    assert!(!disclosed.verify_opening(&opening.elide()));
+
+    // So does an opening with an elided object or predicate.
+    let hollow_object = opening.elide_removing_target(&opening.subject().as_object().unwrap());
+    assert_eq!(hollow_object.digest(), opening.digest());
+    assert!(!disclosed.verify_opening(&hollow_object));
+    let hollow_predicate = opening.elide_removing_target(&opening.subject().as_predicate().unwrap());
+    assert_eq!(hollow_predicate.digest(), opening.digest());
+    assert!(!disclosed.verify_opening(&hollow_predicate));
+
+    assert!(credential.opening_for(&forged.digest()).is_none());
+}
+
+#[test]
+fn test_assertion_commitment() {
+    let envelope = Envelope::new("Alice")
+        .add_assertion("knows", "Bob")
+        .add_assertion("knows", "Carol")
+        .add_assertion("age", 30)
+        .add_assertion_commitment()
+        .check_encoding().unwrap();
+    assert_eq!(envelope.assertions().len(), 4);
+    envelope.verify_assertion_commitment().unwrap();
+
+    // Adding the commitment again replaces it.
+    assert_equivalent!(envelope.add_assertion_commitment(), envelope);
+
+    // Elided assertions are still accounted for.
+    let age = envelope.assertion_with_predicate("age").unwrap();
+    let elided = envelope.elide_removing_target(&age);
+    elided.verify_assertion_commitment().unwrap();
+    assert!(elided.withheld_assertions().unwrap().is_empty());
+
+    // Withheld assertions are detected.
+    let withheld = envelope.remove_assertion(age.clone());
+    assert!(withheld.verify_assertion_commitment().is_err());
+    assert_eq!(withheld.withheld_assertions().unwrap(), vec![age.digest().into_owned()]);
+
+    // So are assertions that were not committed to.
+    let added = envelope.add_assertion("knows", "Dave");
+    assert!(added.verify_assertion_commitment().is_err());
+    assert!(added.withheld_assertions().unwrap().is_empty());
+
+    // An envelope without a commitment cannot be verified.
+    assert!(Envelope::new("Alice").add_assertion("knows", "Bob").verify_assertion_commitment().is_err());
+}
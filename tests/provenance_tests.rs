@@ -0,0 +1,61 @@
+#![cfg(feature = "provenance")]
+
+use bc_envelope::prelude::*;
+
+mod common;
+use crate::common::check_encoding::*;
+
+#[test]
+fn test_edit_journal() {
+    let date = dcbor::Date::from_string("2024-07-04T11:11:11Z").unwrap();
+    let mut journal = EditJournal::new_opt("Alice", Some(date));
+
+    let e1 = Envelope::new("Document");
+    let e2 = e1.add_assertion_journaled("status", "draft", &mut journal);
+    let e3 = e2.replace_assertion_journaled(
+        Envelope::new_assertion("status", "draft"),
+        Envelope::new_assertion("status", "final"),
+        &mut journal
+    ).unwrap();
+    assert_eq!(e3.extract_object_for_predicate::<String>("status").unwrap(), "final");
+    assert_eq!(journal.len(), 3);
+
+    // Removing an assertion the envelope doesn't have records nothing.
+    let unchanged = e3.remove_assertion_journaled(Envelope::new_assertion("status", "draft"), &mut journal);
+    assert_eq!(unchanged.digest(), e3.digest());
+    assert_eq!(journal.len(), 3);
+
+    let entries = journal.entries();
+    assert_eq!(entries[0].extract_subject::<String>().unwrap(), "add");
+    assert_eq!(entries[1].extract_subject::<String>().unwrap(), "remove");
+    assert_eq!(entries[2].extract_subject::<String>().unwrap(), "add");
+    assert_eq!(entries[0].extract_object_for_predicate::<Digest>("before").unwrap(), e1.digest().into_owned());
+    assert_eq!(entries[2].extract_object_for_predicate::<Digest>("after").unwrap(), e3.digest().into_owned());
+    assert_eq!(entries[2].extract_object_for_predicate::<usize>("sequence").unwrap(), 2);
+
+    let journal_envelope = journal.journal_envelope().check_encoding().unwrap();
+    assert_eq!(journal_envelope.extract_subject::<String>().unwrap(), "Alice");
+    assert_eq!(journal_envelope.objects_for_predicate(JOURNAL_ENTRY).len(), 3);
+    assert!(journal_envelope.objects_for_predicate(known_values::DIFF_EDITS).is_empty());
+
+    // The entries are chained, so their order can be recovered and checked.
+    assert_eq!(entries[0].assertions_with_predicate(PREVIOUS_ENTRY).len(), 0);
+    assert_eq!(entries[1].extract_object_for_predicate::<Digest>(PREVIOUS_ENTRY).unwrap(), entries[0].digest().into_owned());
+    let verified = EditJournal::verified_entries(&journal_envelope).unwrap();
+    assert_eq!(verified.len(), 3);
+    for (verified, entry) in verified.iter().zip(entries) {
+        assert!(verified.is_equivalent_to(entry));
+    }
+
+    // Dropping an entry from the middle breaks the chain.
+    let dropped = journal_envelope.remove_assertion(Envelope::new_assertion(JOURNAL_ENTRY, entries[1].clone()));
+    assert!(EditJournal::verified_entries(&dropped).is_err());
+
+    // So does an entry that claims a different predecessor.
+    let forged = entries[2].replace_assertion(
+        Envelope::new_assertion(PREVIOUS_ENTRY, entries[1].digest().into_owned()),
+        Envelope::new_assertion(PREVIOUS_ENTRY, entries[0].digest().into_owned()),
+    ).unwrap();
+    let reordered = dropped.add_assertion(JOURNAL_ENTRY, forged);
+    assert!(EditJournal::verified_entries(&reordered).is_err());
+}
@@ -0,0 +1,92 @@
+#![cfg(feature = "rdf")]
+
+use bc_envelope::prelude::*;
+use indoc::indoc;
+
+fn node(envelope: &Envelope) -> String {
+    format!("_:{}", hex::encode(envelope.digest().data()))
+}
+
+#[test]
+fn test_to_turtle() {
+    let bob = Envelope::new("Bob").add_assertion("age", 42);
+    let e = Envelope::new("Alice")
+        .add_assertion(known_values::IS_A, "Person")
+        .add_assertion("knows", bob.clone())
+        .add_assertion("motto", "Say \"hi\"");
+    let turtle = e.to_turtle();
+
+    assert!(turtle.starts_with(indoc! {r#"
+        @prefix rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#> .
+        @prefix xsd: <http://www.w3.org/2001/XMLSchema#> .
+        @prefix bc: <https://blockchaincommons.com/ns/envelope#> .
+        @prefix kv: <https://blockchaincommons.com/ns/known-value#> .
+        @prefix str: <https://blockchaincommons.com/ns/string-predicate#> .
+    "#}));
+
+    let alice = node(&e);
+    let bob = node(&bob);
+    let lines: Vec<&str> = turtle.lines().collect();
+    assert!(lines.contains(&format!("# root: {}", alice).as_str()));
+    for expected in [
+        format!(r#"{} rdf:value "Alice" ."#, alice),
+        format!(r#"{} kv:isA "Person" ."#, alice),
+        format!(r#"{} str:knows {} ."#, alice, bob),
+        format!(r#"{} str:motto "Say \"hi\"" ."#, alice),
+        format!(r#"{} rdf:value "Bob" ."#, bob),
+        format!(r#"{} str:age "42"^^xsd:integer ."#, bob),
+    ] {
+        assert!(lines.contains(&expected.as_str()), "missing: {}\n{}", expected, turtle);
+    }
+    assert_eq!(lines.iter().filter(|line| line.ends_with(" .") && !line.starts_with('@')).count(), 6);
+}
+
+#[test]
+fn test_to_turtle_known_values_round_trip() {
+    let e = Envelope::new("Alice")
+        .add_assertion(known_values::IS_A, "Person")
+        .add_assertion(KnownValue::new_with_name(1000u64, "likes".to_string()), "Bob");
+    let decoded = Envelope::from_tagged_cbor_data(e.tagged_cbor().to_cbor_data()).unwrap();
+
+    // Decoded known values have no names of their own, but are named from
+    // the registry, so the export does not change.
+    let turtle = e.to_turtle();
+    assert_eq!(decoded.to_turtle(), turtle);
+    assert!(turtle.contains(&format!("{} kv:isA \"Person\" .", node(&e))));
+    assert!(turtle.contains(&format!("{} kv:1000 \"Bob\" .", node(&e))));
+}
+
+#[test]
+fn test_to_turtle_obscured() {
+    let e = Envelope::new("Alice")
+        .add_assertion("knows", "Bob")
+        .add_assertion(1, "one");
+    let knows = e.assertion_with_predicate("knows").unwrap();
+    let elided = e.elide_removing_target(&knows);
+    let turtle = elided.to_turtle();
+
+    let alice = node(&e);
+    assert!(turtle.contains(&format!("{} bc:hasObscuredAssertion {} .", alice, node(&knows))));
+    assert!(turtle.contains(&format!("{} a bc:Elided .", node(&knows))));
+    assert!(turtle.contains("whose predicate is not an IRI"));
+}
+
+#[test]
+fn test_to_turtle_doubles() {
+    let e = Envelope::new("Readings")
+        .add_assertion("half", 1.5)
+        .add_assertion("nan", f64::NAN)
+        .add_assertion("inf", f64::INFINITY)
+        .add_assertion("negInf", f64::NEG_INFINITY);
+    let turtle = e.to_turtle();
+
+    let readings = node(&e);
+    for expected in [
+        format!(r#"{} str:half "1.5e0"^^xsd:double ."#, readings),
+        format!(r#"{} str:nan "NaN"^^xsd:double ."#, readings),
+        format!(r#"{} str:inf "INF"^^xsd:double ."#, readings),
+        format!(r#"{} str:negInf "-INF"^^xsd:double ."#, readings),
+    ] {
+        assert!(turtle.lines().any(|line| line == expected), "missing: {}\n{}", expected, turtle);
+    }
+}
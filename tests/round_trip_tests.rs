@@ -0,0 +1,23 @@
+// Round-trip checking is a process-wide setting, so it is tested in its own
+// binary to keep it from affecting other tests running in parallel.
+
+use bc_envelope::prelude::*;
+
+#[test]
+fn test_verify_round_trip() {
+    let e = Envelope::new("Alice")
+        .add_assertion("knows", "Bob")
+        .wrap_envelope()
+        .add_assertion("note", 42);
+    assert_equivalent!(e.verify_round_trip().unwrap(), e);
+
+    set_round_trip_checking(true);
+    assert_eq!(is_round_trip_checking(), cfg!(debug_assertions));
+    let checked = Envelope::new("Alice")
+        .add_assertion("knows", "Bob")
+        .wrap_envelope()
+        .add_assertion("note", 42);
+    set_round_trip_checking(false);
+    assert!(!is_round_trip_checking());
+    assert_equivalent!(checked, e);
+}
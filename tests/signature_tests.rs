@@ -1,5 +1,9 @@
 #![cfg(feature = "signature")]
 
+use std::time::Duration;
+
+use bc_components::{Nonce, PrivateKeyBase, PublicKeyBaseProvider, ARID};
+use dcbor::Date;
 use indoc::indoc;
 use bc_envelope::prelude::*;
 use known_values::NOTE;
@@ -77,6 +81,36 @@ fn multisigned_plaintext() {
     assert_eq!(received_plaintext, PLAINTEXT_HELLO);
 }
 
+#[test]
+fn test_weighted_signatures() {
+    // Alice is the CFO, with a weight of 2. Bob and Carol are clerks, with a
+    // weight of 1 each. Payments need a total weight of 3.
+    let (cfo, clerk1, clerk2) = (alice_public_key(), bob_public_key(), carol_public_key());
+    let weighted: [(&dyn bc_components::Verifier, u32); 3] = [(&cfo, 2), (&clerk1, 1), (&clerk2, 1)];
+
+    // The CFO and one clerk meet the target.
+    let envelope = hello_envelope()
+        .add_signatures(&[&alice_private_key(), &carol_private_key()]);
+    assert_eq!(envelope.verify_signatures_from_weighted(&weighted, 3).unwrap(), vec![0, 2]);
+
+    // So do the CFO and both clerks, but only the signers needed are returned.
+    let envelope = hello_envelope()
+        .add_signatures(&[&alice_private_key(), &bob_private_key(), &carol_private_key()]);
+    assert_eq!(envelope.verify_signatures_from_weighted(&weighted, 3).unwrap(), vec![0, 1]);
+
+    // Both clerks without the CFO do not.
+    let envelope = hello_envelope()
+        .add_signatures(&[&bob_private_key(), &carol_private_key()]);
+    assert_eq!(envelope.has_signatures_from_weighted(&weighted, 3).unwrap(), None);
+    assert!(envelope.verify_signatures_from_weighted(&weighted, 3).is_err());
+
+    // Listing a clerk three times does not triple their weight.
+    let envelope = hello_envelope().add_signature(&bob_private_key());
+    let repeated: [(&dyn bc_components::Verifier, u32); 3] = [(&clerk1, 1), (&clerk1, 1), (&clerk1, 1)];
+    assert_eq!(envelope.has_signatures_from_weighted(&repeated, 3).unwrap(), None);
+    assert_eq!(envelope.verify_signatures_from_weighted(&repeated, 1).unwrap(), vec![0]);
+}
+
 #[test]
 fn signed_with_metadata() {
     bc_components::register_tags();
@@ -129,3 +163,366 @@ fn signed_with_metadata() {
         .extract_subject::<String>().unwrap();
     assert_eq!(received_plaintext, PLAINTEXT_HELLO);
 }
+
+#[test]
+fn test_signature_report() {
+    let metadata = SignatureMetadata::new()
+        .with_assertion(NOTE, "Alice signed this.");
+    let envelope = hello_envelope()
+        .wrap_envelope()
+        .add_signature_opt(&alice_private_key(), None, Some(metadata))
+        .add_signature(&carol_private_key())
+        .add_assertion(known_values::SIGNED, "not a signature");
+
+    let report = envelope.signature_report(&[&bob_public_key(), &alice_public_key()]);
+    assert_eq!(report.len(), 3);
+    assert!(report.iter().all(|r| r.coverage() == SignatureCoverage::WrappedEnvelope));
+
+    let alice = report.iter().find(|r| r.metadata().is_some()).unwrap();
+    assert!(alice.is_valid());
+    assert_eq!(alice.verifier_index(), Some(1));
+    assert_eq!(alice.algorithm(), Some("Schnorr"));
+    let note = alice.metadata().unwrap().extract_object_for_predicate::<String>(NOTE).unwrap();
+    assert_eq!(note, "Alice signed this.");
+
+    // Carol's signature is well-formed but no verifier matches it.
+    let carol = report.iter().find(|r| r.metadata().is_none() && r.signature().is_some()).unwrap();
+    assert!(!carol.is_valid());
+    assert_eq!(carol.verifier_index(), None);
+
+    let malformed = report.iter().find(|r| r.signature().is_none()).unwrap();
+    assert!(!malformed.is_valid());
+    assert_eq!(malformed.algorithm(), None);
+
+    let subject_report = hello_envelope()
+        .add_signature(&bob_private_key())
+        .signature_report(&[&bob_public_key()]);
+    assert_eq!(subject_report[0].coverage(), SignatureCoverage::Subject);
+    assert_eq!(subject_report[0].verifier_index(), Some(0));
+}
+
+#[test]
+fn test_streaming_bytes_signature() {
+    let payload: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+
+    // The signer never holds the whole payload.
+    let signed = Envelope::new_streaming_bytes(payload.as_slice(), payload.len() as u64)
+        .unwrap()
+        .add_signature(&alice_private_key());
+
+    // The recipient, who has the payload, restores it and verifies.
+    let full = signed
+        .replace_subject_checked(Envelope::new(dcbor::ByteString::from(payload)), false)
+        .unwrap();
+    assert!(full.subject().is_leaf());
+    full.verify_signature_from(&alice_public_key()).unwrap();
+}
+
+struct Directory(Vec<(Envelope, Envelope)>);
+
+impl SignerResolver for Directory {
+    async fn resolve(&self, reference: &Envelope) -> anyhow::Result<Envelope> {
+        self.0
+            .iter()
+            .find(|(key, _)| key.digest() == reference.digest())
+            .map(|(_, document)| document.clone())
+            .ok_or_else(|| anyhow::anyhow!("unknown reference"))
+    }
+}
+
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    let mut future = std::pin::pin!(future);
+    let mut context = std::task::Context::from_waker(std::task::Waker::noop());
+    loop {
+        if let std::task::Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+    }
+}
+
+#[test]
+fn test_verify_signature_resolving() {
+    use bc_envelope::extension::signature::signer_resolver::{PUBLIC_KEYS, VERIFIED_BY};
+
+    // Carol is the trust anchor. Her directory entry only says where her
+    // document can be found.
+    let carol = Envelope::new("Carol").add_assertion(PUBLIC_KEYS, carol_public_key()).wrap_envelope();
+    let carol_stub = Envelope::new("Carol").add_assertion(known_values::DEREFERENCE_VIA, "carol-document");
+
+    // Carol vouches for Alice's document.
+    let alice = Envelope::new("Alice")
+        .add_assertion(PUBLIC_KEYS, bob_public_key())
+        .add_assertion(PUBLIC_KEYS, alice_public_key())
+        .add_assertion(VERIFIED_BY, "carol")
+        .sign(&carol_private_key());
+
+    let directory = Directory(vec![
+        (Envelope::new("alice"), alice.clone()),
+        (Envelope::new("carol"), carol_stub),
+        (Envelope::new("carol-document"), carol.clone()),
+    ]);
+
+    let message = hello_envelope()
+        .add_assertion(VERIFIED_BY, "alice")
+        .add_signature(&alice_private_key());
+    let path = block_on(message.verify_signature_resolving(&directory, 8)).unwrap();
+    assert_eq!(path.len(), 2);
+    assert!(path.links()[0].signed().is_equivalent_to(&message));
+    assert!(path.links()[0].signer().is_equivalent_to(&alice));
+    assert_eq!(path.links()[0].key(), &alice_public_key());
+    assert_eq!(path.links()[1].reference().extract_subject::<String>().unwrap(), "carol");
+    assert_eq!(path.links()[1].key(), &carol_public_key());
+    assert!(path.anchor().is_equivalent_to(&carol));
+
+    // Alice, Carol's stub, and Carol's document make three fetches.
+    assert!(block_on(message.verify_signature_resolving(&directory, 2)).is_err());
+
+    // A message signed by someone other than its claimed signer fails.
+    let forged = hello_envelope()
+        .add_assertion(VERIFIED_BY, "alice")
+        .add_signature(&carol_private_key());
+    assert!(block_on(forged.verify_signature_resolving(&directory, 8)).is_err());
+
+    // So does a message naming an unknown signer.
+    let unknown = hello_envelope()
+        .add_assertion(VERIFIED_BY, "dave")
+        .add_signature(&alice_private_key());
+    assert!(block_on(unknown.verify_signature_resolving(&directory, 8)).is_err());
+
+    // A key appended to Alice's document outside the signed wrapper is not
+    // trusted, even though Carol's signature on the document still verifies.
+    let mallory_private_key = PrivateKeyBase::new();
+    let tampered = alice.add_assertion(PUBLIC_KEYS, mallory_private_key.public_key_base());
+    tampered.verify_signature_from(&carol_public_key()).unwrap();
+    let tampered_directory = Directory(vec![
+        (Envelope::new("alice"), tampered),
+        (Envelope::new("carol"), carol.clone()),
+    ]);
+    let impostor = hello_envelope()
+        .add_assertion(VERIFIED_BY, "alice")
+        .add_signature(&mallory_private_key);
+    assert!(block_on(impostor.verify_signature_resolving(&tampered_directory, 8)).is_err());
+    assert!(block_on(message.verify_signature_resolving(&tampered_directory, 8)).is_ok());
+
+    // A signer's document that is not wrapped is rejected, since none of its
+    // assertions are covered by its signature.
+    let unwrapped = Directory(vec![
+        (Envelope::new("alice"), Envelope::new("Alice").add_assertion(PUBLIC_KEYS, alice_public_key())),
+    ]);
+    assert!(block_on(message.verify_signature_resolving(&unwrapped, 8)).is_err());
+}
+
+#[test]
+fn test_witnesses() {
+    let document = hello_envelope().sign(&alice_private_key());
+    let metadata = SignatureMetadata::new().with_assertion(NOTE, "Witnessed in person.");
+    let (bob, carol) = (bob_private_key(), carol_private_key());
+    let (bob_witness, carol_witness) = (SigningWitness::new(&bob).with_metadata(metadata), SigningWitness::new(&carol));
+    let witnesses = WitnessSet::new()
+        .with_witness(&bob_witness)
+        .with_witness(&carol_witness);
+    assert_eq!(witnesses.len(), 2);
+
+    let notarized = witnesses.notarize(&document, 2).unwrap()
+        .check_encoding().unwrap();
+    let verifiers: [&dyn bc_components::Verifier; 2] = [&bob_public_key(), &carol_public_key()];
+    notarized.verify_witnesses(&verifiers, 2).unwrap();
+
+    // The witness signatures don't affect the document's own signature.
+    notarized.verify_signature_from(&alice_public_key()).unwrap();
+    assert_eq!(notarized.witness_bundle().unwrap().unwrap().extract_subject::<bc_components::Digest>().unwrap(), document.subject().digest().into_owned());
+
+    // Alice is not a witness.
+    assert!(notarized.verify_witnesses(&[&alice_public_key()], 1).is_err());
+    assert!(notarized.verify_witnesses(&verifiers, 3).is_err());
+
+    // Signatures collected later are added to the same bundle.
+    let bob_only = document.add_witness_signatures(&WitnessSet::new().with_witness(&bob_witness).collect(&document)
+        .into_iter().collect::<Result<Vec<_>, _>>().unwrap()).unwrap();
+    assert!(bob_only.verify_witnesses(&verifiers, 2).is_err());
+    let both = bob_only.add_witness_signatures(&[carol_witness.witness(&document.subject().digest()).unwrap()]).unwrap();
+    both.verify_witnesses(&verifiers, 2).unwrap();
+    assert_eq!(both.assertions_with_predicate("witnessedBy").len(), 1);
+
+    // An unwitnessed document fails verification.
+    assert!(document.verify_witnesses(&verifiers, 0).is_err());
+
+    // A witness signature is not a signature on the document's subject.
+    let carol_signature = carol_witness.witness(&document.subject().digest()).unwrap();
+    let forged = document.add_assertion(known_values::SIGNED, carol_signature);
+    assert!(!forged.has_signature_from(&carol_public_key()).unwrap());
+}
+
+#[test]
+fn test_key_rotation() {
+    let date = |s| dcbor::Date::from_string(s).unwrap();
+    let signed_at = |document: &Envelope, key: &bc_components::PrivateKeyBase, s| {
+        let metadata = SignatureMetadata::new().with_assertion(known_values::DATE, date(s));
+        document.wrap_envelope().add_signature_opt(key, None, Some(metadata))
+    };
+
+    // Alice's key is rotated to Bob's, and then Bob's to Carol's.
+    let controller = Envelope::new("Controller")
+        .attest_rotation_at(&alice_private_key(), &[bob_public_key()], date("2024-01-01"))
+        .attest_rotation_at(&bob_private_key(), &[carol_public_key()], date("2025-01-01"))
+        .check_encoding().unwrap();
+    let history = RotationHistory::from_document(vec![alice_public_key()], &controller).unwrap();
+    assert_eq!(history.rotation_count(), 2);
+    assert_eq!(history.keys_at(&date("2023-06-01")), [alice_public_key()]);
+    assert_eq!(history.keys_at(&date("2024-06-01")), [bob_public_key()]);
+    assert_eq!(history.current_keys(), [carol_public_key()]);
+
+    // Signatures made while the signer's key was valid are accepted.
+    signed_at(&hello_envelope(), &alice_private_key(), "2023-06-01").verify_with_rotation_history(&history).unwrap();
+    signed_at(&hello_envelope(), &bob_private_key(), "2024-06-01").verify_with_rotation_history(&history).unwrap();
+    hello_envelope().sign(&carol_private_key()).verify_with_rotation_history(&history).unwrap();
+
+    // Signatures made after the signer's key was rotated out are not.
+    assert!(signed_at(&hello_envelope(), &alice_private_key(), "2024-06-01").verify_with_rotation_history(&history).is_err());
+    assert!(hello_envelope().sign(&bob_private_key()).verify_with_rotation_history(&history).is_err());
+
+    // A retired key can backdate its signature to when it was valid, which is
+    // only caught by rejecting retired keys outright.
+    let backdated = signed_at(&hello_envelope(), &bob_private_key(), "2024-06-01");
+    backdated.verify_with_rotation_history(&history).unwrap();
+    assert!(backdated.verify_with_rotation_history_opt(&history, false).is_err());
+    hello_envelope().sign(&carol_private_key()).verify_with_rotation_history_opt(&history, false).unwrap();
+
+    // A rotation must be attested by a key valid before it.
+    let forged = Envelope::new("Controller")
+        .attest_rotation_at(&bob_private_key(), &[carol_public_key()], date("2024-01-01"));
+    assert!(RotationHistory::from_document(vec![alice_public_key()], &forged).is_err());
+}
+
+#[test]
+fn test_trust_store() {
+    let alice_identity = Envelope::new("Alice").add_assertion("role", "Administrator");
+    let bob_document = Envelope::new("Bob").add_assertion("publicKeys", bob_public_key());
+    let store = TrustStore::new()
+        .with_identity(alice_public_key(), alice_identity.clone())
+        .with_document(&bob_document).unwrap();
+    assert_eq!(store.len(), 2);
+    assert!(TrustStore::new().with_document(&Envelope::new("Carol")).is_err());
+
+    // A trusted signer is reported with its identity.
+    let signed = hello_envelope().sign(&alice_private_key());
+    let signer = signed.verify_signature_trusted(&store).unwrap();
+    assert_eq!(signer.key(), &alice_public_key());
+    assert_equivalent!(signer.identity().clone(), alice_identity);
+    assert_eq!(signer.identity().extract_object_for_predicate::<String>("role").unwrap(), "Administrator");
+
+    let cosigned = hello_envelope().wrap_envelope().add_signatures(&[&alice_private_key() as &dyn bc_components::Signer, &bob_private_key()]);
+    let mut names: Vec<String> = cosigned.trusted_signers(&store).iter()
+        .map(|signer| signer.identity().extract_subject().unwrap())
+        .collect();
+    names.sort();
+    assert_eq!(names, ["Alice", "Bob"]);
+
+    // An untrusted signer is not.
+    assert!(hello_envelope().sign(&carol_private_key()).verify_signature_trusted(&store).is_err());
+
+    // The store survives export and import.
+    let exported = Envelope::from(store.clone()).check_encoding().unwrap();
+    let imported = TrustStore::try_from(exported).unwrap();
+    assert_eq!(imported.len(), 2);
+    assert_equivalent!(imported.identity(&bob_public_key()).unwrap().clone(), bob_document);
+    signed.verify_signature_trusted(&imported).unwrap();
+    assert!(TrustStore::try_from(Envelope::new("Hello.")).is_err());
+}
+
+#[test]
+fn test_verify_corpus() {
+    let corpus: Vec<Vec<u8>> = (0..40)
+        .map(|i| match i % 10 {
+            3 => Envelope::new(i).sign(&bob_private_key()).tagged_cbor_data(),
+            7 => vec![0xd8, 0xc8, 0xff],
+            _ => Envelope::new(i).sign(&alice_private_key()).tagged_cbor_data(),
+        })
+        .collect();
+
+    let mut results: Vec<CorpusResult> =
+        verify_corpus(corpus.clone(), alice_public_key(), CorpusPolicy::new().with_workers(4)).collect();
+    assert_eq!(results.len(), 40);
+    results.sort_by_key(|result| result.index);
+    for result in &results {
+        match result.index % 10 {
+            3 => assert!(!result.is_ok() && result.digest.is_some()),
+            7 => assert!(!result.is_ok() && result.digest.is_none()),
+            _ => {
+                assert!(result.is_ok());
+                let data = &corpus[result.index];
+                let envelope = Envelope::try_from_cbor_data(data.clone()).unwrap();
+                assert_eq!(result.digest.as_ref(), Some(envelope.digest().as_ref()));
+            }
+        }
+    }
+
+    // Checking only structure accepts every envelope that decodes.
+    let policy = CorpusPolicy::new().with_workers(2).with_verify_signatures(false);
+    let failures = verify_corpus(corpus.clone(), alice_public_key(), policy).filter(|result| !result.is_ok()).count();
+    assert_eq!(failures, 4);
+
+    // Results can be abandoned part way through.
+    let first: Vec<_> = verify_corpus(corpus.clone(), alice_public_key(), CorpusPolicy::new()).take(5).collect();
+    assert_eq!(first.len(), 5);
+
+    // A verifier that panics fails only the item being verified.
+    struct PanickingVerifier;
+    impl bc_components::Verifier for PanickingVerifier {
+        fn verify(&self, _: &bc_components::Signature, _: &dyn AsRef<[u8]>) -> bool {
+            panic!("broken verifier")
+        }
+    }
+    let results: Vec<_> = verify_corpus(corpus, PanickingVerifier, CorpusPolicy::new().with_workers(2)).collect();
+    assert_eq!(results.len(), 40);
+    assert!(results.iter().all(|result| !result.is_ok()));
+    let panicked = results.iter().filter(|result| result.result.as_ref().unwrap_err().to_string() == "verification panicked: broken verifier");
+    assert_eq!(panicked.count(), 36);
+}
+
+#[test]
+fn test_presentation() {
+    // Alice issues Bob a credential bound to his key.
+    let credential = Envelope::new("Bob")
+        .add_assertion(known_values::HOLDER, bob_public_key())
+        .add_assertion("degree", "BSc")
+        .add_assertion("year", 2020)
+        .sign(&alice_private_key());
+    let redacted = credential.elide_removing_target(&credential.subject().unwrap_envelope().unwrap().assertion_with_predicate("year").unwrap());
+
+    // Carol, the verifier, asks Bob to present it in response to a nonce.
+    let carol = ARID::new();
+    let nonce = Nonce::new();
+    let presentation = present(&redacted, &carol, &nonce, &bob_private_key());
+    let policy = TimePolicy::new().with_max_skew(Duration::from_secs(60)).with_max_age(Duration::from_secs(5 * 60));
+    let content = verify_presentation(&presentation, &alice_public_key(), &carol, &nonce, &policy).unwrap();
+    assert_eq!(content.extract_object_for_predicate::<String>("degree").unwrap(), "BSc");
+    assert!(content.assertion_with_predicate("year").is_err());
+
+    // The presentation cannot be replayed to another verifier, or with
+    // another nonce, or later.
+    assert_eq!(
+        verify_presentation(&presentation, &alice_public_key(), &ARID::new(), &nonce, &policy).unwrap_err().to_string(),
+        "the presentation is for a different audience"
+    );
+    assert_eq!(
+        verify_presentation(&presentation, &alice_public_key(), &carol, &Nonce::new(), &policy).unwrap_err().to_string(),
+        "the presentation has an unexpected nonce"
+    );
+    let later = policy.clone().with_clock(FixedClock::new(Date::now() + Duration::from_secs(60 * 60)));
+    assert!(verify_presentation(&presentation, &alice_public_key(), &carol, &nonce, &later).is_err());
+
+    // Only the holder can present the credential, and only credentials from
+    // the issuer are accepted.
+    let stolen = present(&redacted, &carol, &nonce, &carol_private_key());
+    assert!(verify_presentation(&stolen, &alice_public_key(), &carol, &nonce, &policy).is_err());
+    assert!(verify_presentation(&presentation, &carol_public_key(), &carol, &nonce, &policy).is_err());
+
+    // A credential without a holder key cannot be presented.
+    let unbound = Envelope::new("Bob").add_assertion("degree", "BSc").sign(&alice_private_key());
+    let presentation = present(&unbound, &carol, &nonce, &bob_private_key());
+    assert_eq!(
+        verify_presentation(&presentation, &alice_public_key(), &carol, &nonce, &policy).unwrap_err().to_string(),
+        "the credential is not bound to a holder key"
+    );
+}
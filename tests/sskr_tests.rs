@@ -1,5 +1,5 @@
 #![cfg(all(feature = "sskr", feature = "types"))]
-use bc_components::{SymmetricKey, SSKRGroupSpec, SSKRSpec};
+use bc_components::{SymmetricKey, SSKRGroupSpec, SSKRShare, SSKRSpec};
 use hex_literal::hex;
 use bc_envelope::prelude::*;
 use indoc::indoc;
@@ -73,3 +73,43 @@ fn test_sskr() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_sskr_joiner() -> anyhow::Result<()> {
+    let content_key = SymmetricKey::new();
+    let secret = Envelope::new("Secret").add_assertion("note", "Two groups are needed.");
+    let encrypted = secret.wrap_envelope().encrypt_subject(&content_key)?;
+    let spec = SSKRSpec::new(2, vec![SSKRGroupSpec::new(2, 3)?, SSKRGroupSpec::new(1, 2)?])?;
+    let groups = encrypted.sskr_split(&spec, &content_key)?;
+
+    let mut joiner = SSKRJoiner::new();
+    assert!(matches!(joiner.add_share(&groups[0][0])?, SSKRJoinStatus::NeedsMore { groups_complete: 0, groups_needed: 2 }));
+    // Receiving a share again is harmless.
+    assert!(matches!(joiner.add_share(&groups[0][0])?, SSKRJoinStatus::NeedsMore { groups_complete: 0, .. }));
+    assert!(matches!(joiner.add_share(&groups[0][2])?, SSKRJoinStatus::NeedsMore { groups_complete: 1, .. }));
+
+    // Shares from another split, and envelopes without shares, are rejected
+    // without disturbing the shares received.
+    let other = encrypted.sskr_split(&spec, &content_key)?;
+    assert!(joiner.add_share(&other[1][0]).is_err());
+    assert!(joiner.add_share(&encrypted).is_err());
+    assert!(joiner.envelope().is_none());
+
+    // So are shares of the wrong length, and shares that pass every check
+    // but cannot be combined, which the joiner forgets again.
+    let share = groups[1][1].object_for_predicate(known_values::SSKR_SHARE)?.extract_subject::<SSKRShare>()?;
+    let mut data = share.data().clone();
+    data.push(0);
+    assert!(joiner.add_share(&encrypted.add_assertion(known_values::SSKR_SHARE, SSKRShare::from_data(data))).is_err());
+    let mut data = share.data().clone();
+    *data.last_mut().unwrap() ^= 1;
+    assert!(joiner.add_share(&encrypted.add_assertion(known_values::SSKR_SHARE, SSKRShare::from_data(data))).is_err());
+    assert!(matches!(joiner.status(), SSKRJoinStatus::NeedsMore { groups_complete: 1, .. }));
+
+    let status = joiner.add_share(&groups[1][1])?;
+    assert!(status.is_complete());
+    let joined = joiner.envelope().unwrap().unwrap_envelope()?;
+    assert_eq!(joined.digest(), secret.digest());
+    assert!(joiner.add_share(&groups[0][1])?.is_complete());
+    Ok(())
+}
@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+
+use bc_envelope::prelude::*;
+
+mod common;
+use crate::common::test_data::*;
+
+fn store() -> HashMap<Digest, Envelope> {
+    [
+        Envelope::new("Alice").add_assertion("knows", "Bob"),
+        Envelope::new("Carol").add_assertion("knows", "Bob").add_assertion("knows", "Dave"),
+        hello_envelope().wrap_envelope(),
+    ]
+    .into_iter()
+    .map(|envelope| (envelope.digest().into_owned(), envelope))
+    .collect()
+}
+
+#[test]
+fn test_paths_matching() {
+    let envelope = Envelope::new("Alice").add_assertion("knows", "Bob");
+    let bob = Envelope::new("Bob");
+    let paths = envelope.paths_matching(&|element: &Envelope| element.digest() == bob.digest());
+    assert_eq!(paths.len(), 1);
+    let path = &paths[0];
+    assert_eq!(path.len(), 3);
+    assert_eq!(path.first().unwrap().digest(), envelope.digest());
+    assert!(path[1].is_assertion());
+    assert_eq!(path.last().unwrap().digest(), bob.digest());
+}
+
+#[test]
+fn test_search_store() {
+    let store = store();
+    let bob = Envelope::new("Bob");
+    let matcher = |element: &Envelope| element.digest() == bob.digest();
+
+    let results: Vec<(Digest, Vec<Envelope>)> = search_store(&store, &matcher).collect();
+    assert_eq!(results.len(), 2);
+    for (digest, path) in &results {
+        assert_eq!(path.first().unwrap().digest().as_ref(), digest);
+        assert_eq!(path.last().unwrap().digest(), bob.digest());
+    }
+
+    // Results are produced lazily.
+    assert_eq!(search_store(&store, &matcher).next().map(|(_, path)| path.len()), Some(3));
+
+    // Every element matches an unconditional matcher.
+    let all = search_store(&store, &|_: &Envelope| true).count();
+    let elements: usize = store.values().map(|envelope| envelope.paths_matching(&|_: &Envelope| true).len()).sum();
+    assert_eq!(all, elements);
+    assert_eq!(search_store(&store, &|_: &Envelope| false).count(), 0);
+}
+
+#[test]
+fn test_structural_patterns() {
+    let envelope = Envelope::new("Carol")
+        .add_assertion("knows", "Bob")
+        .add_assertion("knows", "Dave")
+        .add_assertion("note", Envelope::new("Hi").add_assertion("lang", "en"));
+
+    assert_eq!(envelope.paths_matching(&Pattern::any_node()).len(), 2);
+    assert_eq!(envelope.paths_matching(&Pattern::node_with_assertions_count(3)).len(), 1);
+    assert_eq!(envelope.paths_matching(&Pattern::node_with_assertions_count(1)).len(), 1);
+    assert_eq!(envelope.paths_matching(&Pattern::node_with_assertions_range(2..)).len(), 1);
+    assert_eq!(envelope.paths_matching(&Pattern::node_with_assertions_range(..3)).len(), 1);
+    assert_eq!(envelope.paths_matching(&Pattern::node_with_assertions_range(1..=3)).len(), 2);
+    assert!(envelope.paths_matching(&Pattern::node_with_assertions_range(4..)).is_empty());
+
+    let dave = Envelope::new("Dave");
+    let paths = envelope.paths_matching(&Pattern::digest(dave.digest().into_owned()));
+    assert_eq!(paths.len(), 1);
+    assert_eq!(paths[0].last().unwrap().digest(), dave.digest());
+    let digest = dave.digest();
+    assert!(!envelope.paths_matching(&Pattern::digest_prefix(&digest.data()[..4])).is_empty());
+
+    assert!(envelope.paths_matching(&Pattern::obscured()).is_empty());
+    let elided = envelope.elide_removing_target(&dave);
+    assert_eq!(elided.paths_matching(&Pattern::elided()).len(), 1);
+    assert_eq!(elided.paths_matching(&Pattern::obscured()).len(), 1);
+
+    let store: HashMap<Digest, Envelope> = [(elided.digest().into_owned(), elided.clone())].into_iter().collect();
+    assert_eq!(search_store(&store, &Pattern::elided()).count(), 1);
+}
+
+#[test]
+fn test_query_cache() {
+    let alice = Envelope::new("Alice").add_assertion("knows", "Bob");
+    let carol = Envelope::new("Carol").add_assertion("knows", "Bob").add_assertion("knows", "Dave");
+    let cache = QueryCache::new(2);
+
+    let pattern = Pattern::node_with_assertions_range(1..);
+    assert_eq!(pattern.to_string(), "node({1,})");
+    assert_eq!(cache.paths_matching_pattern(&alice, &pattern).len(), 1);
+    assert_eq!(cache.paths_matching_pattern(&alice, &pattern).len(), 1);
+    assert_eq!(cache.stats(), (1, 1));
+
+    // Results are keyed by structure, so an identical envelope built
+    // separately shares the cached result.
+    let bob = |e: &Envelope| e.extract_subject::<String>().is_ok_and(|s| s == "Bob");
+    let rebuilt = Envelope::new("Alice").add_assertion("knows", "Bob");
+    assert_eq!(cache.paths_matching(&alice, "subject = 'Bob'", &bob).len(), 1);
+    assert_eq!(cache.paths_matching(&rebuilt, "subject = 'Bob'", &bob).len(), 1);
+    assert_eq!(cache.stats(), (2, 2));
+    assert_eq!(cache.len(), 2);
+
+    // The oldest result is evicted when the cache is full.
+    assert_eq!(cache.paths_matching(&carol, "subject = 'Bob'", &bob).len(), 1);
+    assert_eq!(cache.len(), 2);
+    cache.paths_matching_pattern(&alice, &pattern);
+    assert_eq!(cache.stats(), (2, 4));
+
+    cache.clear();
+    assert!(cache.is_empty());
+    assert_eq!(cache.stats(), (0, 0));
+
+    // An elided copy has the same digest but different elements, so it does
+    // not share the result of the envelope it elides, nor the reverse.
+    let elided = alice.elide_removing_target(&alice.assertions()[0]);
+    assert_eq!(elided.digest(), alice.digest());
+    assert_eq!(cache.paths_matching_pattern(&alice, &pattern).len(), 1);
+    assert_eq!(cache.paths_matching_pattern(&elided, &Pattern::obscured()).len(), 1);
+    assert_eq!(cache.paths_matching_pattern(&elided, &pattern).len(), 1);
+    assert!(cache.paths_matching_pattern(&alice, &Pattern::obscured()).is_empty());
+    assert!(cache.paths_matching(&elided, "subject = 'Bob'", &bob).is_empty());
+    assert_eq!(cache.paths_matching(&alice, "subject = 'Bob'", &bob).len(), 1);
+    assert_eq!(cache.stats(), (0, 6));
+
+    // Query text is compared exactly.
+    assert_eq!(cache.paths_matching(&alice, "subject  = 'Bob'", &bob).len(), 1);
+    assert_eq!(cache.stats(), (0, 7));
+}
+
+#[test]
+fn test_unelide_from_store() {
+    let bob = Envelope::new("Bob").add_assertion("knows", "Dave");
+    let knows_bob = Envelope::new_assertion("knows", bob.clone());
+    let original = Envelope::new("Alice")
+        .add_assertion_envelope(knows_bob.clone())
+        .unwrap()
+        .add_assertion("age", 30)
+        .wrap_envelope();
+    let dave = Envelope::new("Dave");
+    let redacted = original.elide_removing_set(&[knows_bob.digest().into_owned(), dave.digest().into_owned()].into());
+
+    // Nothing in the store: nothing restored.
+    let empty: HashMap<Digest, Envelope> = HashMap::new();
+    assert_eq!(redacted.unelide_from_store(&empty).structural_digest(), redacted.structural_digest());
+
+    // The restored assertion contains an elided element that the store also
+    // has.
+    let store: HashMap<Digest, Envelope> = [
+        (knows_bob.digest().into_owned(), knows_bob.clone().elide_removing_target(&dave)),
+        (dave.digest().into_owned(), dave.clone()),
+    ]
+    .into_iter()
+    .collect();
+    let restored = redacted.unelide_from_store(&store);
+    assert_equivalent!(restored, original);
+    assert_eq!(restored.structural_digest(), original.structural_digest());
+
+    // Only part of the document is known.
+    let partial = redacted.walk_unelide(&[knows_bob.elide_removing_target(&dave)]);
+    assert_equivalent!(partial, original);
+    assert_eq!(partial.paths_matching(&Pattern::elided()).len(), 1);
+}
+
+#[test]
+fn test_forest() -> anyhow::Result<()> {
+    let address = Envelope::new("Address").add_assertion("street", "1 Main St").add_assertion("city", "Springfield");
+    let alice = Envelope::new("Alice").add_assertion("address", address.clone()).add_assertion("knows", "Bob");
+    let carol = Envelope::new("Carol").add_assertion("address", address.clone());
+    let elided = alice.elide_removing_target(&address);
+
+    let mut forest = Forest::new();
+    assert_eq!(forest.add_root(&alice), 0);
+    let elements = forest.element_count();
+    forest.add_root(&carol);
+    // Carol shares her address assertion with Alice, so adds only her subject
+    // and her node.
+    assert_eq!(forest.element_count(), elements + 2);
+    forest.add_root(&elided);
+    assert_eq!(forest.len(), 3);
+    assert_eq!(forest.root(2).unwrap().digest(), alice.digest());
+    assert!(forest.root(2).unwrap().is_identical_to(&elided));
+    assert!(forest.root(0).unwrap().is_identical_to(&alice));
+
+    // The matcher is called once for each distinct element.
+    let calls = std::cell::Cell::new(0);
+    let matcher = |element: &Envelope| {
+        calls.set(calls.get() + 1);
+        element.digest() == Envelope::new("Springfield").digest()
+    };
+    let paths = forest.paths_matching(&matcher);
+    assert_eq!(calls.get(), forest.element_count());
+    assert_eq!(paths.iter().map(|(root, _)| *root).collect::<Vec<_>>(), vec![0, 1]);
+    for (root, path) in &paths {
+        assert_eq!(path.first().unwrap().digest(), forest.root(*root).unwrap().digest());
+        assert_eq!(path.len(), alice.paths_matching(&matcher)[0].len());
+    }
+
+    // Shared subtrees are encoded once.
+    let cbor = CBOR::from(&forest);
+    let separate: usize = forest.roots().map(|root| root.tagged_cbor_data().len()).sum();
+    assert!(cbor.to_cbor_data().len() < separate);
+    let restored = Forest::try_from(cbor)?;
+    assert_eq!(restored.len(), 3);
+    assert_eq!(restored.element_count(), forest.element_count());
+    for (a, b) in restored.roots().zip(forest.roots()) {
+        assert!(a.is_identical_to(b));
+    }
+    assert!(Forest::try_from(CBOR::from(vec![CBOR::from(vec![CBOR::from(vec![0u64, 5])]), CBOR::from(Vec::<u64>::new())])).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_forest_shared_dag() -> anyhow::Result<()> {
+    // Each element is an assertion of the previous element about itself, so
+    // the tree has 2^64 paths but only 65 distinct elements.
+    let mut elements = vec![Envelope::new("leaf").tagged_cbor()];
+    let mut envelope = Envelope::new("leaf");
+    for i in 1..=64u64 {
+        elements.push(CBOR::from(vec![2, i - 1, i - 1]));
+        if i <= 16 {
+            envelope = Envelope::new_assertion(envelope.clone(), envelope);
+        }
+    }
+    let forest = Forest::try_from(CBOR::from(vec![CBOR::from(elements), CBOR::from(vec![16u64, 64])]))?;
+    assert_eq!(forest.len(), 2);
+    assert_eq!(forest.element_count(), 65);
+    assert_eq!(forest.root(0).unwrap().digest(), envelope.digest());
+
+    let mut forest = Forest::new();
+    forest.add_root(&envelope);
+    assert_eq!(forest.element_count(), 17);
+    Ok(())
+}
@@ -0,0 +1,60 @@
+use bc_envelope::prelude::*;
+use dcbor::Date;
+
+fn mapping() -> TableMapping {
+    TableMapping::new()
+        .with_subject_column("id", ColumnType::Text)
+        .with_column("name", "name", ColumnType::Text)
+        .with_column("age", "age", ColumnType::Integer)
+        .with_column("member", "member", ColumnType::Boolean)
+        .with_column("joined", "joined", ColumnType::Date)
+}
+
+#[test]
+fn test_table_envelopes() -> anyhow::Result<()> {
+    let header = ["id", "name", "age", "member", "joined", "ignored"];
+    let rows = [
+        ["A1", "Alice", "30", "true", "2024-01-02", "x"],
+        ["B2", "Bob", "", "false", "2023-06-30", "y"],
+    ];
+    let envelopes = mapping().envelopes(&header, rows)?;
+    assert_eq!(envelopes.len(), 2);
+
+    let alice = &envelopes[0];
+    assert_eq!(alice.extract_subject::<String>()?, "A1");
+    assert_eq!(alice.extract_object_for_predicate::<String>("name")?, "Alice");
+    assert_eq!(alice.extract_object_for_predicate::<i64>("age")?, 30);
+    assert!(alice.extract_object_for_predicate::<bool>("member")?);
+    assert_eq!(alice.extract_object_for_predicate::<Date>("joined")?, Date::from_string("2024-01-02")?);
+    assert_eq!(alice.assertions().len(), 4);
+
+    // Empty cells are skipped.
+    let bob = &envelopes[1];
+    assert!(bob.assertion_with_predicate("age").is_err());
+    assert_eq!(bob.assertions().len(), 3);
+
+    // Bad cells and missing columns are errors.
+    assert!(mapping().envelopes(&header, [["C3", "Carol", "old", "true", "2024-01-02", ""]]).is_err());
+    assert!(mapping().envelopes(&["id", "name"], [["C3", "Carol"]]).is_err());
+
+    let collection = mapping().collection("Members", "member", &header, rows)?;
+    assert_eq!(collection.objects_for_predicate("member").len(), 2);
+    Ok(())
+}
+
+#[test]
+fn test_table_envelopes_from_csv() -> anyhow::Result<()> {
+    let csv = "id,name,age\r\nA1,\"Smith, Alice\",30\nB2,\"Bob \"\"B\"\" Jones\",41\n";
+    let mapping = TableMapping::new()
+        .with_column("name", "name", ColumnType::Text)
+        .with_column("age", "age", ColumnType::Integer);
+    let envelopes = mapping.envelopes_from_csv(csv)?;
+    assert_eq!(envelopes.len(), 2);
+    assert!(envelopes[0].subject().is_null());
+    assert_eq!(envelopes[0].extract_object_for_predicate::<String>("name")?, "Smith, Alice");
+    assert_eq!(envelopes[1].extract_object_for_predicate::<String>("name")?, "Bob \"B\" Jones");
+    assert_eq!(envelopes[1].extract_object_for_predicate::<i64>("age")?, 41);
+
+    assert!(mapping.envelopes_from_csv("name,age\n\"unterminated,1\n").is_err());
+    Ok(())
+}
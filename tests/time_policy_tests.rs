@@ -0,0 +1,66 @@
+#![cfg(feature = "known_value")]
+
+use std::time::Duration;
+
+use bc_envelope::prelude::*;
+use dcbor::Date;
+
+mod common;
+#[cfg(feature = "signature")]
+use crate::common::test_data::*;
+
+fn policy_at(now: &str) -> TimePolicy {
+    TimePolicy::new().with_clock(FixedClock::new(Date::from_string(now).unwrap()))
+}
+
+#[test]
+fn test_time_policy() {
+    let ticket = Envelope::new("Ticket")
+        .add_assertion(known_values::VALID_FROM, Date::from_string("2024-07-01T00:00:00Z").unwrap())
+        .add_assertion(known_values::VALID_UNTIL, Date::from_string("2024-07-31T00:00:00Z").unwrap());
+
+    assert!(ticket.check_validity(&policy_at("2024-07-15T00:00:00Z")).is_ok());
+    assert!(ticket.check_validity(&policy_at("2024-06-30T23:59:00Z")).is_err());
+    assert!(ticket.check_validity(&policy_at("2024-07-31T00:01:00Z")).is_err());
+
+    // A clock slightly ahead or behind is tolerated with enough skew.
+    let skew = Duration::from_secs(5 * 60);
+    assert!(ticket.check_validity(&policy_at("2024-06-30T23:59:00Z").with_max_skew(skew)).is_ok());
+    assert!(ticket.check_validity(&policy_at("2024-07-31T00:01:00Z").with_max_skew(skew)).is_ok());
+
+    // Dated envelopes are rejected if they are dated in the future or are
+    // older than the maximum age.
+    let dated = Envelope::new("Hello.")
+        .add_assertion(known_values::DATE, Date::from_string("2024-07-04T11:11:11Z").unwrap());
+    let policy = policy_at("2024-07-04T11:20:00Z").with_max_age(Duration::from_secs(10 * 60));
+    assert!(dated.check_validity(&policy).is_ok());
+    assert!(dated.check_validity(&policy_at("2024-07-04T11:00:00Z")).is_err());
+    assert!(dated.check_validity(&policy_at("2024-07-04T11:30:00Z").with_max_age(Duration::from_secs(10 * 60))).is_err());
+
+    // Any skew or age can be given without overflowing the date range.
+    let lenient = policy_at("2024-07-04T11:20:00Z").with_max_skew(Duration::MAX).with_max_age(Duration::MAX);
+    assert!(ticket.check_validity(&lenient).is_ok());
+    assert!(dated.check_validity(&lenient).is_ok());
+
+    let policy = TimePolicy::new();
+    assert_eq!(policy.max_skew(), Duration::ZERO);
+    assert_eq!(policy.max_age(), None);
+}
+
+#[cfg(feature = "signature")]
+#[test]
+fn test_verify_signature_from_with_policy() {
+    let metadata = SignatureMetadata::new()
+        .with_assertion(known_values::DATE, Date::from_string("2024-07-04T11:11:11Z").unwrap());
+    let envelope = hello_envelope()
+        .wrap_envelope()
+        .add_signature_opt(&alice_private_key(), None, Some(metadata));
+
+    let policy = policy_at("2024-07-04T12:00:00Z");
+    assert!(envelope.verify_signature_from_with_policy(&alice_public_key(), &policy).is_ok());
+    assert!(envelope.verify_signature_from_with_policy(&bob_public_key(), &policy).is_err());
+
+    // A signature dated in the future is rejected.
+    let policy = policy_at("2024-07-04T11:00:00Z");
+    assert!(envelope.verify_signature_from_with_policy(&alice_public_key(), &policy).is_err());
+}
@@ -0,0 +1,96 @@
+use bc_envelope::prelude::*;
+
+mod common;
+use crate::common::check_encoding::*;
+
+fn person() -> Envelope {
+    Envelope::new("Alice")
+        .add_assertion("knows", "Bob")
+        .add_assertion("age", 30)
+        .add_assertion("address", Envelope::new("Main St").add_assertion("city", "Springfield"))
+}
+
+#[test]
+fn test_map_leaves() {
+    let envelope = person().wrap_envelope();
+    let upper = envelope.map_leaves(|cbor| match cbor.clone().into_case() {
+        CBORCase::Text(text) => text.to_uppercase().into(),
+        _ => cbor.clone(),
+    }).unwrap().check_encoding().unwrap();
+    let expected = Envelope::new("ALICE")
+        .add_assertion("KNOWS", "BOB")
+        .add_assertion("AGE", 30)
+        .add_assertion("ADDRESS", Envelope::new("MAIN ST").add_assertion("CITY", "SPRINGFIELD"))
+        .wrap_envelope();
+    assert_equivalent!(upper, expected);
+
+    // Leaves inside elided elements are untouched.
+    let elided = person().elide_removing_target(&person().subject());
+    let mapped = elided.map_leaves(|_| CBOR::from("X")).unwrap();
+    assert!(mapped.subject().is_elided());
+
+    // An identity mapping returns an equivalent envelope.
+    assert_equivalent!(envelope.map_leaves(|cbor| cbor.clone()).unwrap(), envelope);
+
+    // Assertions that become identical are merged.
+    let envelope = Envelope::new("Alice").add_assertion("knows", "Bob").add_assertion("knows", "Carol");
+    let merged = envelope.map_leaves(|_| CBOR::from("X")).unwrap().check_encoding().unwrap();
+    assert_eq!(merged.assertions().len(), 1);
+    assert_equivalent!(merged, Envelope::new("X").add_assertion("X", "X"));
+}
+
+#[test]
+fn test_map_objects_for_predicate() {
+    let envelope = person().add_assertion("knows", "Carol");
+    let mapped = envelope.map_objects_for_predicate("knows", |object| {
+        Envelope::new(format!("{} Smith", object.extract_subject::<String>().unwrap()))
+    }).check_encoding().unwrap();
+    let mut knows: Vec<String> = mapped.objects_for_predicate("knows")
+        .iter()
+        .map(|object| object.extract_subject().unwrap())
+        .collect();
+    knows.sort();
+    assert_eq!(knows, vec!["Bob Smith", "Carol Smith"]);
+    assert_eq!(mapped.extract_object_for_predicate::<i32>("age").unwrap(), 30);
+    assert_eq!(mapped.assertions().len(), envelope.assertions().len());
+
+    // No matching predicate leaves the envelope unchanged.
+    assert_equivalent!(envelope.map_objects_for_predicate("likes", |_| Envelope::new("X")), envelope);
+
+    // Assertions that become identical are merged.
+    let merged = envelope.map_objects_for_predicate("knows", |_| Envelope::new("Dan")).check_encoding().unwrap();
+    assert_eq!(merged.assertions_with_predicate("knows").len(), 1);
+    assert_eq!(merged.assertions().len(), envelope.assertions().len() - 1);
+}
+
+#[cfg(feature = "salt")]
+#[test]
+fn test_map_objects_for_predicate_salted() {
+    let envelope = Envelope::new("Alice").add_assertion_salted("knows", "Bob", true);
+    let mapped = envelope.map_objects_for_predicate("knows", |_| Envelope::new("Carol"));
+    let assertion = mapped.assertions().first().unwrap().clone();
+
+    // The salt on the assertion is kept.
+    assert_eq!(assertion.assertions_with_predicate(known_values::SALT).len(), 1);
+    assert_eq!(assertion.subject().as_object().unwrap().extract_subject::<String>().unwrap(), "Carol");
+}
+
+#[test]
+fn test_map_subtree() {
+    let envelope = person();
+    let address = envelope.object_for_predicate("address").unwrap();
+    let city = address.object_for_predicate("city").unwrap();
+
+    let moved = envelope.map_subtree(&city.digest(), |_| Envelope::new("Shelbyville")).unwrap()
+        .check_encoding().unwrap();
+    let new_address = moved.object_for_predicate("address").unwrap();
+    assert_eq!(new_address.extract_object_for_predicate::<String>("city").unwrap(), "Shelbyville");
+    assert_eq!(moved.extract_object_for_predicate::<String>("knows").unwrap(), "Bob");
+
+    // A digest that is not in the envelope is an error.
+    assert!(envelope.map_subtree(&Envelope::new("Nowhere").digest(), |e| e).is_err());
+
+    // So is a replacement that cannot appear in place of the element.
+    let knows = envelope.assertion_with_predicate("knows").unwrap();
+    assert!(envelope.map_subtree(&knows.digest(), |_| Envelope::new("Bob")).is_err());
+}
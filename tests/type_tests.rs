@@ -34,3 +34,90 @@ fn test_fake_numbers() {
     let array = (0..100).map(|_| rng_next_in_closed_range(&mut rng, &(-50..=50))).collect::<Vec<_>>();
     assert_eq!(format!("{:?}", array), "[-43, -6, 43, -34, -34, 17, -9, 24, 17, -29, -32, -44, 12, -15, -46, 20, 50, -31, -50, 36, -28, -23, 6, -27, -31, -45, -27, 26, 31, -23, 24, 19, -32, 43, -18, -17, 6, -13, -1, -27, 4, -48, -4, -44, -6, 17, -15, 22, 15, 20, -25, -35, -33, -27, -17, -44, -27, 15, -14, -38, -29, -12, 8, 43, 49, -42, -11, -1, -42, -26, -25, 22, -13, 14, 42, -29, -38, 17, 2, 5, 5, -31, 27, -3, 39, -12, 42, 46, -17, -25, -46, -19, 16, 2, -45, 41, 12, -22, 43, -11]");
 }
+
+#[cfg(feature = "known_value")]
+known_value_enum! {
+    /// The status of a request.
+    enum Status {
+        Ok = 103 => "OK",
+        Processing = 104,
+    }
+}
+
+#[cfg(feature = "known_value")]
+#[test]
+fn test_known_value_enum() {
+    assert_eq!(Status::VARIANTS, &[Status::Ok, Status::Processing]);
+    assert_eq!(Status::Ok.known_value().name(), "OK");
+    assert_eq!(Status::Processing.known_value().name(), "Processing");
+    assert_eq!(Status::from_known_value(&KnownValue::new(104)), Some(Status::Processing));
+    assert_eq!(Status::from_known_value(&KnownValue::new(105)), None);
+
+    let envelope = Envelope::new(Status::Ok);
+    assert_eq!(envelope.as_known_value().unwrap().value(), 103);
+    assert_eq!(Status::try_from(envelope).unwrap(), Status::Ok);
+
+    let request = Envelope::new("Request").add_assertion("status", Status::Processing);
+    let status = request.object_for_predicate("status").unwrap();
+    assert_eq!(Status::try_from(status).unwrap(), Status::Processing);
+
+    assert!(matches!(
+        Status::try_from(Envelope::new(known_values::NOTE)),
+        Err(bc_envelope::EnvelopeError::UnexpectedKnownValue(4))
+    ));
+    assert!(matches!(Status::try_from(Envelope::new("OK")), Err(bc_envelope::EnvelopeError::NotKnownValue)));
+}
+
+#[cfg(feature = "known_value")]
+#[test]
+fn test_translate_known_values() {
+    let ours = KnownValue::new_with_static_name(1000, "shipTo");
+    let theirs = KnownValue::new_with_static_name(2000, "deliverTo");
+    let translation = KnownValueTranslation::new()
+        .with_mapping(1000, theirs.clone())
+        .with_mapping(known_values::NOTE.value(), KnownValue::new_with_static_name(known_values::NOTE.value(), "remark"));
+
+    let address = Envelope::new("Address").add_assertion(ours.clone(), "Alice");
+    let envelope = Envelope::new("Order")
+        .add_assertion(ours.clone(), "Bob")
+        .add_assertion(known_values::NOTE, "Fragile")
+        .add_assertion("billTo", address.clone())
+        .add_assertion("secret", Envelope::new("Secret").add_assertion(ours.clone(), "Carol").elide())
+        .check_encoding().unwrap();
+    let (translated, report) = envelope.translate_known_values(&translation).unwrap();
+    let translated = translated.check_encoding().unwrap();
+
+    // Nested predicates are translated, and the elided assertion is reported.
+    assert_eq!(translated.extract_object_for_predicate::<String>(theirs.clone()).unwrap(), "Bob");
+    assert!(translated.assertion_with_predicate(ours.clone()).is_err());
+    let translated_address = translated.object_for_predicate("billTo").unwrap();
+    assert_eq!(translated_address.extract_object_for_predicate::<String>(theirs.clone()).unwrap(), "Alice");
+    assert_eq!(report.predicates().len(), 3);
+    assert_eq!(report.obscured_elements().len(), 1);
+    assert!(!report.is_digest_preserving());
+    assert_eq!(report.original(), &envelope.digest().into_owned());
+    assert_eq!(report.translated(), &translated.digest().into_owned());
+
+    // Renaming a known value keeps its digest.
+    let note = report.predicates().iter().find(|predicate| predicate.from == known_values::NOTE.value()).unwrap();
+    assert_eq!(note.original, note.translated);
+    let (renamed, report) = Envelope::new("Order").add_assertion(known_values::NOTE, "Fragile")
+        .translate_known_values(&translation).unwrap();
+    assert!(report.is_digest_preserving());
+    assert_eq!(renamed.assertion_with_predicate(known_values::NOTE).unwrap().as_predicate().unwrap().as_known_value().unwrap().name(), "remark");
+
+    // The audit envelope records each rewrite.
+    let audit = Envelope::from(&report);
+    assert_eq!(audit.extract_subject::<bc_components::Digest>().unwrap(), renamed.digest().into_owned());
+    let (_, report) = envelope.translate_known_values(&translation).unwrap();
+    let audit = Envelope::from(&report).check_encoding().unwrap();
+    assert_eq!(audit.assertions_with_predicate("rewrote").len(), 3);
+
+    // The inverse translation restores the original.
+    let (restored, _) = translated.translate_known_values(&translation.inverse()).unwrap();
+    assert_eq!(restored.digest(), envelope.digest());
+
+    // Translations that make assertions identical are rejected.
+    let collision = Envelope::new("Order").add_assertion(ours, "Bob").add_assertion(theirs, "Bob");
+    assert!(collision.translate_known_values(&translation).is_err());
+}